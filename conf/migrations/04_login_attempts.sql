@@ -0,0 +1,16 @@
+-- login_attempts
+
+CREATE TYPE login_attempt_key_type AS ENUM ('Email', 'IpAddress');
+
+CREATE TABLE login_attempts (
+    key_type login_attempt_key_type NOT NULL,
+    key VARCHAR(255) NOT NULL,
+    failed_count INTEGER NOT NULL,
+    locked_until TIMESTAMP WITHOUT TIME ZONE,
+    unlock_token_hash VARCHAR(255),
+    last_failed_at TIMESTAMP WITHOUT TIME ZONE NOT NULL,
+    created_at TIMESTAMP WITHOUT TIME ZONE NOT NULL,
+    updated_at TIMESTAMP WITHOUT TIME ZONE NOT NULL,
+    PRIMARY KEY(key_type, key)
+);
+CREATE UNIQUE INDEX login_attempts_unlock_token_hash_unique_index ON login_attempts(unlock_token_hash);
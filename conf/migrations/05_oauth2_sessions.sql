@@ -0,0 +1,11 @@
+-- oauth2_sessions
+
+CREATE TABLE oauth2_sessions (
+    state VARCHAR(255) NOT NULL PRIMARY KEY,
+    provider_type VARCHAR(255) NOT NULL,
+    nonce VARCHAR(255) NOT NULL,
+    code_verifier VARCHAR(255) NOT NULL,
+    expires_at TIMESTAMP WITHOUT TIME ZONE NOT NULL,
+    created_at TIMESTAMP WITHOUT TIME ZONE NOT NULL
+);
+CREATE INDEX oauth2_sessions_expires_at_index ON oauth2_sessions(expires_at);
@@ -0,0 +1,9 @@
+-- user_auth_providers
+
+-- A user can link multiple providers, so user_id is no longer unique.
+ALTER TABLE user_auth_providers DROP CONSTRAINT user_auth_providers_user_id_key;
+CREATE INDEX user_auth_providers_user_id_index ON user_auth_providers(user_id);
+CREATE UNIQUE INDEX user_auth_providers_user_id_provider_type_unique_index ON user_auth_providers(user_id, provider_type);
+
+-- users.authentication_type only records how the account was created.
+COMMENT ON COLUMN users.authentication_type IS 'sign-up method, see user_auth_emails and user_auth_providers for the linked login methods';
@@ -0,0 +1,18 @@
+-- api_keys
+
+CREATE TYPE api_key_scope AS ENUM ('FileConvert', 'Account');
+
+CREATE TABLE api_keys (
+    id VARCHAR(255) NOT NULL PRIMARY KEY,
+    user_id VARCHAR(255) NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    prefix VARCHAR(255) NOT NULL,
+    key_hash VARCHAR(255) NOT NULL,
+    scopes api_key_scope[] NOT NULL,
+    expires_at TIMESTAMP WITHOUT TIME ZONE,
+    last_used_at TIMESTAMP WITHOUT TIME ZONE,
+    created_at TIMESTAMP WITHOUT TIME ZONE NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+CREATE UNIQUE INDEX api_keys_key_hash_unique_index ON api_keys(key_hash);
+CREATE INDEX api_keys_user_id_index ON api_keys(user_id);
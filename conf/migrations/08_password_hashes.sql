@@ -0,0 +1,11 @@
+-- user_auth_emails
+
+-- Hashes become self-describing PHC strings. Existing rows were produced by PBKDF2-HMAC-SHA256 with 1024 iterations
+-- and stored as hex, so they are re-encoded in place and upgraded to the configured algorithm on the next login.
+UPDATE user_auth_emails
+    SET password_hash = '$pbkdf2-sha256$i=1024$'
+        || rtrim(encode(decode(salt, 'hex'), 'base64'), '=')
+        || '$'
+        || rtrim(encode(decode(password_hash, 'hex'), 'base64'), '=');
+
+ALTER TABLE user_auth_emails DROP COLUMN salt;
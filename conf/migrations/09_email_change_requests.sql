@@ -0,0 +1,11 @@
+-- email_change_requests
+
+CREATE TABLE email_change_requests (
+    user_id VARCHAR(255) NOT NULL PRIMARY KEY,
+    new_email VARCHAR(255) NOT NULL,
+    token VARCHAR(255) NOT NULL,
+    expires_at TIMESTAMP WITHOUT TIME ZONE NOT NULL,
+    created_at TIMESTAMP WITHOUT TIME ZONE NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+CREATE UNIQUE INDEX email_change_requests_token_unique_index ON email_change_requests(token);
@@ -0,0 +1,4 @@
+-- user_auth_emails
+
+ALTER TABLE user_auth_emails ADD COLUMN confirmation_sent_at TIMESTAMP WITHOUT TIME ZONE;
+UPDATE user_auth_emails SET confirmation_sent_at = created_at WHERE email_verified = false;
@@ -0,0 +1,9 @@
+-- users
+
+CREATE TYPE user_locale AS ENUM ('Ja', 'En');
+
+ALTER TABLE users ADD COLUMN display_name VARCHAR(255);
+ALTER TABLE users ADD COLUMN locale user_locale NOT NULL DEFAULT 'Ja';
+ALTER TABLE users ADD COLUMN time_zone VARCHAR(255) NOT NULL DEFAULT 'UTC';
+ALTER TABLE users ADD COLUMN avatar_job_id VARCHAR(255);
+ALTER TABLE users ADD COLUMN pending_avatar_job_id VARCHAR(255);
@@ -0,0 +1,14 @@
+-- data_export_jobs
+
+CREATE TYPE data_export_job_status AS ENUM ('Waiting', 'Processing', 'Completed', 'Failed');
+
+CREATE TABLE data_export_jobs (
+    id VARCHAR(255) NOT NULL PRIMARY KEY,
+    user_id VARCHAR(255) NOT NULL,
+    status data_export_job_status NOT NULL,
+    failed_reason TEXT,
+    created_at TIMESTAMP WITHOUT TIME ZONE NOT NULL,
+    updated_at TIMESTAMP WITHOUT TIME ZONE NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+CREATE INDEX data_export_jobs_user_id_index ON data_export_jobs(user_id);
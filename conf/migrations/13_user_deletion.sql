@@ -0,0 +1,8 @@
+-- users
+
+ALTER TABLE users ADD COLUMN deletion_scheduled_at TIMESTAMP WITHOUT TIME ZONE;
+CREATE INDEX users_deletion_scheduled_at_index ON users(deletion_scheduled_at) WHERE deletion_scheduled_at IS NOT NULL;
+
+-- file_convert_jobs
+
+CREATE INDEX file_convert_jobs_user_id_index ON file_convert_jobs(user_id);
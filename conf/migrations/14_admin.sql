@@ -0,0 +1,37 @@
+-- users
+
+ALTER TABLE users ADD COLUMN disabled_at TIMESTAMP WITHOUT TIME ZONE;
+
+-- admin_audit_logs
+
+CREATE TYPE admin_audit_action AS ENUM (
+    'SearchUsers',
+    'ViewUser',
+    'ViewUserJobs',
+    'ForceLogout',
+    'DisableUser',
+    'EnableUser',
+    'UpdateUserRole',
+    'ViewFailedJobs',
+    'RetryFileConvertJob',
+    'RetryEmailSendJob'
+);
+
+CREATE TABLE admin_audit_logs (
+    id VARCHAR(255) NOT NULL PRIMARY KEY,
+    operator_user_id VARCHAR(255) NOT NULL,
+    action admin_audit_action NOT NULL,
+    target_id VARCHAR(255),
+    detail TEXT,
+    created_at TIMESTAMP WITHOUT TIME ZONE NOT NULL
+);
+CREATE INDEX admin_audit_logs_operator_user_id_index ON admin_audit_logs(operator_user_id);
+CREATE INDEX admin_audit_logs_target_id_index ON admin_audit_logs(target_id);
+
+-- file_convert_jobs
+
+CREATE INDEX file_convert_jobs_status_index ON file_convert_jobs(status) WHERE status = 'Failed';
+
+-- email_send_job_batch_details
+
+CREATE INDEX email_send_job_batch_details_status_index ON email_send_job_batch_details(status) WHERE status = 'Failed';
@@ -0,0 +1,7 @@
+-- rate_limit_buckets
+
+CREATE TABLE rate_limit_buckets (
+    key VARCHAR(255) NOT NULL PRIMARY KEY,
+    tokens DOUBLE PRECISION NOT NULL,
+    updated_at TIMESTAMP WITHOUT TIME ZONE NOT NULL
+);
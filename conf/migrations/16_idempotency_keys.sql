@@ -0,0 +1,13 @@
+-- idempotency_keys
+
+CREATE TABLE idempotency_keys (
+    key VARCHAR(512) NOT NULL PRIMARY KEY,
+    fingerprint VARCHAR(64) NOT NULL,
+    status_code INTEGER,
+    content_type VARCHAR(255),
+    body BYTEA,
+    expires_at TIMESTAMP WITHOUT TIME ZONE NOT NULL,
+    created_at TIMESTAMP WITHOUT TIME ZONE NOT NULL,
+    updated_at TIMESTAMP WITHOUT TIME ZONE NOT NULL
+);
+CREATE INDEX idempotency_keys_expires_at_index ON idempotency_keys(expires_at);
@@ -11,8 +11,12 @@ case $1 in
     ;;
 "email-send-feedback")
     ;;
+"data-export")
+    ;;
+"account-purge")
+    ;;
 *)
-    echo "Usage: $0 <file-convert|email-send|email-send-feedback>"
+    echo "Usage: $0 <file-convert|email-send|email-send-feedback|data-export|account-purge>"
     exit 1
     ;;
 esac
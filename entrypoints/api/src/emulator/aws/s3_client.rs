@@ -1,4 +1,5 @@
 use std::{
+    collections::HashMap,
     io,
     net::SocketAddr,
     path::{Path, PathBuf},
@@ -27,8 +28,11 @@ use tower_http::cors::CorsLayer;
 use tracing::{error, info};
 use url::Url;
 
-use omnius_core_cloud::{Result, aws::s3::S3Client};
-use omnius_opxs_base::util::Terminable;
+use omnius_core_base::error::OmniErrorBuilder as _;
+use omnius_core_cloud::{Error, ErrorKind, Result, aws::s3::S3Client};
+use omnius_opxs_base::{aws::S3ObjectMetadataClient, util::Terminable};
+
+const METADATA_QUERY_PREFIX: &str = "x-amz-meta-";
 
 #[allow(unused)]
 #[derive(Debug, Clone)]
@@ -141,6 +145,18 @@ async fn put_content(
 
     let file_path = state.working_dir;
     let file_path = file_path.join(params.key.replace("/", "_"));
+
+    // Like S3, metadata signed into the presigned URL is stored with the object.
+    let metadata: HashMap<String, String> = params
+        .rest
+        .iter()
+        .filter_map(|(k, v)| k.strip_prefix(METADATA_QUERY_PREFIX).map(|k| (k.to_string(), v.clone())))
+        .collect();
+    let metadata = serde_json::to_vec(&metadata).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    fs::write(metadata_path(&file_path), metadata)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
     let mut file_writer = match tokio::fs::File::create(file_path).await {
         Ok(file) => BufWriter::new(file),
         _ => return Err(StatusCode::NOT_FOUND),
@@ -164,6 +180,14 @@ async fn put_content(
 #[derive(serde::Deserialize)]
 struct PutContentQuery {
     key: String,
+    #[serde(flatten)]
+    rest: HashMap<String, String>,
+}
+
+fn metadata_path(file_path: &Path) -> PathBuf {
+    let mut v = file_path.as_os_str().to_owned();
+    v.push(".metadata.json");
+    PathBuf::from(v)
 }
 
 #[async_trait]
@@ -198,6 +222,38 @@ impl S3Client for S3ClientEmulator {
     }
 }
 
+#[async_trait]
+impl S3ObjectMetadataClient for S3ClientEmulator {
+    async fn gen_put_presigned_uri_with_metadata(
+        &self,
+        key: &str,
+        _start_time: DateTime<Utc>,
+        _expires_in: Duration,
+        metadata: &HashMap<String, String>,
+    ) -> Result<String> {
+        let mut url = self.option.base_url.clone();
+        {
+            let mut query = url.query_pairs_mut();
+            query.append_pair("key", key);
+            for (k, v) in metadata.iter() {
+                query.append_pair(&format!("{METADATA_QUERY_PREFIX}{k}"), v);
+            }
+        }
+        Ok(url.to_string())
+    }
+
+    async fn get_object_metadata(&self, key: &str) -> Result<HashMap<String, String>> {
+        let file_path = PathBuf::from(&self.option.working_dir);
+        let file_path = file_path.join(key.replace("/", "_"));
+        let metadata = match fs::read(metadata_path(&file_path)).await {
+            Ok(v) => v,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(HashMap::new()),
+            Err(e) => return Err(e.into()),
+        };
+        serde_json::from_slice(&metadata).map_err(|e| Error::builder().kind(ErrorKind::InvalidFormat).source(e).build())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use chrono::TimeDelta;
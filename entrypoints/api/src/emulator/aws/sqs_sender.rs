@@ -1,22 +1,40 @@
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 use async_trait::async_trait;
 use tokio::sync::{Mutex as TokioMutex, mpsc};
 
 use omnius_core_cloud::{Error, ErrorKind, Result, aws::sqs::SqsSender};
+use omnius_opxs_base::aws::SqsMessageSender;
 
 use crate::prelude::*;
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SqsEmulatorMessage {
+    pub body: String,
+    pub attributes: HashMap<String, String>,
+}
+
 pub struct SqsSenderEmulator {
-    message_sender: mpsc::Sender<String>,
-    pub message_receiver: Arc<TokioMutex<mpsc::Receiver<String>>>,
+    message_sender: mpsc::Sender<SqsEmulatorMessage>,
+    pub message_receiver: Arc<TokioMutex<mpsc::Receiver<SqsEmulatorMessage>>>,
 }
 
 #[async_trait]
 impl SqsSender for SqsSenderEmulator {
     async fn send_message(&self, message: &str) -> Result<()> {
+        SqsMessageSender::send_message(self, message, &HashMap::new()).await
+    }
+}
+
+#[async_trait]
+impl SqsMessageSender for SqsSenderEmulator {
+    async fn send_message(&self, body: &str, attributes: &HashMap<String, String>) -> Result<()> {
+        let message = SqsEmulatorMessage {
+            body: body.to_string(),
+            attributes: attributes.clone(),
+        };
         self.message_sender
-            .send(message.to_string())
+            .send(message)
             .await
             .map_err(|_| Error::builder().kind(ErrorKind::IoError).build())?;
         Ok(())
@@ -26,7 +44,7 @@ impl SqsSender for SqsSenderEmulator {
 impl SqsSenderEmulator {
     #[allow(unused)]
     pub fn new() -> Self {
-        let (message_sender, message_receiver) = mpsc::channel::<String>(32);
+        let (message_sender, message_receiver) = mpsc::channel::<SqsEmulatorMessage>(32);
         Self {
             message_sender,
             message_receiver: Arc::new(TokioMutex::new(message_receiver)),
@@ -44,15 +62,19 @@ mod tests {
     #[tokio::test]
     async fn send_test() -> TestResult {
         let sqs_sender = SqsSenderEmulator::new();
-        sqs_sender.send_message("test1").await?;
-        sqs_sender.send_message("test2").await?;
-        sqs_sender.send_message("test3").await?;
+        SqsSender::send_message(&sqs_sender, "test1").await?;
+        SqsSender::send_message(&sqs_sender, "test2").await?;
+        let attributes = HashMap::from([("traceparent".to_string(), "value".to_string())]);
+        SqsMessageSender::send_message(&sqs_sender, "test3", &attributes).await?;
+
+        let v = sqs_sender.message_receiver.lock().await.recv().await.unwrap();
+        assert_eq!(v.body, "test1");
 
-        let v: String = sqs_sender.message_receiver.lock().await.recv().await.unwrap();
-        assert_eq!(v, "test1");
+        let v = sqs_sender.message_receiver.lock().await.recv().await.unwrap();
+        assert_eq!(v.body, "test2");
 
-        let v: String = sqs_sender.message_receiver.lock().await.recv().await.unwrap();
-        assert_eq!(v, "test2");
+        let v = sqs_sender.message_receiver.lock().await.recv().await.unwrap();
+        assert_eq!(v.attributes, attributes);
 
         Ok(())
     }
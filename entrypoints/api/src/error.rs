@@ -2,6 +2,7 @@ use std::backtrace::Backtrace;
 
 use axum::http::StatusCode;
 use serde::Serialize;
+use tracing::{debug, warn};
 use utoipa::ToSchema;
 
 use omnius_core_base::error::{OmniError, OmniErrorBuilder};
@@ -111,8 +112,17 @@ pub enum ErrorKind {
     TokenExpired,
     NotFound,
     Unauthorized,
+    Forbidden,
     Duplicated,
     UnsupportedType,
+    LoginThrottled,
+    AccountLocked,
+    AccountDisabled,
+    LinkRequired,
+    LastCredential,
+    WeakPassword,
+    EmailNotVerified,
+    RateLimited,
 }
 
 impl std::fmt::Display for ErrorKind {
@@ -135,8 +145,17 @@ impl std::fmt::Display for ErrorKind {
             ErrorKind::TokenExpired => write!(fmt, "token expired"),
             ErrorKind::NotFound => write!(fmt, "not found"),
             ErrorKind::Unauthorized => write!(fmt, "unauthorized"),
+            ErrorKind::Forbidden => write!(fmt, "forbidden"),
             ErrorKind::Duplicated => write!(fmt, "duplicated"),
             ErrorKind::UnsupportedType => write!(fmt, "unsupported type"),
+            ErrorKind::LoginThrottled => write!(fmt, "login throttled"),
+            ErrorKind::AccountLocked => write!(fmt, "account locked"),
+            ErrorKind::AccountDisabled => write!(fmt, "account disabled"),
+            ErrorKind::LinkRequired => write!(fmt, "link required"),
+            ErrorKind::LastCredential => write!(fmt, "last credential"),
+            ErrorKind::WeakPassword => write!(fmt, "weak password"),
+            ErrorKind::EmailNotVerified => write!(fmt, "email not verified"),
+            ErrorKind::RateLimited => write!(fmt, "rate limited"),
         }
     }
 }
@@ -166,7 +185,7 @@ impl From<serde_json::Error> for Error {
 impl From<std::env::VarError> for Error {
     fn from(e: std::env::VarError) -> Self {
         match e {
-            std::env::VarError::NotPresent => Error::builder().kind(ErrorKind::NotFound).message("not found env var").build(),
+            std::env::VarError::NotPresent => Error::builder().kind(ErrorKind::UnexpectedError).message("not found env var").build(),
             std::env::VarError::NotUnicode(_) => Error::builder().kind(ErrorKind::InvalidFormat).message("invalid utf-8").build(),
         }
     }
@@ -287,7 +306,7 @@ impl From<validator::ValidationErrors> for Error {
 impl From<axum::extract::rejection::JsonRejection> for Error {
     fn from(e: axum::extract::rejection::JsonRejection) -> Self {
         Error::builder()
-            .kind(ErrorKind::InvalidFormat)
+            .kind(ErrorKind::InvalidRequest)
             .message("JSON parsing failed")
             .source(e)
             .build()
@@ -303,7 +322,7 @@ impl From<omnius_core_cloud::Error> for Error {
             omnius_core_cloud::ErrorKind::AwsError => Error::builder().kind(ErrorKind::AwsError).source(e).build(),
             omnius_core_cloud::ErrorKind::GcpError => Error::builder().kind(ErrorKind::GcpError).source(e).build(),
             omnius_core_cloud::ErrorKind::InvalidFormat => Error::builder().kind(ErrorKind::InvalidFormat).source(e).build(),
-            omnius_core_cloud::ErrorKind::NotFound => Error::builder().kind(ErrorKind::NotFound).source(e).build(),
+            omnius_core_cloud::ErrorKind::NotFound => Error::builder().kind(ErrorKind::UnexpectedError).source(e).build(),
         }
     }
 }
@@ -322,10 +341,20 @@ impl From<omnius_opxs_auth::Error> for Error {
             omnius_opxs_auth::ErrorKind::AwsError => Error::builder().kind(ErrorKind::AwsError).source(e).build(),
             omnius_opxs_auth::ErrorKind::GcpError => Error::builder().kind(ErrorKind::GcpError).source(e).build(),
             omnius_opxs_auth::ErrorKind::InvalidFormat => Error::builder().kind(ErrorKind::InvalidFormat).source(e).build(),
+            omnius_opxs_auth::ErrorKind::InvalidRequest => Error::builder().kind(ErrorKind::InvalidRequest).source(e).build(),
             omnius_opxs_auth::ErrorKind::NotFound => Error::builder().kind(ErrorKind::NotFound).source(e).build(),
             omnius_opxs_auth::ErrorKind::TokenExpired => Error::builder().kind(ErrorKind::TokenExpired).source(e).build(),
             omnius_opxs_auth::ErrorKind::Unauthorized => Error::builder().kind(ErrorKind::Unauthorized).source(e).build(),
+            omnius_opxs_auth::ErrorKind::Forbidden => Error::builder().kind(ErrorKind::Forbidden).source(e).build(),
             omnius_opxs_auth::ErrorKind::Duplicated => Error::builder().kind(ErrorKind::Duplicated).source(e).build(),
+            omnius_opxs_auth::ErrorKind::LoginThrottled => Error::builder().kind(ErrorKind::LoginThrottled).source(e).build(),
+            omnius_opxs_auth::ErrorKind::AccountLocked => Error::builder().kind(ErrorKind::AccountLocked).source(e).build(),
+            omnius_opxs_auth::ErrorKind::AccountDisabled => Error::builder().kind(ErrorKind::AccountDisabled).source(e).build(),
+            omnius_opxs_auth::ErrorKind::LinkRequired => Error::builder().kind(ErrorKind::LinkRequired).source(e).build(),
+            omnius_opxs_auth::ErrorKind::LastCredential => Error::builder().kind(ErrorKind::LastCredential).source(e).build(),
+            omnius_opxs_auth::ErrorKind::WeakPassword => Error::builder().kind(ErrorKind::WeakPassword).source(e).build(),
+            omnius_opxs_auth::ErrorKind::EmailNotVerified => Error::builder().kind(ErrorKind::EmailNotVerified).source(e).build(),
+            omnius_opxs_auth::ErrorKind::RateLimited => Error::builder().kind(ErrorKind::RateLimited).source(e).build(),
         }
     }
 }
@@ -378,35 +407,355 @@ impl From<omnius_opxs_file_convert::Error> for Error {
     }
 }
 
-#[derive(Debug, Serialize, Clone, ToSchema)]
+impl From<omnius_opxs_data_export::Error> for Error {
+    fn from(e: omnius_opxs_data_export::Error) -> Self {
+        match e.kind() {
+            omnius_opxs_data_export::ErrorKind::Unknown => Error::builder().kind(ErrorKind::Unknown).source(e).build(),
+            omnius_opxs_data_export::ErrorKind::IoError => Error::builder().kind(ErrorKind::IoError).source(e).build(),
+            omnius_opxs_data_export::ErrorKind::TimeError => Error::builder().kind(ErrorKind::TimeError).source(e).build(),
+            omnius_opxs_data_export::ErrorKind::SerdeError => Error::builder().kind(ErrorKind::SerdeError).source(e).build(),
+            omnius_opxs_data_export::ErrorKind::DatabaseError => Error::builder().kind(ErrorKind::DatabaseError).source(e).build(),
+            omnius_opxs_data_export::ErrorKind::HttpClientError => Error::builder().kind(ErrorKind::HttpClientError).source(e).build(),
+            omnius_opxs_data_export::ErrorKind::CryptoError => Error::builder().kind(ErrorKind::CryptoError).source(e).build(),
+            omnius_opxs_data_export::ErrorKind::TaskError => Error::builder().kind(ErrorKind::UnexpectedError).source(e).build(),
+            omnius_opxs_data_export::ErrorKind::UnexpectedError => Error::builder().kind(ErrorKind::UnexpectedError).source(e).build(),
+            omnius_opxs_data_export::ErrorKind::AwsError => Error::builder().kind(ErrorKind::AwsError).source(e).build(),
+            omnius_opxs_data_export::ErrorKind::GcpError => Error::builder().kind(ErrorKind::GcpError).source(e).build(),
+            omnius_opxs_data_export::ErrorKind::InvalidFormat => Error::builder().kind(ErrorKind::InvalidFormat).source(e).build(),
+            omnius_opxs_data_export::ErrorKind::NotFound => Error::builder().kind(ErrorKind::NotFound).source(e).build(),
+            omnius_opxs_data_export::ErrorKind::TokenExpired => Error::builder().kind(ErrorKind::TokenExpired).source(e).build(),
+            omnius_opxs_data_export::ErrorKind::Unauthorized => Error::builder().kind(ErrorKind::Unauthorized).source(e).build(),
+            omnius_opxs_data_export::ErrorKind::Duplicated => Error::builder().kind(ErrorKind::Duplicated).source(e).build(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq, ToSchema)]
 pub enum ApiErrorCode {
     InternalServerError,
     InvalidRequest,
+    ValidationFailed,
+    UnsupportedType,
     NotFound,
     TokenExpired,
     Unauthorized,
+    Forbidden,
     Duplicated,
-}
-
-impl axum::response::IntoResponse for ApiErrorCode {
-    fn into_response(self) -> axum::response::Response {
-        use axum::Json;
-
-        let status_code = match &self {
+    LoginThrottled,
+    AccountLocked,
+    AccountDisabled,
+    LinkRequired,
+    LastCredential,
+    WeakPassword,
+    EmailNotVerified,
+    RateLimited,
+    IdempotencyKeyMismatch,
+    IdempotencyKeyInProgress,
+}
+
+impl ApiErrorCode {
+    pub fn status_code(&self) -> StatusCode {
+        match self {
             ApiErrorCode::InternalServerError => StatusCode::INTERNAL_SERVER_ERROR,
             ApiErrorCode::InvalidRequest => StatusCode::BAD_REQUEST,
+            ApiErrorCode::ValidationFailed => StatusCode::UNPROCESSABLE_ENTITY,
+            ApiErrorCode::UnsupportedType => StatusCode::UNPROCESSABLE_ENTITY,
             ApiErrorCode::NotFound => StatusCode::NOT_FOUND,
             ApiErrorCode::TokenExpired => StatusCode::UNAUTHORIZED,
             ApiErrorCode::Unauthorized => StatusCode::UNAUTHORIZED,
+            ApiErrorCode::Forbidden => StatusCode::FORBIDDEN,
             ApiErrorCode::Duplicated => StatusCode::CONFLICT,
-        };
+            ApiErrorCode::LoginThrottled => StatusCode::TOO_MANY_REQUESTS,
+            ApiErrorCode::AccountLocked => StatusCode::LOCKED,
+            ApiErrorCode::AccountDisabled => StatusCode::FORBIDDEN,
+            ApiErrorCode::LinkRequired => StatusCode::CONFLICT,
+            ApiErrorCode::LastCredential => StatusCode::CONFLICT,
+            ApiErrorCode::WeakPassword => StatusCode::BAD_REQUEST,
+            ApiErrorCode::EmailNotVerified => StatusCode::FORBIDDEN,
+            ApiErrorCode::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+            ApiErrorCode::IdempotencyKeyMismatch => StatusCode::UNPROCESSABLE_ENTITY,
+            ApiErrorCode::IdempotencyKeyInProgress => StatusCode::CONFLICT,
+        }
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            ApiErrorCode::InternalServerError => "Internal server error",
+            ApiErrorCode::InvalidRequest => "Invalid request",
+            ApiErrorCode::ValidationFailed => "Validation failed",
+            ApiErrorCode::UnsupportedType => "Unsupported type",
+            ApiErrorCode::NotFound => "Not found",
+            ApiErrorCode::TokenExpired => "Token expired",
+            ApiErrorCode::Unauthorized => "Unauthorized",
+            ApiErrorCode::Forbidden => "Forbidden",
+            ApiErrorCode::Duplicated => "Duplicated",
+            ApiErrorCode::LoginThrottled => "Login throttled",
+            ApiErrorCode::AccountLocked => "Account locked",
+            ApiErrorCode::AccountDisabled => "Account disabled",
+            ApiErrorCode::LinkRequired => "Link required",
+            ApiErrorCode::LastCredential => "Last credential",
+            ApiErrorCode::WeakPassword => "Weak password",
+            ApiErrorCode::EmailNotVerified => "Email not verified",
+            ApiErrorCode::RateLimited => "Rate limited",
+            ApiErrorCode::IdempotencyKeyMismatch => "Idempotency key reused with a different request",
+            ApiErrorCode::IdempotencyKeyInProgress => "Idempotency key in progress",
+        }
+    }
+}
 
-        let message = ApiErrorMessage { error_code: self.clone() };
-        (status_code, Json(message)).into_response()
+impl From<&ErrorKind> for ApiErrorCode {
+    fn from(kind: &ErrorKind) -> Self {
+        match kind {
+            ErrorKind::Unknown
+            | ErrorKind::IoError
+            | ErrorKind::TimeError
+            | ErrorKind::SerdeError
+            | ErrorKind::DatabaseError
+            | ErrorKind::HttpClientError
+            | ErrorKind::CryptoError
+            | ErrorKind::UnexpectedError
+            | ErrorKind::AwsError
+            | ErrorKind::GcpError
+            | ErrorKind::InvalidFormat => ApiErrorCode::InternalServerError,
+
+            ErrorKind::InvalidRequest => ApiErrorCode::InvalidRequest,
+            ErrorKind::TokenExpired => ApiErrorCode::TokenExpired,
+            ErrorKind::NotFound => ApiErrorCode::NotFound,
+            ErrorKind::Unauthorized => ApiErrorCode::Unauthorized,
+            ErrorKind::Forbidden => ApiErrorCode::Forbidden,
+            ErrorKind::Duplicated => ApiErrorCode::Duplicated,
+            ErrorKind::UnsupportedType => ApiErrorCode::UnsupportedType,
+            ErrorKind::LoginThrottled => ApiErrorCode::LoginThrottled,
+            ErrorKind::AccountLocked => ApiErrorCode::AccountLocked,
+            ErrorKind::AccountDisabled => ApiErrorCode::AccountDisabled,
+            ErrorKind::LinkRequired => ApiErrorCode::LinkRequired,
+            ErrorKind::LastCredential => ApiErrorCode::LastCredential,
+            ErrorKind::WeakPassword => ApiErrorCode::WeakPassword,
+            ErrorKind::EmailNotVerified => ApiErrorCode::EmailNotVerified,
+            ErrorKind::RateLimited => ApiErrorCode::RateLimited,
+        }
     }
 }
 
+impl From<Error> for ApiErrorCode {
+    fn from(e: Error) -> Self {
+        let code = ApiErrorCode::from(e.kind());
+        if code == ApiErrorCode::InternalServerError {
+            warn!(error = ?e);
+        } else {
+            debug!(error = ?e);
+        }
+        code
+    }
+}
+
+impl From<omnius_opxs_auth::Error> for ApiErrorCode {
+    fn from(e: omnius_opxs_auth::Error) -> Self {
+        Error::from(e).into()
+    }
+}
+
+impl From<omnius_opxs_email_send::Error> for ApiErrorCode {
+    fn from(e: omnius_opxs_email_send::Error) -> Self {
+        Error::from(e).into()
+    }
+}
+
+impl From<omnius_opxs_file_convert::Error> for ApiErrorCode {
+    fn from(e: omnius_opxs_file_convert::Error) -> Self {
+        Error::from(e).into()
+    }
+}
+
+impl From<omnius_opxs_data_export::Error> for ApiErrorCode {
+    fn from(e: omnius_opxs_data_export::Error) -> Self {
+        Error::from(e).into()
+    }
+}
+
+impl axum::response::IntoResponse for ApiErrorCode {
+    fn into_response(self) -> axum::response::Response {
+        ApiErrorMessage::from(self).into_response()
+    }
+}
+
+/// Error body in the RFC 7807 `application/problem+json` format, extended with `error_code` and field `details`.
 #[derive(Debug, Serialize, ToSchema)]
 pub struct ApiErrorMessage {
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    pub title: String,
+    pub status: u16,
     pub error_code: ApiErrorCode,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub details: Vec<ApiErrorDetail>,
+}
+
+#[derive(Debug, Serialize, Clone, ToSchema)]
+pub struct ApiErrorDetail {
+    pub field: String,
+    pub code: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+impl From<ApiErrorCode> for ApiErrorMessage {
+    fn from(error_code: ApiErrorCode) -> Self {
+        Self::new(error_code, vec![])
+    }
+}
+
+impl From<Error> for ApiErrorMessage {
+    fn from(e: Error) -> Self {
+        ApiErrorCode::from(e).into()
+    }
+}
+
+impl From<omnius_opxs_auth::Error> for ApiErrorMessage {
+    fn from(e: omnius_opxs_auth::Error) -> Self {
+        ApiErrorCode::from(e).into()
+    }
+}
+
+impl ApiErrorMessage {
+    pub fn new(error_code: ApiErrorCode, details: Vec<ApiErrorDetail>) -> Self {
+        Self {
+            problem_type: "about:blank".to_string(),
+            title: error_code.title().to_string(),
+            status: error_code.status_code().as_u16(),
+            error_code,
+            details,
+        }
+    }
+
+    pub fn weak_password(field: &str, e: &omnius_opxs_auth::Error) -> Self {
+        let details = omnius_opxs_auth::password::PasswordPolicyError::find(e)
+            .map(|v| {
+                v.violations
+                    .iter()
+                    .map(|violation| ApiErrorDetail {
+                        field: field.to_string(),
+                        code: format!("{violation:?}"),
+                        message: None,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self::new(ApiErrorCode::WeakPassword, details)
+    }
+
+    pub fn validation(e: &validator::ValidationErrors) -> Self {
+        let mut details = Vec::new();
+        collect_validation_details("", e, &mut details);
+
+        Self::new(ApiErrorCode::ValidationFailed, details)
+    }
+}
+
+// Nested structs and lists are flattened into dotted paths such as `items[0].name`.
+fn collect_validation_details(prefix: &str, errors: &validator::ValidationErrors, details: &mut Vec<ApiErrorDetail>) {
+    use validator::ValidationErrorsKind;
+
+    let mut entries: Vec<_> = errors.errors().iter().collect();
+    entries.sort_by(|a, b| a.0.cmp(b.0));
+
+    for (field, kind) in entries {
+        let path = if prefix.is_empty() {
+            field.to_string()
+        } else {
+            format!("{prefix}.{field}")
+        };
+        match kind {
+            ValidationErrorsKind::Field(errors) => {
+                for error in errors {
+                    details.push(ApiErrorDetail {
+                        field: path.clone(),
+                        code: error.code.to_string(),
+                        message: error.message.as_ref().map(|v| v.to_string()),
+                    });
+                }
+            }
+            ValidationErrorsKind::Struct(errors) => collect_validation_details(&path, errors, details),
+            ValidationErrorsKind::List(errors) => {
+                for (index, errors) in errors {
+                    collect_validation_details(&format!("{path}[{index}]"), errors, details);
+                }
+            }
+        }
+    }
+}
+
+impl axum::response::IntoResponse for ApiErrorMessage {
+    fn into_response(self) -> axum::response::Response {
+        use axum::{
+            Json,
+            http::{HeaderValue, header},
+        };
+
+        (
+            self.error_code.status_code(),
+            [(header::CONTENT_TYPE, HeaderValue::from_static("application/problem+json"))],
+            Json(self),
+        )
+            .into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use validator::Validate;
+
+    use super::*;
+
+    #[derive(Validate)]
+    struct Input {
+        #[validate(length(min = 1))]
+        name: String,
+        #[validate(nested)]
+        items: Vec<Item>,
+    }
+
+    #[derive(Validate)]
+    struct Item {
+        #[validate(range(max = 10, message = "too large"))]
+        count: u32,
+    }
+
+    #[test]
+    fn error_code_test() {
+        assert_eq!(ApiErrorCode::from(&ErrorKind::InvalidRequest), ApiErrorCode::InvalidRequest);
+        assert_eq!(ApiErrorCode::from(&ErrorKind::InvalidFormat), ApiErrorCode::InternalServerError);
+
+        let e = omnius_opxs_auth::Error::builder().kind(omnius_opxs_auth::ErrorKind::InvalidRequest).build();
+        assert_eq!(ApiErrorCode::from(e), ApiErrorCode::InvalidRequest);
+        let e = omnius_opxs_auth::Error::builder().kind(omnius_opxs_auth::ErrorKind::InvalidFormat).build();
+        assert_eq!(ApiErrorCode::from(e), ApiErrorCode::InternalServerError);
+
+        assert_eq!(ApiErrorCode::from(Error::from(std::env::VarError::NotPresent)), ApiErrorCode::InternalServerError);
+    }
+
+    #[test]
+    fn validation_test() {
+        let input = Input {
+            name: "".to_string(),
+            items: vec![Item { count: 1 }, Item { count: 11 }],
+        };
+        let message = ApiErrorMessage::validation(&input.validate().unwrap_err());
+
+        assert_eq!(
+            serde_json::to_value(&message).unwrap(),
+            json!({
+                "type": "about:blank",
+                "title": "Validation failed",
+                "status": 422,
+                "error_code": "ValidationFailed",
+                "details": [
+                    { "field": "items[1].count", "code": "range", "message": "too large" },
+                    { "field": "name", "code": "length" },
+                ],
+            })
+        );
+    }
 }
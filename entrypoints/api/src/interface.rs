@@ -1,6 +1,10 @@
 mod extractors;
 mod features;
+mod idempotency;
+mod metrics;
+mod rate_limit;
 mod server;
+mod version;
 
-pub use server::ApiDoc;
 pub use server::WebServer;
+pub use version::ApiVersion;
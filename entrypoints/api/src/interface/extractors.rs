@@ -1,31 +1,53 @@
+use std::{
+    convert::Infallible,
+    marker::PhantomData,
+    net::{IpAddr, SocketAddr},
+};
+
 use axum::{
     Json, RequestPartsExt as _,
-    extract::{FromRequest, FromRequestParts, Request, rejection::JsonRejection},
-    http::request::Parts,
+    extract::{ConnectInfo, FromRequest, FromRequestParts, Request, rejection::JsonRejection},
+    http::{Extensions, HeaderMap, header, request::Parts},
 };
 use axum_extra::{
     TypedHeader,
     headers::{Authorization, authorization::Bearer},
 };
+use lambda_http::request::RequestContext;
+use ring::digest;
 use serde::de::DeserializeOwned;
 use validator::Validate;
 
-use omnius_opxs_auth::{crypto::jwt, model::User};
+use omnius_opxs_auth::{
+    api_key::ApiKeyService,
+    crypto::jwt,
+    model::{ApiKeyScope, User},
+    permission::PermissionMarker,
+};
 
-use crate::{prelude::*, shared::state::AppState};
+use crate::{error::ApiErrorDetail, prelude::*, shared::state::AppState};
 
 impl FromRequestParts<AppState> for User {
     type Rejection = ApiErrorCode;
 
     async fn from_request_parts(parts: &mut Parts, state: &AppState) -> std::result::Result<Self, Self::Rejection> {
+        if let Some(api_key) = parts.headers.get(API_KEY_HEADER) {
+            let api_key = api_key.to_str().map_err(|_| ApiErrorCode::Unauthorized)?.to_string();
+            return authenticate_api_key(parts, state, &api_key).await;
+        }
+
         let TypedHeader(Authorization(bearer)) = match parts.extract::<TypedHeader<Authorization<Bearer>>>().await {
             Ok(v) => v,
             Err(e) => {
-                warn!(error = ?e);
-                return Err(ApiErrorCode::InternalServerError);
+                debug!(error = ?e);
+                return Err(ApiErrorCode::Unauthorized);
             }
         };
 
+        if ApiKeyService::is_api_key(bearer.token()) {
+            return authenticate_api_key(parts, state, bearer.token()).await;
+        }
+
         let access_token = bearer.token();
         let now = state.service.clock.now();
         let claims = match jwt::verify(&state.conf.auth.jwt.secret.current, access_token, now) {
@@ -49,6 +71,81 @@ impl FromRequestParts<AppState> for User {
     }
 }
 
+pub(crate) const API_KEY_HEADER: &str = "x-api-key";
+
+// Identifies the caller for middleware keys; only the signature is checked, the account is resolved later by the handler.
+pub(crate) fn peek_subject(state: &AppState, headers: &HeaderMap) -> Option<String> {
+    let token = headers
+        .get(API_KEY_HEADER)
+        .or_else(|| headers.get(header::AUTHORIZATION))
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.strip_prefix("Bearer ").unwrap_or(v).trim())?;
+
+    if ApiKeyService::is_api_key(token) {
+        let hash = hex::encode(digest::digest(&digest::SHA256, token.as_bytes()));
+        return Some(format!("api_key:{}", &hash[..32]));
+    }
+
+    let now = state.service.clock.now();
+    jwt::verify(&state.conf.auth.jwt.secret.current, token, now).ok().map(|v| v.sub)
+}
+
+// Routers opt in to API key access by attaching the scope they require as an extension.
+async fn authenticate_api_key(parts: &Parts, state: &AppState, secret: &str) -> std::result::Result<User, ApiErrorCode> {
+    let api_key = match state.service.api_key.authenticate(secret).await {
+        Ok(v) => v,
+        Err(e) => {
+            warn!(error = ?e);
+            return Err(ApiErrorCode::Unauthorized);
+        }
+    };
+
+    let Some(scope) = parts.extensions.get::<ApiKeyScope>() else {
+        warn!(api_key_id = api_key.id, "api key is not accepted on this route");
+        return Err(ApiErrorCode::Forbidden);
+    };
+    if !api_key.scopes.contains(scope) {
+        warn!(api_key_id = api_key.id, required = ?scope, "api key scope check failed");
+        return Err(ApiErrorCode::Forbidden);
+    }
+
+    match state.service.user.get_user(&api_key.user_id).await {
+        Ok(v) => Ok(v),
+        Err(e) => {
+            warn!(error = ?e);
+            Err(ApiErrorCode::Unauthorized)
+        }
+    }
+}
+
+pub struct RequirePermission<P> {
+    pub user: User,
+    _marker: PhantomData<P>,
+}
+
+impl<P> FromRequestParts<AppState> for RequirePermission<P>
+where
+    P: PermissionMarker + Send + Sync,
+{
+    type Rejection = ApiErrorCode;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> std::result::Result<Self, Self::Rejection> {
+        let user = User::from_request_parts(parts, state).await?;
+        Self::authorize(user)
+    }
+}
+
+impl<P: PermissionMarker> RequirePermission<P> {
+    fn authorize(user: User) -> std::result::Result<Self, ApiErrorCode> {
+        if !user.role.has_permission(P::PERMISSION) {
+            warn!(user_id = user.id, role = ?user.role, required = ?P::PERMISSION, "permission check failed");
+            return Err(ApiErrorCode::Forbidden);
+        }
+
+        Ok(Self { user, _marker: PhantomData })
+    }
+}
+
 // https://github.com/tokio-rs/axum/blob/main/examples/validator/src/main.rs
 #[derive(Debug, Clone, Copy, Default)]
 pub struct ValidatedJson<T>(pub T);
@@ -59,21 +156,156 @@ where
     T: DeserializeOwned + Validate,
     Json<T>: FromRequest<S, Rejection = JsonRejection>,
 {
-    type Rejection = ApiErrorCode;
+    type Rejection = ApiErrorMessage;
 
     async fn from_request(req: Request, state: &S) -> std::result::Result<Self, Self::Rejection> {
         let Json(value) = match Json::<T>::from_request(req, state).await {
             Ok(v) => v,
+            Err(JsonRejection::JsonDataError(e)) => {
+                debug!(error = ?e);
+                return Err(ApiErrorMessage::new(
+                    ApiErrorCode::ValidationFailed,
+                    vec![ApiErrorDetail {
+                        field: "body".to_string(),
+                        code: "invalid_data".to_string(),
+                        message: Some(e.body_text()),
+                    }],
+                ));
+            }
             Err(e) => {
-                warn!(error = ?e);
-                return Err(ApiErrorCode::InternalServerError);
+                debug!(error = ?e);
+                return Err(ApiErrorCode::InvalidRequest.into());
             }
         };
         if let Err(e) = value.validate() {
-            warn!(error = ?e);
-            return Err(ApiErrorCode::InvalidRequest);
+            debug!(error = ?e);
+            return Err(ApiErrorMessage::validation(&e));
         }
 
         Ok(ValidatedJson(value))
     }
 }
+
+/// Address of the caller, used to key rate limits and recorded with login attempts.
+/// In Lambda mode it is the source IP reported by API Gateway. Otherwise it is the connecting peer, or, when that peer
+/// is one of `server.trusted_proxies`, the right-most `X-Forwarded-For` hop not added by a trusted proxy.
+/// The left part of `X-Forwarded-For` is written by the client and is never used, as it can be spoofed freely.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientIp(pub String);
+
+impl ClientIp {
+    pub(crate) fn resolve(headers: &HeaderMap, extensions: &Extensions, trusted_proxies: &[IpAddr]) -> Self {
+        if let Some(ip) = extensions.get::<RequestContext>().and_then(lambda_source_ip) {
+            return ClientIp(ip);
+        }
+
+        let Some(ConnectInfo(peer)) = extensions.get::<ConnectInfo<SocketAddr>>() else {
+            return ClientIp("unknown".to_string());
+        };
+        let mut ip = peer.ip();
+        if !trusted_proxies.contains(&ip) {
+            return ClientIp(ip.to_string());
+        }
+
+        let hops = headers
+            .get_all("x-forwarded-for")
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .map(|v| v.trim().parse::<IpAddr>().ok())
+            .collect::<Vec<_>>();
+        for hop in hops.into_iter().rev() {
+            // A malformed hop may be client-written, so the last trusted address is kept instead.
+            let Some(hop) = hop else {
+                break;
+            };
+            ip = hop;
+            if !trusted_proxies.contains(&hop) {
+                break;
+            }
+        }
+
+        ClientIp(ip.to_string())
+    }
+}
+
+fn lambda_source_ip(context: &RequestContext) -> Option<String> {
+    match context {
+        RequestContext::ApiGatewayV2(n) => n.http.source_ip.clone(),
+        RequestContext::ApiGatewayV1(n) => n.identity.source_ip.clone(),
+        RequestContext::WebSocket(n) => n.identity.source_ip.clone(),
+        _ => None,
+    }
+}
+
+impl FromRequestParts<AppState> for ClientIp {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> std::result::Result<Self, Self::Rejection> {
+        Ok(Self::resolve(&parts.headers, &parts.extensions, &state.conf.server.trusted_proxies))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        http::{HeaderValue, StatusCode},
+        response::IntoResponse as _,
+    };
+    use chrono::Utc;
+
+    use omnius_opxs_auth::{
+        model::{UserLocale, UserRole},
+        permission::markers::RolesWrite,
+    };
+
+    use super::*;
+
+    #[test]
+    fn require_permission_test() {
+        let user = |role| User {
+            id: "user_id".to_string(),
+            name: "user_name".to_string(),
+            role,
+            locale: UserLocale::Ja,
+            created_at: Utc::now().naive_utc(),
+            updated_at: Utc::now().naive_utc(),
+        };
+
+        let res = RequirePermission::<RolesWrite>::authorize(user(UserRole::User))
+            .err()
+            .unwrap()
+            .into_response();
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+        let RequirePermission { user, .. } = RequirePermission::<RolesWrite>::authorize(user(UserRole::Admin)).ok().unwrap();
+        assert_eq!(user.role, UserRole::Admin);
+    }
+
+    #[test]
+    fn client_ip_test() {
+        let proxy: IpAddr = "10.0.0.1".parse().unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert("x-forwarded-for", HeaderValue::from_static("1.1.1.1, 2.2.2.2"));
+
+        // Without a connection the header alone is not believed.
+        assert_eq!(ClientIp::resolve(&headers, &Extensions::new(), &[proxy]).0, "unknown");
+
+        // An untrusted peer is the client, whatever it claims to forward for.
+        let mut extensions = Extensions::new();
+        extensions.insert(ConnectInfo(SocketAddr::from(([3, 3, 3, 3], 1234))));
+        assert_eq!(ClientIp::resolve(&headers, &extensions, &[proxy]).0, "3.3.3.3");
+
+        // A trusted proxy appends the address it saw; anything left of it was sent by the client.
+        let mut extensions = Extensions::new();
+        extensions.insert(ConnectInfo(SocketAddr::from(([10, 0, 0, 1], 1234))));
+        assert_eq!(ClientIp::resolve(&headers, &extensions, &[proxy]).0, "2.2.2.2");
+        assert_eq!(ClientIp::resolve(&headers, &extensions, &[]).0, "10.0.0.1");
+
+        headers.insert("x-forwarded-for", HeaderValue::from_static("1.1.1.1, 10.0.0.1"));
+        assert_eq!(ClientIp::resolve(&headers, &extensions, &[proxy]).0, "1.1.1.1");
+
+        headers.insert("x-forwarded-for", HeaderValue::from_static("garbage"));
+        assert_eq!(ClientIp::resolve(&headers, &extensions, &[proxy]).0, "10.0.0.1");
+    }
+}
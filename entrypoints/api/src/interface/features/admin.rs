@@ -0,0 +1,418 @@
+use axum::{
+    Json, Router,
+    extract::{Path, Query, State},
+    routing::{get, post, put},
+};
+use hyper::StatusCode;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use validator::Validate;
+
+use omnius_opxs_auth::{
+    model::{AdminAuditLog, AdminUser, AdminUserDetail, UserRole},
+    permission::markers::{JobsRead, JobsWrite, RolesWrite, UsersRead, UsersWrite},
+};
+use omnius_opxs_email_send::EmailSendJobBatchDetail;
+use omnius_opxs_file_convert::FileConvertJob;
+
+use crate::{
+    interface::{
+        extractors::{RequirePermission, ValidatedJson},
+        version::ApiVersion,
+    },
+    prelude::*,
+    shared::state::AppState,
+};
+
+const DEFAULT_LIMIT: i64 = 50;
+
+#[allow(unused)]
+pub fn gen_service(state: AppState, version: ApiVersion) -> Router {
+    Router::new()
+        .route("/users", get(search_users))
+        .route("/users/{user_id}", get(get_user))
+        .route("/users/{user_id}/jobs", get(get_user_jobs))
+        .route("/users/{user_id}/logout", post(force_logout))
+        .route("/users/{user_id}/disable", post(disable_user))
+        .route("/users/{user_id}/enable", post(enable_user))
+        .route("/users/{user_id}/role", put(update_role))
+        .route("/jobs/file-convert/failed", get(failed_file_convert_jobs))
+        .route("/jobs/file-convert/{job_id}/retry", post(retry_file_convert_job))
+        .route("/jobs/email-send/failed", get(failed_email_send_jobs))
+        .route("/jobs/email-send/{job_id}/retry", post(retry_email_send_job))
+        .route("/audit-logs", get(audit_logs))
+        .with_state(state)
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SearchUsersInput {
+    /// user id (exact match), name or email (partial match)
+    pub query: String,
+    pub limit: Option<i64>,
+}
+
+#[utoipa::path(
+    get,
+    tag = "admin",
+    operation_id = "adminSearchUsers",
+    path = "/api/v1/admin/users",
+    params(SearchUsersInput),
+    responses(
+        (status = 200, body = Vec<AdminUser>),
+        (status = 400, body = ApiErrorMessage),
+        (status = 403, body = ApiErrorMessage),
+        (status = 500, body = ApiErrorMessage)
+    ),
+    security(
+        ("bearer_token" = [])
+    )
+)]
+pub async fn search_users(
+    State(state): State<AppState>,
+    RequirePermission { user, .. }: RequirePermission<UsersRead>,
+    Query(input): Query<SearchUsersInput>,
+) -> ApiResult<Json<Vec<AdminUser>>> {
+    let users = state
+        .service
+        .admin
+        .search_users(&user, &input.query, input.limit.unwrap_or(DEFAULT_LIMIT))
+        .await?;
+
+    Ok(Json(users))
+}
+
+#[utoipa::path(
+    get,
+    tag = "admin",
+    operation_id = "adminGetUser",
+    path = "/api/v1/admin/users/{user_id}",
+    params(
+        ("user_id" = String, Path, description = "target user id")
+    ),
+    responses(
+        (status = 200, body = AdminUserDetail),
+        (status = 403, body = ApiErrorMessage),
+        (status = 404, body = ApiErrorMessage),
+        (status = 500, body = ApiErrorMessage)
+    ),
+    security(
+        ("bearer_token" = [])
+    )
+)]
+pub async fn get_user(
+    State(state): State<AppState>,
+    RequirePermission { user, .. }: RequirePermission<UsersRead>,
+    Path(user_id): Path<String>,
+) -> ApiResult<Json<AdminUserDetail>> {
+    let detail = state.service.admin.get_user_detail(&user, &user_id).await?;
+
+    Ok(Json(detail))
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct UserJobsOutput {
+    pub file_convert_jobs: Vec<FileConvertJob>,
+}
+
+#[utoipa::path(
+    get,
+    tag = "admin",
+    operation_id = "adminGetUserJobs",
+    path = "/api/v1/admin/users/{user_id}/jobs",
+    params(
+        ("user_id" = String, Path, description = "target user id")
+    ),
+    responses(
+        (status = 200, body = UserJobsOutput),
+        (status = 403, body = ApiErrorMessage),
+        (status = 500, body = ApiErrorMessage)
+    ),
+    security(
+        ("bearer_token" = [])
+    )
+)]
+pub async fn get_user_jobs(
+    State(state): State<AppState>,
+    RequirePermission { user, .. }: RequirePermission<JobsRead>,
+    Path(user_id): Path<String>,
+) -> ApiResult<Json<UserJobsOutput>> {
+    let file_convert_jobs = state.service.admin.get_user_jobs(&user, &user_id).await?;
+
+    Ok(Json(UserJobsOutput { file_convert_jobs }))
+}
+
+#[utoipa::path(
+    post,
+    tag = "admin",
+    operation_id = "adminForceLogout",
+    path = "/api/v1/admin/users/{user_id}/logout",
+    params(
+        ("user_id" = String, Path, description = "target user id")
+    ),
+    responses(
+        (status = 200),
+        (status = 403, body = ApiErrorMessage),
+        (status = 404, body = ApiErrorMessage),
+        (status = 500, body = ApiErrorMessage)
+    ),
+    security(
+        ("bearer_token" = [])
+    )
+)]
+pub async fn force_logout(
+    State(state): State<AppState>,
+    RequirePermission { user, .. }: RequirePermission<UsersWrite>,
+    Path(user_id): Path<String>,
+) -> ApiResult<StatusCode> {
+    state.service.admin.force_logout(&user, &user_id).await?;
+
+    Ok(StatusCode::OK)
+}
+
+#[utoipa::path(
+    post,
+    tag = "admin",
+    operation_id = "adminDisableUser",
+    path = "/api/v1/admin/users/{user_id}/disable",
+    params(
+        ("user_id" = String, Path, description = "target user id")
+    ),
+    responses(
+        (status = 200),
+        (status = 403, body = ApiErrorMessage),
+        (status = 404, body = ApiErrorMessage),
+        (status = 500, body = ApiErrorMessage)
+    ),
+    security(
+        ("bearer_token" = [])
+    )
+)]
+pub async fn disable_user(
+    State(state): State<AppState>,
+    RequirePermission { user, .. }: RequirePermission<UsersWrite>,
+    Path(user_id): Path<String>,
+) -> ApiResult<StatusCode> {
+    state.service.admin.disable_user(&user, &user_id).await?;
+
+    Ok(StatusCode::OK)
+}
+
+#[utoipa::path(
+    post,
+    tag = "admin",
+    operation_id = "adminEnableUser",
+    path = "/api/v1/admin/users/{user_id}/enable",
+    params(
+        ("user_id" = String, Path, description = "target user id")
+    ),
+    responses(
+        (status = 200),
+        (status = 403, body = ApiErrorMessage),
+        (status = 404, body = ApiErrorMessage),
+        (status = 500, body = ApiErrorMessage)
+    ),
+    security(
+        ("bearer_token" = [])
+    )
+)]
+pub async fn enable_user(
+    State(state): State<AppState>,
+    RequirePermission { user, .. }: RequirePermission<UsersWrite>,
+    Path(user_id): Path<String>,
+) -> ApiResult<StatusCode> {
+    state.service.admin.enable_user(&user, &user_id).await?;
+
+    Ok(StatusCode::OK)
+}
+
+#[utoipa::path(
+    put,
+    tag = "admin",
+    operation_id = "adminUpdateUserRole",
+    path = "/api/v1/admin/users/{user_id}/role",
+    params(
+        ("user_id" = String, Path, description = "target user id")
+    ),
+    request_body = UpdateRoleInput,
+    responses(
+        (status = 200),
+        (status = 403, body = ApiErrorMessage),
+        (status = 404, body = ApiErrorMessage),
+        (status = 500, body = ApiErrorMessage)
+    ),
+    security(
+        ("bearer_token" = [])
+    )
+)]
+pub async fn update_role(
+    State(state): State<AppState>,
+    RequirePermission { user, .. }: RequirePermission<RolesWrite>,
+    Path(user_id): Path<String>,
+    ValidatedJson(input): ValidatedJson<UpdateRoleInput>,
+) -> ApiResult<StatusCode> {
+    state.service.admin.update_role(&user, &user_id, &input.role).await?;
+
+    Ok(StatusCode::OK)
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct UpdateRoleInput {
+    pub role: UserRole,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListInput {
+    pub limit: Option<i64>,
+}
+
+#[utoipa::path(
+    get,
+    tag = "admin",
+    operation_id = "adminFailedFileConvertJobs",
+    path = "/api/v1/admin/jobs/file-convert/failed",
+    params(ListInput),
+    responses(
+        (status = 200, body = Vec<FileConvertJob>),
+        (status = 403, body = ApiErrorMessage),
+        (status = 500, body = ApiErrorMessage)
+    ),
+    security(
+        ("bearer_token" = [])
+    )
+)]
+pub async fn failed_file_convert_jobs(
+    State(state): State<AppState>,
+    RequirePermission { user, .. }: RequirePermission<JobsRead>,
+    Query(input): Query<ListInput>,
+) -> ApiResult<Json<Vec<FileConvertJob>>> {
+    let jobs = state
+        .service
+        .admin
+        .get_failed_file_convert_jobs(&user, input.limit.unwrap_or(DEFAULT_LIMIT))
+        .await?;
+
+    Ok(Json(jobs))
+}
+
+#[utoipa::path(
+    post,
+    tag = "admin",
+    operation_id = "adminRetryFileConvertJob",
+    path = "/api/v1/admin/jobs/file-convert/{job_id}/retry",
+    params(
+        ("job_id" = String, Path, description = "failed job id")
+    ),
+    responses(
+        (status = 200),
+        (status = 403, body = ApiErrorMessage),
+        (status = 404, body = ApiErrorMessage),
+        (status = 500, body = ApiErrorMessage)
+    ),
+    security(
+        ("bearer_token" = [])
+    )
+)]
+pub async fn retry_file_convert_job(
+    State(state): State<AppState>,
+    RequirePermission { user, .. }: RequirePermission<JobsWrite>,
+    Path(job_id): Path<String>,
+) -> ApiResult<StatusCode> {
+    state.service.admin.retry_file_convert_job(&user, &job_id).await?;
+
+    Ok(StatusCode::OK)
+}
+
+#[utoipa::path(
+    get,
+    tag = "admin",
+    operation_id = "adminFailedEmailSendJobs",
+    path = "/api/v1/admin/jobs/email-send/failed",
+    params(ListInput),
+    responses(
+        (status = 200, body = Vec<EmailSendJobBatchDetail>),
+        (status = 403, body = ApiErrorMessage),
+        (status = 500, body = ApiErrorMessage)
+    ),
+    security(
+        ("bearer_token" = [])
+    )
+)]
+pub async fn failed_email_send_jobs(
+    State(state): State<AppState>,
+    RequirePermission { user, .. }: RequirePermission<JobsRead>,
+    Query(input): Query<ListInput>,
+) -> ApiResult<Json<Vec<EmailSendJobBatchDetail>>> {
+    let details = state
+        .service
+        .admin
+        .get_failed_email_send_jobs(&user, input.limit.unwrap_or(DEFAULT_LIMIT))
+        .await?;
+
+    Ok(Json(details))
+}
+
+#[utoipa::path(
+    post,
+    tag = "admin",
+    operation_id = "adminRetryEmailSendJob",
+    path = "/api/v1/admin/jobs/email-send/{job_id}/retry",
+    params(
+        ("job_id" = String, Path, description = "failed job id")
+    ),
+    responses(
+        (status = 200),
+        (status = 403, body = ApiErrorMessage),
+        (status = 404, body = ApiErrorMessage),
+        (status = 500, body = ApiErrorMessage)
+    ),
+    security(
+        ("bearer_token" = [])
+    )
+)]
+pub async fn retry_email_send_job(
+    State(state): State<AppState>,
+    RequirePermission { user, .. }: RequirePermission<JobsWrite>,
+    Path(job_id): Path<String>,
+) -> ApiResult<StatusCode> {
+    state.service.admin.retry_email_send_job(&user, &job_id).await?;
+
+    Ok(StatusCode::OK)
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AuditLogsInput {
+    pub target_id: Option<String>,
+    pub limit: Option<i64>,
+}
+
+#[utoipa::path(
+    get,
+    tag = "admin",
+    operation_id = "adminAuditLogs",
+    path = "/api/v1/admin/audit-logs",
+    params(AuditLogsInput),
+    responses(
+        (status = 200, body = Vec<AdminAuditLog>),
+        (status = 403, body = ApiErrorMessage),
+        (status = 500, body = ApiErrorMessage)
+    ),
+    security(
+        ("bearer_token" = [])
+    )
+)]
+pub async fn audit_logs(
+    State(state): State<AppState>,
+    RequirePermission { user, .. }: RequirePermission<UsersRead>,
+    Query(input): Query<AuditLogsInput>,
+) -> ApiResult<Json<Vec<AdminAuditLog>>> {
+    let logs = state
+        .service
+        .admin
+        .get_audit_logs(&user, input.target_id.as_deref(), input.limit.unwrap_or(DEFAULT_LIMIT))
+        .await?;
+
+    Ok(Json(logs))
+}
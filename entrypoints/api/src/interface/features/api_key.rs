@@ -0,0 +1,106 @@
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    routing::{delete, get, post},
+};
+use chrono::NaiveDateTime;
+use hyper::StatusCode;
+use serde::Deserialize;
+use utoipa::ToSchema;
+use validator::Validate;
+
+use omnius_opxs_auth::model::{ApiKey, ApiKeyScope, IssuedApiKey, User};
+
+use crate::{
+    interface::{extractors::ValidatedJson, version::ApiVersion},
+    prelude::*,
+    shared::state::AppState,
+};
+
+// API keys cannot manage API keys: this router attaches no ApiKeyScope, so only access tokens are accepted.
+#[allow(unused)]
+pub fn gen_service(state: AppState, version: ApiVersion) -> Router {
+    Router::new()
+        .route("/", get(list))
+        .route("/", post(create))
+        .route("/{api_key_id}", delete(revoke))
+        .with_state(state)
+}
+
+#[utoipa::path(
+    get,
+    tag = "api-key",
+    operation_id = "apiKeyList",
+    path = "/api/v1/api-keys",
+    responses(
+        (status = 200, body = Vec<ApiKey>),
+        (status = 500, body = ApiErrorMessage)
+    ),
+    security(
+        ("bearer_token" = [])
+    )
+)]
+pub async fn list(State(state): State<AppState>, user: User) -> ApiResult<Json<Vec<ApiKey>>> {
+    let api_keys = state.service.api_key.get_keys(&user.id).await?;
+
+    Ok(Json(api_keys))
+}
+
+#[utoipa::path(
+    post,
+    tag = "api-key",
+    operation_id = "apiKeyCreate",
+    path = "/api/v1/api-keys",
+    request_body = CreateInput,
+    responses(
+        (status = 200, body = IssuedApiKey),
+        (status = 400, body = ApiErrorMessage),
+        (status = 422, body = ApiErrorMessage),
+        (status = 500, body = ApiErrorMessage)
+    ),
+    security(
+        ("bearer_token" = [])
+    )
+)]
+pub async fn create(State(state): State<AppState>, user: User, ValidatedJson(input): ValidatedJson<CreateInput>) -> ApiResult<Json<IssuedApiKey>> {
+    let expires_at = input.expires_at.map(|v| v.and_utc());
+    let issued = state
+        .service
+        .api_key
+        .create(&user.id, &input.name, &input.scopes, expires_at.as_ref())
+        .await?;
+
+    Ok(Json(issued))
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct CreateInput {
+    #[validate(length(min = 1, max = 255))]
+    pub name: String,
+    #[validate(length(min = 1))]
+    pub scopes: Vec<ApiKeyScope>,
+    pub expires_at: Option<NaiveDateTime>,
+}
+
+#[utoipa::path(
+    delete,
+    tag = "api-key",
+    operation_id = "apiKeyRevoke",
+    path = "/api/v1/api-keys/{api_key_id}",
+    params(
+        ("api_key_id" = String, Path, description = "api key id")
+    ),
+    responses(
+        (status = 200),
+        (status = 404, body = ApiErrorMessage),
+        (status = 500, body = ApiErrorMessage)
+    ),
+    security(
+        ("bearer_token" = [])
+    )
+)]
+pub async fn revoke(State(state): State<AppState>, user: User, Path(api_key_id): Path<String>) -> ApiResult<StatusCode> {
+    state.service.api_key.revoke(&user.id, &api_key_id).await?;
+
+    Ok(StatusCode::OK)
+}
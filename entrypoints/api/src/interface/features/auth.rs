@@ -1,28 +1,80 @@
 pub mod email;
 pub mod google;
+pub mod provider;
 
 use axum::{
-    Json, Router,
+    Extension, Json, Router,
     extract::State,
+    middleware,
     routing::{delete, get, post},
 };
+use chrono::{TimeZone as _, Utc};
 use hyper::StatusCode;
 use serde::Deserialize;
 use utoipa::ToSchema;
 use validator::Validate;
 
-use omnius_opxs_auth::model::{AuthToken, User};
+use omnius_opxs_auth::model::{ApiKeyScope, AuthToken, User, UserAuthMethod};
 
-use crate::{interface::extractors::ValidatedJson, prelude::*, shared::state::AppState};
+use crate::{
+    interface::{
+        extractors::ValidatedJson,
+        rate_limit::{RateLimiter, rate_limit},
+        version::{ApiVersion, Deprecation, deprecated},
+    },
+    prelude::*,
+    shared::state::AppState,
+};
+
+/// Documented paths of the v1 token endpoints. v2 serves them only at `/api/v2/auth/token`.
+pub(crate) const TOKEN_V1_DEPRECATED_PATHS: [&str; 2] = ["/api/v1/auth/token", "/api/v1/token"];
+
+fn token_v1_deprecation() -> Deprecation {
+    Deprecation {
+        deprecated_at: Utc.with_ymd_and_hms(2026, 11, 1, 0, 0, 0).unwrap(),
+        sunset_at: Utc.with_ymd_and_hms(2027, 5, 1, 0, 0, 0).unwrap(),
+        successor: Some("/api/v2/auth/token"),
+    }
+}
 
 #[allow(unused)]
-pub fn gen_service(state: AppState) -> Router {
-    Router::new()
+pub fn gen_service(state: AppState, version: ApiVersion) -> Router {
+    let token_service = Router::new().route("/token", post(token_refresh)).route("/token", delete(token_delete));
+    let token_service = match version {
+        ApiVersion::V1 => token_service.layer(middleware::from_fn_with_state(token_v1_deprecation(), deprecated)),
+        ApiVersion::V2 => token_service,
+    };
+
+    // API keys can read the account but never obtain a session or change how it is signed in to,
+    // so a leaked key cannot be turned into a takeover. Those routes require a session token.
+    let account_service = Router::new()
         .route("/me", get(me))
-        .route("/token", post(token_refresh))
-        .route("/token", delete(token_delete))
-        .nest_service("/email", email::gen_service(state.clone()))
-        .nest_service("/google", google::gen_service(state.clone()))
+        .route("/methods", get(methods))
+        .layer(Extension(ApiKeyScope::Account));
+
+    Router::new()
+        .merge(account_service)
+        .merge(token_service)
+        .nest("/email", email::gen_service(state.clone()))
+        .nest("/google", google::gen_service(state.clone()))
+        .route("/{provider}/nonce", get(provider::nonce))
+        .route("/{provider}/register", post(provider::register))
+        .route("/{provider}/login", post(provider::login))
+        .route("/{provider}/unregister", post(provider::unregister))
+        .route("/{provider}/link", post(provider::link))
+        .route("/{provider}/link", delete(provider::unlink))
+        .layer(middleware::from_fn_with_state(RateLimiter::auth(state.clone()), rate_limit))
+        .with_state(state)
+}
+
+/// The refresh endpoint was documented at `/api/v1/token` while being routed at `/api/v1/auth/token`.
+/// v1 serves both paths so that clients generated from either keep working until the sunset.
+#[allow(unused)]
+pub fn gen_legacy_service(state: AppState) -> Router {
+    Router::new()
+        .route("/token", post(token_refresh_legacy))
+        .layer(middleware::from_fn_with_state(token_v1_deprecation(), deprecated))
+        .layer(middleware::from_fn_with_state(RateLimiter::auth(state.clone()), rate_limit))
         .with_state(state)
 }
 
@@ -32,7 +84,7 @@ pub fn gen_service(state: AppState) -> Router {
     operation_id = "authMe",
     path = "/api/v1/auth/me",
     responses(
-        (status = 200),
+        (status = 200, body = User),
         (status = 500, body = ApiErrorMessage)
     ),
     security(
@@ -43,29 +95,71 @@ pub async fn me(user: User) -> ApiResult<Json<User>> {
     Ok(Json(user))
 }
 
+#[utoipa::path(
+    get,
+    tag = "auth",
+    operation_id = "authMethods",
+    path = "/api/v1/auth/methods",
+    responses(
+        (status = 200, body = Vec<UserAuthMethod>),
+        (status = 500, body = ApiErrorMessage)
+    ),
+    security(
+        ("bearer_token" = [])
+    )
+)]
+pub async fn methods(State(state): State<AppState>, user: User) -> ApiResult<Json<Vec<UserAuthMethod>>> {
+    let methods = state.service.account_link.get_auth_methods(&user.id).await?;
+
+    Ok(Json(methods))
+}
+
+async fn detach_provider(state: &AppState, user: &User, provider_type: &str) -> ApiResult<StatusCode> {
+    state.service.account_link.detach_provider(&user.id, provider_type).await?;
+
+    Ok(StatusCode::OK)
+}
+
 #[utoipa::path(
     post,
     tag = "auth",
     operation_id = "authTokenRefresh",
-    path = "/api/v1/token",
+    path = "/api/v1/auth/token",
     request_body = RefreshInput,
     responses(
         (status = 200, body = AuthToken),
+        (status = 401, body = ApiErrorMessage),
+        (status = 422, body = ApiErrorMessage),
         (status = 500, body = ApiErrorMessage)
     )
 )]
 pub async fn token_refresh(State(state): State<AppState>, ValidatedJson(input): ValidatedJson<RefreshInput>) -> ApiResult<Json<AuthToken>> {
     let auth_token = match state.service.token.refresh(&input.refresh_token).await {
         Ok(v) => v,
-        Err(e) => {
-            warn!(error = ?e);
-            return Err(ApiErrorCode::InternalServerError);
-        }
+        Err(e) if *e.kind() == omnius_opxs_auth::ErrorKind::NotFound => return Err(ApiErrorCode::Unauthorized),
+        Err(e) => return Err(e.into()),
     };
 
     Ok(Json(auth_token))
 }
 
+#[utoipa::path(
+    post,
+    tag = "auth",
+    operation_id = "authTokenRefreshLegacy",
+    path = "/api/v1/token",
+    request_body = RefreshInput,
+    responses(
+        (status = 200, body = AuthToken),
+        (status = 401, body = ApiErrorMessage),
+        (status = 422, body = ApiErrorMessage),
+        (status = 500, body = ApiErrorMessage)
+    )
+)]
+pub async fn token_refresh_legacy(state: State<AppState>, input: ValidatedJson<RefreshInput>) -> ApiResult<Json<AuthToken>> {
+    token_refresh(state, input).await
+}
+
 #[derive(Deserialize, ToSchema, Validate)]
 pub struct RefreshInput {
     pub refresh_token: String,
@@ -85,10 +179,63 @@ pub struct RefreshInput {
     )
 )]
 pub async fn token_delete(State(state): State<AppState>, user: User) -> ApiResult<StatusCode> {
-    if let Err(e) = state.service.token.delete(user.id.as_str()).await {
-        warn!(error = ?e);
-        return Err(ApiErrorCode::InternalServerError);
-    }
+    state.service.token.delete(user.id.as_str()).await?;
 
     Ok(StatusCode::OK)
 }
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        body::Body,
+        http::{Method, Request, header},
+    };
+    use serial_test::serial;
+    use testresult::TestResult;
+    use tower::ServiceExt as _;
+
+    use crate::shared::testkit;
+
+    use super::*;
+
+    #[tokio::test]
+    #[serial]
+    async fn api_key_scope_test() -> TestResult {
+        let (_container, state) = testkit::gen_state().await?;
+        testkit::create_user(&state, "user_id").await?;
+        let issued = state.service.api_key.create("user_id", "key", &[ApiKeyScope::Account], None).await?;
+
+        let app = Router::new().nest("/auth", gen_service(state, ApiVersion::V2));
+        let send = |method: Method, uri: &str| {
+            let req = Request::builder()
+                .method(method)
+                .uri(uri)
+                .header(header::AUTHORIZATION, format!("Bearer {}", issued.secret))
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from("{}"));
+            let app = app.clone();
+            async move { TestResult::<StatusCode>::Ok(app.oneshot(req?).await?.status()) }
+        };
+
+        assert_eq!(send(Method::GET, "/auth/me").await?, StatusCode::OK);
+
+        // Routes that change how the account is signed in to require a session token.
+        for (method, uri) in [
+            (Method::POST, "/auth/email/unregister"),
+            (Method::POST, "/auth/email/link"),
+            (Method::DELETE, "/auth/email/link"),
+            (Method::PUT, "/auth/email/password"),
+            (Method::POST, "/auth/email/change"),
+            (Method::POST, "/auth/google/unregister"),
+            (Method::POST, "/auth/google/link"),
+            (Method::DELETE, "/auth/google/link"),
+            (Method::POST, "/auth/github/unregister"),
+            (Method::POST, "/auth/github/link"),
+            (Method::DELETE, "/auth/github/link"),
+        ] {
+            assert_eq!(send(method.clone(), uri).await?, StatusCode::FORBIDDEN, "{method} {uri}");
+        }
+
+        Ok(())
+    }
+}
@@ -1,21 +1,48 @@
-use axum::{Json, Router, extract::State, routing::post};
+use axum::{
+    Json, Router,
+    extract::State,
+    middleware,
+    routing::{delete, post, put},
+};
 use hyper::StatusCode;
 use serde::Deserialize;
 use url::Url;
 use utoipa::ToSchema;
 use validator::Validate;
 
-use omnius_opxs_auth::model::{AuthToken, User};
+use omnius_opxs_auth::model::{AuthToken, User, UserLocale};
 
-use crate::{interface::extractors::ValidatedJson, prelude::*, shared::state::AppState};
+use crate::{
+    interface::{
+        extractors::{ClientIp, ValidatedJson},
+        idempotency::idempotency,
+        rate_limit::{RateLimiter, rate_limit},
+    },
+    prelude::*,
+    service::mailer::to_email_locale,
+    shared::state::AppState,
+};
 
 #[allow(unused)]
 pub fn gen_service(state: AppState) -> Router {
     Router::new()
-        .route("/register", post(register))
+        .route(
+            "/register",
+            post(register).layer(middleware::from_fn_with_state(state.clone(), idempotency)),
+        )
         .route("/confirm", post(confirm))
-        .route("/unregister", post(unregister))
+        .route(
+            "/resend",
+            post(resend).layer(middleware::from_fn_with_state(RateLimiter::email_resend(state.clone()), rate_limit)),
+        )
         .route("/login", post(login))
+        .route("/unlock", post(unlock))
+        .route("/change/confirm", post(change_confirm))
+        .route("/unregister", post(unregister))
+        .route("/link", post(link))
+        .route("/link", delete(unlink))
+        .route("/password", put(change_password))
+        .route("/change", post(change))
         .with_state(state)
 }
 
@@ -25,51 +52,63 @@ pub fn gen_service(state: AppState) -> Router {
     operation_id = "authEmailRegister",
     path = "/api/v1/auth/email/register",
     request_body = RegisterInput,
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "replays the stored response when a request is retried with the same key")
+    ),
     responses(
         (status = 200),
+        (status = 400, body = ApiErrorMessage),
+        (status = 409, body = ApiErrorMessage),
+        (status = 422, body = ApiErrorMessage),
+        (status = 429, body = ApiErrorMessage),
         (status = 500, body = ApiErrorMessage)
     )
 )]
-pub async fn register(State(state): State<AppState>, ValidatedJson(input): ValidatedJson<RegisterInput>) -> ApiResult<StatusCode> {
-    let token = match state.service.email_auth.register(&input.name, &input.email, &input.password).await {
+pub async fn register(
+    State(state): State<AppState>,
+    ValidatedJson(input): ValidatedJson<RegisterInput>,
+) -> std::result::Result<StatusCode, ApiErrorMessage> {
+    let token = match state
+        .service
+        .email_auth
+        .register(&input.name, &input.email, &input.password, &input.locale)
+        .await
+    {
         Ok(v) => v,
         Err(e) if *e.kind() == omnius_opxs_auth::ErrorKind::Duplicated => return Ok(StatusCode::OK),
-        Err(e) => {
-            warn!(error = ?e);
-            return Err(ApiErrorCode::InternalServerError);
-        }
+        Err(e) if *e.kind() == omnius_opxs_auth::ErrorKind::WeakPassword => return Err(ApiErrorMessage::weak_password("password", &e)),
+        Err(e) => return Err(e.into()),
     };
 
-    let email_confirm_url = match Url::parse_with_params(
+    send_email_confirm(&state, &input.name, &input.email, &token, &input.locale).await?;
+
+    Ok(StatusCode::OK)
+}
+
+async fn send_email_confirm(state: &AppState, user_name: &str, email: &str, token: &str, locale: &UserLocale) -> ApiResult<()> {
+    let email_confirm_url = Url::parse_with_params(
         format!("{}auth/register/email/confirm", state.conf.web.origin.as_str()).as_str(),
         &[("token", token)],
-    ) {
-        Ok(v) => v.to_string(),
-        Err(e) => {
-            warn!(error = ?e);
-            return Err(ApiErrorCode::InternalServerError);
-        }
-    };
+    )
+    .map_err(Error::from)?
+    .to_string();
 
     let job_id = state.service.tsid_provider.lock().create().to_string();
 
-    if let Err(e) = state
+    state
         .service
         .email_send_job_creator
-        .create_job(
+        .create_email_confirm_job(
             &job_id,
-            &input.name,
-            &input.email,
+            user_name,
+            email,
             &state.conf.email.from_email_address,
             &email_confirm_url,
+            &to_email_locale(locale),
         )
-        .await
-    {
-        warn!(error = ?e);
-        return Err(ApiErrorCode::InternalServerError);
-    }
+        .await?;
 
-    Ok(StatusCode::OK)
+    Ok(())
 }
 
 #[derive(Deserialize, ToSchema, Validate)]
@@ -78,8 +117,10 @@ pub struct RegisterInput {
     pub name: String,
     #[validate(email)]
     pub email: String,
-    #[validate(length(min = 4))]
+    #[validate(length(min = 1, max = 256))]
     pub password: String,
+    #[serde(default)]
+    pub locale: UserLocale,
 }
 
 #[utoipa::path(
@@ -87,28 +128,19 @@ pub struct RegisterInput {
     tag = "auth",
     operation_id = "authEmailConfirm",
     path = "/api/v1/auth/email/confirm",
-    request_body = RegisterInput,
+    request_body = ConfirmInput,
     responses(
-        (status = 200),
+        (status = 200, body = AuthToken),
+        (status = 400, body = ApiErrorMessage),
+        (status = 403, body = ApiErrorMessage),
+        (status = 422, body = ApiErrorMessage),
         (status = 500, body = ApiErrorMessage)
     )
 )]
 pub async fn confirm(State(state): State<AppState>, ValidatedJson(input): ValidatedJson<ConfirmInput>) -> ApiResult<Json<AuthToken>> {
-    let user_id = match state.service.email_auth.confirm(&input.token).await {
-        Ok(v) => v,
-        Err(e) => {
-            warn!(error = ?e);
-            return Err(ApiErrorCode::InternalServerError);
-        }
-    };
+    let user_id = state.service.email_auth.confirm(&input.token).await?;
 
-    let auth_token = match state.service.token.create(&user_id).await {
-        Ok(v) => v,
-        Err(e) => {
-            warn!(error = ?e);
-            return Err(ApiErrorCode::InternalServerError);
-        }
-    };
+    let auth_token = state.service.token.create(&user_id).await?;
 
     Ok(Json(auth_token))
 }
@@ -117,12 +149,12 @@ pub async fn confirm(State(state): State<AppState>, ValidatedJson(input): Valida
 pub struct ConfirmInput {
     pub token: String,
 }
+
 #[utoipa::path(
     post,
     tag = "auth",
     operation_id = "authEmailUnregister",
     path = "/api/v1/auth/email/unregister",
-    request_body = RegisterInput,
     responses(
         (status = 200),
         (status = 500, body = ApiErrorMessage)
@@ -132,10 +164,7 @@ pub struct ConfirmInput {
     )
 )]
 pub async fn unregister(State(state): State<AppState>, user: User) -> ApiResult<StatusCode> {
-    if let Err(e) = state.service.email_auth.unregister(user.id.as_str()).await {
-        warn!(error = ?e);
-        return Err(ApiErrorCode::InternalServerError);
-    }
+    state.service.email_auth.unregister(user.id.as_str()).await?;
 
     Ok(StatusCode::OK)
 }
@@ -148,25 +177,29 @@ pub async fn unregister(State(state): State<AppState>, user: User) -> ApiResult<
     request_body = LoginInput,
     responses(
         (status = 200, body = AuthToken),
+        (status = 403, body = ApiErrorMessage),
+        (status = 422, body = ApiErrorMessage),
+        (status = 423, body = ApiErrorMessage),
+        (status = 429, body = ApiErrorMessage),
         (status = 500, body = ApiErrorMessage)
     )
 )]
-async fn login(State(state): State<AppState>, ValidatedJson(input): ValidatedJson<LoginInput>) -> ApiResult<Json<AuthToken>> {
-    let user_id = match state.service.email_auth.login(&input.email, &input.password).await {
+async fn login(
+    State(state): State<AppState>,
+    ClientIp(ip_address): ClientIp,
+    ValidatedJson(input): ValidatedJson<LoginInput>,
+) -> ApiResult<Json<AuthToken>> {
+    // Unknown accounts and wrong passwords are reported identically.
+    let user_id = match state.service.email_auth.login(&input.email, &input.password, &ip_address).await {
         Ok(v) => v,
-        Err(e) => {
+        Err(e) if *e.kind() == omnius_opxs_auth::ErrorKind::NotFound => {
             warn!(error = ?e);
             return Err(ApiErrorCode::Unauthorized);
         }
+        Err(e) => return Err(e.into()),
     };
 
-    let auth_token = match state.service.token.create(&user_id).await {
-        Ok(v) => v,
-        Err(e) => {
-            warn!(error = ?e);
-            return Err(ApiErrorCode::InternalServerError);
-        }
-    };
+    let auth_token = state.service.token.create(&user_id).await?;
 
     Ok(Json(auth_token))
 }
@@ -178,3 +211,232 @@ pub struct LoginInput {
     #[validate(length(min = 8))]
     pub password: String,
 }
+
+#[utoipa::path(
+    post,
+    tag = "auth",
+    operation_id = "authEmailResend",
+    path = "/api/v1/auth/email/resend",
+    request_body = ResendInput,
+    responses(
+        (status = 200),
+        (status = 429, body = ApiErrorMessage),
+        (status = 500, body = ApiErrorMessage)
+    )
+)]
+pub async fn resend(State(state): State<AppState>, ValidatedJson(input): ValidatedJson<ResendInput>) -> ApiResult<StatusCode> {
+    if RateLimiter::email_resend(state.clone())
+        .check_email(&input.email)
+        .await
+        .is_some_and(|v| !v.allowed)
+    {
+        return Err(ApiErrorCode::RateLimited);
+    }
+
+    // Unknown, already verified and recently sent addresses get the same response as a successful resend.
+    let (user, token) = match state.service.email_auth.resend_confirm(&input.email).await {
+        Ok(Some(v)) => v,
+        Ok(None) => return Ok(StatusCode::OK),
+        Err(e) => return Err(e.into()),
+    };
+
+    send_email_confirm(&state, &user.name, &input.email, &token, &user.locale).await?;
+
+    Ok(StatusCode::OK)
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct ResendInput {
+    #[validate(email)]
+    pub email: String,
+}
+
+#[utoipa::path(
+    post,
+    tag = "auth",
+    operation_id = "authEmailUnlock",
+    path = "/api/v1/auth/email/unlock",
+    request_body = UnlockInput,
+    responses(
+        (status = 200),
+        (status = 422, body = ApiErrorMessage),
+        (status = 500, body = ApiErrorMessage)
+    )
+)]
+pub async fn unlock(State(state): State<AppState>, ValidatedJson(input): ValidatedJson<UnlockInput>) -> ApiResult<StatusCode> {
+    state.service.email_auth.unlock(&input.token).await?;
+
+    Ok(StatusCode::OK)
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct UnlockInput {
+    pub token: String,
+}
+
+#[utoipa::path(
+    post,
+    tag = "auth",
+    operation_id = "authEmailLink",
+    path = "/api/v1/auth/email/link",
+    request_body = LinkInput,
+    responses(
+        (status = 200),
+        (status = 400, body = ApiErrorMessage),
+        (status = 409, body = ApiErrorMessage),
+        (status = 422, body = ApiErrorMessage),
+        (status = 500, body = ApiErrorMessage)
+    ),
+    security(
+        ("bearer_token" = [])
+    )
+)]
+pub async fn link(
+    State(state): State<AppState>,
+    user: User,
+    ValidatedJson(input): ValidatedJson<LinkInput>,
+) -> std::result::Result<StatusCode, ApiErrorMessage> {
+    let token = match state.service.email_auth.attach(&user.id, &user.name, &input.email, &input.password).await {
+        Ok(v) => v,
+        Err(e) if *e.kind() == omnius_opxs_auth::ErrorKind::WeakPassword => return Err(ApiErrorMessage::weak_password("password", &e)),
+        Err(e) => return Err(e.into()),
+    };
+
+    send_email_confirm(&state, &user.name, &input.email, &token, &user.locale).await?;
+
+    Ok(StatusCode::OK)
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct LinkInput {
+    #[validate(email)]
+    pub email: String,
+    #[validate(length(min = 1, max = 256))]
+    pub password: String,
+}
+
+#[utoipa::path(
+    delete,
+    tag = "auth",
+    operation_id = "authEmailUnlink",
+    path = "/api/v1/auth/email/link",
+    responses(
+        (status = 200),
+        (status = 404, body = ApiErrorMessage),
+        (status = 409, body = ApiErrorMessage),
+        (status = 500, body = ApiErrorMessage)
+    ),
+    security(
+        ("bearer_token" = [])
+    )
+)]
+pub async fn unlink(State(state): State<AppState>, user: User) -> ApiResult<StatusCode> {
+    state.service.account_link.detach_email(&user.id).await?;
+
+    Ok(StatusCode::OK)
+}
+
+#[utoipa::path(
+    put,
+    tag = "auth",
+    operation_id = "authEmailChangePassword",
+    path = "/api/v1/auth/email/password",
+    request_body = ChangePasswordInput,
+    responses(
+        (status = 200),
+        (status = 400, body = ApiErrorMessage),
+        (status = 401, body = ApiErrorMessage),
+        (status = 404, body = ApiErrorMessage),
+        (status = 422, body = ApiErrorMessage),
+        (status = 500, body = ApiErrorMessage)
+    ),
+    security(
+        ("bearer_token" = [])
+    )
+)]
+pub async fn change_password(
+    State(state): State<AppState>,
+    user: User,
+    ValidatedJson(input): ValidatedJson<ChangePasswordInput>,
+) -> std::result::Result<StatusCode, ApiErrorMessage> {
+    match state
+        .service
+        .email_auth
+        .change_password(&user.id, &input.current_password, &input.new_password)
+        .await
+    {
+        Ok(_) => Ok(StatusCode::OK),
+        Err(e) if *e.kind() == omnius_opxs_auth::ErrorKind::WeakPassword => Err(ApiErrorMessage::weak_password("new_password", &e)),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct ChangePasswordInput {
+    #[validate(length(min = 1, max = 256))]
+    pub current_password: String,
+    #[validate(length(min = 1, max = 256))]
+    pub new_password: String,
+}
+
+#[utoipa::path(
+    post,
+    tag = "auth",
+    operation_id = "authEmailChange",
+    path = "/api/v1/auth/email/change",
+    request_body = ChangeInput,
+    responses(
+        (status = 200),
+        (status = 401, body = ApiErrorMessage),
+        (status = 404, body = ApiErrorMessage),
+        (status = 409, body = ApiErrorMessage),
+        (status = 422, body = ApiErrorMessage),
+        (status = 500, body = ApiErrorMessage)
+    ),
+    security(
+        ("bearer_token" = [])
+    )
+)]
+pub async fn change(State(state): State<AppState>, user: User, ValidatedJson(input): ValidatedJson<ChangeInput>) -> ApiResult<StatusCode> {
+    state
+        .service
+        .email_auth
+        .request_email_change(&user.id, &input.password, &input.new_email)
+        .await?;
+
+    Ok(StatusCode::OK)
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct ChangeInput {
+    #[validate(email)]
+    pub new_email: String,
+    #[validate(length(min = 1, max = 256))]
+    pub password: String,
+}
+
+#[utoipa::path(
+    post,
+    tag = "auth",
+    operation_id = "authEmailChangeConfirm",
+    path = "/api/v1/auth/email/change/confirm",
+    request_body = ChangeConfirmInput,
+    responses(
+        (status = 200),
+        (status = 404, body = ApiErrorMessage),
+        (status = 409, body = ApiErrorMessage),
+        (status = 422, body = ApiErrorMessage),
+        (status = 500, body = ApiErrorMessage)
+    )
+)]
+pub async fn change_confirm(State(state): State<AppState>, ValidatedJson(input): ValidatedJson<ChangeConfirmInput>) -> ApiResult<StatusCode> {
+    state.service.email_auth.confirm_email_change(&input.token).await?;
+
+    Ok(StatusCode::OK)
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct ChangeConfirmInput {
+    #[validate(length(min = 1))]
+    pub token: String,
+}
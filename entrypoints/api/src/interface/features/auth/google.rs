@@ -1,14 +1,13 @@
 use axum::{
     Json, Router,
     extract::State,
-    routing::{get, post},
+    routing::{delete, get, post},
 };
 use axum_extra::extract::cookie::{Cookie, SignedCookieJar};
 use hyper::StatusCode;
 use serde::{Deserialize, Serialize};
 use tracing::error;
 use utoipa::ToSchema;
-use uuid::Uuid;
 
 use omnius_opxs_auth::model::{AuthToken, User};
 
@@ -21,6 +20,8 @@ pub fn gen_service(state: AppState) -> Router {
         .route("/register", post(register))
         .route("/login", post(login))
         .route("/unregister", post(unregister))
+        .route("/link", post(link))
+        .route("/link", delete(unlink))
         .with_state(state)
 }
 
@@ -30,19 +31,29 @@ pub fn gen_service(state: AppState) -> Router {
     operation_id = "authGoogleNonce",
     path = "/api/v1/auth/google/nonce",
     responses(
-        (status = 200, body = NonceOutput)
+        (status = 200, body = NonceOutput),
+        (status = 500, body = ApiErrorMessage)
     )
 )]
-pub async fn nonce(jar: SignedCookieJar) -> ApiResult<(SignedCookieJar, Json<NonceOutput>)> {
-    let value = Uuid::new_v4().simple().to_string();
-    let jar = jar.add(Cookie::new("nonce", value.clone()));
-    let res = Json(NonceOutput { value });
+pub async fn nonce(State(state): State<AppState>, jar: SignedCookieJar) -> ApiResult<(SignedCookieJar, Json<NonceOutput>)> {
+    let session = state.service.google_auth.create_session().await?;
+
+    let jar = jar.add(Cookie::new("oauth2_state", session.state.clone()));
+    let res = Json(NonceOutput {
+        value: session.nonce,
+        state: session.state,
+        code_challenge: session.code_challenge,
+        code_challenge_method: session.code_challenge_method,
+    });
     Ok((jar, res))
 }
 
 #[derive(Serialize, ToSchema)]
 pub struct NonceOutput {
     pub value: String,
+    pub state: String,
+    pub code_challenge: String,
+    pub code_challenge_method: String,
 }
 
 #[utoipa::path(
@@ -50,8 +61,11 @@ pub struct NonceOutput {
     tag = "auth",
     operation_id = "authGoogleRegister",
     path = "/api/v1/auth/google/register",
+    request_body = RegisterInput,
     responses(
         (status = 200, body = AuthToken),
+        (status = 403, body = ApiErrorMessage),
+        (status = 409, body = ApiErrorMessage),
         (status = 500, body = ApiErrorMessage)
     )
 )]
@@ -60,27 +74,19 @@ pub async fn register(
     jar: SignedCookieJar,
     Json(input): Json<RegisterInput>,
 ) -> ApiResult<(SignedCookieJar, Json<AuthToken>)> {
-    let Some(nonce) = jar.get("nonce").map(|cookie| cookie.value().to_owned()) else {
+    let Some(session_state) = jar.get("oauth2_state").map(|cookie| cookie.value().to_owned()) else {
         return Err(ApiErrorCode::InvalidRequest);
     };
 
-    let jar = jar.remove(Cookie::build("nonce"));
+    let jar = jar.remove(Cookie::build("oauth2_state"));
 
-    let user_id = match state.service.google_auth.register(&input.code, &input.redirect_uri, &nonce).await {
-        Ok(v) => v,
-        Err(e) => {
-            error!(error = %e, "google auth register failed");
-            return Err(ApiErrorCode::InternalServerError);
-        }
-    };
+    let user_id = state
+        .service
+        .google_auth
+        .register(&input.code, &input.redirect_uri, &input.state, &session_state)
+        .await?;
 
-    let auth_token = match state.service.token.create(&user_id).await {
-        Ok(v) => v,
-        Err(e) => {
-            warn!(error = ?e);
-            return Err(ApiErrorCode::InternalServerError);
-        }
-    };
+    let auth_token = state.service.token.create(&user_id).await?;
 
     Ok((jar, Json(auth_token)))
 }
@@ -89,6 +95,7 @@ pub async fn register(
 pub struct RegisterInput {
     pub redirect_uri: String,
     pub code: String,
+    pub state: String,
 }
 
 #[utoipa::path(
@@ -96,39 +103,41 @@ pub struct RegisterInput {
     tag = "auth",
     operation_id = "authGoogleLogin",
     path = "/api/v1/auth/google/login",
+    request_body = LoginInput,
     responses(
         (status = 200, body = AuthToken),
+        (status = 403, body = ApiErrorMessage),
+        (status = 409, body = ApiErrorMessage),
         (status = 500, body = ApiErrorMessage)
     )
 )]
-pub async fn login(State(state): State<AppState>, jar: SignedCookieJar, Json(input): Json<LoginInput>) -> ApiResult<Json<AuthToken>> {
-    let Some(nonce) = jar.get("nonce").map(|cookie| cookie.value().to_owned()) else {
+pub async fn login(
+    State(state): State<AppState>,
+    jar: SignedCookieJar,
+    Json(input): Json<LoginInput>,
+) -> ApiResult<(SignedCookieJar, Json<AuthToken>)> {
+    let Some(session_state) = jar.get("oauth2_state").map(|cookie| cookie.value().to_owned()) else {
         return Err(ApiErrorCode::InvalidRequest);
     };
 
-    let user_id = match state.service.google_auth.login(&input.code, &input.redirect_uri, &nonce).await {
-        Ok(v) => v,
-        Err(e) => {
-            error!(error = %e, "google auth login failed");
-            return Err(ApiErrorCode::InternalServerError);
-        }
-    };
+    let jar = jar.remove(Cookie::build("oauth2_state"));
 
-    let auth_token = match state.service.token.create(&user_id).await {
-        Ok(v) => v,
-        Err(e) => {
-            warn!(error = ?e);
-            return Err(ApiErrorCode::InternalServerError);
-        }
-    };
+    let user_id = state
+        .service
+        .google_auth
+        .login(&input.code, &input.redirect_uri, &input.state, &session_state)
+        .await?;
+
+    let auth_token = state.service.token.create(&user_id).await?;
 
-    Ok(Json(auth_token))
+    Ok((jar, Json(auth_token)))
 }
 
 #[derive(Deserialize, ToSchema)]
 pub struct LoginInput {
     pub redirect_uri: String,
     pub code: String,
+    pub state: String,
 }
 
 #[utoipa::path(
@@ -145,9 +154,68 @@ pub struct LoginInput {
     )
 )]
 pub async fn unregister(State(state): State<AppState>, user: User) -> ApiResult<StatusCode> {
-    if let Err(e) = state.service.google_auth.unregister(user.id.as_str()).await {
-        warn!(error = ?e);
-        return Err(ApiErrorCode::InternalServerError);
-    }
+    state.service.google_auth.unregister(user.id.as_str()).await?;
     Ok(StatusCode::OK)
 }
+
+#[utoipa::path(
+    post,
+    tag = "auth",
+    operation_id = "authGoogleLink",
+    path = "/api/v1/auth/google/link",
+    request_body = LinkInput,
+    responses(
+        (status = 200),
+        (status = 409, body = ApiErrorMessage),
+        (status = 500, body = ApiErrorMessage)
+    ),
+    security(
+        ("bearer_token" = [])
+    )
+)]
+pub async fn link(
+    State(state): State<AppState>,
+    user: User,
+    jar: SignedCookieJar,
+    Json(input): Json<LinkInput>,
+) -> ApiResult<(SignedCookieJar, StatusCode)> {
+    let Some(session_state) = jar.get("oauth2_state").map(|cookie| cookie.value().to_owned()) else {
+        return Err(ApiErrorCode::InvalidRequest);
+    };
+
+    let jar = jar.remove(Cookie::build("oauth2_state"));
+
+    state
+        .service
+        .google_auth
+        .link(&user.id, &input.code, &input.redirect_uri, &input.state, &session_state)
+        .await?;
+
+    Ok((jar, StatusCode::OK))
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct LinkInput {
+    pub redirect_uri: String,
+    pub code: String,
+    pub state: String,
+}
+
+#[utoipa::path(
+    delete,
+    tag = "auth",
+    operation_id = "authGoogleUnlink",
+    path = "/api/v1/auth/google/link",
+    responses(
+        (status = 200),
+        (status = 404, body = ApiErrorMessage),
+        (status = 409, body = ApiErrorMessage),
+        (status = 500, body = ApiErrorMessage)
+    ),
+    security(
+        ("bearer_token" = [])
+    )
+)]
+pub async fn unlink(State(state): State<AppState>, user: User) -> ApiResult<StatusCode> {
+    super::detach_provider(&state, &user, "google").await
+}
@@ -0,0 +1,248 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+};
+use axum_extra::extract::cookie::{Cookie, SignedCookieJar};
+use hyper::StatusCode;
+use serde::{Deserialize, Serialize};
+use tracing::error;
+use utoipa::ToSchema;
+
+use omnius_opxs_auth::{
+    model::{AuthToken, User},
+    provider::ProviderAuthService,
+};
+
+use crate::{prelude::*, shared::state::AppState};
+
+fn get_provider_auth<'a>(state: &'a AppState, provider: &str) -> ApiResult<&'a ProviderAuthService> {
+    state.service.provider_auths.get(provider).ok_or(ApiErrorCode::NotFound)
+}
+
+#[utoipa::path(
+    get,
+    tag = "auth",
+    operation_id = "authProviderNonce",
+    path = "/api/v1/auth/{provider}/nonce",
+    params(
+        ("provider" = String, Path, description = "provider type (e.g. github, microsoft)")
+    ),
+    responses(
+        (status = 200, body = ProviderNonceOutput),
+        (status = 404, body = ApiErrorMessage),
+        (status = 500, body = ApiErrorMessage)
+    )
+)]
+pub async fn nonce(
+    State(state): State<AppState>,
+    Path(provider): Path<String>,
+    jar: SignedCookieJar,
+) -> ApiResult<(SignedCookieJar, Json<ProviderNonceOutput>)> {
+    let provider_auth = get_provider_auth(&state, &provider)?;
+
+    let session = provider_auth.create_session().await?;
+
+    let jar = jar.add(Cookie::new("oauth2_state", session.state.clone()));
+    let res = Json(ProviderNonceOutput {
+        value: session.nonce,
+        state: session.state,
+        code_challenge: session.code_challenge,
+        code_challenge_method: session.code_challenge_method,
+    });
+    Ok((jar, res))
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ProviderNonceOutput {
+    pub value: String,
+    pub state: String,
+    pub code_challenge: String,
+    pub code_challenge_method: String,
+}
+
+#[utoipa::path(
+    post,
+    tag = "auth",
+    operation_id = "authProviderRegister",
+    path = "/api/v1/auth/{provider}/register",
+    params(
+        ("provider" = String, Path, description = "provider type (e.g. github, microsoft)")
+    ),
+    request_body = RegisterInput,
+    responses(
+        (status = 200, body = AuthToken),
+        (status = 403, body = ApiErrorMessage),
+        (status = 404, body = ApiErrorMessage),
+        (status = 500, body = ApiErrorMessage)
+    )
+)]
+pub async fn register(
+    State(state): State<AppState>,
+    Path(provider): Path<String>,
+    jar: SignedCookieJar,
+    Json(input): Json<RegisterInput>,
+) -> ApiResult<(SignedCookieJar, Json<AuthToken>)> {
+    let provider_auth = get_provider_auth(&state, &provider)?;
+
+    let Some(session_state) = jar.get("oauth2_state").map(|cookie| cookie.value().to_owned()) else {
+        return Err(ApiErrorCode::InvalidRequest);
+    };
+
+    let jar = jar.remove(Cookie::build("oauth2_state"));
+
+    let user_id = provider_auth
+        .register(&input.code, &input.redirect_uri, &input.state, &session_state)
+        .await?;
+
+    let auth_token = state.service.token.create(&user_id).await?;
+
+    Ok((jar, Json(auth_token)))
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct RegisterInput {
+    pub redirect_uri: String,
+    pub code: String,
+    pub state: String,
+}
+
+#[utoipa::path(
+    post,
+    tag = "auth",
+    operation_id = "authProviderLogin",
+    path = "/api/v1/auth/{provider}/login",
+    params(
+        ("provider" = String, Path, description = "provider type (e.g. github, microsoft)")
+    ),
+    request_body = LoginInput,
+    responses(
+        (status = 200, body = AuthToken),
+        (status = 403, body = ApiErrorMessage),
+        (status = 404, body = ApiErrorMessage),
+        (status = 500, body = ApiErrorMessage)
+    )
+)]
+pub async fn login(
+    State(state): State<AppState>,
+    Path(provider): Path<String>,
+    jar: SignedCookieJar,
+    Json(input): Json<LoginInput>,
+) -> ApiResult<(SignedCookieJar, Json<AuthToken>)> {
+    let provider_auth = get_provider_auth(&state, &provider)?;
+
+    let Some(session_state) = jar.get("oauth2_state").map(|cookie| cookie.value().to_owned()) else {
+        return Err(ApiErrorCode::InvalidRequest);
+    };
+
+    let jar = jar.remove(Cookie::build("oauth2_state"));
+
+    let user_id = provider_auth
+        .login(&input.code, &input.redirect_uri, &input.state, &session_state)
+        .await?;
+
+    let auth_token = state.service.token.create(&user_id).await?;
+
+    Ok((jar, Json(auth_token)))
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct LoginInput {
+    pub redirect_uri: String,
+    pub code: String,
+    pub state: String,
+}
+
+#[utoipa::path(
+    post,
+    tag = "auth",
+    operation_id = "authProviderUnregister",
+    path = "/api/v1/auth/{provider}/unregister",
+    params(
+        ("provider" = String, Path, description = "provider type (e.g. github, microsoft)")
+    ),
+    responses(
+        (status = 200),
+        (status = 404, body = ApiErrorMessage),
+        (status = 500, body = ApiErrorMessage)
+    ),
+    security(
+        ("bearer_token" = [])
+    )
+)]
+pub async fn unregister(State(state): State<AppState>, Path(provider): Path<String>, user: User) -> ApiResult<StatusCode> {
+    let provider_auth = get_provider_auth(&state, &provider)?;
+
+    provider_auth.unregister(user.id.as_str()).await?;
+    Ok(StatusCode::OK)
+}
+
+#[utoipa::path(
+    post,
+    tag = "auth",
+    operation_id = "authProviderLink",
+    path = "/api/v1/auth/{provider}/link",
+    params(
+        ("provider" = String, Path, description = "provider type (e.g. github, microsoft)")
+    ),
+    request_body = LinkInput,
+    responses(
+        (status = 200),
+        (status = 404, body = ApiErrorMessage),
+        (status = 409, body = ApiErrorMessage),
+        (status = 500, body = ApiErrorMessage)
+    ),
+    security(
+        ("bearer_token" = [])
+    )
+)]
+pub async fn link(
+    State(state): State<AppState>,
+    Path(provider): Path<String>,
+    user: User,
+    jar: SignedCookieJar,
+    Json(input): Json<LinkInput>,
+) -> ApiResult<(SignedCookieJar, StatusCode)> {
+    let provider_auth = get_provider_auth(&state, &provider)?;
+
+    let Some(session_state) = jar.get("oauth2_state").map(|cookie| cookie.value().to_owned()) else {
+        return Err(ApiErrorCode::InvalidRequest);
+    };
+
+    let jar = jar.remove(Cookie::build("oauth2_state"));
+
+    provider_auth
+        .link(&user.id, &input.code, &input.redirect_uri, &input.state, &session_state)
+        .await?;
+
+    Ok((jar, StatusCode::OK))
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct LinkInput {
+    pub redirect_uri: String,
+    pub code: String,
+    pub state: String,
+}
+
+#[utoipa::path(
+    delete,
+    tag = "auth",
+    operation_id = "authProviderUnlink",
+    path = "/api/v1/auth/{provider}/link",
+    params(
+        ("provider" = String, Path, description = "provider type (e.g. github, microsoft)")
+    ),
+    responses(
+        (status = 200),
+        (status = 404, body = ApiErrorMessage),
+        (status = 409, body = ApiErrorMessage),
+        (status = 500, body = ApiErrorMessage)
+    ),
+    security(
+        ("bearer_token" = [])
+    )
+)]
+pub async fn unlink(State(state): State<AppState>, Path(provider): Path<String>, user: User) -> ApiResult<StatusCode> {
+    get_provider_auth(&state, &provider)?;
+    super::detach_provider(&state, &user, &provider).await
+}
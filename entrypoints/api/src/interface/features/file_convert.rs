@@ -1,10 +1,15 @@
 pub mod image;
 
-use axum::Router;
+use axum::{Extension, Router};
 
-use crate::shared::state::AppState;
+use omnius_opxs_auth::model::ApiKeyScope;
+
+use crate::{interface::version::ApiVersion, shared::state::AppState};
 
 #[allow(unused)]
-pub fn gen_service(state: AppState) -> Router {
-    Router::new().nest_service("/image", image::gen_service(state.clone())).with_state(state)
+pub fn gen_service(state: AppState, version: ApiVersion) -> Router {
+    Router::new()
+        .nest("/image", image::gen_service(state.clone()))
+        .layer(Extension(ApiKeyScope::FileConvert))
+        .with_state(state)
 }
@@ -1,10 +1,11 @@
 use axum::{
     Json, Router,
     extract::{Query, State},
+    middleware,
     routing::{get, post},
 };
 use serde::{Deserialize, Serialize};
-use utoipa::ToSchema;
+use utoipa::{IntoParams, ToSchema};
 use validator::Validate;
 
 use omnius_opxs_auth::model::User;
@@ -12,12 +13,28 @@ use omnius_opxs_file_convert::{
     FileConvertImageInputFileType, FileConvertImageOutputFileType, FileConvertImageRequestParam, FileConvertJobStatus, FileConvertJobType,
 };
 
-use crate::{interface::extractors::ValidatedJson, prelude::*, shared::state::AppState};
+use crate::{
+    interface::{
+        extractors::ValidatedJson,
+        idempotency::idempotency,
+        rate_limit::{RateLimiter, rate_limit},
+    },
+    prelude::*,
+    shared::state::AppState,
+};
 
 #[allow(unused)]
 pub fn gen_service(state: AppState) -> Router {
     Router::new()
-        .route("/upload", post(upload))
+        .route(
+            "/upload",
+            post(upload)
+                .layer(middleware::from_fn_with_state(state.clone(), idempotency))
+                .layer(middleware::from_fn_with_state(
+                    RateLimiter::file_convert_upload(state.clone()),
+                    rate_limit,
+                )),
+        )
         .route("/status", get(status))
         .with_state(state)
 }
@@ -28,8 +45,14 @@ pub fn gen_service(state: AppState) -> Router {
     operation_id = "fileConvertImageUpload",
     path = "/api/v1/file-convert/image/upload",
     request_body = UploadInput,
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "replays the stored response when a request is retried with the same key")
+    ),
     responses(
         (status = 200, body = UploadOutput),
+        (status = 409, body = ApiErrorMessage),
+        (status = 422, body = ApiErrorMessage),
+        (status = 429, body = ApiErrorMessage),
         (status = 500, body = ApiErrorMessage)
     ),
     security(
@@ -42,7 +65,7 @@ pub async fn upload(State(state): State<AppState>, user: User, ValidatedJson(inp
         in_type: input.in_type,
         out_type: input.out_type,
     };
-    let upload_url = match state
+    let upload_url = state
         .service
         .image_convert_job_creator
         .create_job(
@@ -53,22 +76,17 @@ pub async fn upload(State(state): State<AppState>, user: User, ValidatedJson(inp
             &input.in_file_name,
             &input.out_file_name,
         )
-        .await
-    {
-        Ok(v) => v,
-        Err(e) => {
-            warn!(error = ?e);
-            return Err(ApiErrorCode::InternalServerError);
-        }
-    };
+        .await?;
 
     Ok(Json(UploadOutput { job_id, upload_url }))
 }
 
 #[derive(Deserialize, ToSchema, Validate)]
 pub struct UploadInput {
+    #[validate(length(min = 1, max = 255))]
     pub in_file_name: String,
     pub in_type: FileConvertImageInputFileType,
+    #[validate(length(min = 1, max = 255))]
     pub out_file_name: String,
     pub out_type: FileConvertImageOutputFileType,
 }
@@ -84,7 +102,7 @@ pub struct UploadOutput {
     tag = "file-convert",
     operation_id = "fileConvertImageStatus",
     path = "/api/v1/file-convert/image/status",
-    request_body = StatusInput,
+    params(StatusInput),
     responses(
         (status = 200, body = StatusOutput),
         (status = 500, body = ApiErrorMessage)
@@ -94,18 +112,13 @@ pub struct UploadOutput {
     )
 )]
 pub async fn status(State(state): State<AppState>, input: Query<StatusInput>, user: User) -> ApiResult<Json<StatusOutput>> {
-    let (status, download_url) = match state.service.image_convert_job_creator.get_download_url(&input.job_id, &user.id).await {
-        Ok(v) => v,
-        Err(e) => {
-            warn!(error = ?e);
-            return Err(ApiErrorCode::InternalServerError);
-        }
-    };
+    let (status, download_url) = state.service.image_convert_job_creator.get_download_url(&input.job_id, &user.id).await?;
 
     Ok(Json(StatusOutput { status, download_url }))
 }
 
-#[derive(Deserialize, ToSchema, Validate)]
+#[derive(Deserialize, ToSchema, IntoParams, Validate)]
+#[into_params(parameter_in = Query)]
 pub struct StatusInput {
     pub job_id: String,
 }
@@ -1,13 +1,17 @@
-use axum::{Json, extract::State};
-use serde_json::Value;
+use axum::{Json, extract::State, http::StatusCode};
+use serde_json::{Value, json};
 
-use crate::{prelude::*, shared::state::AppState};
+use crate::{
+    prelude::*,
+    service::health::service::{HealthStatus, ReadinessReport},
+    shared::state::AppState,
+};
 
 #[utoipa::path(
     get,
     tag = "health",
     operation_id = "health",
-    path = "/api/health",
+    path = "/api/v1/health",
     responses(
         (status = 200),
         (status = 500, body = ApiErrorMessage)
@@ -15,12 +19,38 @@ use crate::{prelude::*, shared::state::AppState};
 )]
 #[allow(unused)]
 pub async fn check(State(state): State<AppState>) -> ApiResult<Json<Value>> {
-    let ret = match state.service.health.check().await {
-        Ok(v) => v,
-        Err(e) => {
-            warn!(error = ?e);
-            return Err(ApiErrorCode::InternalServerError);
-        }
-    };
+    let ret = state.service.health.check().await?;
     Ok(Json(ret))
 }
+
+#[utoipa::path(
+    get,
+    tag = "health",
+    operation_id = "health_live",
+    path = "/api/v1/health/live",
+    responses(
+        (status = 200)
+    )
+)]
+pub async fn live() -> Json<Value> {
+    Json(json!({ "status": "up" }))
+}
+
+#[utoipa::path(
+    get,
+    tag = "health",
+    operation_id = "health_ready",
+    path = "/api/v1/health/ready",
+    responses(
+        (status = 200, body = ReadinessReport),
+        (status = 503, body = ReadinessReport)
+    )
+)]
+pub async fn ready(State(state): State<AppState>) -> (StatusCode, Json<ReadinessReport>) {
+    let report = state.service.health.ready().await;
+    let status_code = match report.status {
+        HealthStatus::Down => StatusCode::SERVICE_UNAVAILABLE,
+        _ => StatusCode::OK,
+    };
+    (status_code, Json(report))
+}
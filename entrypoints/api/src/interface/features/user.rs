@@ -0,0 +1,240 @@
+use axum::{
+    Extension, Json, Router,
+    extract::{Path, State},
+    routing::{get, post},
+};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+
+use omnius_opxs_auth::model::{ApiKeyScope, User, UserLocale, UserProfile, UserProfileUpdate, UserRole};
+use omnius_opxs_data_export::DataExportJobStatus;
+use omnius_opxs_file_convert::{FileConvertImageInputFileType, FileConvertImageOutputFileType, FileConvertJobType, FileConvertThumbnailRequestParam};
+
+use crate::{
+    interface::{extractors::ValidatedJson, version::ApiVersion},
+    prelude::*,
+    shared::state::AppState,
+};
+
+const AVATAR_SIZE: u32 = 256;
+
+#[allow(unused)]
+pub fn gen_service(state: AppState, version: ApiVersion) -> Router {
+    Router::new()
+        .route("/me", get(me).patch(update_me))
+        .route("/me/avatar", post(upload_avatar))
+        .route("/me/exports", post(create_export))
+        .route("/me/exports/{job_id}", get(export_status))
+        .layer(Extension(ApiKeyScope::Account))
+        .with_state(state)
+}
+
+#[utoipa::path(
+    get,
+    tag = "user",
+    operation_id = "userMe",
+    path = "/api/v1/users/me",
+    responses(
+        (status = 200, body = ProfileOutput),
+        (status = 500, body = ApiErrorMessage)
+    ),
+    security(
+        ("bearer_token" = [])
+    )
+)]
+pub async fn me(State(state): State<AppState>, user: User) -> ApiResult<Json<ProfileOutput>> {
+    let profile = state.service.user.get_profile(&user.id).await?;
+
+    Ok(Json(to_output(&state, profile).await))
+}
+
+#[utoipa::path(
+    patch,
+    tag = "user",
+    operation_id = "userUpdateMe",
+    path = "/api/v1/users/me",
+    request_body = UpdateProfileInput,
+    responses(
+        (status = 200, body = ProfileOutput),
+        (status = 400, body = ApiErrorMessage),
+        (status = 422, body = ApiErrorMessage),
+        (status = 500, body = ApiErrorMessage)
+    ),
+    security(
+        ("bearer_token" = [])
+    )
+)]
+pub async fn update_me(
+    State(state): State<AppState>,
+    user: User,
+    ValidatedJson(input): ValidatedJson<UpdateProfileInput>,
+) -> ApiResult<Json<ProfileOutput>> {
+    let update = UserProfileUpdate {
+        display_name: input.display_name,
+        locale: input.locale,
+        time_zone: input.time_zone,
+    };
+    let profile = state.service.user.update_profile(&user.id, &update).await?;
+
+    Ok(Json(to_output(&state, profile).await))
+}
+
+// The avatar is resized by a file-convert thumbnail job; its URL appears on the profile once the job completes.
+#[utoipa::path(
+    post,
+    tag = "user",
+    operation_id = "userUploadAvatar",
+    path = "/api/v1/users/me/avatar",
+    request_body = AvatarUploadInput,
+    responses(
+        (status = 200, body = AvatarUploadOutput),
+        (status = 422, body = ApiErrorMessage),
+        (status = 500, body = ApiErrorMessage)
+    ),
+    security(
+        ("bearer_token" = [])
+    )
+)]
+pub async fn upload_avatar(
+    State(state): State<AppState>,
+    user: User,
+    ValidatedJson(input): ValidatedJson<AvatarUploadInput>,
+) -> ApiResult<Json<AvatarUploadOutput>> {
+    let job_id = state.service.tsid_provider.lock().create().to_string();
+    let param = FileConvertThumbnailRequestParam {
+        in_type: input.in_type,
+        out_type: FileConvertImageOutputFileType::WebP,
+        size: AVATAR_SIZE,
+    };
+    let upload_url = state
+        .service
+        .image_convert_job_creator
+        .create_job(
+            &job_id,
+            &user.id,
+            &FileConvertJobType::Thumbnail,
+            &param,
+            &input.in_file_name,
+            "avatar.webp",
+        )
+        .await?;
+
+    state.service.user.set_pending_avatar(&user.id, &job_id).await?;
+
+    Ok(Json(AvatarUploadOutput { job_id, upload_url }))
+}
+
+// The archive is built asynchronously; the user is also notified by email with a longer-lived download link.
+#[utoipa::path(
+    post,
+    tag = "user",
+    operation_id = "userCreateExport",
+    path = "/api/v1/users/me/exports",
+    responses(
+        (status = 200, body = ExportOutput),
+        (status = 500, body = ApiErrorMessage)
+    ),
+    security(
+        ("bearer_token" = [])
+    )
+)]
+pub async fn create_export(State(state): State<AppState>, user: User) -> ApiResult<Json<ExportOutput>> {
+    let job_id = state.service.tsid_provider.lock().create().to_string();
+    let job_id = state.service.data_export_job_creator.create_job(&job_id, &user.id).await?;
+
+    Ok(Json(ExportOutput { job_id }))
+}
+
+#[utoipa::path(
+    get,
+    tag = "user",
+    operation_id = "userExportStatus",
+    path = "/api/v1/users/me/exports/{job_id}",
+    params(
+        ("job_id" = String, Path, description = "export job id")
+    ),
+    responses(
+        (status = 200, body = ExportStatusOutput),
+        (status = 404, body = ApiErrorMessage),
+        (status = 500, body = ApiErrorMessage)
+    ),
+    security(
+        ("bearer_token" = [])
+    )
+)]
+pub async fn export_status(State(state): State<AppState>, user: User, Path(job_id): Path<String>) -> ApiResult<Json<ExportStatusOutput>> {
+    let (status, download_url) = state.service.data_export_job_creator.get_download_url(&job_id, &user.id).await?;
+
+    Ok(Json(ExportStatusOutput { status, download_url }))
+}
+
+async fn to_output(state: &AppState, profile: UserProfile) -> ProfileOutput {
+    let avatar_url = match profile.avatar_job_id.as_deref() {
+        Some(job_id) => match state.service.image_convert_job_creator.get_download_url(job_id, &profile.id).await {
+            Ok((_, v)) => v,
+            Err(e) => {
+                warn!(error = ?e);
+                None
+            }
+        },
+        None => None,
+    };
+
+    ProfileOutput {
+        id: profile.id,
+        name: profile.name,
+        role: profile.role,
+        display_name: profile.display_name,
+        locale: profile.locale,
+        time_zone: profile.time_zone,
+        avatar_url,
+        updated_at: profile.updated_at,
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ProfileOutput {
+    pub id: String,
+    pub name: String,
+    pub role: UserRole,
+    pub display_name: Option<String>,
+    pub locale: UserLocale,
+    pub time_zone: String,
+    pub avatar_url: Option<String>,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct UpdateProfileInput {
+    #[validate(length(max = 64))]
+    pub display_name: Option<String>,
+    pub locale: Option<UserLocale>,
+    #[validate(length(min = 1, max = 64))]
+    pub time_zone: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct AvatarUploadInput {
+    #[validate(length(min = 1, max = 255))]
+    pub in_file_name: String,
+    pub in_type: FileConvertImageInputFileType,
+}
+
+#[derive(Serialize, ToSchema, Validate)]
+pub struct AvatarUploadOutput {
+    pub job_id: String,
+    pub upload_url: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ExportOutput {
+    pub job_id: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ExportStatusOutput {
+    pub status: DataExportJobStatus,
+    pub download_url: Option<String>,
+}
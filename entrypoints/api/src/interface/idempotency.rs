@@ -0,0 +1,172 @@
+use axum::{
+    body::{Body, Bytes, to_bytes},
+    extract::{Request, State},
+    http::{HeaderName, HeaderValue, StatusCode, header, request::Parts},
+    middleware::Next,
+    response::{IntoResponse as _, Response},
+};
+use ring::digest;
+
+use crate::{
+    interface::extractors::peek_subject,
+    prelude::*,
+    service::idempotency::service::{IdempotencyState, StoredResponse},
+    shared::state::AppState,
+};
+
+pub(crate) const IDEMPOTENCY_KEY: HeaderName = HeaderName::from_static("idempotency-key");
+pub(crate) const IDEMPOTENT_REPLAYED: HeaderName = HeaderName::from_static("idempotent-replayed");
+const KEY_MAX_LENGTH: usize = 255;
+const BODY_LIMIT: usize = 1024 * 1024;
+
+pub async fn idempotency(State(state): State<AppState>, req: Request, next: Next) -> Response {
+    let Some(key) = req.headers().get(IDEMPOTENCY_KEY) else {
+        return next.run(req).await;
+    };
+    let key = match key.to_str() {
+        Ok(v) if !v.is_empty() && v.len() <= KEY_MAX_LENGTH => v.to_string(),
+        _ => return ApiErrorCode::InvalidRequest.into_response(),
+    };
+
+    let (parts, body) = req.into_parts();
+    let body = match to_bytes(body, BODY_LIMIT).await {
+        Ok(v) => v,
+        Err(e) => {
+            debug!(error = ?e);
+            return ApiErrorCode::InvalidRequest.into_response();
+        }
+    };
+    let fingerprint = fingerprint(&parts, &body);
+
+    // Keys are scoped per caller so that different clients never see each other's responses.
+    // Anonymous callers can not be told apart, so only a retry of the very same request is replayed for them.
+    let key = match peek_subject(&state, &parts.headers) {
+        Some(subject) => format!("{subject}:{key}"),
+        None => {
+            let mut ctx = digest::Context::new(&digest::SHA256);
+            ctx.update(key.as_bytes());
+            ctx.update(b"\n");
+            ctx.update(fingerprint.as_bytes());
+            format!("anon:{}", hex::encode(ctx.finish()))
+        }
+    };
+
+    match state.service.idempotency.begin(&key, &fingerprint).await {
+        Ok(IdempotencyState::Started) => {}
+        Ok(IdempotencyState::InProgress) => return ApiErrorCode::IdempotencyKeyInProgress.into_response(),
+        Ok(IdempotencyState::Mismatch) => return ApiErrorCode::IdempotencyKeyMismatch.into_response(),
+        Ok(IdempotencyState::Completed(stored)) => return replay(stored),
+        Err(e) => {
+            warn!(error = ?e, "idempotency key lookup failed");
+            return ApiErrorCode::InternalServerError.into_response();
+        }
+    }
+
+    let res = next.run(Request::from_parts(parts, Body::from(body))).await;
+
+    // Server errors are not cached so that the client can retry with the same key.
+    if res.status().is_server_error() {
+        release(&state, &key).await;
+        return res;
+    }
+
+    let (parts, body) = res.into_parts();
+    let body = match to_bytes(body, usize::MAX).await {
+        Ok(v) => v,
+        Err(e) => {
+            warn!(error = ?e, "failed to read response body");
+            release(&state, &key).await;
+            return ApiErrorCode::InternalServerError.into_response();
+        }
+    };
+
+    let stored = StoredResponse {
+        status_code: parts.status.as_u16(),
+        content_type: parts
+            .headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.to_string()),
+        body: body.to_vec(),
+    };
+    if let Err(e) = state.service.idempotency.complete(&key, &stored).await {
+        warn!(error = ?e, "idempotency response store failed");
+        release(&state, &key).await;
+    }
+
+    Response::from_parts(parts, Body::from(body))
+}
+
+fn fingerprint(parts: &Parts, body: &Bytes) -> String {
+    let mut ctx = digest::Context::new(&digest::SHA256);
+    ctx.update(parts.method.as_str().as_bytes());
+    ctx.update(b" ");
+    ctx.update(parts.uri.path().as_bytes());
+    ctx.update(b"\n");
+    ctx.update(body);
+    hex::encode(ctx.finish())
+}
+
+fn replay(stored: StoredResponse) -> Response {
+    let mut res = Response::new(Body::from(stored.body));
+    *res.status_mut() = StatusCode::from_u16(stored.status_code).unwrap_or(StatusCode::OK);
+    if let Some(content_type) = stored.content_type.and_then(|v| HeaderValue::from_str(&v).ok()) {
+        res.headers_mut().insert(header::CONTENT_TYPE, content_type);
+    }
+    res.headers_mut().insert(IDEMPOTENT_REPLAYED, HeaderValue::from_static("true"));
+    res
+}
+
+async fn release(state: &AppState, key: &str) {
+    if let Err(e) = state.service.idempotency.release(key).await {
+        warn!(error = ?e, "idempotency key release failed");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{Router, http::Request};
+    use serial_test::serial;
+    use testresult::TestResult;
+    use tower::ServiceExt as _;
+
+    use crate::{
+        interface::{features::auth, version::ApiVersion},
+        shared::testkit,
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    #[serial]
+    async fn anonymous_replay_test() -> TestResult {
+        let (_container, state) = testkit::gen_state().await?;
+
+        let app = Router::new().nest("/auth", auth::gen_service(state, ApiVersion::V2));
+        let send = |key: &str, email: &str| {
+            let req = Request::post("/auth/email/register")
+                .header(IDEMPOTENCY_KEY, key)
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(format!(
+                    r#"{{"name":"test_user","email":"{email}","password":"c0rrect-horse-battery-staple"}}"#
+                )));
+            let app = app.clone();
+            async move { TestResult::<Response>::Ok(app.oneshot(req?).await?) }
+        };
+
+        let res = send("key", "user@example.com").await?;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(res.headers().get(IDEMPOTENT_REPLAYED).is_none());
+
+        let res = send("key", "user@example.com").await?;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers().get(IDEMPOTENT_REPLAYED).unwrap(), "true");
+
+        // Another anonymous caller reusing the key with a different body gets its own scope.
+        let res = send("key", "other@example.com").await?;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(res.headers().get(IDEMPOTENT_REPLAYED).is_none());
+
+        Ok(())
+    }
+}
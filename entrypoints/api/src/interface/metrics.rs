@@ -0,0 +1,62 @@
+use std::{sync::LazyLock, time::Instant};
+
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::header,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use prometheus::{HistogramVec, IntCounterVec, IntGaugeVec, register_histogram_vec, register_int_counter_vec, register_int_gauge_vec};
+
+use crate::{prelude::*, shared::state::AppState};
+
+static HTTP_REQUESTS_TOTAL: LazyLock<IntCounterVec> =
+    LazyLock::new(|| register_int_counter_vec!("http_requests_total", "Number of HTTP requests", &["method", "route", "status"]).unwrap());
+
+static HTTP_REQUEST_DURATION_SECONDS: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register_histogram_vec!(
+        "http_request_duration_seconds",
+        "HTTP request latency",
+        &["method", "route", "status"],
+        vec![0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0]
+    )
+    .unwrap()
+});
+
+static DB_POOL_CONNECTIONS: LazyLock<IntGaugeVec> =
+    LazyLock::new(|| register_int_gauge_vec!("db_pool_connections", "Number of database pool connections", &["state"]).unwrap());
+
+pub async fn track(req: Request, next: Next) -> Response {
+    // The route template keeps label cardinality bounded, unlike the raw request path.
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|n| n.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+    let method = req.method().to_string();
+
+    let start = Instant::now();
+    let res = next.run(req).await;
+    let status = res.status().as_u16().to_string();
+
+    HTTP_REQUESTS_TOTAL.with_label_values(&[&method, &route, &status]).inc();
+    HTTP_REQUEST_DURATION_SECONDS
+        .with_label_values(&[&method, &route, &status])
+        .observe(start.elapsed().as_secs_f64());
+
+    res
+}
+
+pub async fn render(State(state): State<AppState>) -> ApiResult<Response> {
+    let size = state.db.size() as i64;
+    let idle = state.db.num_idle() as i64;
+    DB_POOL_CONNECTIONS.with_label_values(&["idle"]).set(idle);
+    DB_POOL_CONNECTIONS.with_label_values(&["active"]).set(size - idle);
+
+    let body = omnius_opxs_base::metrics::render().map_err(|e| {
+        warn!(error = ?e, "metrics render failed");
+        ApiErrorCode::InternalServerError
+    })?;
+
+    Ok(([(header::CONTENT_TYPE, prometheus::TEXT_FORMAT)], body).into_response())
+}
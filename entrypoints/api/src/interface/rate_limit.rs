@@ -0,0 +1,169 @@
+use axum::{
+    extract::{Request, State},
+    http::{HeaderMap, HeaderName, HeaderValue, header},
+    middleware::Next,
+    response::{IntoResponse as _, Response},
+};
+use omnius_opxs_base::{RateLimitPolicyConfig, TokenBucketConfig};
+use ring::digest;
+
+use crate::{
+    interface::extractors::{ClientIp, peek_subject},
+    prelude::*,
+    service::rate_limit::service::RateLimitDecision,
+    shared::state::AppState,
+};
+
+pub(crate) const RATELIMIT_LIMIT: HeaderName = HeaderName::from_static("ratelimit-limit");
+pub(crate) const RATELIMIT_REMAINING: HeaderName = HeaderName::from_static("ratelimit-remaining");
+pub(crate) const RATELIMIT_RESET: HeaderName = HeaderName::from_static("ratelimit-reset");
+
+#[derive(Clone)]
+pub struct RateLimiter {
+    pub state: AppState,
+    pub group: &'static str,
+    pub policy: RateLimitPolicyConfig,
+}
+
+impl RateLimiter {
+    pub fn auth(state: AppState) -> Self {
+        let policy = state.conf.rate_limit.auth.clone();
+        Self {
+            state,
+            group: "auth",
+            policy,
+        }
+    }
+
+    pub fn email_resend(state: AppState) -> Self {
+        let policy = state.conf.rate_limit.email_resend.clone();
+        Self {
+            state,
+            group: "email_resend",
+            policy,
+        }
+    }
+
+    pub fn file_convert_upload(state: AppState) -> Self {
+        let policy = state.conf.rate_limit.file_convert_upload.clone();
+        Self {
+            state,
+            group: "file_convert_upload",
+            policy,
+        }
+    }
+
+    async fn check(&self, ip: &str, headers: &HeaderMap) -> Option<RateLimitDecision> {
+        let mut keys = Vec::new();
+        if let Some(bucket) = &self.policy.per_ip {
+            keys.push((format!("{}:ip:{}", self.group, ip), bucket));
+        }
+        if let Some(bucket) = &self.policy.per_user
+            && let Some(user_key) = peek_subject(&self.state, headers)
+        {
+            keys.push((format!("{}:user:{}", self.group, user_key), bucket));
+        }
+
+        self.take(keys).await
+    }
+
+    /// Applies `per_email` for requests that name an address without a session.
+    /// Registered and unknown addresses share the same buckets, so the outcome does not reveal which ones exist.
+    pub async fn check_email(&self, email: &str) -> Option<RateLimitDecision> {
+        let bucket = self.policy.per_email.as_ref()?;
+        // Hashed so that the store does not hold addresses.
+        let hash = hex::encode(digest::digest(&digest::SHA256, email.trim().to_lowercase().as_bytes()));
+        self.take(vec![(format!("{}:email:{}", self.group, &hash[..32]), bucket)]).await
+    }
+
+    async fn take(&self, keys: Vec<(String, &TokenBucketConfig)>) -> Option<RateLimitDecision> {
+        let mut result: Option<RateLimitDecision> = None;
+        for (key, bucket) in keys {
+            let decision = match self.state.service.rate_limit.check(&key, bucket).await {
+                Ok(v) => v,
+                Err(e) => {
+                    // Fail open so that an unavailable store does not take the API down with it.
+                    warn!(error = ?e, key, "rate limit check failed");
+                    continue;
+                }
+            };
+
+            // The remaining buckets are left untouched, so a denied request does not use up their tokens.
+            if !decision.allowed {
+                return Some(decision);
+            }
+
+            result = match result {
+                Some(prev) if prev.remaining <= decision.remaining => Some(prev),
+                _ => Some(decision),
+            };
+        }
+
+        result
+    }
+}
+
+pub async fn rate_limit(State(limiter): State<RateLimiter>, req: Request, next: Next) -> Response {
+    let ClientIp(ip) = ClientIp::resolve(req.headers(), req.extensions(), &limiter.state.conf.server.trusted_proxies);
+    let Some(decision) = limiter.check(&ip, req.headers()).await else {
+        return next.run(req).await;
+    };
+
+    let mut res = if decision.allowed {
+        next.run(req).await
+    } else {
+        warn!(group = limiter.group, ip, "rate limited");
+        let mut res = ApiErrorCode::RateLimited.into_response();
+        if let Some(retry_after) = decision.retry_after_secs {
+            res.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+        }
+        res
+    };
+
+    let headers = res.headers_mut();
+    headers.insert(RATELIMIT_LIMIT, HeaderValue::from(decision.limit));
+    headers.insert(RATELIMIT_REMAINING, HeaderValue::from(decision.remaining));
+    headers.insert(RATELIMIT_RESET, HeaderValue::from(decision.reset_after_secs));
+
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use serial_test::serial;
+    use testresult::TestResult;
+
+    use crate::shared::testkit;
+
+    use super::*;
+
+    #[tokio::test]
+    #[serial]
+    async fn take_test() -> TestResult {
+        let (_container, state) = testkit::gen_state().await?;
+        let limiter = RateLimiter::auth(state.clone());
+
+        let small = TokenBucketConfig {
+            capacity: 1,
+            refill_per_minute: 1,
+        };
+        let large = TokenBucketConfig {
+            capacity: 5,
+            refill_per_minute: 1,
+        };
+        let keys = || vec![("test:small".to_string(), &small), ("test:large".to_string(), &large)];
+
+        // the lowest remaining count is reported
+        let decision = limiter.take(keys()).await.unwrap();
+        assert!(decision.allowed);
+        assert_eq!(decision.remaining, 0);
+
+        // the first denial stops the check before the next bucket is charged
+        let decision = limiter.take(keys()).await.unwrap();
+        assert!(!decision.allowed);
+        let decision = state.service.rate_limit.check("test:large", &large).await?;
+        assert_eq!(decision.remaining, 3);
+
+        Ok(())
+    }
+}
@@ -1,14 +1,25 @@
-use std::sync::LazyLock;
+use std::{
+    net::SocketAddr,
+    sync::{Arc, LazyLock},
+    time::Duration,
+};
 
 use axum::{
     Router,
     extract::Request,
-    http::{HeaderName, HeaderValue},
+    http::{HeaderName, HeaderValue, Method, header},
+    middleware,
     response::{Redirect, Response},
     routing::get,
 };
-use omnius_opxs_base::util::Terminable as _;
+use axum_server::{Handle, tls_rustls::RustlsConfig};
+use omnius_opxs_base::{
+    ServeMode, ServerConfig, WebConfig,
+    telemetry::{self, Telemetry},
+    util::Terminable as _,
+};
 use parking_lot::Mutex;
+use tower::Service as _;
 use tower_http::{
     cors::CorsLayer,
     request_id::{MakeRequestId, PropagateRequestIdLayer, RequestId, SetRequestIdLayer},
@@ -16,18 +27,28 @@ use tower_http::{
 };
 use tracing::Span;
 use utoipa::OpenApi;
-use utoipa_swagger_ui::SwaggerUi;
+use utoipa_swagger_ui::{SwaggerUi, Url};
+
+use crate::{
+    interface::{
+        extractors::API_KEY_HEADER,
+        features::*,
+        idempotency::{IDEMPOTENCY_KEY, IDEMPOTENT_REPLAYED},
+        metrics,
+        rate_limit::{RATELIMIT_LIMIT, RATELIMIT_REMAINING, RATELIMIT_RESET},
+        version::{ApiVersion, DEPRECATION, SUNSET},
+    },
+    prelude::*,
+    shared::state::AppState,
+};
 
-use crate::{interface::features::*, prelude::*, shared::state::AppState};
+const GRACEFUL_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
 
 pub struct WebServer;
 
 impl WebServer {
-    pub async fn serve(state: AppState) -> Result<()> {
-        let cors_layer = CorsLayer::new()
-            .allow_origin(tower_http::cors::Any)
-            .allow_methods(tower_http::cors::Any)
-            .allow_headers(tower_http::cors::Any);
+    pub async fn serve(state: AppState, telemetry: Arc<Telemetry>) -> Result<()> {
+        let cors_layer = Self::cors_layer(&state.conf.web)?;
 
         let x_request_id = HeaderName::from_static("x-request-id");
         let set_request_id_layer = SetRequestIdLayer::new(x_request_id.clone(), MyRequestId::new());
@@ -38,12 +59,14 @@ impl WebServer {
                 let x_request_id = HeaderName::from_static("x-request-id");
                 let request_id = request.headers().get(&x_request_id).and_then(|id| id.to_str().ok()).unwrap_or("unknown");
 
-                tracing::info_span!(
+                let span = tracing::info_span!(
                     "http_request",
                     request_id = %request_id,
                     method = %request.method(),
                     uri = %request.uri(),
-                )
+                );
+                telemetry::set_parent_from_headers(&span, request.headers());
+                span
             })
             .on_response(|response: &Response<_>, latency: std::time::Duration, _span: &Span| {
                 tracing::info!(
@@ -53,42 +76,196 @@ impl WebServer {
                 );
             });
 
+        let mut swagger_ui = SwaggerUi::new("/api/docs");
+        let mut api = Router::new().route("/", get(|| async { Redirect::permanent("/api/docs") }));
+        for version in ApiVersion::ALL {
+            swagger_ui = swagger_ui.url(Self::openapi_url(version), version.openapi());
+            api = api.nest(&format!("/{}", version.as_str()), Self::gen_version_service(state.clone(), version));
+        }
+
         let app = Router::new()
             .route("/", get(|| async { Redirect::permanent("/api/docs") }))
-            .merge(SwaggerUi::new("/api/docs").url("/api/api-doc/openapi.json", ApiDoc::openapi()))
-            .nest_service(
-                "/api",
-                Router::new().route("/", get(|| async { Redirect::permanent("/api/docs") })).nest_service(
-                    "/v1",
-                    Router::new()
-                        .route("/health", get(health::check))
-                        .with_state(state.clone())
-                        .nest_service("/auth", auth::gen_service(state.clone()))
-                        .nest_service("/file-convert", file_convert::gen_service(state.clone())),
-                ),
-            )
+            .merge(swagger_ui)
+            .nest("/api", api)
+            .route_layer(middleware::from_fn(metrics::track))
             .layer(cors_layer)
             .layer(trace_layer)
             .layer(propagate_request_id_layer)
             .layer(set_request_id_layer);
 
-        if cfg!(debug_assertions) {
-            // Run app on local server
-            let addr = std::net::SocketAddr::from(([0, 0, 0, 0], 8080));
-            let listener = tokio::net::TcpListener::bind(addr).await?;
-            axum::serve(listener, app).await?;
-        } else {
-            // Run app on AWS Lambda
-            let app = tower::ServiceBuilder::new().layer(axum_aws_lambda::LambdaLayer::default()).service(app);
-            lambda_http::run(app)
-                .await
-                .map_err(|e| Error::builder().kind(ErrorKind::UnexpectedError).source(e).build())?;
+        match state.conf.server.mode {
+            ServeMode::Http => {
+                let metrics_app = Router::new().route("/metrics", get(metrics::render)).with_state(state.clone());
+                Self::serve_http(&state.conf.server, app, metrics_app).await?;
+            }
+            ServeMode::Lambda => {
+                let app = tower::ServiceBuilder::new().layer(axum_aws_lambda::LambdaLayer::default()).service(app);
+                let app = lambda_http::service_fn(move |req: lambda_http::Request| {
+                    let mut app = app.clone();
+                    let telemetry = telemetry.clone();
+                    async move {
+                        std::future::poll_fn(|cx| app.poll_ready(cx)).await?;
+                        let res = app.call(req).await;
+                        telemetry.flush();
+                        res
+                    }
+                });
+                lambda_http::run(app)
+                    .await
+                    .map_err(|e| Error::builder().kind(ErrorKind::UnexpectedError).source(e).build())?;
+            }
         }
 
         state.service.terminate().await;
 
         Ok(())
     }
+
+    fn gen_version_service(state: AppState, version: ApiVersion) -> Router {
+        let router = Router::new()
+            .route("/health", get(health::check))
+            .route("/health/live", get(health::live))
+            .route("/health/ready", get(health::ready))
+            .with_state(state.clone())
+            .nest("/admin", admin::gen_service(state.clone(), version))
+            .nest("/api-keys", api_key::gen_service(state.clone(), version))
+            .nest("/auth", auth::gen_service(state.clone(), version))
+            .nest("/file-convert", file_convert::gen_service(state.clone(), version))
+            .nest("/users", user::gen_service(state.clone(), version));
+
+        match version {
+            ApiVersion::V1 => router.merge(auth::gen_legacy_service(state)),
+            ApiVersion::V2 => router,
+        }
+    }
+
+    // v1 keeps the document URL it had before versioning, since tooling fetches it.
+    fn openapi_url(version: ApiVersion) -> Url<'static> {
+        match version {
+            ApiVersion::V1 => Url::with_primary("v1", "/api/api-doc/openapi.json", true),
+            ApiVersion::V2 => Url::new("v2", "/api/api-doc/v2/openapi.json"),
+        }
+    }
+
+    fn cors_layer(conf: &WebConfig) -> Result<CorsLayer> {
+        if conf.cors.permissive {
+            return Ok(CorsLayer::new()
+                .allow_origin(tower_http::cors::Any)
+                .allow_methods(tower_http::cors::Any)
+                .allow_headers(tower_http::cors::Any));
+        }
+
+        // Browsers send the origin without a trailing slash, unlike the configured web origin.
+        let origins = std::iter::once(&conf.origin)
+            .chain(conf.cors.extra_origins.iter())
+            .map(|n| HeaderValue::from_str(n.trim_end_matches('/')))
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|e| {
+                Error::builder()
+                    .kind(ErrorKind::InvalidFormat)
+                    .message("invalid cors origin")
+                    .source(e)
+                    .build()
+            })?;
+
+        Ok(CorsLayer::new()
+            .allow_origin(origins)
+            .allow_methods([Method::GET, Method::POST, Method::PUT, Method::PATCH, Method::DELETE])
+            .allow_headers([
+                header::AUTHORIZATION,
+                header::CONTENT_TYPE,
+                HeaderName::from_static(API_KEY_HEADER),
+                IDEMPOTENCY_KEY,
+                HeaderName::from_static("traceparent"),
+                HeaderName::from_static("tracestate"),
+            ])
+            .expose_headers([
+                HeaderName::from_static("x-request-id"),
+                header::RETRY_AFTER,
+                RATELIMIT_LIMIT,
+                RATELIMIT_REMAINING,
+                RATELIMIT_RESET,
+                IDEMPOTENT_REPLAYED,
+                DEPRECATION,
+                SUNSET,
+                header::LINK,
+            ])
+            .allow_credentials(true)
+            .max_age(Duration::from_secs(conf.cors.max_age_secs)))
+    }
+
+    async fn serve_http(conf: &ServerConfig, app: Router, metrics_app: Router) -> Result<()> {
+        let handle = Handle::new();
+        tokio::spawn({
+            let handle = handle.clone();
+            async move {
+                shutdown_signal().await;
+                info!("shutdown signal received, draining in-flight requests");
+                handle.graceful_shutdown(Some(GRACEFUL_SHUTDOWN_TIMEOUT));
+            }
+        });
+
+        if let Some(metrics_bind_address) = conf.metrics_bind_address {
+            let handle = handle.clone();
+            tokio::spawn(async move {
+                info!(addr = %metrics_bind_address, "serving metrics on http");
+                if let Err(e) = axum_server::bind(metrics_bind_address)
+                    .handle(handle)
+                    .serve(metrics_app.into_make_service())
+                    .await
+                {
+                    warn!(error = ?e, "metrics listener failed");
+                }
+            });
+        }
+
+        let service = app.into_make_service_with_connect_info::<SocketAddr>();
+        match &conf.tls {
+            Some(tls) => {
+                let rustls_config = RustlsConfig::from_pem_file(&tls.cert_path, &tls.key_path).await?;
+                info!(addr = %conf.bind_address, "listening on https");
+                axum_server::bind_rustls(conf.bind_address, rustls_config)
+                    .handle(handle)
+                    .serve(service)
+                    .await?;
+            }
+            None => {
+                info!(addr = %conf.bind_address, "listening on http");
+                axum_server::bind(conf.bind_address).handle(handle).serve(service).await?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            warn!(error = ?e, "failed to listen for ctrl-c");
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                warn!(error = ?e, "failed to listen for SIGTERM");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
 }
 
 static TSID_PROVIDER: LazyLock<Mutex<Box<dyn omnius_core_base::tsid::TsidProvider + Send + Sync>>> = LazyLock::new(|| {
@@ -116,6 +293,7 @@ impl MakeRequestId for MyRequestId {
     }
 }
 
+/// Routes served by every API version. Paths are documented under `/api/v1`, see `ApiVersion::openapi` for how later versions rewrite them.
 #[derive(OpenApi)]
 #[openapi(
     info(
@@ -128,31 +306,116 @@ impl MakeRequestId for MyRequestId {
     ),
     paths(
         health::check,
+        health::live,
+        health::ready,
+        admin::search_users,
+        admin::get_user,
+        admin::get_user_jobs,
+        admin::force_logout,
+        admin::disable_user,
+        admin::enable_user,
+        admin::update_role,
+        admin::failed_file_convert_jobs,
+        admin::retry_file_convert_job,
+        admin::failed_email_send_jobs,
+        admin::retry_email_send_job,
+        admin::audit_logs,
+        api_key::list,
+        api_key::create,
+        api_key::revoke,
         auth::me,
+        auth::methods,
+        auth::token_refresh,
+        auth::token_delete,
         auth::email::register,
+        auth::email::confirm,
+        auth::email::unregister,
         auth::email::login,
+        auth::email::resend,
+        auth::email::unlock,
+        auth::email::link,
+        auth::email::unlink,
+        auth::email::change_password,
+        auth::email::change,
+        auth::email::change_confirm,
         auth::google::nonce,
         auth::google::register,
         auth::google::login,
+        auth::google::unregister,
+        auth::google::link,
+        auth::google::unlink,
+        auth::provider::nonce,
+        auth::provider::register,
+        auth::provider::login,
+        auth::provider::unregister,
+        auth::provider::link,
+        auth::provider::unlink,
         file_convert::image::upload,
         file_convert::image::status,
+        user::me,
+        user::update_me,
+        user::upload_avatar,
+        user::create_export,
+        user::export_status,
     ),
     components(
         schemas(
             auth::email::RegisterInput,
+            auth::email::ConfirmInput,
             auth::email::LoginInput,
+            auth::email::ResendInput,
+            auth::email::UnlockInput,
+            auth::email::LinkInput,
+            auth::email::ChangePasswordInput,
+            auth::email::ChangeInput,
+            auth::email::ChangeConfirmInput,
             auth::google::NonceOutput,
             auth::google::RegisterInput,
             auth::google::LoginInput,
+            auth::google::LinkInput,
+            auth::provider::ProviderNonceOutput,
+            auth::provider::RegisterInput,
+            auth::provider::LoginInput,
+            auth::provider::LinkInput,
+            admin::UpdateRoleInput,
+            admin::UserJobsOutput,
+            api_key::CreateInput,
             omnius_opxs_auth::model::AuthToken,
+            omnius_opxs_auth::model::User,
+            omnius_opxs_auth::model::UserRole,
+            omnius_opxs_auth::model::UserAuthMethod,
+            omnius_opxs_auth::model::ApiKey,
+            omnius_opxs_auth::model::ApiKeyScope,
+            omnius_opxs_auth::model::IssuedApiKey,
+            omnius_opxs_auth::model::AdminUser,
+            omnius_opxs_auth::model::AdminUserDetail,
+            omnius_opxs_auth::model::UserSession,
+            omnius_opxs_auth::model::AdminAuditAction,
+            omnius_opxs_auth::model::AdminAuditLog,
             file_convert::image::UploadInput,
             file_convert::image::UploadOutput,
             file_convert::image::StatusInput,
             file_convert::image::StatusOutput,
             omnius_opxs_file_convert::FileConvertJobStatus,
+            omnius_opxs_file_convert::FileConvertJobType,
+            omnius_opxs_file_convert::FileConvertJob,
+            omnius_opxs_email_send::EmailSendJobBatchDetail,
+            omnius_opxs_email_send::EmailSendJobBatchDetailStatus,
+            omnius_opxs_data_export::DataExportJobStatus,
             omnius_opxs_file_convert::FileConvertImageInputFileType,
             omnius_opxs_file_convert::FileConvertImageOutputFileType,
+            user::ProfileOutput,
+            user::UpdateProfileInput,
+            user::AvatarUploadInput,
+            user::AvatarUploadOutput,
+            user::ExportOutput,
+            user::ExportStatusOutput,
+            omnius_opxs_auth::model::UserLocale,
+            crate::service::health::service::HealthStatus,
+            crate::service::health::service::ComponentHealth,
+            crate::service::health::service::ReadinessReport,
             crate::error::ApiErrorMessage,
+            crate::error::ApiErrorDetail,
             crate::error::ApiErrorCode
         )
     ),
@@ -160,6 +423,11 @@ impl MakeRequestId for MyRequestId {
 )]
 pub struct ApiDoc;
 
+/// Routes served only by v1.
+#[derive(OpenApi)]
+#[openapi(paths(auth::token_refresh_legacy))]
+pub struct ApiDocV1;
+
 struct SecurityAddon;
 
 impl utoipa::Modify for SecurityAddon {
@@ -174,3 +442,92 @@ impl utoipa::Modify for SecurityAddon {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        body::Body,
+        http::{Request, StatusCode},
+    };
+    use omnius_opxs_base::CorsConfig;
+    use testresult::TestResult;
+    use tower::ServiceExt as _;
+
+    use super::*;
+
+    fn gen_app(permissive: bool) -> TestResult<Router> {
+        let conf = WebConfig {
+            origin: "https://example.com/".to_string(),
+            cors: CorsConfig {
+                permissive,
+                extra_origins: vec!["http://localhost:3000".to_string()],
+                max_age_secs: 600,
+            },
+        };
+        Ok(Router::new()
+            .route("/ping", get(|| async { "pong" }))
+            .layer(WebServer::cors_layer(&conf)?))
+    }
+
+    fn preflight(origin: &str) -> TestResult<Request<Body>> {
+        Ok(Request::builder()
+            .method(Method::OPTIONS)
+            .uri("/ping")
+            .header(header::ORIGIN, origin)
+            .header(header::ACCESS_CONTROL_REQUEST_METHOD, "POST")
+            .header(header::ACCESS_CONTROL_REQUEST_HEADERS, "authorization,idempotency-key,x-api-key")
+            .body(Body::empty())?)
+    }
+
+    #[tokio::test]
+    async fn cors_test() -> TestResult {
+        let app = gen_app(false)?;
+
+        // the configured origin is matched without its trailing slash
+        let res = app.clone().oneshot(preflight("https://example.com")?).await?;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN).unwrap(), "https://example.com");
+        assert_eq!(res.headers().get(header::ACCESS_CONTROL_ALLOW_CREDENTIALS).unwrap(), "true");
+        assert_eq!(res.headers().get(header::ACCESS_CONTROL_MAX_AGE).unwrap(), "600");
+        let allow_headers = res.headers().get(header::ACCESS_CONTROL_ALLOW_HEADERS).unwrap().to_str()?;
+        assert!(allow_headers.contains("idempotency-key"));
+        assert!(allow_headers.contains(API_KEY_HEADER));
+
+        let req = Request::get("/ping")
+            .header(header::ORIGIN, "http://localhost:3000")
+            .body(Body::empty())?;
+        let res = app.clone().oneshot(req).await?;
+        assert_eq!(res.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN).unwrap(), "http://localhost:3000");
+        let expose_headers = res.headers().get(header::ACCESS_CONTROL_EXPOSE_HEADERS).unwrap().to_str()?;
+        assert!(expose_headers.contains("idempotent-replayed"));
+
+        let res = app.oneshot(preflight("https://evil.example.com")?).await?;
+        assert!(res.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN).is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn cors_permissive_test() -> TestResult {
+        let app = gen_app(true)?;
+
+        let res = app.oneshot(preflight("https://evil.example.com")?).await?;
+        assert_eq!(res.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN).unwrap(), "*");
+        assert!(res.headers().get(header::ACCESS_CONTROL_ALLOW_CREDENTIALS).is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn cors_invalid_origin_test() {
+        let conf = WebConfig {
+            origin: "https://example.com\n".to_string(),
+            cors: CorsConfig {
+                permissive: false,
+                extra_origins: vec![],
+                max_age_secs: 600,
+            },
+        };
+        assert_eq!(*WebServer::cors_layer(&conf).unwrap_err().kind(), ErrorKind::InvalidFormat);
+    }
+}
@@ -0,0 +1,566 @@
+use std::str::FromStr;
+
+use axum::{
+    extract::{Request, State},
+    http::{HeaderName, HeaderValue, header},
+    middleware::Next,
+    response::Response,
+};
+use chrono::{DateTime, Utc};
+use utoipa::{
+    OpenApi as _,
+    openapi::{Deprecated, OpenApi},
+};
+
+use crate::{
+    interface::{
+        features::auth,
+        server::{ApiDoc, ApiDocV1},
+    },
+    prelude::*,
+};
+
+pub(crate) const DEPRECATION: HeaderName = HeaderName::from_static("deprecation");
+pub(crate) const SUNSET: HeaderName = HeaderName::from_static("sunset");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApiVersion {
+    V1,
+    V2,
+}
+
+impl ApiVersion {
+    pub const ALL: [ApiVersion; 2] = [ApiVersion::V1, ApiVersion::V2];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApiVersion::V1 => "v1",
+            ApiVersion::V2 => "v2",
+        }
+    }
+
+    /// Routes shared by every version are documented under `/api/v1`, and later versions rewrite the prefix.
+    pub fn openapi(&self) -> OpenApi {
+        let mut doc = ApiDoc::openapi();
+        match self {
+            ApiVersion::V1 => {
+                doc.merge(ApiDocV1::openapi());
+                mark_deprecated(&mut doc, &auth::TOKEN_V1_DEPRECATED_PATHS);
+            }
+            ApiVersion::V2 => {
+                let v1_prefix = format!("/api/{}/", ApiVersion::V1.as_str());
+                let prefix = format!("/api/{}/", self.as_str());
+                doc.paths.paths = std::mem::take(&mut doc.paths.paths)
+                    .into_iter()
+                    .map(|(path, item)| match path.strip_prefix(&v1_prefix) {
+                        Some(rest) => (format!("{prefix}{rest}"), item),
+                        None => (path, item),
+                    })
+                    .collect();
+            }
+        }
+        doc.info.version = self.as_str().to_string();
+        doc
+    }
+}
+
+impl FromStr for ApiVersion {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        ApiVersion::ALL.into_iter().find(|n| n.as_str() == s).ok_or_else(|| {
+            Error::builder()
+                .kind(ErrorKind::InvalidFormat)
+                .message(format!("invalid api version: {s}"))
+                .build()
+        })
+    }
+}
+
+fn mark_deprecated(doc: &mut OpenApi, paths: &[&str]) {
+    for path in paths {
+        let Some(item) = doc.paths.paths.get_mut(*path) else {
+            continue;
+        };
+        for operation in [&mut item.get, &mut item.put, &mut item.post, &mut item.delete, &mut item.patch]
+            .into_iter()
+            .flatten()
+        {
+            operation.deprecated = Some(Deprecated::True);
+        }
+    }
+}
+
+/// Announces that an endpoint is deprecated (RFC 9745) and when it will be removed (RFC 8594).
+#[derive(Debug, Clone)]
+pub struct Deprecation {
+    pub deprecated_at: DateTime<Utc>,
+    pub sunset_at: DateTime<Utc>,
+    pub successor: Option<&'static str>,
+}
+
+pub async fn deprecated(State(deprecation): State<Deprecation>, req: Request, next: Next) -> Response {
+    let mut res = next.run(req).await;
+
+    let headers = res.headers_mut();
+    if let Ok(v) = HeaderValue::from_str(&format!("@{}", deprecation.deprecated_at.timestamp())) {
+        headers.insert(DEPRECATION, v);
+    }
+    if let Ok(v) = HeaderValue::from_str(&deprecation.sunset_at.format("%a, %d %b %Y %H:%M:%S GMT").to_string()) {
+        headers.insert(SUNSET, v);
+    }
+    if let Some(successor) = deprecation.successor
+        && let Ok(v) = HeaderValue::from_str(&format!("<{successor}>; rel=\"successor-version\""))
+    {
+        headers.append(header::LINK, v);
+    }
+
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeSet, sync::Arc};
+
+    use axum::{
+        Json, Router,
+        body::{Body, to_bytes},
+        http::StatusCode,
+        middleware,
+        response::IntoResponse as _,
+        routing::post,
+    };
+    use chrono::TimeZone as _;
+    use parking_lot::Mutex;
+    use serde_json::{Value, json};
+    use testresult::TestResult;
+    use tower::ServiceExt as _;
+
+    use omnius_opxs_client::{
+        OpxsClient,
+        api::{
+            api_key::CreateInput,
+            auth::{ChangeInput, ChangePasswordInput, LinkInput, LoginInput, ProviderInput, RegisterInput},
+            file_convert::UploadInput,
+            user::{AvatarUploadInput, UpdateProfileInput},
+        },
+        model::{ApiKeyScope, FileConvertImageInputFileType, FileConvertImageOutputFileType, UserLocale, UserRole},
+    };
+
+    use super::*;
+
+    const METHODS: [&str; 5] = ["get", "put", "post", "delete", "patch"];
+    const OAUTH2_STATE: &str = "oauth2_state=signed";
+
+    // The aggregate health check is left to monitoring tools, the client uses live and ready instead.
+    const CLIENT_UNCOVERED: [&str; 1] = ["get /api/v2/health"];
+
+    /// Stands in for the API as `ApiVersion::V2.openapi()` documents it.
+    /// Each request is answered with an example of the documented response, and requests the document does not allow are recorded.
+    #[derive(Clone)]
+    struct MockApi {
+        doc: Arc<Value>,
+        called: Arc<Mutex<BTreeSet<String>>>,
+        errors: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl MockApi {
+        fn ensure<T>(&self, res: omnius_opxs_client::Result<T>) -> T {
+            match res {
+                Ok(v) => v,
+                Err(e) => panic!("{e:?}: {:?}", self.errors.lock()),
+            }
+        }
+    }
+
+    async fn respond(State(mock): State<MockApi>, req: Request) -> Response {
+        match serve(&mock, req).await {
+            Ok(res) => res,
+            Err(e) => {
+                mock.errors.lock().push(e);
+                StatusCode::BAD_REQUEST.into_response()
+            }
+        }
+    }
+
+    async fn serve(mock: &MockApi, req: Request) -> std::result::Result<Response, String> {
+        let method = req.method().as_str().to_lowercase();
+        let path = req.uri().path().to_string();
+        let query = req.uri().query().unwrap_or_default().to_string();
+        let cookie = req.headers().get(header::COOKIE).and_then(|n| n.to_str().ok()).map(|n| n.to_string());
+        let body = to_bytes(req.into_body(), usize::MAX).await.map_err(|e| e.to_string())?;
+
+        let (template, operation) = find_operation(&mock.doc, &method, &path).ok_or_else(|| format!("{method} {path}: not documented"))?;
+        let key = format!("{method} {template}");
+        mock.called.lock().insert(key.clone());
+
+        let params: Vec<&Value> = operation["parameters"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter(|n| n["in"] == "query")
+            .collect();
+        let names: Vec<String> = url::form_urlencoded::parse(query.as_bytes()).map(|(n, _)| n.into_owned()).collect();
+        if let Some(name) = names.iter().find(|name| !params.iter().any(|n| n["name"] == name.as_str())) {
+            return Err(format!("{key}: query {name} is not documented"));
+        }
+        if let Some(param) = params
+            .iter()
+            .find(|n| n["required"] == true && !names.iter().any(|name| n["name"] == name.as_str()))
+        {
+            return Err(format!("{key}: query {} is required", param["name"]));
+        }
+
+        match operation["requestBody"]["content"]["application/json"].get("schema") {
+            Some(schema) => {
+                let value: Value = serde_json::from_slice(&body).map_err(|e| format!("{key}: {e}"))?;
+                validate(&mock.doc, schema, &value, &key)?;
+            }
+            None if !body.is_empty() => return Err(format!("{key}: request body is not documented")),
+            None => {}
+        }
+
+        let oauth2 = template.starts_with("/api/v2/auth/{provider}/") || template.starts_with("/api/v2/auth/google/");
+        let completes = method == "post" && ["/register", "/login", "/link"].iter().any(|n| template.ends_with(n));
+        if oauth2 && completes && cookie.as_deref() != Some(OAUTH2_STATE) {
+            return Err(format!("{key}: state cookie is not sent"));
+        }
+
+        let mut res = match operation["responses"]["200"]["content"]["application/json"].get("schema") {
+            Some(schema) => Json(example(&mock.doc, schema)).into_response(),
+            None => StatusCode::OK.into_response(),
+        };
+        if template.ends_with("/nonce") {
+            let cookie = HeaderValue::from_str(&format!("{OAUTH2_STATE}; Path=/")).map_err(|e| e.to_string())?;
+            res.headers_mut().insert(header::SET_COOKIE, cookie);
+        }
+        Ok(res)
+    }
+
+    // Literal segments win over path parameters, e.g. `/auth/google/nonce` over `/auth/{provider}/nonce`.
+    fn find_operation<'a>(doc: &'a Value, method: &str, path: &str) -> Option<(&'a str, &'a Value)> {
+        let segments: Vec<&str> = path.split('/').collect();
+        doc["paths"]
+            .as_object()?
+            .iter()
+            .filter_map(|(template, item)| Some((template.as_str(), item.get(method)?)))
+            .filter(|(template, _)| {
+                let parts: Vec<&str> = template.split('/').collect();
+                parts.len() == segments.len() && parts.iter().zip(&segments).all(|(p, s)| p == s || p.starts_with('{'))
+            })
+            .min_by_key(|(template, _)| template.matches('{').count())
+    }
+
+    fn resolve<'a>(doc: &'a Value, schema: &'a Value) -> &'a Value {
+        match schema.get("$ref").and_then(Value::as_str) {
+            Some(v) => resolve(doc, &doc["components"]["schemas"][v.trim_start_matches("#/components/schemas/")]),
+            None => schema,
+        }
+    }
+
+    fn types(schema: &Value) -> Vec<&str> {
+        match &schema["type"] {
+            Value::String(v) => vec![v.as_str()],
+            Value::Array(v) => v.iter().filter_map(Value::as_str).collect(),
+            _ => vec![],
+        }
+    }
+
+    fn example(doc: &Value, schema: &Value) -> Value {
+        let schema = resolve(doc, schema);
+        if let Some(variants) = schema.get("oneOf").or_else(|| schema.get("anyOf")).and_then(Value::as_array) {
+            return variants
+                .iter()
+                .find(|n| n["type"] != "null")
+                .map(|n| example(doc, n))
+                .unwrap_or(Value::Null);
+        }
+        if let Some(all) = schema.get("allOf").and_then(Value::as_array) {
+            let mut merged = serde_json::Map::new();
+            for part in all {
+                match example(doc, part) {
+                    Value::Object(v) => merged.extend(v),
+                    v => return v,
+                }
+            }
+            return Value::Object(merged);
+        }
+        if let Some(values) = schema.get("enum").and_then(Value::as_array) {
+            return values.iter().find(|n| !n.is_null()).cloned().unwrap_or(Value::Null);
+        }
+
+        match types(schema).into_iter().find(|n| *n != "null") {
+            Some("string") if schema["format"] == "date-time" => json!("2099-01-01T00:00:00"),
+            Some("string") => json!("text"),
+            Some("integer") | Some("number") => json!(0),
+            Some("boolean") => json!(false),
+            Some("array") => json!([example(doc, &schema["items"])]),
+            _ => Value::Object(
+                schema["properties"]
+                    .as_object()
+                    .into_iter()
+                    .flatten()
+                    .map(|(name, property)| (name.clone(), example(doc, property)))
+                    .collect(),
+            ),
+        }
+    }
+
+    fn validate(doc: &Value, schema: &Value, value: &Value, at: &str) -> std::result::Result<(), String> {
+        let schema = resolve(doc, schema);
+        if let Some(variants) = schema.get("oneOf").or_else(|| schema.get("anyOf")).and_then(Value::as_array) {
+            return match variants.iter().any(|n| validate(doc, n, value, at).is_ok()) {
+                true => Ok(()),
+                false => Err(format!("{at}: {value} matches no variant")),
+            };
+        }
+        if let Some(all) = schema.get("allOf").and_then(Value::as_array) {
+            return all.iter().try_for_each(|n| validate(doc, n, value, at));
+        }
+
+        let types = types(schema);
+        if value.is_null() {
+            return match types.contains(&"null") {
+                true => Ok(()),
+                false => Err(format!("{at}: null is not allowed")),
+            };
+        }
+        if let Some(values) = schema.get("enum").and_then(Value::as_array)
+            && !values.contains(value)
+        {
+            return Err(format!("{at}: {value} is not one of {values:?}"));
+        }
+
+        match types.into_iter().find(|n| *n != "null") {
+            Some("string") if value.is_string() => Ok(()),
+            Some("integer") if value.is_i64() || value.is_u64() => Ok(()),
+            Some("number") if value.is_number() => Ok(()),
+            Some("boolean") if value.is_boolean() => Ok(()),
+            Some("array") if value.is_array() => value
+                .as_array()
+                .into_iter()
+                .flatten()
+                .enumerate()
+                .try_for_each(|(i, item)| validate(doc, &schema["items"], item, &format!("{at}[{i}]"))),
+            Some("object") | None if value.is_object() => {
+                let object = value.as_object().into_iter().flatten();
+                for (name, v) in object {
+                    let Some(property) = schema["properties"].get(name) else {
+                        return Err(format!("{at}.{name}: not documented"));
+                    };
+                    validate(doc, property, v, &format!("{at}.{name}"))?;
+                }
+                match schema["required"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(Value::as_str)
+                    .find(|n| value.get(n).is_none())
+                {
+                    Some(name) => Err(format!("{at}.{name}: required")),
+                    None => Ok(()),
+                }
+            }
+            None => Ok(()),
+            Some(typ) => Err(format!("{at}: {value} is not {typ}")),
+        }
+    }
+
+    #[tokio::test]
+    async fn deprecated_test() -> TestResult {
+        let deprecation = Deprecation {
+            deprecated_at: Utc.with_ymd_and_hms(2026, 11, 1, 0, 0, 0).unwrap(),
+            sunset_at: Utc.with_ymd_and_hms(2027, 5, 1, 0, 0, 0).unwrap(),
+            successor: Some("/api/v2/auth/token"),
+        };
+        let app = Router::new()
+            .route("/token", post(|| async {}))
+            .layer(middleware::from_fn_with_state(deprecation, deprecated));
+
+        let res = app.oneshot(Request::post("/token").body(Body::empty())?).await?;
+        assert_eq!(res.headers()[DEPRECATION], "@1793491200");
+        assert_eq!(res.headers()[SUNSET], "Sat, 01 May 2027 00:00:00 GMT");
+        assert_eq!(res.headers()[header::LINK], "</api/v2/auth/token>; rel=\"successor-version\"");
+
+        // Each version documents its own routes, and only v1 carries the deprecated token paths.
+        let v1 = ApiVersion::V1.openapi();
+        assert!(matches!(
+            v1.paths.paths["/api/v1/token"].post.as_ref().unwrap().deprecated,
+            Some(Deprecated::True)
+        ));
+        assert!(matches!(
+            v1.paths.paths["/api/v1/auth/token"].delete.as_ref().unwrap().deprecated,
+            Some(Deprecated::True)
+        ));
+        assert!(v1.paths.paths.contains_key("/api/v1/users/me"));
+        let v2 = ApiVersion::V2.openapi();
+        assert!(!v2.paths.paths.contains_key("/api/v2/token"));
+        assert!(!v2.paths.paths.keys().any(|n| n.starts_with("/api/v1/")));
+        assert!(v2.paths.paths.contains_key("/api/v2/health"));
+        assert!(v2.paths.paths.contains_key("/api/v2/auth/email/confirm"));
+        assert!(!matches!(
+            v2.paths.paths["/api/v2/auth/token"].post.as_ref().unwrap().deprecated,
+            Some(Deprecated::True)
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn client_test() -> TestResult {
+        let mock = MockApi {
+            doc: Arc::new(serde_json::to_value(ApiVersion::V2.openapi())?),
+            called: Default::default(),
+            errors: Default::default(),
+        };
+        let app = Router::new().fallback(respond).with_state(mock.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let client = OpxsClient::new(&format!("http://{}", listener.local_addr()?))?;
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let auth = client.auth();
+        mock.ensure(
+            auth.email_register(&RegisterInput {
+                name: "name".to_string(),
+                email: "user@example.com".to_string(),
+                password: "password".to_string(),
+                locale: UserLocale::En,
+            })
+            .await,
+        );
+        mock.ensure(auth.email_confirm("token").await);
+        mock.ensure(auth.email_resend("user@example.com").await);
+        mock.ensure(
+            auth.email_login(&LoginInput {
+                email: "user@example.com".to_string(),
+                password: "password".to_string(),
+            })
+            .await,
+        );
+        mock.ensure(auth.email_unlock("token").await);
+        mock.ensure(auth.email_change_confirm("token").await);
+        mock.ensure(auth.me().await);
+        mock.ensure(auth.methods().await);
+        mock.ensure(auth.refresh().await);
+        mock.ensure(
+            auth.email_link(&LinkInput {
+                email: "user@example.com".to_string(),
+                password: "password".to_string(),
+            })
+            .await,
+        );
+        mock.ensure(auth.email_unlink().await);
+        mock.ensure(
+            auth.email_change_password(&ChangePasswordInput {
+                current_password: "password".to_string(),
+                new_password: "new_password".to_string(),
+            })
+            .await,
+        );
+        mock.ensure(
+            auth.email_change(&ChangeInput {
+                new_email: "new@example.com".to_string(),
+                password: "password".to_string(),
+            })
+            .await,
+        );
+        mock.ensure(auth.email_unregister().await);
+
+        let input = ProviderInput {
+            redirect_uri: "https://example.com/callback".to_string(),
+            code: "code".to_string(),
+            state: "state".to_string(),
+        };
+        for provider in ["google", "github"] {
+            mock.ensure(auth.provider_nonce(provider).await);
+            mock.ensure(auth.provider_register(provider, &input).await);
+            mock.ensure(auth.provider_nonce(provider).await);
+            mock.ensure(auth.provider_login(provider, &input).await);
+            mock.ensure(auth.provider_nonce(provider).await);
+            mock.ensure(auth.provider_link(provider, &input).await);
+            mock.ensure(auth.provider_unlink(provider).await);
+            mock.ensure(auth.provider_unregister(provider).await);
+        }
+
+        let api_key = client.api_key();
+        mock.ensure(api_key.list().await);
+        mock.ensure(
+            api_key
+                .create(&CreateInput {
+                    name: "ci".to_string(),
+                    scopes: vec![ApiKeyScope::FileConvert],
+                    expires_at: None,
+                })
+                .await,
+        );
+        mock.ensure(api_key.revoke("api_key_id").await);
+
+        let user = client.user();
+        mock.ensure(user.me().await);
+        mock.ensure(
+            user.update_me(&UpdateProfileInput {
+                time_zone: Some("Asia/Tokyo".to_string()),
+                ..Default::default()
+            })
+            .await,
+        );
+        mock.ensure(
+            user.upload_avatar(&AvatarUploadInput {
+                in_file_name: "avatar.png".to_string(),
+                in_type: FileConvertImageInputFileType::Png,
+            })
+            .await,
+        );
+        mock.ensure(user.create_export().await);
+        mock.ensure(user.export_status("job_id").await);
+
+        let file_convert = client.file_convert();
+        mock.ensure(
+            file_convert
+                .image_upload(&UploadInput {
+                    in_file_name: "in.png".to_string(),
+                    in_type: FileConvertImageInputFileType::Png,
+                    out_file_name: "out.webp".to_string(),
+                    out_type: FileConvertImageOutputFileType::WebP,
+                })
+                .await,
+        );
+        mock.ensure(file_convert.image_status("job_id").await);
+
+        let admin = client.admin();
+        mock.ensure(admin.search_users("user", Some(10)).await);
+        mock.ensure(admin.get_user("user_id").await);
+        mock.ensure(admin.get_user_jobs("user_id").await);
+        mock.ensure(admin.force_logout("user_id").await);
+        mock.ensure(admin.disable_user("user_id").await);
+        mock.ensure(admin.enable_user("user_id").await);
+        mock.ensure(admin.update_role("user_id", UserRole::Admin).await);
+        mock.ensure(admin.failed_file_convert_jobs(Some(10)).await);
+        mock.ensure(admin.retry_file_convert_job("job_id").await);
+        mock.ensure(admin.failed_email_send_jobs(None).await);
+        mock.ensure(admin.retry_email_send_job("job_id").await);
+        mock.ensure(admin.audit_logs(Some("user_id"), Some(10)).await);
+
+        mock.ensure(client.health().live().await);
+        mock.ensure(client.health().ready().await);
+        mock.ensure(auth.logout().await);
+
+        assert!(mock.errors.lock().is_empty(), "{:?}", mock.errors.lock());
+
+        let documented: BTreeSet<String> = mock.doc["paths"]
+            .as_object()
+            .into_iter()
+            .flatten()
+            .flat_map(|(template, item)| {
+                METHODS
+                    .iter()
+                    .filter(|method| item.get(*method).is_some())
+                    .map(move |method| format!("{method} {template}"))
+            })
+            .collect();
+        let uncovered: Vec<String> = documented.difference(&mock.called.lock()).cloned().collect();
+        assert_eq!(uncovered, CLIENT_UNCOVERED);
+
+        Ok(())
+    }
+}
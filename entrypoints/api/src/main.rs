@@ -1,17 +1,19 @@
+use std::net::{IpAddr, SocketAddr};
 use std::path::PathBuf;
 use std::sync::Arc;
 
 use clap::Parser;
 use tracing::info;
-use tracing_subscriber::EnvFilter;
-use utoipa::OpenApi as _;
 
 use omnius_core_base::clock::ClockUtc;
 use omnius_core_migration::postgres::PostgresMigrator;
 
-use omnius_opxs_base::{AppConfig, AppInfo, RunMode, WorldValidator};
+use omnius_opxs_base::{
+    AppConfig, AppInfo, RunMode, ServeMode, TlsConfig, WorldValidator,
+    telemetry::{Telemetry, TelemetryConfig},
+};
 
-use crate::{interface::ApiDoc, shared::state::AppState};
+use crate::{interface::ApiVersion, shared::state::AppState};
 
 mod emulator;
 mod error;
@@ -27,6 +29,31 @@ const APP_NAME: &str = "opxs-api";
 struct Args {
     #[arg(long = "gen-openapi", value_name = "PATH")]
     gen_openapi: Option<PathBuf>,
+
+    /// API version of the generated OpenAPI document (v1, v2).
+    #[arg(long = "api-version", value_name = "VERSION", default_value = "v1", requires = "gen_openapi")]
+    api_version: ApiVersion,
+
+    /// Overrides the configured serving mode (http, lambda).
+    #[arg(long = "serve-mode", value_name = "MODE")]
+    serve_mode: Option<ServeMode>,
+
+    #[arg(long = "bind", value_name = "ADDR")]
+    bind_address: Option<SocketAddr>,
+
+    #[arg(long = "tls-cert", value_name = "PATH", requires = "tls_key")]
+    tls_cert: Option<PathBuf>,
+
+    #[arg(long = "tls-key", value_name = "PATH", requires = "tls_cert")]
+    tls_key: Option<PathBuf>,
+
+    /// Reverse proxy whose X-Forwarded-For entries are trusted. Repeatable.
+    #[arg(long = "trusted-proxy", value_name = "IP")]
+    trusted_proxies: Vec<IpAddr>,
+
+    /// Serves /metrics on this address in http mode. Disabled by default.
+    #[arg(long = "metrics-bind", value_name = "ADDR")]
+    metrics_bind_address: Option<SocketAddr>,
 }
 
 #[tokio::main]
@@ -38,20 +65,14 @@ async fn main() -> anyhow::Result<()> {
             std::fs::create_dir_all(parent)?;
         }
 
-        let yaml = ApiDoc::openapi().to_yaml().unwrap();
+        let yaml = args.api_version.openapi().to_yaml().unwrap();
         std::fs::write(&path, yaml)?;
         println!("openapi generated: {}", path.display());
 
         return Ok(());
     }
 
-    if cfg!(debug_assertions) {
-        let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info,sqlx=off"));
-        tracing_subscriber::fmt().with_env_filter(filter).with_target(false).init();
-    } else {
-        let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info,sqlx=off"));
-        tracing_subscriber::fmt().with_env_filter(filter).with_target(false).json().init();
-    }
+    let telemetry = Arc::new(Telemetry::init(APP_NAME, &TelemetryConfig::from_env())?);
 
     info!("----- start -----");
 
@@ -59,7 +80,20 @@ async fn main() -> anyhow::Result<()> {
     let info = AppInfo::new(APP_NAME, mode)?;
     info!("info: {}", info);
 
-    let conf = AppConfig::load(&info).await?;
+    let mut conf = AppConfig::load(&info).await?;
+    if let Some(mode) = args.serve_mode {
+        conf.server.mode = mode;
+    }
+    if let Some(bind_address) = args.bind_address {
+        conf.server.bind_address = bind_address;
+    }
+    if let (Some(cert_path), Some(key_path)) = (args.tls_cert, args.tls_key) {
+        conf.server.tls = Some(TlsConfig { cert_path, key_path });
+    }
+    conf.server.trusted_proxies.extend(args.trusted_proxies);
+    if let Some(metrics_bind_address) = args.metrics_bind_address {
+        conf.server.metrics_bind_address = Some(metrics_bind_address);
+    }
 
     let clock = Arc::new(ClockUtc {});
     let world_verifier = WorldValidator::new(&info, &conf.postgres.url, clock).await?;
@@ -69,11 +103,13 @@ async fn main() -> anyhow::Result<()> {
         world_verifier.notify(notify_conf).await?;
     }
 
-    let migrator = PostgresMigrator::new(&conf.postgres.url, "./conf/migrations", APP_NAME, "").await?;
+    let migrator = PostgresMigrator::new(&conf.postgres.url, shared::MIGRATIONS_DIR, APP_NAME, "").await?;
     migrator.migrate().await?;
 
     let state = AppState::new(info, conf).await?;
-    interface::WebServer::serve(state).await?;
+    interface::WebServer::serve(state, telemetry.clone()).await?;
+
+    telemetry.shutdown();
 
     Ok(())
 }
@@ -1 +1,4 @@
 pub mod health;
+pub mod idempotency;
+pub mod mailer;
+pub mod rate_limit;
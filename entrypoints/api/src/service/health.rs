@@ -1,2 +1,3 @@
+pub mod probe;
 pub mod repo;
 pub mod service;
@@ -0,0 +1,197 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use async_trait::async_trait;
+
+use omnius_core_cloud::aws::s3::S3ClientImpl;
+
+use crate::prelude::*;
+
+use super::repo::WorldRepo;
+
+#[async_trait]
+pub trait HealthProbe {
+    fn name(&self) -> &str;
+    /// Whether a failure of this component makes the whole instance not ready.
+    /// Non-critical failures only degrade the features that depend on them.
+    fn critical(&self) -> bool;
+    async fn check(&self) -> Result<()>;
+}
+
+pub struct DatabaseProbe {
+    pub world_repo: Arc<WorldRepo>,
+}
+
+#[async_trait]
+impl HealthProbe for DatabaseProbe {
+    fn name(&self) -> &str {
+        "database"
+    }
+
+    fn critical(&self) -> bool {
+        true
+    }
+
+    async fn check(&self) -> Result<()> {
+        self.world_repo.ping().await
+    }
+}
+
+pub struct MigrationProbe {
+    pub world_repo: Arc<WorldRepo>,
+    pub migrations_dir: PathBuf,
+}
+
+#[async_trait]
+impl HealthProbe for MigrationProbe {
+    fn name(&self) -> &str {
+        "migration"
+    }
+
+    fn critical(&self) -> bool {
+        true
+    }
+
+    async fn check(&self) -> Result<()> {
+        let mut expected: Option<String> = None;
+        let mut entries = tokio::fs::read_dir(&self.migrations_dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().is_some_and(|n| n == "sql")
+                && let Some(stem) = path.file_stem().map(|n| n.to_string_lossy().to_string())
+                && expected.as_ref().is_none_or(|n| *n < stem)
+            {
+                expected = Some(stem);
+            }
+        }
+        let Some(expected) = expected else {
+            return Ok(());
+        };
+
+        let applied = self.world_repo.get_latest_migration().await?.unwrap_or_default();
+        if !applied.starts_with(&expected) {
+            return Err(Error::builder()
+                .kind(ErrorKind::UnexpectedError)
+                .message(format!("expected migration {expected}, but applied {applied}"))
+                .build());
+        }
+
+        Ok(())
+    }
+}
+
+pub struct S3Probe {
+    pub name: String,
+    pub s3_client: Arc<S3ClientImpl>,
+}
+
+#[async_trait]
+impl HealthProbe for S3Probe {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn critical(&self) -> bool {
+        false
+    }
+
+    async fn check(&self) -> Result<()> {
+        self.s3_client
+            .client
+            .head_bucket()
+            .bucket(&self.s3_client.bucket)
+            .send()
+            .await
+            .map_err(|e| {
+                Error::builder()
+                    .kind(ErrorKind::AwsError)
+                    .message("s3 head bucket failed")
+                    .source(e)
+                    .build()
+            })?;
+        Ok(())
+    }
+}
+
+pub struct SqsProbe {
+    pub name: String,
+    pub client: aws_sdk_sqs::Client,
+    pub queue_url: String,
+}
+
+#[async_trait]
+impl HealthProbe for SqsProbe {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn critical(&self) -> bool {
+        false
+    }
+
+    async fn check(&self) -> Result<()> {
+        self.client
+            .get_queue_attributes()
+            .queue_url(&self.queue_url)
+            .attribute_names(aws_sdk_sqs::types::QueueAttributeName::ApproximateNumberOfMessages)
+            .send()
+            .await
+            .map_err(|e| {
+                Error::builder()
+                    .kind(ErrorKind::AwsError)
+                    .message("sqs get queue attributes failed")
+                    .source(e)
+                    .build()
+            })?;
+        Ok(())
+    }
+}
+
+pub struct ImageConverterProbe {
+    pub path: PathBuf,
+}
+
+impl ImageConverterProbe {
+    pub fn from_env() -> Self {
+        let dir = std::env::var("IMAGE_CONVERTER_DIR").unwrap_or_default();
+        Self {
+            path: Path::new(&dir).join("Omnius.ImageConverter"),
+        }
+    }
+}
+
+#[async_trait]
+impl HealthProbe for ImageConverterProbe {
+    fn name(&self) -> &str {
+        "image_converter"
+    }
+
+    fn critical(&self) -> bool {
+        false
+    }
+
+    async fn check(&self) -> Result<()> {
+        let metadata = tokio::fs::metadata(&self.path).await?;
+        if !metadata.is_file() {
+            return Err(Error::builder()
+                .kind(ErrorKind::NotFound)
+                .message(format!("{} is not a file", self.path.display()))
+                .build());
+        }
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt as _;
+            if metadata.permissions().mode() & 0o111 == 0 {
+                return Err(Error::builder()
+                    .kind(ErrorKind::Forbidden)
+                    .message(format!("{} is not executable", self.path.display()))
+                    .build());
+            }
+        }
+
+        Ok(())
+    }
+}
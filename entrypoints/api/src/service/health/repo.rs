@@ -17,4 +17,18 @@ impl WorldRepo {
         row.try_get("value")
             .map_err(|e| Error::builder().kind(ErrorKind::UnexpectedError).source(e).build())
     }
+
+    pub async fn ping(&self) -> Result<()> {
+        sqlx::query("SELECT 1").execute(self.db.as_ref()).await?;
+        Ok(())
+    }
+
+    pub async fn get_latest_migration(&self) -> Result<Option<String>> {
+        let row = sqlx::query("SELECT MAX(name) AS name FROM _migrations")
+            .fetch_one(self.db.as_ref())
+            .await?;
+
+        row.try_get("name")
+            .map_err(|e| Error::builder().kind(ErrorKind::UnexpectedError).source(e).build())
+    }
 }
@@ -1,16 +1,49 @@
-use std::sync::Arc;
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
+use futures::future::join_all;
 use omnius_opxs_base::AppInfo;
+use serde::Serialize;
 use serde_json::{Value, json};
+use utoipa::ToSchema;
 
-use super::repo::WorldRepo;
+use super::{probe::HealthProbe, repo::WorldRepo};
 
 use crate::prelude::*;
 
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthStatus {
+    Up,
+    Degraded,
+    Down,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct ComponentHealth {
+    pub name: String,
+    pub status: HealthStatus,
+    pub critical: bool,
+    pub latency_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct ReadinessReport {
+    pub status: HealthStatus,
+    pub components: Vec<ComponentHealth>,
+}
+
 #[derive(Clone)]
 pub struct HealthService {
     pub info: AppInfo,
     pub world_repo: Arc<WorldRepo>,
+    pub probes: Vec<Arc<dyn HealthProbe + Send + Sync>>,
 }
 
 impl HealthService {
@@ -21,6 +54,46 @@ impl HealthService {
         });
         Ok(ret)
     }
+
+    pub async fn ready(&self) -> ReadinessReport {
+        let components = join_all(self.probes.iter().map(|n| Self::run_probe(n.as_ref()))).await;
+
+        let status = if components.iter().any(|n| n.critical && n.status == HealthStatus::Down) {
+            HealthStatus::Down
+        } else if components.iter().any(|n| n.status == HealthStatus::Down) {
+            HealthStatus::Degraded
+        } else {
+            HealthStatus::Up
+        };
+
+        ReadinessReport { status, components }
+    }
+
+    async fn run_probe(probe: &(dyn HealthProbe + Send + Sync)) -> ComponentHealth {
+        let start = Instant::now();
+        let res = tokio::time::timeout(PROBE_TIMEOUT, probe.check()).await;
+        let latency_ms = start.elapsed().as_millis() as u64;
+
+        let (status, message) = match res {
+            Ok(Ok(())) => (HealthStatus::Up, None),
+            Ok(Err(e)) => {
+                warn!(component = probe.name(), error = ?e, "health probe failed");
+                (HealthStatus::Down, Some(e.to_string()))
+            }
+            Err(_) => {
+                warn!(component = probe.name(), "health probe timed out");
+                (HealthStatus::Down, Some("timed out".to_string()))
+            }
+        };
+
+        ComponentHealth {
+            name: probe.name().to_string(),
+            status,
+            critical: probe.critical(),
+            latency_ms,
+            message,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -34,9 +107,16 @@ mod tests {
     use sqlx::postgres::PgPoolOptions;
     use testresult::TestResult;
 
+    use omnius_core_migration::postgres::PostgresMigrator;
     use omnius_core_testkit::containers::postgres::PostgresContainer;
 
-    use crate::{service::health::repo::WorldRepo, shared};
+    use crate::{
+        service::health::{
+            probe::{DatabaseProbe, HealthProbe, ImageConverterProbe, MigrationProbe},
+            repo::WorldRepo,
+        },
+        shared,
+    };
 
     use super::*;
 
@@ -62,7 +142,28 @@ mod tests {
                 .await?,
         );
         let world_repo = Arc::new(WorldRepo { db });
-        let service = HealthService { info, world_repo };
+
+        let migrations_dir = concat!(env!("CARGO_MANIFEST_DIR"), "/../../conf/migrations");
+        let working_dir = tempfile::tempdir()?;
+        let image_converter_path = working_dir.path().join("Omnius.ImageConverter");
+        std::fs::write(&image_converter_path, b"")?;
+
+        let service = HealthService {
+            info: info.clone(),
+            world_repo: world_repo.clone(),
+            probes: vec![
+                Arc::new(DatabaseProbe {
+                    world_repo: world_repo.clone(),
+                }) as Arc<dyn HealthProbe + Send + Sync>,
+                Arc::new(MigrationProbe {
+                    world_repo: world_repo.clone(),
+                    migrations_dir: migrations_dir.into(),
+                }),
+                Arc::new(ImageConverterProbe {
+                    path: image_converter_path.clone(),
+                }),
+            ],
+        };
 
         assert_eq!(
             service.check().await?,
@@ -72,6 +173,30 @@ mod tests {
             })
         );
 
+        // Migrations are not applied yet and the converter is not executable.
+        let report = service.ready().await;
+        assert_eq!(report.status, HealthStatus::Down);
+        let statuses: Vec<(&str, HealthStatus)> = report.components.iter().map(|n| (n.name.as_str(), n.status)).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                ("database", HealthStatus::Up),
+                ("migration", HealthStatus::Down),
+                ("image_converter", HealthStatus::Down),
+            ]
+        );
+
+        let migrator = PostgresMigrator::new(&container.connection_string, migrations_dir, "opxs-api", "").await?;
+        migrator.migrate().await?;
+        assert_eq!(service.ready().await.status, HealthStatus::Degraded);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt as _;
+            std::fs::set_permissions(&image_converter_path, std::fs::Permissions::from_mode(0o755))?;
+            assert_eq!(service.ready().await.status, HealthStatus::Up);
+        }
+
         Ok(())
     }
 }
@@ -0,0 +1,102 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use sqlx::PgPool;
+
+use crate::prelude::*;
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct IdempotencyRecord {
+    pub key: String,
+    pub fingerprint: String,
+    pub status_code: Option<i32>,
+    pub content_type: Option<String>,
+    pub body: Option<Vec<u8>>,
+    pub expires_at: NaiveDateTime,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+pub struct IdempotencyRepo {
+    pub db: Arc<PgPool>,
+}
+
+impl IdempotencyRepo {
+    // Expired rows and in-progress rows older than `stale_before` are replaced in the same statement,
+    // so that a key can be reused after its TTL or after the request holding it died without releasing it.
+    pub async fn try_insert(
+        &self,
+        key: &str,
+        fingerprint: &str,
+        expires_at: &DateTime<Utc>,
+        stale_before: &DateTime<Utc>,
+        now: &DateTime<Utc>,
+    ) -> Result<bool> {
+        let res = sqlx::query(
+            r#"
+INSERT INTO idempotency_keys (key, fingerprint, expires_at, created_at, updated_at)
+    VALUES ($1, $2, $3, $4, $4)
+    ON CONFLICT (key) DO UPDATE SET
+        fingerprint = $2, status_code = NULL, content_type = NULL, body = NULL, expires_at = $3, created_at = $4, updated_at = $4
+    WHERE idempotency_keys.expires_at <= $4 OR (idempotency_keys.status_code IS NULL AND idempotency_keys.updated_at <= $5);
+"#,
+        )
+        .bind(key)
+        .bind(fingerprint)
+        .bind(expires_at)
+        .bind(now)
+        .bind(stale_before)
+        .execute(self.db.as_ref())
+        .await?;
+
+        Ok(res.rows_affected() == 1)
+    }
+
+    pub async fn get(&self, key: &str) -> Result<Option<IdempotencyRecord>> {
+        let record: Option<IdempotencyRecord> = sqlx::query_as(
+            r#"
+SELECT *
+    FROM idempotency_keys
+    WHERE key = $1;
+"#,
+        )
+        .bind(key)
+        .fetch_optional(self.db.as_ref())
+        .await?;
+
+        Ok(record)
+    }
+
+    pub async fn update_response(&self, key: &str, status_code: i32, content_type: Option<&str>, body: &[u8], now: &DateTime<Utc>) -> Result<()> {
+        sqlx::query(
+            r#"
+UPDATE idempotency_keys
+    SET status_code = $2, content_type = $3, body = $4, updated_at = $5
+    WHERE key = $1;
+"#,
+        )
+        .bind(key)
+        .bind(status_code)
+        .bind(content_type)
+        .bind(body)
+        .bind(now)
+        .execute(self.db.as_ref())
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete(&self, key: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+DELETE FROM idempotency_keys
+    WHERE key = $1;
+"#,
+        )
+        .bind(key)
+        .execute(self.db.as_ref())
+        .await?;
+
+        Ok(())
+    }
+}
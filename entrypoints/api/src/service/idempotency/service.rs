@@ -0,0 +1,149 @@
+use std::sync::Arc;
+
+use chrono::{Duration, Utc};
+
+use omnius_core_base::clock::Clock;
+
+use super::repo::IdempotencyRepo;
+
+use crate::prelude::*;
+
+const KEY_EXPIRES_IN: Duration = Duration::hours(24);
+// Longer than any request can run, so only records left behind by a crashed instance are taken over.
+const IN_PROGRESS_TIMEOUT: Duration = Duration::minutes(5);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredResponse {
+    pub status_code: u16,
+    pub content_type: Option<String>,
+    pub body: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IdempotencyState {
+    Started,
+    InProgress,
+    Mismatch,
+    Completed(StoredResponse),
+}
+
+#[derive(Clone)]
+pub struct IdempotencyService {
+    pub idempotency_repo: Arc<IdempotencyRepo>,
+    pub clock: Arc<dyn Clock<Utc> + Send + Sync>,
+}
+
+impl IdempotencyService {
+    pub async fn begin(&self, key: &str, fingerprint: &str) -> Result<IdempotencyState> {
+        let now = self.clock.now();
+        let expires_at = now + KEY_EXPIRES_IN;
+        let stale_before = now - IN_PROGRESS_TIMEOUT;
+
+        if self
+            .idempotency_repo
+            .try_insert(key, fingerprint, &expires_at, &stale_before, &now)
+            .await?
+        {
+            return Ok(IdempotencyState::Started);
+        }
+
+        // The row can only vanish between the two statements if the first request released it.
+        let Some(record) = self.idempotency_repo.get(key).await? else {
+            return Ok(IdempotencyState::InProgress);
+        };
+
+        if record.fingerprint != fingerprint {
+            return Ok(IdempotencyState::Mismatch);
+        }
+
+        match (record.status_code, record.body) {
+            (Some(status_code), Some(body)) => Ok(IdempotencyState::Completed(StoredResponse {
+                status_code: u16::try_from(status_code).map_err(|e| Error::builder().kind(ErrorKind::InvalidFormat).source(e).build())?,
+                content_type: record.content_type,
+                body,
+            })),
+            _ => Ok(IdempotencyState::InProgress),
+        }
+    }
+
+    pub async fn complete(&self, key: &str, response: &StoredResponse) -> Result<()> {
+        let now = self.clock.now();
+        self.idempotency_repo
+            .update_response(key, response.status_code as i32, response.content_type.as_deref(), &response.body, &now)
+            .await
+    }
+
+    pub async fn release(&self, key: &str) -> Result<()> {
+        self.idempotency_repo.delete(key).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::DateTime;
+    use sqlx::postgres::PgPoolOptions;
+    use testresult::TestResult;
+
+    use omnius_core_migration::postgres::PostgresMigrator;
+    use omnius_core_testkit::containers::postgres::PostgresContainer;
+
+    use omnius_opxs_base::testkit::ClockMock;
+
+    use crate::shared;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn simple_test() -> TestResult {
+        let container = PostgresContainer::new(shared::POSTGRES_VERSION).await?;
+
+        let db = Arc::new(
+            PgPoolOptions::new()
+                .max_connections(100)
+                .idle_timeout(Some(Duration::minutes(15).to_std()?))
+                .connect(&container.connection_string)
+                .await?,
+        );
+
+        let migrations_path = concat!(env!("CARGO_MANIFEST_DIR"), "/../../conf/migrations");
+        let migrator = PostgresMigrator::new(&container.connection_string, migrations_path, "opxs-api", "").await?;
+        migrator.migrate().await?;
+
+        let clock = Arc::new(ClockMock::new(DateTime::parse_from_rfc3339("2000-01-01T00:00:00Z")?.with_timezone(&Utc)));
+        let service = IdempotencyService {
+            idempotency_repo: Arc::new(IdempotencyRepo { db }),
+            clock: clock.clone(),
+        };
+
+        assert_eq!(service.begin("user:key", "fingerprint").await?, IdempotencyState::Started);
+        assert_eq!(service.begin("user:key", "fingerprint").await?, IdempotencyState::InProgress);
+        assert_eq!(service.begin("user:key", "other").await?, IdempotencyState::Mismatch);
+
+        let response = StoredResponse {
+            status_code: 200,
+            content_type: Some("application/json".to_string()),
+            body: b"{}".to_vec(),
+        };
+        service.complete("user:key", &response).await?;
+        assert_eq!(
+            service.begin("user:key", "fingerprint").await?,
+            IdempotencyState::Completed(response.clone())
+        );
+
+        service.release("user:key").await?;
+        assert_eq!(service.begin("user:key", "fingerprint").await?, IdempotencyState::Started);
+
+        // an in-progress record that was never completed or released is taken over once stale
+        clock.advance(Duration::minutes(4));
+        assert_eq!(service.begin("user:key", "fingerprint").await?, IdempotencyState::InProgress);
+        clock.advance(Duration::minutes(2));
+        assert_eq!(service.begin("user:key", "fingerprint").await?, IdempotencyState::Started);
+
+        // completed records are kept until they expire
+        service.complete("user:key", &response).await?;
+        clock.advance(Duration::hours(1));
+        assert_eq!(service.begin("user:key", "fingerprint").await?, IdempotencyState::Completed(response));
+
+        Ok(())
+    }
+}
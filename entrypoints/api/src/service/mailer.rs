@@ -0,0 +1,110 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use parking_lot::Mutex;
+use url::Url;
+
+use omnius_core_base::tsid::TsidProvider;
+
+use omnius_opxs_auth::{mailer::AuthMailer, model::UserLocale};
+use omnius_opxs_base::{EmailConfig, WebConfig};
+use omnius_opxs_email_send::{EmailLocale, EmailSendJobCreator};
+
+use crate::prelude::*;
+
+pub struct AuthMailerImpl {
+    pub tsid_provider: Arc<Mutex<dyn TsidProvider + Send + Sync>>,
+    pub email_send_job_creator: Arc<EmailSendJobCreator>,
+    pub web_conf: WebConfig,
+    pub email_conf: EmailConfig,
+}
+
+impl AuthMailerImpl {
+    fn gen_web_url(&self, path: &str, token: &str) -> omnius_opxs_auth::Result<String> {
+        let url = Url::parse_with_params(format!("{}{}", self.web_conf.origin, path).as_str(), &[("token", token)]).map_err(to_auth_error)?;
+        Ok(url.to_string())
+    }
+}
+
+#[async_trait]
+impl AuthMailer for AuthMailerImpl {
+    async fn send_account_unlock(&self, user_name: &str, email: &str, unlock_token: &str, locale: &UserLocale) -> omnius_opxs_auth::Result<()> {
+        let unlock_url = self.gen_web_url("auth/email/unlock", unlock_token)?;
+        let job_id = self.tsid_provider.lock().create().to_string();
+
+        self.email_send_job_creator
+            .create_account_unlock_job(
+                &job_id,
+                user_name,
+                email,
+                &self.email_conf.from_email_address,
+                &unlock_url,
+                &to_email_locale(locale),
+            )
+            .await
+            .map_err(to_auth_error)?;
+
+        Ok(())
+    }
+
+    async fn send_email_change_confirm(&self, user_name: &str, new_email: &str, token: &str, locale: &UserLocale) -> omnius_opxs_auth::Result<()> {
+        let email_change_confirm_url = self.gen_web_url("auth/email/change/confirm", token)?;
+        let job_id = self.tsid_provider.lock().create().to_string();
+
+        self.email_send_job_creator
+            .create_email_change_confirm_job(
+                &job_id,
+                user_name,
+                new_email,
+                &self.email_conf.from_email_address,
+                &email_change_confirm_url,
+                &to_email_locale(locale),
+            )
+            .await
+            .map_err(to_auth_error)?;
+
+        Ok(())
+    }
+
+    async fn send_email_changed_notice(
+        &self,
+        user_name: &str,
+        old_email: &str,
+        new_email: &str,
+        locale: &UserLocale,
+    ) -> omnius_opxs_auth::Result<()> {
+        let job_id = self.tsid_provider.lock().create().to_string();
+
+        self.email_send_job_creator
+            .create_email_changed_notice_job(
+                &job_id,
+                user_name,
+                old_email,
+                &self.email_conf.from_email_address,
+                new_email,
+                &to_email_locale(locale),
+            )
+            .await
+            .map_err(to_auth_error)?;
+
+        Ok(())
+    }
+}
+
+pub fn to_email_locale(locale: &UserLocale) -> EmailLocale {
+    match locale {
+        UserLocale::Ja => EmailLocale::Ja,
+        UserLocale::En => EmailLocale::En,
+    }
+}
+
+fn to_auth_error<E>(e: E) -> omnius_opxs_auth::Error
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    omnius_opxs_auth::Error::builder()
+        .kind(omnius_opxs_auth::ErrorKind::UnexpectedError)
+        .message("auth mail send failed")
+        .source(e)
+        .build()
+}
@@ -0,0 +1,87 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use sqlx::PgPool;
+
+use crate::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TokenBucketState {
+    pub allowed: bool,
+    pub tokens: f64,
+}
+
+#[async_trait]
+pub trait RateLimitStore {
+    /// Refills the bucket for the elapsed time and consumes one token when available.
+    async fn take(&self, key: &str, capacity: f64, refill_per_sec: f64, now: &DateTime<Utc>) -> Result<TokenBucketState>;
+    async fn delete_idle(&self, updated_before: &DateTime<Utc>) -> Result<u64>;
+}
+
+pub struct RateLimitStoreImpl {
+    pub db: Arc<PgPool>,
+}
+
+#[async_trait]
+impl RateLimitStore for RateLimitStoreImpl {
+    async fn take(&self, key: &str, capacity: f64, refill_per_sec: f64, now: &DateTime<Utc>) -> Result<TokenBucketState> {
+        let mut tx = self.db.begin().await?;
+
+        sqlx::query(
+            r#"
+INSERT INTO rate_limit_buckets (key, tokens, updated_at)
+    VALUES ($1, $2, $3)
+    ON CONFLICT (key) DO NOTHING;
+"#,
+        )
+        .bind(key)
+        .bind(capacity)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+
+        let (tokens, updated_at): (f64, NaiveDateTime) = sqlx::query_as(
+            r#"
+SELECT tokens, updated_at
+    FROM rate_limit_buckets
+    WHERE key = $1
+    FOR UPDATE;
+"#,
+        )
+        .bind(key)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let elapsed = (now.naive_utc() - updated_at).num_milliseconds().max(0) as f64 / 1000.0;
+        let tokens = (tokens + elapsed * refill_per_sec).min(capacity);
+        let allowed = tokens >= 1.0;
+        let tokens = if allowed { tokens - 1.0 } else { tokens };
+
+        sqlx::query(
+            r#"
+UPDATE rate_limit_buckets
+    SET tokens = $2, updated_at = $3
+    WHERE key = $1;
+"#,
+        )
+        .bind(key)
+        .bind(tokens)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(TokenBucketState { allowed, tokens })
+    }
+
+    async fn delete_idle(&self, updated_before: &DateTime<Utc>) -> Result<u64> {
+        let res = sqlx::query("DELETE FROM rate_limit_buckets WHERE updated_at < $1;")
+            .bind(updated_before)
+            .execute(self.db.as_ref())
+            .await?;
+
+        Ok(res.rows_affected())
+    }
+}
@@ -0,0 +1,145 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Duration, Utc};
+use parking_lot::Mutex;
+
+use omnius_core_base::clock::Clock;
+use omnius_opxs_base::TokenBucketConfig;
+
+use super::repo::RateLimitStore;
+
+use crate::prelude::*;
+
+// A bucket untouched for a day has refilled under every configured policy, so dropping it is the same as keeping a full one.
+const IDLE_BUCKET_TTL: Duration = Duration::days(1);
+const CLEANUP_INTERVAL: Duration = Duration::hours(1);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RateLimitDecision {
+    pub allowed: bool,
+    pub limit: u32,
+    pub remaining: u32,
+    pub reset_after_secs: u64,
+    pub retry_after_secs: Option<u64>,
+}
+
+#[derive(Clone)]
+pub struct RateLimitService {
+    pub store: Arc<dyn RateLimitStore + Send + Sync>,
+    pub clock: Arc<dyn Clock<Utc> + Send + Sync>,
+    pub last_cleaned_at: Arc<Mutex<Option<DateTime<Utc>>>>,
+}
+
+impl RateLimitService {
+    pub fn new(store: Arc<dyn RateLimitStore + Send + Sync>, clock: Arc<dyn Clock<Utc> + Send + Sync>) -> Self {
+        Self {
+            store,
+            clock,
+            last_cleaned_at: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    pub async fn check(&self, key: &str, bucket: &TokenBucketConfig) -> Result<RateLimitDecision> {
+        let now = self.clock.now();
+        self.cleanup_if_due(&now).await;
+        let capacity = bucket.capacity as f64;
+        let refill_per_sec = bucket.refill_per_minute.max(1) as f64 / 60.0;
+
+        let state = self.store.take(key, capacity, refill_per_sec, &now).await?;
+
+        let retry_after_secs = (!state.allowed).then(|| ((1.0 - state.tokens) / refill_per_sec).ceil().max(1.0) as u64);
+
+        Ok(RateLimitDecision {
+            allowed: state.allowed,
+            limit: bucket.capacity,
+            remaining: state.tokens.floor().max(0.0) as u32,
+            reset_after_secs: ((capacity - state.tokens) / refill_per_sec).ceil().max(0.0) as u64,
+            retry_after_secs,
+        })
+    }
+
+    // Runs at most once per interval on each instance; a failure only delays the cleanup.
+    async fn cleanup_if_due(&self, now: &DateTime<Utc>) {
+        {
+            let mut last_cleaned_at = self.last_cleaned_at.lock();
+            if last_cleaned_at.is_some_and(|v| *now - v < CLEANUP_INTERVAL) {
+                return;
+            }
+            *last_cleaned_at = Some(*now);
+        }
+
+        match self.store.delete_idle(&(*now - IDLE_BUCKET_TTL)).await {
+            Ok(count) => debug!("deleted idle rate limit buckets: {}", count),
+            Err(e) => warn!(error = ?e, "failed to delete idle rate limit buckets"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::postgres::PgPoolOptions;
+    use testresult::TestResult;
+
+    use omnius_core_migration::postgres::PostgresMigrator;
+    use omnius_core_testkit::containers::postgres::PostgresContainer;
+
+    use omnius_opxs_base::testkit::ClockMock;
+
+    use crate::{service::rate_limit::repo::RateLimitStoreImpl, shared};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn simple_test() -> TestResult {
+        let container = PostgresContainer::new(shared::POSTGRES_VERSION).await?;
+
+        let db = Arc::new(
+            PgPoolOptions::new()
+                .max_connections(100)
+                .idle_timeout(Some(Duration::minutes(15).to_std()?))
+                .connect(&container.connection_string)
+                .await?,
+        );
+
+        let migrations_path = concat!(env!("CARGO_MANIFEST_DIR"), "/../../conf/migrations");
+        let migrator = PostgresMigrator::new(&container.connection_string, migrations_path, "opxs-api", "").await?;
+        migrator.migrate().await?;
+
+        let clock = Arc::new(ClockMock::new(DateTime::parse_from_rfc3339("2000-01-01T00:00:00Z")?.with_timezone(&Utc)));
+        let service = RateLimitService::new(Arc::new(RateLimitStoreImpl { db: db.clone() }), clock.clone());
+
+        let bucket = TokenBucketConfig {
+            capacity: 2,
+            refill_per_minute: 6,
+        };
+
+        let decision = service.check("auth:ip:127.0.0.1", &bucket).await?;
+        assert!(decision.allowed);
+        assert_eq!(decision.remaining, 1);
+
+        let decision = service.check("auth:ip:127.0.0.1", &bucket).await?;
+        assert!(decision.allowed);
+        assert_eq!(decision.remaining, 0);
+        assert_eq!(decision.reset_after_secs, 20);
+
+        let decision = service.check("auth:ip:127.0.0.1", &bucket).await?;
+        assert!(!decision.allowed);
+        assert_eq!(decision.retry_after_secs, Some(10));
+
+        // other keys have their own bucket
+        assert!(service.check("auth:ip:127.0.0.2", &bucket).await?.allowed);
+
+        clock.advance(Duration::seconds(10));
+        let decision = service.check("auth:ip:127.0.0.1", &bucket).await?;
+        assert!(decision.allowed);
+        assert_eq!(decision.remaining, 0);
+
+        // idle buckets are deleted by the next check after the cleanup interval
+        clock.advance(Duration::days(2));
+        assert!(service.check("auth:ip:127.0.0.3", &bucket).await?.allowed);
+        let keys: Vec<(String,)> = sqlx::query_as("SELECT key FROM rate_limit_buckets").fetch_all(db.as_ref()).await?;
+        assert_eq!(keys, vec![("auth:ip:127.0.0.3".to_string(),)]);
+
+        Ok(())
+    }
+}
@@ -1,5 +1,10 @@
 pub mod service;
 pub mod state;
 
+#[cfg(test)]
+pub mod testkit;
+
+pub const MIGRATIONS_DIR: &str = "./conf/migrations";
+
 #[allow(unused)]
 pub const POSTGRES_VERSION: &str = "15.1";
@@ -1,14 +1,14 @@
-use std::{path::Path, sync::Arc};
+use std::{collections::HashMap, path::Path, sync::Arc};
 
 use async_trait::async_trait;
 use aws_config::BehaviorVersion;
-use chrono::Utc;
+use chrono::{Duration, Utc};
 use futures::FutureExt;
 use parking_lot::Mutex;
 use sqlx::PgPool;
 use tempfile::{TempDir, tempdir};
 use tokio::{sync::Mutex as TokioMutex, task::JoinHandle};
-use tracing::error;
+use tracing::{error, warn};
 
 use omnius_core_base::{
     clock::{Clock, ClockUtc},
@@ -18,20 +18,44 @@ use omnius_core_base::{
 use omnius_core_cloud::aws::{s3::S3ClientImpl, sqs::SqsSenderImpl};
 
 use omnius_opxs_auth::{
+    admin::{AdminRepo, AdminService},
+    api_key::{ApiKeyRepo, ApiKeyService},
     crypto::kdf::{Kdf, KdfAlgorithm},
     email::{EmailAuthRepo, EmailAuthService},
-    provider::{GoogleAuthService, GoogleOAuth2ProviderImpl, ProviderAuthRepo},
+    link::{AccountLinkRepo, AccountLinkService},
+    password::{BreachedPasswordList, PasswordPolicy},
+    provider::{
+        GitHubOAuth2ProviderImpl, GoogleAuthService, GoogleJwksFetcherImpl, GoogleOAuth2ProviderImpl, JwksCache, OAuth2Provider, OAuth2SessionRepo,
+        OAuth2SessionService, OidcIdTokenVerifier, OidcOAuth2ProviderImpl, ProviderAuthRepo, ProviderAuthService,
+    },
+    throttle::{LoginThrottleRepo, LoginThrottleService},
     token::{TokenRepo, TokenService},
     user::{UserRepo, UserService},
 };
-use omnius_opxs_base::{AppConfig, AppInfo, util::Terminable};
+use omnius_opxs_base::{
+    AppConfig, AppInfo, AuthConfig, PasswordPolicyConfig,
+    aws::{S3ObjectMetadataClient as _, S3ObjectMetadataClientImpl, SqsMessageSenderImpl},
+    telemetry,
+    util::Terminable,
+};
+use omnius_opxs_data_export::{DataExportExecutor, DataExportJobCreator, DataExportJobRepository, DataExportJobSqsMessage};
 use omnius_opxs_email_send::{EmailSendExecutor, EmailSendJobBatchSqsMessage, EmailSendJobCreator, EmailSendJobRepository};
-use omnius_opxs_file_convert::{FileConvertExecutor, FileConvertJobCreator, FileConvertJobRepository, ImageConverterImpl};
+use omnius_opxs_file_convert::{FileConvertExecutor, FileConvertJobCreator, FileConvertJobRepository, FileConvertJobSqsMessage, ImageConverterImpl};
 
 use crate::{
     emulator::aws::{S3ClientEmulator, S3ClientEmulatorOption, SesSenderEmulator, SqsSenderEmulator},
     prelude::*,
-    service::health::{repo::WorldRepo, service::HealthService},
+    service::{
+        health::{
+            probe::{DatabaseProbe, HealthProbe, ImageConverterProbe, MigrationProbe, S3Probe, SqsProbe},
+            repo::WorldRepo,
+            service::HealthService,
+        },
+        idempotency::{repo::IdempotencyRepo, service::IdempotencyService},
+        mailer::AuthMailerImpl,
+        rate_limit::{repo::RateLimitStoreImpl, service::RateLimitService},
+    },
+    shared::MIGRATIONS_DIR,
 };
 
 pub struct AppService {
@@ -39,14 +63,21 @@ pub struct AppService {
     pub random_bytes_provider: Arc<Mutex<dyn RandomBytesProvider + Send + Sync>>,
     pub tsid_provider: Arc<Mutex<dyn TsidProvider + Send + Sync>>,
 
-    pub email_send_job_creator: EmailSendJobCreator,
-    pub image_convert_job_creator: FileConvertJobCreator,
+    pub email_send_job_creator: Arc<EmailSendJobCreator>,
+    pub image_convert_job_creator: Arc<FileConvertJobCreator>,
+    pub data_export_job_creator: DataExportJobCreator,
 
     pub health: HealthService,
     pub email_auth: EmailAuthService,
     pub google_auth: GoogleAuthService,
+    pub provider_auths: HashMap<String, ProviderAuthService>,
+    pub account_link: AccountLinkService,
     pub token: TokenService,
+    pub api_key: ApiKeyService,
     pub user: UserService,
+    pub admin: AdminService,
+    pub rate_limit: RateLimitService,
+    pub idempotency: IdempotencyService,
 
     #[allow(clippy::type_complexity)]
     terminables: Box<TokioMutex<Option<Vec<Arc<dyn Terminable + Send + Sync>>>>>,
@@ -63,7 +94,7 @@ impl AppService {
         let tsid_provider = Arc::new(Mutex::new(TsidProviderImpl::new(ClockUtc, RandomBytesProviderImpl::new(), 16)));
 
         let sdk_config = aws_config::load_defaults(BehaviorVersion::latest()).await;
-        let send_email_sqs_sender = Arc::new(SqsSenderImpl {
+        let send_email_sqs_sender = Arc::new(SqsMessageSenderImpl {
             client: aws_sdk_sqs::Client::new(&sdk_config),
             queue_url: conf
                 .email
@@ -85,57 +116,196 @@ impl AppService {
                 .bucket
                 .clone(),
         });
+        let image_convert_s3_metadata_client = Arc::new(S3ObjectMetadataClientImpl {
+            client: image_convert_s3_client.client.clone(),
+            bucket: image_convert_s3_client.bucket.clone(),
+        });
+        let image_convert_sqs_sender = Arc::new(SqsMessageSenderImpl {
+            client: aws_sdk_sqs::Client::new(&sdk_config),
+            queue_url: conf
+                .image
+                .convert
+                .sqs
+                .as_ref()
+                .ok_or_else(|| Error::builder().kind(ErrorKind::NotFound).message("sqs config is not found").build())?
+                .queue_url
+                .clone(),
+            delay_seconds: None,
+        });
+        let data_export_sqs_sender = Arc::new(SqsSenderImpl {
+            client: aws_sdk_sqs::Client::new(&sdk_config),
+            queue_url: conf
+                .data_export
+                .sqs
+                .as_ref()
+                .ok_or_else(|| Error::builder().kind(ErrorKind::NotFound).message("sqs config is not found").build())?
+                .queue_url
+                .clone(),
+            delay_seconds: None,
+        });
+        let data_export_s3_client = Arc::new(S3ClientImpl {
+            client: aws_sdk_s3::Client::new(&sdk_config),
+            bucket: conf
+                .data_export
+                .s3
+                .as_ref()
+                .ok_or_else(|| Error::builder().kind(ErrorKind::NotFound).message("s3 config is not found").build())?
+                .bucket
+                .clone(),
+        });
+
+        let email_send_job_creator = Arc::new(EmailSendJobCreator {
+            email_send_job_repository: Arc::new(EmailSendJobRepository {
+                db: db.clone(),
+                clock: clock.clone(),
+            }),
+            sqs_sender: send_email_sqs_sender.clone(),
+        });
+        let auth_mailer = Arc::new(AuthMailerImpl {
+            tsid_provider: tsid_provider.clone(),
+            email_send_job_creator: email_send_job_creator.clone(),
+            web_conf: conf.web.clone(),
+            email_conf: conf.email.clone(),
+        });
+        let email_auth_repo = Arc::new(EmailAuthRepo {
+            db: db.clone(),
+            clock: clock.clone(),
+            tsid_provider: tsid_provider.clone(),
+        });
+        let provider_auth_repo = Arc::new(ProviderAuthRepo {
+            db: db.clone(),
+            clock: clock.clone(),
+            tsid_provider: tsid_provider.clone(),
+        });
+        let oauth2_session_service = Arc::new(OAuth2SessionService {
+            clock: clock.clone(),
+            random_bytes_provider: random_bytes_provider.clone(),
+            session_repo: Arc::new(OAuth2SessionRepo {
+                db: db.clone(),
+                clock: clock.clone(),
+            }),
+        });
+        let user_repo = Arc::new(UserRepo {
+            db: db.clone(),
+            clock: clock.clone(),
+        });
+        let link_repo = Arc::new(AccountLinkRepo { db: db.clone() });
+        let world_repo = Arc::new(WorldRepo { db: db.clone() });
+        let api_key_repo = Arc::new(ApiKeyRepo {
+            db: db.clone(),
+            clock: clock.clone(),
+        });
+
+        let image_convert_job_creator = Arc::new(FileConvertJobCreator {
+            file_convert_job_repository: Arc::new(FileConvertJobRepository {
+                db: db.clone(),
+                clock: clock.clone(),
+                tsid_provider: tsid_provider.clone(),
+            }),
+            clock: clock.clone(),
+            s3_client: image_convert_s3_client.clone(),
+            s3_metadata_client: image_convert_s3_metadata_client,
+            sqs_sender: image_convert_sqs_sender.clone(),
+        });
 
         Ok(Self {
             clock: clock.clone(),
             random_bytes_provider: random_bytes_provider.clone(),
             tsid_provider: tsid_provider.clone(),
 
-            email_send_job_creator: EmailSendJobCreator {
-                email_send_job_repository: Arc::new(EmailSendJobRepository {
-                    db: db.clone(),
-                    clock: clock.clone(),
-                }),
-                sqs_sender: send_email_sqs_sender.clone(),
-            },
+            email_send_job_creator: email_send_job_creator.clone(),
 
-            image_convert_job_creator: FileConvertJobCreator {
-                file_convert_job_repository: Arc::new(FileConvertJobRepository {
+            image_convert_job_creator: image_convert_job_creator.clone(),
+            data_export_job_creator: DataExportJobCreator {
+                data_export_job_repository: Arc::new(DataExportJobRepository {
                     db: db.clone(),
                     clock: clock.clone(),
-                    tsid_provider: tsid_provider.clone(),
                 }),
                 clock: clock.clone(),
-                s3_client: image_convert_s3_client,
+                s3_client: data_export_s3_client.clone(),
+                sqs_sender: data_export_sqs_sender.clone(),
             },
 
             health: HealthService {
                 info: info.clone(),
-                world_repo: Arc::new(WorldRepo { db: db.clone() }),
+                world_repo: world_repo.clone(),
+                probes: vec![
+                    Arc::new(DatabaseProbe {
+                        world_repo: world_repo.clone(),
+                    }) as Arc<dyn HealthProbe + Send + Sync>,
+                    Arc::new(MigrationProbe {
+                        world_repo: world_repo.clone(),
+                        migrations_dir: MIGRATIONS_DIR.into(),
+                    }),
+                    Arc::new(S3Probe {
+                        name: "image_convert_s3".to_string(),
+                        s3_client: image_convert_s3_client,
+                    }),
+                    Arc::new(S3Probe {
+                        name: "data_export_s3".to_string(),
+                        s3_client: data_export_s3_client,
+                    }),
+                    Arc::new(SqsProbe {
+                        name: "email_send_sqs".to_string(),
+                        client: send_email_sqs_sender.client.clone(),
+                        queue_url: send_email_sqs_sender.queue_url.clone(),
+                    }),
+                    Arc::new(SqsProbe {
+                        name: "image_convert_sqs".to_string(),
+                        client: image_convert_sqs_sender.client.clone(),
+                        queue_url: image_convert_sqs_sender.queue_url.clone(),
+                    }),
+                    Arc::new(SqsProbe {
+                        name: "data_export_sqs".to_string(),
+                        client: data_export_sqs_sender.client.clone(),
+                        queue_url: data_export_sqs_sender.queue_url.clone(),
+                    }),
+                ],
             },
             email_auth: EmailAuthService {
-                auth_repo: Arc::new(EmailAuthRepo {
-                    db: db.clone(),
-                    clock: clock.clone(),
-                    tsid_provider: tsid_provider.clone(),
-                }),
+                auth_repo: email_auth_repo.clone(),
+                user_repo: user_repo.clone(),
                 clock: clock.clone(),
                 random_bytes_provider: random_bytes_provider.clone(),
                 jwt_conf: conf.auth.jwt.clone(),
+                unregister_conf: conf.auth.unregister.clone(),
                 kdf: Kdf {
-                    algorithm: KdfAlgorithm::Pbkdf2HmacSha256,
-                    iterations: 1024,
+                    algorithm: KdfAlgorithm::Argon2id {
+                        memory_cost: 19 * 1024,
+                        time_cost: 2,
+                        parallelism: 1,
+                    },
                 },
+                password_policy: Arc::new(Self::new_password_policy(&conf.auth.password).await?),
+                login_throttle: Arc::new(LoginThrottleService {
+                    clock: clock.clone(),
+                    random_bytes_provider: random_bytes_provider.clone(),
+                    throttle_repo: Arc::new(LoginThrottleRepo {
+                        db: db.clone(),
+                        clock: clock.clone(),
+                    }),
+                }),
+                mailer: auth_mailer,
             },
             google_auth: GoogleAuthService {
-                oauth2_provider: Arc::new(GoogleOAuth2ProviderImpl {}),
-                auth_repo: Arc::new(ProviderAuthRepo {
-                    db: db.clone(),
-                    clock: clock.clone(),
-                    tsid_provider: tsid_provider.clone(),
+                clock: clock.clone(),
+                oauth2_provider: Arc::new(GoogleOAuth2ProviderImpl {
+                    id_token_verifier: Arc::new(OidcIdTokenVerifier::google(
+                        clock.clone(),
+                        Arc::new(JwksCache::new(clock.clone(), Arc::new(GoogleJwksFetcherImpl), Duration::hours(1))),
+                        &conf.auth.google.client_id,
+                    )),
                 }),
+                auth_repo: provider_auth_repo.clone(),
+                user_repo: user_repo.clone(),
+                session_service: oauth2_session_service.clone(),
+                email_auth_repo,
                 auth_conf: conf.auth.clone(),
             },
+            provider_auths: Self::new_provider_auths(&conf.auth, clock.clone(), provider_auth_repo, user_repo.clone(), oauth2_session_service),
+            account_link: AccountLinkService {
+                link_repo: link_repo.clone(),
+            },
             token: TokenService {
                 clock: clock.clone(),
                 random_bytes_provider: random_bytes_provider.clone(),
@@ -145,8 +315,28 @@ impl AppService {
                     clock: clock.clone(),
                 }),
             },
-            user: UserService {
-                user_repo: Arc::new(UserRepo { db }),
+            api_key: ApiKeyService {
+                clock: clock.clone(),
+                random_bytes_provider: random_bytes_provider.clone(),
+                tsid_provider: tsid_provider.clone(),
+                api_key_repo: api_key_repo.clone(),
+            },
+            user: UserService { user_repo },
+            admin: AdminService {
+                admin_repo: Arc::new(AdminRepo {
+                    db: db.clone(),
+                    clock: clock.clone(),
+                    tsid_provider: tsid_provider.clone(),
+                }),
+                link_repo,
+                api_key_repo,
+                file_convert_job_creator: image_convert_job_creator,
+                email_send_job_creator: email_send_job_creator.clone(),
+            },
+            rate_limit: RateLimitService::new(Arc::new(RateLimitStoreImpl { db: db.clone() }), clock.clone()),
+            idempotency: IdempotencyService {
+                idempotency_repo: Arc::new(IdempotencyRepo { db }),
+                clock: clock.clone(),
             },
 
             terminables: Box::new(TokioMutex::new(None)),
@@ -166,13 +356,13 @@ impl AppService {
 
         let email_send_job_creator = {
             let sqs_sender = Arc::new(SqsSenderEmulator::new());
-            let job_creator = EmailSendJobCreator {
+            let job_creator = Arc::new(EmailSendJobCreator {
                 email_send_job_repository: Arc::new(EmailSendJobRepository {
                     db: db.clone(),
                     clock: clock.clone(),
                 }),
                 sqs_sender: sqs_sender.clone(),
-            };
+            });
 
             let db = db.clone();
             let clock = clock.clone();
@@ -187,8 +377,11 @@ impl AppService {
 
                 loop {
                     if let Some(message) = message_receiver.lock().await.recv().await {
-                        let message = match serde_json::from_str::<EmailSendJobBatchSqsMessage>(&message) {
-                            Ok(message) => message,
+                        let message = match serde_json::from_str::<EmailSendJobBatchSqsMessage>(&message.body) {
+                            Ok(m) => EmailSendJobBatchSqsMessage {
+                                trace_context: message.attributes,
+                                ..m
+                            },
                             _ => {
                                 error!("email send sqs message parse failed");
                                 continue;
@@ -206,7 +399,7 @@ impl AppService {
             job_creator
         };
 
-        let image_convert_job_creator = {
+        let (image_convert_job_creator, image_convert_s3_client) = {
             let working_dir = tempdir()?;
 
             let option = S3ClientEmulatorOption {
@@ -215,7 +408,8 @@ impl AppService {
                 working_dir: working_dir.path().to_path_buf(),
             };
             let s3_client = Arc::new(S3ClientEmulator::new(option)?);
-            let job_creator = FileConvertJobCreator {
+            let sqs_sender = Arc::new(SqsSenderEmulator::new());
+            let job_creator = Arc::new(FileConvertJobCreator {
                 file_convert_job_repository: Arc::new(FileConvertJobRepository {
                     db: db.clone(),
                     clock: clock.clone(),
@@ -223,6 +417,102 @@ impl AppService {
                 }),
                 clock: clock.clone(),
                 s3_client: s3_client.clone(),
+                s3_metadata_client: s3_client.clone(),
+                sqs_sender: sqs_sender.clone(),
+            });
+
+            terminables.push(s3_client.clone());
+
+            let executor = Arc::new(FileConvertExecutor {
+                file_convert_job_repository: Arc::new(FileConvertJobRepository {
+                    db: db.clone(),
+                    clock: clock.clone(),
+                    tsid_provider: tsid_provider.clone(),
+                }),
+                s3_client: s3_client.clone(),
+                image_converter: Arc::new(ImageConverterImpl),
+            });
+
+            let put_event_receiver = s3_client.put_event_receiver.clone();
+            let message_receiver = sqs_sender.message_receiver.clone();
+
+            let join_handle: JoinHandle<()> = tokio::spawn({
+                let executor = executor.clone();
+                async move {
+                    loop {
+                        if let Some(message) = message_receiver.lock().await.recv().await {
+                            let message = match serde_json::from_str::<FileConvertJobSqsMessage>(&message.body) {
+                                Ok(m) => FileConvertJobSqsMessage {
+                                    trace_context: message.attributes,
+                                    ..m
+                                },
+                                _ => {
+                                    error!("image convert sqs message parse failed");
+                                    continue;
+                                }
+                            };
+
+                            if let Err(err) = executor.execute(&[message]).await {
+                                error!("image convert error: {:?}", err);
+                            }
+                        }
+                    }
+                }
+            });
+            join_handles.push(join_handle);
+
+            let join_handle: JoinHandle<()> = tokio::spawn({
+                let s3_client = s3_client.clone();
+                async move {
+                    loop {
+                        if let Some(key) = put_event_receiver.lock().await.recv().await {
+                            let p = Path::new(&key);
+                            let job_id = p
+                                .file_name()
+                                .ok_or_else(|| anyhow::anyhow!("file name is not found"))
+                                .unwrap()
+                                .to_string_lossy()
+                                .to_string();
+                            let trace_context = match s3_client.get_object_metadata(&key).await {
+                                Ok(metadata) => telemetry::trace_context_from(metadata.iter().map(|(k, v)| (k.as_str(), v.as_str()))),
+                                Err(err) => {
+                                    warn!("image convert trace context restore failed: {:?}", err);
+                                    HashMap::new()
+                                }
+                            };
+                            let message = FileConvertJobSqsMessage { job_id, trace_context };
+                            if let Err(err) = executor.execute(&[message]).await {
+                                error!("image convert error: {:?}", err);
+                            }
+                        }
+                    }
+                }
+            });
+            join_handles.push(join_handle);
+            temp_dirs.push(working_dir);
+
+            let s3_client = job_creator.s3_client.clone();
+            (job_creator, s3_client)
+        };
+
+        let data_export_job_creator = {
+            let working_dir = tempdir()?;
+
+            let option = S3ClientEmulatorOption {
+                base_url: "http://localhost:40001".parse()?,
+                listen_addr: "0.0.0.0:40001".parse()?,
+                working_dir: working_dir.path().to_path_buf(),
+            };
+            let s3_client = Arc::new(S3ClientEmulator::new(option)?);
+            let sqs_sender = Arc::new(SqsSenderEmulator::new());
+            let job_creator = DataExportJobCreator {
+                data_export_job_repository: Arc::new(DataExportJobRepository {
+                    db: db.clone(),
+                    clock: clock.clone(),
+                }),
+                clock: clock.clone(),
+                s3_client: s3_client.clone(),
+                sqs_sender: sqs_sender.clone(),
             };
 
             terminables.push(s3_client.clone());
@@ -230,27 +520,33 @@ impl AppService {
             let db = db.clone();
             let clock = clock.clone();
             let tsid_provider = tsid_provider.clone();
-            let s3_client = s3_client.clone();
-            let put_event_receiver = s3_client.put_event_receiver.clone();
+            let email_send_job_creator = email_send_job_creator.clone();
+            let from_email_address = conf.email.from_email_address.clone();
+            let message_receiver = sqs_sender.message_receiver.clone();
 
             let join_handle: JoinHandle<()> = tokio::spawn(async move {
-                let executor = FileConvertExecutor {
-                    file_convert_job_repository: Arc::new(FileConvertJobRepository { db, clock, tsid_provider }),
+                let executor = DataExportExecutor {
+                    data_export_job_repository: Arc::new(DataExportJobRepository { db, clock: clock.clone() }),
+                    clock,
+                    tsid_provider,
                     s3_client,
-                    image_converter: Arc::new(ImageConverterImpl),
+                    file_convert_s3_client: image_convert_s3_client,
+                    email_send_job_creator,
+                    from_email_address,
                 };
 
                 loop {
-                    if let Some(key) = put_event_receiver.lock().await.recv().await {
-                        let p = Path::new(&key);
-                        let job_id = p
-                            .file_name()
-                            .ok_or_else(|| anyhow::anyhow!("file name is not found"))
-                            .unwrap()
-                            .to_string_lossy()
-                            .to_string();
-                        if let Err(err) = executor.execute(&[job_id]).await {
-                            error!("image convert error: {:?}", err);
+                    if let Some(message) = message_receiver.lock().await.recv().await {
+                        let message = match serde_json::from_str::<DataExportJobSqsMessage>(&message.body) {
+                            Ok(message) => message,
+                            _ => {
+                                error!("data export sqs message parse failed");
+                                continue;
+                            }
+                        };
+
+                        if let Err(err) = executor.execute(&[message.job_id]).await {
+                            error!("data export execute error: {:?}", err);
                         }
                     }
                 }
@@ -261,42 +557,109 @@ impl AppService {
             job_creator
         };
 
+        let auth_mailer = Arc::new(AuthMailerImpl {
+            tsid_provider: tsid_provider.clone(),
+            email_send_job_creator: email_send_job_creator.clone(),
+            web_conf: conf.web.clone(),
+            email_conf: conf.email.clone(),
+        });
+        let email_auth_repo = Arc::new(EmailAuthRepo {
+            db: db.clone(),
+            clock: clock.clone(),
+            tsid_provider: tsid_provider.clone(),
+        });
+        let provider_auth_repo = Arc::new(ProviderAuthRepo {
+            db: db.clone(),
+            clock: clock.clone(),
+            tsid_provider: tsid_provider.clone(),
+        });
+        let oauth2_session_service = Arc::new(OAuth2SessionService {
+            clock: clock.clone(),
+            random_bytes_provider: random_bytes_provider.clone(),
+            session_repo: Arc::new(OAuth2SessionRepo {
+                db: db.clone(),
+                clock: clock.clone(),
+            }),
+        });
+        let user_repo = Arc::new(UserRepo {
+            db: db.clone(),
+            clock: clock.clone(),
+        });
+        let link_repo = Arc::new(AccountLinkRepo { db: db.clone() });
+        let world_repo = Arc::new(WorldRepo { db: db.clone() });
+        let api_key_repo = Arc::new(ApiKeyRepo {
+            db: db.clone(),
+            clock: clock.clone(),
+        });
+
         Ok(Self {
             clock: clock.clone(),
             random_bytes_provider: random_bytes_provider.clone(),
             tsid_provider: tsid_provider.clone(),
 
-            email_send_job_creator,
+            email_send_job_creator: email_send_job_creator.clone(),
 
-            image_convert_job_creator,
+            image_convert_job_creator: image_convert_job_creator.clone(),
+            data_export_job_creator,
 
             health: HealthService {
                 info: info.clone(),
-                world_repo: Arc::new(WorldRepo { db: db.clone() }),
+                world_repo: world_repo.clone(),
+                probes: vec![
+                    Arc::new(DatabaseProbe {
+                        world_repo: world_repo.clone(),
+                    }) as Arc<dyn HealthProbe + Send + Sync>,
+                    Arc::new(MigrationProbe {
+                        world_repo: world_repo.clone(),
+                        migrations_dir: MIGRATIONS_DIR.into(),
+                    }),
+                    Arc::new(ImageConverterProbe::from_env()),
+                ],
             },
             email_auth: EmailAuthService {
-                auth_repo: Arc::new(EmailAuthRepo {
-                    db: db.clone(),
-                    clock: clock.clone(),
-                    tsid_provider: tsid_provider.clone(),
-                }),
+                auth_repo: email_auth_repo.clone(),
+                user_repo: user_repo.clone(),
                 clock: clock.clone(),
                 random_bytes_provider: random_bytes_provider.clone(),
                 jwt_conf: conf.auth.jwt.clone(),
+                unregister_conf: conf.auth.unregister.clone(),
                 kdf: Kdf {
-                    algorithm: KdfAlgorithm::Pbkdf2HmacSha256,
-                    iterations: 1024,
+                    algorithm: KdfAlgorithm::Argon2id {
+                        memory_cost: 19 * 1024,
+                        time_cost: 2,
+                        parallelism: 1,
+                    },
                 },
+                password_policy: Arc::new(Self::new_password_policy(&conf.auth.password).await?),
+                login_throttle: Arc::new(LoginThrottleService {
+                    clock: clock.clone(),
+                    random_bytes_provider: random_bytes_provider.clone(),
+                    throttle_repo: Arc::new(LoginThrottleRepo {
+                        db: db.clone(),
+                        clock: clock.clone(),
+                    }),
+                }),
+                mailer: auth_mailer,
             },
             google_auth: GoogleAuthService {
-                oauth2_provider: Arc::new(GoogleOAuth2ProviderImpl {}),
-                auth_repo: Arc::new(ProviderAuthRepo {
-                    db: db.clone(),
-                    clock: clock.clone(),
-                    tsid_provider: tsid_provider.clone(),
+                clock: clock.clone(),
+                oauth2_provider: Arc::new(GoogleOAuth2ProviderImpl {
+                    id_token_verifier: Arc::new(OidcIdTokenVerifier::google(
+                        clock.clone(),
+                        Arc::new(JwksCache::new(clock.clone(), Arc::new(GoogleJwksFetcherImpl), Duration::hours(1))),
+                        &conf.auth.google.client_id,
+                    )),
                 }),
+                auth_repo: provider_auth_repo.clone(),
+                user_repo: user_repo.clone(),
+                session_service: oauth2_session_service.clone(),
+                email_auth_repo,
                 auth_conf: conf.auth.clone(),
             },
+            provider_auths: Self::new_provider_auths(&conf.auth, clock.clone(), provider_auth_repo, user_repo.clone(), oauth2_session_service),
+            account_link: AccountLinkService {
+                link_repo: link_repo.clone(),
+            },
             token: TokenService {
                 clock: clock.clone(),
                 random_bytes_provider: random_bytes_provider.clone(),
@@ -306,8 +669,28 @@ impl AppService {
                     clock: clock.clone(),
                 }),
             },
-            user: UserService {
-                user_repo: Arc::new(UserRepo { db }),
+            api_key: ApiKeyService {
+                clock: clock.clone(),
+                random_bytes_provider: random_bytes_provider.clone(),
+                tsid_provider: tsid_provider.clone(),
+                api_key_repo: api_key_repo.clone(),
+            },
+            user: UserService { user_repo },
+            admin: AdminService {
+                admin_repo: Arc::new(AdminRepo {
+                    db: db.clone(),
+                    clock: clock.clone(),
+                    tsid_provider: tsid_provider.clone(),
+                }),
+                link_repo,
+                api_key_repo,
+                file_convert_job_creator: image_convert_job_creator,
+                email_send_job_creator: email_send_job_creator.clone(),
+            },
+            rate_limit: RateLimitService::new(Arc::new(RateLimitStoreImpl { db: db.clone() }), clock.clone()),
+            idempotency: IdempotencyService {
+                idempotency_repo: Arc::new(IdempotencyRepo { db }),
+                clock: clock.clone(),
             },
 
             terminables: Box::new(TokioMutex::new(Some(terminables))),
@@ -315,6 +698,63 @@ impl AppService {
             temp_dirs,
         })
     }
+
+    async fn new_password_policy(conf: &PasswordPolicyConfig) -> Result<PasswordPolicy> {
+        let breached_list = match &conf.breached_password_dir {
+            Some(dir) => Some(Arc::new(BreachedPasswordList::open(dir).await?)),
+            None => None,
+        };
+
+        Ok(PasswordPolicy {
+            min_length: conf.min_length,
+            min_score: conf.min_score,
+            breached_list,
+        })
+    }
+
+    fn new_provider_auths(
+        auth_conf: &AuthConfig,
+        clock: Arc<dyn Clock<Utc> + Send + Sync>,
+        auth_repo: Arc<ProviderAuthRepo>,
+        user_repo: Arc<UserRepo>,
+        session_service: Arc<OAuth2SessionService>,
+    ) -> HashMap<String, ProviderAuthService> {
+        let mut providers: Vec<Arc<dyn OAuth2Provider + Send + Sync>> = Vec::new();
+
+        if let Some(github) = &auth_conf.github {
+            providers.push(Arc::new(GitHubOAuth2ProviderImpl {
+                client_id: github.client_id.clone(),
+                client_secret: github.client_secret.clone(),
+            }));
+        }
+
+        for oidc in auth_conf.oidc.iter() {
+            providers.push(Arc::new(OidcOAuth2ProviderImpl::new(
+                &oidc.name,
+                &oidc.issuer_url,
+                &oidc.client_id,
+                &oidc.client_secret,
+                clock.clone(),
+            )));
+        }
+
+        providers
+            .into_iter()
+            .map(|oauth2_provider| {
+                (
+                    oauth2_provider.provider_type().to_string(),
+                    ProviderAuthService {
+                        oauth2_provider,
+                        auth_repo: auth_repo.clone(),
+                        user_repo: user_repo.clone(),
+                        session_service: session_service.clone(),
+                        clock: clock.clone(),
+                        unregister_conf: auth_conf.unregister.clone(),
+                    },
+                )
+            })
+            .collect()
+    }
 }
 
 #[async_trait]
@@ -0,0 +1,52 @@
+use chrono::Utc;
+use testresult::TestResult;
+
+use omnius_core_migration::postgres::PostgresMigrator;
+use omnius_core_testkit::containers::postgres::PostgresContainer;
+
+use omnius_opxs_auth::model::{UserAuthenticationType, UserRole};
+use omnius_opxs_base::{AppConfig, AppInfo, RunMode};
+
+use crate::shared::{POSTGRES_VERSION, state::AppState};
+
+/// Builds the local mode state against a fresh database.
+/// The local emulators listen on fixed ports, so tests using it must be `#[serial]`.
+pub async fn gen_state() -> TestResult<(PostgresContainer, AppState)> {
+    let container = PostgresContainer::new(POSTGRES_VERSION).await?;
+
+    let migrations_path = concat!(env!("CARGO_MANIFEST_DIR"), "/../../conf/migrations");
+    let migrator = PostgresMigrator::new(&container.connection_string, migrations_path, "opxs-api", "").await?;
+    migrator.migrate().await?;
+
+    let info = AppInfo {
+        app_name: "opxs-api".to_string(),
+        mode: RunMode::Local,
+        git_tag: "test".to_string(),
+    };
+    let mut conf = AppConfig::load(&info).await?;
+    conf.postgres.url = container.connection_string.clone();
+
+    let state = AppState::new(info, conf).await?;
+
+    Ok((container, state))
+}
+
+pub async fn create_user(state: &AppState, user_id: &str) -> TestResult {
+    let now = Utc::now();
+    sqlx::query(
+        r#"
+INSERT INTO users (id, name, authentication_type, role, created_at, updated_at)
+    VALUES ($1, $2, $3, $4, $5, $6)
+"#,
+    )
+    .bind(user_id)
+    .bind("test_user_name")
+    .bind(UserAuthenticationType::Email)
+    .bind(UserRole::User)
+    .bind(now)
+    .bind(now)
+    .execute(state.db.as_ref())
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,63 @@
+use std::sync::Arc;
+
+use aws_config::BehaviorVersion;
+use chrono::Duration;
+use lambda_runtime::{LambdaEvent, run, service_fn};
+use sqlx::postgres::PgPoolOptions;
+use tracing::info;
+use tracing_subscriber::EnvFilter;
+
+use omnius_core_base::clock::ClockUtc;
+
+use omnius_opxs_account_purge::{AccountPurgeExecutor, AccountPurgeRepository, S3ObjectRemoverImpl};
+use omnius_opxs_base::{AppConfig, AppInfo, RunMode};
+
+const APP_NAME: &str = "opxs-batch-account-purge";
+
+// Invoked on a schedule; the event payload is not used.
+async fn handler(_event: LambdaEvent<serde_json::Value>) -> std::result::Result<(), lambda_runtime::Error> {
+    let mode = RunMode::from_env()?;
+    let info = AppInfo::new(APP_NAME, mode)?;
+    info!("info: {}", info);
+
+    let conf = AppConfig::load(&info).await?;
+    let db = Arc::new(
+        PgPoolOptions::new()
+            .max_connections(100)
+            .idle_timeout(Some(Duration::minutes(15).to_std()?))
+            .connect(&conf.postgres.url)
+            .await?,
+    );
+    let clock = Arc::new(ClockUtc {});
+
+    let sdk_config = aws_config::load_defaults(BehaviorVersion::latest()).await;
+    let executor = AccountPurgeExecutor {
+        account_purge_repository: Arc::new(AccountPurgeRepository { db: db.clone(), clock }),
+        file_convert_s3_remover: Arc::new(S3ObjectRemoverImpl {
+            client: aws_sdk_s3::Client::new(&sdk_config),
+            bucket: conf.image.convert.s3.ok_or_else(|| anyhow::anyhow!("s3 config is not found"))?.bucket,
+        }),
+        data_export_s3_remover: Arc::new(S3ObjectRemoverImpl {
+            client: aws_sdk_s3::Client::new(&sdk_config),
+            bucket: conf.data_export.s3.ok_or_else(|| anyhow::anyhow!("s3 config is not found"))?.bucket,
+        }),
+    };
+    let report = executor.execute().await?;
+    info!("purged users: {}, failed users: {:?}", report.purged_count, report.failed_user_ids);
+
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> std::result::Result<(), lambda_runtime::Error> {
+    if cfg!(debug_assertions) {
+        let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info,sqlx=off"));
+        tracing_subscriber::fmt().with_env_filter(filter).with_target(false).init();
+    } else {
+        let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info,sqlx=off"));
+        tracing_subscriber::fmt().with_env_filter(filter).with_target(false).json().init();
+    }
+
+    info!("----- start -----");
+    run(service_fn(handler)).await
+}
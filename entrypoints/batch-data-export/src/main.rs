@@ -0,0 +1,103 @@
+use std::sync::Arc;
+
+use aws_config::BehaviorVersion;
+use aws_lambda_events::sqs::SqsEvent;
+use chrono::Duration;
+use lambda_runtime::{LambdaEvent, run, service_fn};
+use parking_lot::Mutex;
+use sqlx::postgres::PgPoolOptions;
+use tracing::info;
+use tracing_subscriber::EnvFilter;
+
+use omnius_core_base::{clock::ClockUtc, random_bytes::RandomBytesProviderImpl, tsid::TsidProviderImpl};
+use omnius_core_cloud::aws::s3::S3ClientImpl;
+
+use omnius_opxs_base::{AppConfig, AppInfo, RunMode, aws::SqsMessageSenderImpl};
+use omnius_opxs_data_export::{DataExportExecutor, DataExportJobRepository, DataExportJobSqsMessage};
+use omnius_opxs_email_send::{EmailSendJobCreator, EmailSendJobRepository};
+
+const APP_NAME: &str = "opxs-batch-data-export";
+
+async fn handler_sub(job_ids: &[String]) -> std::result::Result<(), lambda_runtime::Error> {
+    let mode = RunMode::from_env()?;
+    let info = AppInfo::new(APP_NAME, mode)?;
+    info!("info: {}", info);
+
+    let conf = AppConfig::load(&info).await?;
+    let db = Arc::new(
+        PgPoolOptions::new()
+            .max_connections(100)
+            .idle_timeout(Some(Duration::minutes(15).to_std()?))
+            .connect(&conf.postgres.url)
+            .await?,
+    );
+    let clock = Arc::new(ClockUtc {});
+    let tsid_provider = Arc::new(Mutex::new(TsidProviderImpl::new(ClockUtc, RandomBytesProviderImpl::new(), 16)));
+
+    let sdk_config = aws_config::load_defaults(BehaviorVersion::latest()).await;
+    let executor = DataExportExecutor {
+        data_export_job_repository: Arc::new(DataExportJobRepository {
+            db: db.clone(),
+            clock: clock.clone(),
+        }),
+        clock: clock.clone(),
+        tsid_provider,
+        s3_client: Arc::new(S3ClientImpl {
+            client: aws_sdk_s3::Client::new(&sdk_config),
+            bucket: conf.data_export.s3.ok_or_else(|| anyhow::anyhow!("s3 config is not found"))?.bucket,
+        }),
+        file_convert_s3_client: Arc::new(S3ClientImpl {
+            client: aws_sdk_s3::Client::new(&sdk_config),
+            bucket: conf.image.convert.s3.ok_or_else(|| anyhow::anyhow!("s3 config is not found"))?.bucket,
+        }),
+        email_send_job_creator: Arc::new(EmailSendJobCreator {
+            email_send_job_repository: Arc::new(EmailSendJobRepository { db: db.clone(), clock }),
+            sqs_sender: Arc::new(SqsMessageSenderImpl {
+                client: aws_sdk_sqs::Client::new(&sdk_config),
+                queue_url: conf.email.sqs.ok_or_else(|| anyhow::anyhow!("sqs config is not found"))?.queue_url,
+                delay_seconds: None,
+            }),
+        }),
+        from_email_address: conf.email.from_email_address,
+    };
+    executor.execute(job_ids).await?;
+
+    Ok(())
+}
+
+async fn handler(event: LambdaEvent<serde_json::Value>) -> std::result::Result<(), lambda_runtime::Error> {
+    let (event, _context) = event.into_parts();
+
+    let mut job_ids: Vec<String> = Vec::new();
+
+    if let Ok(event) = serde_json::from_value::<SqsEvent>(event.clone()) {
+        info!("sqs event");
+        for v in event.records.into_iter().flat_map(|n| n.body).collect::<Vec<_>>() {
+            info!("{:?}", v);
+            let m = serde_json::from_str::<DataExportJobSqsMessage>(&v)?;
+            job_ids.push(m.job_id);
+        }
+    } else {
+        info!("raw event");
+        let m = serde_json::from_value::<DataExportJobSqsMessage>(event)?;
+        job_ids.push(m.job_id);
+    }
+
+    handler_sub(&job_ids).await?;
+
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> std::result::Result<(), lambda_runtime::Error> {
+    if cfg!(debug_assertions) {
+        let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info,sqlx=off"));
+        tracing_subscriber::fmt().with_env_filter(filter).with_target(false).init();
+    } else {
+        let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info,sqlx=off"));
+        tracing_subscriber::fmt().with_env_filter(filter).with_target(false).json().init();
+    }
+
+    info!("----- start -----");
+    run(service_fn(handler)).await
+}
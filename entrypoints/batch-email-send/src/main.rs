@@ -1,21 +1,26 @@
-use std::sync::Arc;
+use std::sync::{Arc, LazyLock};
 
 use aws_config::BehaviorVersion;
 use aws_lambda_events::event::sqs::SqsEvent;
-use chrono::Duration;
+use chrono::{Duration, Utc};
 use lambda_runtime::{LambdaEvent, run, service_fn};
 use sqlx::postgres::PgPoolOptions;
 use tracing::info;
-use tracing_subscriber::EnvFilter;
 
 use omnius_core_base::clock::ClockUtc;
 use omnius_core_cloud::aws::ses::SesSenderImpl;
 
-use omnius_opxs_base::{AppConfig, AppInfo, RunMode};
+use omnius_opxs_base::{
+    AppConfig, AppInfo, RunMode,
+    metrics::EmfEmitter,
+    telemetry::{self, Telemetry, TelemetryConfig},
+};
 use omnius_opxs_email_send::{EmailSendExecutor, EmailSendJobBatchSqsMessage, EmailSendJobRepository};
 
 const APP_NAME: &str = "opxs-batch-email-send";
 
+static EMF_EMITTER: LazyLock<EmfEmitter> = LazyLock::new(|| EmfEmitter::new("opxs/batch-email-send"));
+
 async fn handler_sub(ms: &[EmailSendJobBatchSqsMessage]) -> Result<(), lambda_runtime::Error> {
     let mode = RunMode::from_env()?;
     let info = AppInfo::new(APP_NAME, mode)?;
@@ -55,9 +60,17 @@ async fn handler(event: LambdaEvent<serde_json::Value>) -> Result<(), lambda_run
 
     if let Ok(event) = serde_json::from_value::<SqsEvent>(event.clone()) {
         info!("sqs event");
-        for v in event.records.into_iter().flat_map(|n| n.body).collect::<Vec<_>>() {
+        for r in event.records.into_iter() {
+            let Some(v) = r.body else {
+                continue;
+            };
             let m = serde_json::from_str::<EmailSendJobBatchSqsMessage>(&v)?;
-            ms.push(m);
+            let trace_context = telemetry::trace_context_from(
+                r.message_attributes
+                    .iter()
+                    .filter_map(|(k, v)| Some((k.as_str(), v.string_value.as_deref()?))),
+            );
+            ms.push(EmailSendJobBatchSqsMessage { trace_context, ..m });
         }
     } else {
         info!("raw event");
@@ -65,21 +78,23 @@ async fn handler(event: LambdaEvent<serde_json::Value>) -> Result<(), lambda_run
         ms.push(m);
     }
 
-    handler_sub(&ms).await?;
-
-    Ok(())
+    let res = handler_sub(&ms).await;
+    EMF_EMITTER.emit(&Utc::now());
+    res
 }
 
 #[tokio::main]
 async fn main() -> Result<(), lambda_runtime::Error> {
-    if cfg!(debug_assertions) {
-        let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info,sqlx=off"));
-        tracing_subscriber::fmt().with_env_filter(filter).with_target(false).init();
-    } else {
-        let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info,sqlx=off"));
-        tracing_subscriber::fmt().with_env_filter(filter).with_target(false).json().init();
-    }
+    let telemetry = Arc::new(Telemetry::init(APP_NAME, &TelemetryConfig::from_env())?);
 
     info!("----- start -----");
-    run(service_fn(handler)).await
+    run(service_fn(move |event| {
+        let telemetry = telemetry.clone();
+        async move {
+            let res = handler(event).await;
+            telemetry.flush();
+            res
+        }
+    }))
+    .await
 }
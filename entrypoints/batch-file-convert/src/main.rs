@@ -1,23 +1,40 @@
-use std::{path::Path, sync::Arc};
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::{Arc, LazyLock},
+};
 
 use aws_config::BehaviorVersion;
 use aws_lambda_events::sqs::SqsEvent;
-use chrono::Duration;
+use chrono::{Duration, Utc};
 use lambda_runtime::{LambdaEvent, run, service_fn};
 use parking_lot::Mutex;
 use sqlx::postgres::PgPoolOptions;
-use tracing::info;
-use tracing_subscriber::EnvFilter;
+use tracing::{info, warn};
 
 use omnius_core_base::{clock::ClockUtc, random_bytes::RandomBytesProviderImpl, tsid::TsidProviderImpl};
 use omnius_core_cloud::aws::s3::S3ClientImpl;
 
-use omnius_opxs_base::{AppConfig, AppInfo, RunMode};
-use omnius_opxs_file_convert::{FileConvertExecutor, FileConvertJobRepository, ImageConvertJobSqsMessage, ImageConverterImpl};
+use omnius_opxs_base::{
+    AppConfig, AppInfo, RunMode,
+    aws::{S3ObjectMetadataClient as _, S3ObjectMetadataClientImpl},
+    metrics::EmfEmitter,
+    telemetry::{self, Telemetry, TelemetryConfig},
+};
+use omnius_opxs_file_convert::{
+    FileConvertExecutor, FileConvertJobRepository, FileConvertJobSqsMessage, ImageConvertJobSqsMessage, ImageConverterImpl,
+};
 
 const APP_NAME: &str = "opxs-batch-file-convert";
 
-async fn handler_sub(job_ids: &[String]) -> std::result::Result<(), lambda_runtime::Error> {
+static EMF_EMITTER: LazyLock<EmfEmitter> = LazyLock::new(|| EmfEmitter::new("opxs/batch-file-convert"));
+
+enum Job {
+    Message(FileConvertJobSqsMessage),
+    Uploaded { key: String },
+}
+
+async fn handler_sub(jobs: Vec<Job>) -> std::result::Result<(), lambda_runtime::Error> {
     let mode = RunMode::from_env()?;
     let info = AppInfo::new(APP_NAME, mode)?;
     info!("info: {}", info);
@@ -33,19 +50,46 @@ async fn handler_sub(job_ids: &[String]) -> std::result::Result<(), lambda_runti
     let clock = Arc::new(ClockUtc {});
     let tsid_provider = Arc::new(Mutex::new(TsidProviderImpl::new(ClockUtc, RandomBytesProviderImpl::new(), 16)));
 
+    let s3_client = aws_sdk_s3::Client::new(&aws_config::load_defaults(BehaviorVersion::latest()).await);
+    let bucket = conf.image.convert.s3.ok_or_else(|| anyhow::anyhow!("s3 config is not found"))?.bucket;
+    let s3_metadata_client = S3ObjectMetadataClientImpl {
+        client: s3_client.clone(),
+        bucket: bucket.clone(),
+    };
+
+    let mut ms: Vec<FileConvertJobSqsMessage> = Vec::new();
+    for job in jobs {
+        match job {
+            Job::Message(m) => ms.push(m),
+            Job::Uploaded { key } => {
+                let job_id = Path::new(&key)
+                    .file_name()
+                    .ok_or_else(|| anyhow::anyhow!("file name is not found"))?
+                    .to_string_lossy()
+                    .to_string();
+                // The upload request's trace context is stored in the object metadata by the presigned URL.
+                let trace_context = match s3_metadata_client.get_object_metadata(&key).await {
+                    Ok(metadata) => telemetry::trace_context_from(metadata.iter().map(|(k, v)| (k.as_str(), v.as_str()))),
+                    Err(e) => {
+                        warn!("trace context restore failed: {:?}", e);
+                        HashMap::new()
+                    }
+                };
+                ms.push(FileConvertJobSqsMessage { job_id, trace_context });
+            }
+        }
+    }
+
     let executor = FileConvertExecutor {
         file_convert_job_repository: Arc::new(FileConvertJobRepository {
             db: db.clone(),
             clock,
             tsid_provider,
         }),
-        s3_client: Arc::new(S3ClientImpl {
-            client: aws_sdk_s3::Client::new(&aws_config::load_defaults(BehaviorVersion::latest()).await),
-            bucket: conf.image.convert.s3.ok_or_else(|| anyhow::anyhow!("s3 config is not found"))?.bucket,
-        }),
+        s3_client: Arc::new(S3ClientImpl { client: s3_client, bucket }),
         image_converter: Arc::new(ImageConverterImpl),
     };
-    executor.execute(job_ids).await?;
+    executor.execute(&ms).await?;
 
     Ok(())
 }
@@ -53,21 +97,27 @@ async fn handler_sub(job_ids: &[String]) -> std::result::Result<(), lambda_runti
 async fn handler(event: LambdaEvent<serde_json::Value>) -> std::result::Result<(), lambda_runtime::Error> {
     let (event, _context) = event.into_parts();
 
-    let mut job_ids: Vec<String> = Vec::new();
+    let mut jobs: Vec<Job> = Vec::new();
 
     if let Ok(event) = serde_json::from_value::<SqsEvent>(event.clone()) {
         info!("sqs event");
-        for v in event.records.into_iter().flat_map(|n| n.body).collect::<Vec<_>>() {
+        for r in event.records.into_iter() {
+            let Some(v) = r.body else {
+                continue;
+            };
             info!("{:?}", v);
+            if let Ok(m) = serde_json::from_str::<FileConvertJobSqsMessage>(&v) {
+                let trace_context = telemetry::trace_context_from(
+                    r.message_attributes
+                        .iter()
+                        .filter_map(|(k, v)| Some((k.as_str(), v.string_value.as_deref()?))),
+                );
+                jobs.push(Job::Message(FileConvertJobSqsMessage { trace_context, ..m }));
+                continue;
+            }
             let m = serde_json::from_str::<ImageConvertJobSqsMessage>(&v)?;
             for v in m.records {
-                let p = Path::new(&v.s3.object.key);
-                let job_id = p
-                    .file_name()
-                    .ok_or_else(|| anyhow::anyhow!("file name is not found"))?
-                    .to_string_lossy()
-                    .to_string();
-                job_ids.push(job_id);
+                jobs.push(Job::Uploaded { key: v.s3.object.key });
             }
         }
     } else {
@@ -78,26 +128,26 @@ async fn handler(event: LambdaEvent<serde_json::Value>) -> std::result::Result<(
             .as_str()
             .ok_or_else(|| anyhow::anyhow!("key is not string"))?
             .to_string();
-        let p = Path::new(&key);
-        let job_id = p.file_name().ok_or_else(|| anyhow::anyhow!("file name is not found"))?.to_string_lossy();
-        job_ids.push(job_id.to_string());
+        jobs.push(Job::Uploaded { key });
     }
 
-    handler_sub(&job_ids).await?;
-
-    Ok(())
+    let res = handler_sub(jobs).await;
+    EMF_EMITTER.emit(&Utc::now());
+    res
 }
 
 #[tokio::main]
 async fn main() -> std::result::Result<(), lambda_runtime::Error> {
-    if cfg!(debug_assertions) {
-        let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info,sqlx=off"));
-        tracing_subscriber::fmt().with_env_filter(filter).with_target(false).init();
-    } else {
-        let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info,sqlx=off"));
-        tracing_subscriber::fmt().with_env_filter(filter).with_target(false).json().init();
-    }
+    let telemetry = Arc::new(Telemetry::init(APP_NAME, &TelemetryConfig::from_env())?);
 
     info!("----- start -----");
-    run(service_fn(handler)).await
+    run(service_fn(move |event| {
+        let telemetry = telemetry.clone();
+        async move {
+            let res = handler(event).await;
+            telemetry.flush();
+            res
+        }
+    }))
+    .await
 }
@@ -0,0 +1,190 @@
+use std::sync::Arc;
+
+use tracing::{error, info};
+
+use crate::{AccountPurgeRepository, S3ObjectRemover, prelude::*};
+
+const PURGE_BATCH_SIZE: i64 = 100;
+
+pub struct AccountPurgeExecutor {
+    pub account_purge_repository: Arc<AccountPurgeRepository>,
+    pub file_convert_s3_remover: Arc<dyn S3ObjectRemover + Send + Sync>,
+    pub data_export_s3_remover: Arc<dyn S3ObjectRemover + Send + Sync>,
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct AccountPurgeReport {
+    pub purged_count: usize,
+    pub failed_user_ids: Vec<String>,
+}
+
+impl AccountPurgeExecutor {
+    /// Purges every account whose grace period has ended.
+    /// A failed account is logged and skipped for the rest of the run; it stays due and is retried by the next run.
+    pub async fn execute(&self) -> Result<AccountPurgeReport> {
+        let mut report = AccountPurgeReport::default();
+
+        loop {
+            let user_ids = self
+                .account_purge_repository
+                .get_due_user_ids(&report.failed_user_ids, PURGE_BATCH_SIZE)
+                .await?;
+            if user_ids.is_empty() {
+                break;
+            }
+
+            for user_id in user_ids.into_iter() {
+                info!("Start purging user: {}", user_id);
+                match self.execute_one(&user_id).await {
+                    Ok(()) => report.purged_count += 1,
+                    Err(e) => {
+                        error!(error = ?e, "Failed to purge user: {}", user_id);
+                        report.failed_user_ids.push(user_id);
+                    }
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
+    // S3 objects go first so that a failure leaves the rows in place and the next run retries the whole account.
+    async fn execute_one(&self, user_id: &str) -> Result<()> {
+        let file_convert_keys: Vec<String> = self
+            .account_purge_repository
+            .get_file_convert_job_ids(user_id)
+            .await?
+            .into_iter()
+            .flat_map(|job_id| [format!("in/{job_id}"), format!("out/{job_id}")])
+            .collect();
+        if !file_convert_keys.is_empty() {
+            self.file_convert_s3_remover.delete_objects(&file_convert_keys).await?;
+        }
+
+        let data_export_keys: Vec<String> = self
+            .account_purge_repository
+            .get_data_export_job_ids(user_id)
+            .await?
+            .into_iter()
+            .map(|job_id| format!("out/{job_id}.zip"))
+            .collect();
+        if !data_export_keys.is_empty() {
+            self.data_export_s3_remover.delete_objects(&data_export_keys).await?;
+        }
+
+        self.account_purge_repository.delete_user(user_id).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+    use sqlx::postgres::PgPoolOptions;
+    use testresult::TestResult;
+
+    use omnius_core_base::clock::ClockUtc;
+    use omnius_core_migration::postgres::PostgresMigrator;
+    use omnius_core_testkit::containers::postgres::PostgresContainer;
+
+    use omnius_opxs_base::shared::POSTGRES_VERSION;
+
+    use crate::S3ObjectRemoverMock;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn simple_test() -> TestResult {
+        let container = PostgresContainer::new(POSTGRES_VERSION).await?;
+
+        let db = Arc::new(
+            PgPoolOptions::new()
+                .max_connections(100)
+                .idle_timeout(Some(Duration::minutes(15).to_std().unwrap()))
+                .connect(&container.connection_string)
+                .await
+                .unwrap(),
+        );
+        let clock = Arc::new(ClockUtc {});
+
+        let migrations_path = concat!(env!("CARGO_MANIFEST_DIR"), "/../../conf/migrations");
+        let migrator = PostgresMigrator::new(&container.connection_string, migrations_path, "opxs-api", "")
+            .await
+            .unwrap();
+        migrator.migrate().await.unwrap();
+
+        sqlx::query(
+            r#"
+INSERT INTO users (id, name, authentication_type, role, deletion_scheduled_at, created_at, updated_at)
+    VALUES
+        ('due_user_id', 'due_user', 'Email', 'User', now() - interval '1 day', now(), now()),
+        ('broken_user_id', 'broken_user', 'Email', 'User', now() - interval '2 day', now(), now()),
+        ('pending_user_id', 'pending_user', 'Email', 'User', now() + interval '1 day', now(), now()),
+        ('active_user_id', 'active_user', 'Email', 'User', NULL, now(), now());
+INSERT INTO user_auth_emails (user_id, email, password_hash, email_verified, created_at, updated_at)
+    VALUES ('due_user_id', 'due@example.com', 'hash', true, now(), now());
+INSERT INTO file_convert_jobs (id, user_id, type, status, in_file_name, out_file_name, created_at, updated_at)
+    VALUES
+        ('due_job_id', 'due_user_id', 'Image', 'Completed', 'in.jpg', 'out.png', now(), now()),
+        ('broken_job_id', 'broken_user_id', 'Image', 'Completed', 'in.jpg', 'out.png', now(), now()),
+        ('pending_job_id', 'pending_user_id', 'Image', 'Completed', 'in.jpg', 'out.png', now(), now());
+INSERT INTO data_export_jobs (id, user_id, status, created_at, updated_at)
+    VALUES ('due_export_id', 'due_user_id', 'Completed', now(), now());
+"#,
+        )
+        .execute(db.as_ref())
+        .await?;
+
+        let file_convert_s3_remover = Arc::new(S3ObjectRemoverMock::new());
+        file_convert_s3_remover.failing_keys.lock().push("in/broken_job_id".to_string());
+        let data_export_s3_remover = Arc::new(S3ObjectRemoverMock::new());
+        let executor = AccountPurgeExecutor {
+            account_purge_repository: Arc::new(AccountPurgeRepository { db: db.clone(), clock }),
+            file_convert_s3_remover: file_convert_s3_remover.clone(),
+            data_export_s3_remover: data_export_s3_remover.clone(),
+        };
+
+        // the broken account is due first, its failure does not stop the run
+        let report = executor.execute().await?;
+        assert_eq!(report.purged_count, 1);
+        assert_eq!(report.failed_user_ids, vec!["broken_user_id".to_string()]);
+
+        assert_eq!(
+            *file_convert_s3_remover.delete_objects_inputs.lock(),
+            vec![
+                vec!["in/broken_job_id".to_string(), "out/broken_job_id".to_string()],
+                vec!["in/due_job_id".to_string(), "out/due_job_id".to_string()]
+            ]
+        );
+        assert_eq!(
+            *data_export_s3_remover.delete_objects_inputs.lock(),
+            vec![vec!["out/due_export_id.zip".to_string()]]
+        );
+
+        let user_ids: Vec<(String,)> = sqlx::query_as("SELECT id FROM users ORDER BY id").fetch_all(db.as_ref()).await?;
+        assert_eq!(
+            user_ids,
+            vec![
+                ("active_user_id".to_string(),),
+                ("broken_user_id".to_string(),),
+                ("pending_user_id".to_string(),)
+            ]
+        );
+        let job_ids: Vec<(String,)> = sqlx::query_as("SELECT id FROM file_convert_jobs ORDER BY id")
+            .fetch_all(db.as_ref())
+            .await?;
+        assert_eq!(job_ids, vec![("broken_job_id".to_string(),), ("pending_job_id".to_string(),)]);
+
+        // the next run retries the failed account
+        file_convert_s3_remover.failing_keys.lock().clear();
+        assert_eq!(
+            executor.execute().await?,
+            AccountPurgeReport {
+                purged_count: 1,
+                failed_user_ids: vec![]
+            }
+        );
+        assert_eq!(executor.execute().await?, AccountPurgeReport::default());
+
+        Ok(())
+    }
+}
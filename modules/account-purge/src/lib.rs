@@ -0,0 +1,16 @@
+mod error;
+mod executor;
+mod prelude;
+mod remover;
+mod repo;
+
+mod result {
+    #[allow(unused)]
+    pub type Result<T> = std::result::Result<T, crate::error::Error>;
+}
+
+pub use error::*;
+pub use executor::*;
+pub use remover::*;
+pub use repo::*;
+pub use result::*;
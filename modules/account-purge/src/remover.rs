@@ -0,0 +1,5 @@
+mod s3;
+mod s3_mock;
+
+pub use s3::*;
+pub use s3_mock::*;
@@ -0,0 +1,62 @@
+use async_trait::async_trait;
+use aws_sdk_s3::types::{Delete, ObjectIdentifier};
+
+use crate::prelude::*;
+
+// The shared `S3Client` only covers presigning and single-object transfers, so deletion gets its own small trait.
+#[async_trait]
+pub trait S3ObjectRemover {
+    async fn delete_objects(&self, keys: &[String]) -> Result<()>;
+}
+
+// DeleteObjects accepts at most 1000 keys per request.
+const DELETE_OBJECTS_MAX_KEYS: usize = 1000;
+
+pub struct S3ObjectRemoverImpl {
+    pub client: aws_sdk_s3::Client,
+    pub bucket: String,
+}
+
+#[async_trait]
+impl S3ObjectRemover for S3ObjectRemoverImpl {
+    async fn delete_objects(&self, keys: &[String]) -> Result<()> {
+        for chunk in keys.chunks(DELETE_OBJECTS_MAX_KEYS) {
+            let objects = chunk
+                .iter()
+                .map(|key| ObjectIdentifier::builder().key(key).build())
+                .collect::<std::result::Result<Vec<_>, _>>()
+                .map_err(|e| Error::builder().kind(ErrorKind::AwsError).message("invalid object key").source(e).build())?;
+            let delete = Delete::builder().set_objects(Some(objects)).quiet(true).build().map_err(|e| {
+                Error::builder()
+                    .kind(ErrorKind::AwsError)
+                    .message("invalid delete request")
+                    .source(e)
+                    .build()
+            })?;
+
+            let output = self
+                .client
+                .delete_objects()
+                .bucket(&self.bucket)
+                .delete(delete)
+                .send()
+                .await
+                .map_err(|e| {
+                    Error::builder()
+                        .kind(ErrorKind::AwsError)
+                        .message("delete objects failed")
+                        .source(e)
+                        .build()
+                })?;
+
+            if let Some(e) = output.errors().first() {
+                return Err(Error::builder()
+                    .kind(ErrorKind::AwsError)
+                    .message(format!("delete object failed: {:?} {:?}", e.key(), e.message()))
+                    .build());
+            }
+        }
+
+        Ok(())
+    }
+}
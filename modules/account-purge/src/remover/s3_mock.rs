@@ -0,0 +1,39 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use parking_lot::Mutex;
+
+use crate::prelude::*;
+
+use super::S3ObjectRemover;
+
+pub struct S3ObjectRemoverMock {
+    pub delete_objects_inputs: Arc<Mutex<Vec<Vec<String>>>>,
+    pub failing_keys: Arc<Mutex<Vec<String>>>,
+}
+
+#[async_trait]
+impl S3ObjectRemover for S3ObjectRemoverMock {
+    async fn delete_objects(&self, keys: &[String]) -> Result<()> {
+        self.delete_objects_inputs.lock().push(keys.to_vec());
+        if keys.iter().any(|key| self.failing_keys.lock().contains(key)) {
+            return Err(Error::builder().kind(ErrorKind::AwsError).message("delete objects failed").build());
+        }
+        Ok(())
+    }
+}
+
+impl S3ObjectRemoverMock {
+    pub fn new() -> Self {
+        Self {
+            delete_objects_inputs: Arc::new(Mutex::new(vec![])),
+            failing_keys: Arc::new(Mutex::new(vec![])),
+        }
+    }
+}
+
+impl Default for S3ObjectRemoverMock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,86 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+use sqlx::PgPool;
+
+use omnius_core_base::clock::Clock;
+
+use crate::prelude::*;
+
+pub struct AccountPurgeRepository {
+    pub db: Arc<PgPool>,
+    pub clock: Arc<dyn Clock<Utc> + Send + Sync>,
+}
+
+impl AccountPurgeRepository {
+    pub async fn get_due_user_ids(&self, excluded_user_ids: &[String], limit: i64) -> Result<Vec<String>> {
+        let now = self.clock.now();
+
+        let res: Vec<(String,)> = sqlx::query_as(
+            r#"
+SELECT id
+    FROM users
+    WHERE deletion_scheduled_at <= $1 AND NOT (id = ANY($2))
+    ORDER BY deletion_scheduled_at
+    LIMIT $3;
+"#,
+        )
+        .bind(now)
+        .bind(excluded_user_ids)
+        .bind(limit)
+        .fetch_all(self.db.as_ref())
+        .await?;
+
+        Ok(res.into_iter().map(|(v,)| v).collect())
+    }
+
+    pub async fn get_file_convert_job_ids(&self, user_id: &str) -> Result<Vec<String>> {
+        let res: Vec<(String,)> = sqlx::query_as("SELECT id FROM file_convert_jobs WHERE user_id = $1;")
+            .bind(user_id)
+            .fetch_all(self.db.as_ref())
+            .await?;
+
+        Ok(res.into_iter().map(|(v,)| v).collect())
+    }
+
+    pub async fn get_data_export_job_ids(&self, user_id: &str) -> Result<Vec<String>> {
+        let res: Vec<(String,)> = sqlx::query_as("SELECT id FROM data_export_jobs WHERE user_id = $1;")
+            .bind(user_id)
+            .fetch_all(self.db.as_ref())
+            .await?;
+
+        Ok(res.into_iter().map(|(v,)| v).collect())
+    }
+
+    // Auth rows, sessions, API keys and data export jobs are removed by `ON DELETE CASCADE`;
+    // file convert jobs and login attempts are not linked to `users` and are deleted explicitly.
+    pub async fn delete_user(&self, user_id: &str) -> Result<()> {
+        let now = self.clock.now();
+
+        let mut tx = self.db.begin().await?;
+
+        let due: Option<(String,)> = sqlx::query_as("SELECT id FROM users WHERE id = $1 AND deletion_scheduled_at <= $2 FOR UPDATE;")
+            .bind(user_id)
+            .bind(now)
+            .fetch_optional(&mut *tx)
+            .await?;
+        if due.is_none() {
+            return Err(Error::builder().kind(ErrorKind::NotFound).message("user is not due for deletion").build());
+        }
+
+        let queries = vec![
+            sqlx::query("DELETE FROM file_convert_jobs WHERE user_id = $1").bind(user_id),
+            sqlx::query("DELETE FROM login_attempts WHERE key_type = 'Email' AND key IN (SELECT email FROM user_auth_emails WHERE user_id = $1)")
+                .bind(user_id),
+            sqlx::query("DELETE FROM users WHERE id = $1").bind(user_id),
+        ];
+
+        for query in queries {
+            query.execute(&mut *tx).await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,218 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+use parking_lot::Mutex;
+use sqlx::{PgPool, Postgres, Transaction};
+
+use omnius_core_base::{clock::Clock, tsid::TsidProvider};
+
+use crate::{
+    model::{AdminAuditAction, AdminAuditLog, AdminUser, UserRole, UserSession},
+    prelude::*,
+};
+
+pub struct AdminRepo {
+    pub db: Arc<PgPool>,
+    pub clock: Arc<dyn Clock<Utc> + Send + Sync>,
+    pub tsid_provider: Arc<Mutex<dyn TsidProvider + Send + Sync>>,
+}
+
+impl AdminRepo {
+    // Write actions take the transaction so that they are committed together with their audit log.
+    pub async fn begin(&self) -> Result<Transaction<'static, Postgres>> {
+        Ok(self.db.begin().await?)
+    }
+
+    pub async fn search_users(&self, query: &str, limit: i64) -> Result<Vec<AdminUser>> {
+        let pattern = format!("%{}%", query.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_"));
+        let users: Vec<AdminUser> = sqlx::query_as(
+            r#"
+SELECT u.id, u.name, u.role, e.email, u.disabled_at, u.deletion_scheduled_at, u.created_at, u.updated_at
+    FROM users u
+    LEFT JOIN user_auth_emails e ON e.user_id = u.id
+    WHERE u.id = $1 OR u.name ILIKE $2 OR e.email ILIKE $2
+    ORDER BY u.created_at DESC
+    LIMIT $3;
+"#,
+        )
+        .bind(query)
+        .bind(pattern)
+        .bind(limit)
+        .fetch_all(self.db.as_ref())
+        .await?;
+
+        Ok(users)
+    }
+
+    pub async fn get_user(&self, user_id: &str) -> Result<AdminUser> {
+        let user: Option<AdminUser> = sqlx::query_as(
+            r#"
+SELECT u.id, u.name, u.role, e.email, u.disabled_at, u.deletion_scheduled_at, u.created_at, u.updated_at
+    FROM users u
+    LEFT JOIN user_auth_emails e ON e.user_id = u.id
+    WHERE u.id = $1;
+"#,
+        )
+        .bind(user_id)
+        .fetch_optional(self.db.as_ref())
+        .await?;
+
+        user.ok_or_else(|| Error::builder().kind(ErrorKind::NotFound).message("User not found").build())
+    }
+
+    pub async fn get_sessions(&self, user_id: &str) -> Result<Vec<UserSession>> {
+        let now = self.clock.now();
+        let sessions: Vec<UserSession> = sqlx::query_as(
+            r#"
+SELECT ip_address, user_agent, expires_at, created_at
+    FROM refresh_tokens
+    WHERE user_id = $1 AND expires_at > $2
+    ORDER BY created_at DESC;
+"#,
+        )
+        .bind(user_id)
+        .bind(now)
+        .fetch_all(self.db.as_ref())
+        .await?;
+
+        Ok(sessions)
+    }
+
+    pub async fn delete_sessions(&self, tx: &mut Transaction<'_, Postgres>, user_id: &str) -> Result<u64> {
+        let res = sqlx::query(
+            r#"
+DELETE FROM refresh_tokens
+    WHERE user_id = $1;
+"#,
+        )
+        .bind(user_id)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(res.rows_affected())
+    }
+
+    pub async fn disable_user(&self, tx: &mut Transaction<'_, Postgres>, user_id: &str) -> Result<()> {
+        let now = self.clock.now();
+
+        let res = sqlx::query(
+            r#"
+UPDATE users
+    SET disabled_at = COALESCE(disabled_at, $2), updated_at = $2
+    WHERE id = $1;
+"#,
+        )
+        .bind(user_id)
+        .bind(now)
+        .execute(&mut **tx)
+        .await?;
+
+        if res.rows_affected() < 1 {
+            return Err(Error::builder().kind(ErrorKind::NotFound).message("User not found").build());
+        }
+
+        sqlx::query(
+            r#"
+DELETE FROM refresh_tokens
+    WHERE user_id = $1;
+"#,
+        )
+        .bind(user_id)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn enable_user(&self, tx: &mut Transaction<'_, Postgres>, user_id: &str) -> Result<()> {
+        let now = self.clock.now();
+
+        let res = sqlx::query(
+            r#"
+UPDATE users
+    SET disabled_at = NULL, updated_at = $2
+    WHERE id = $1;
+"#,
+        )
+        .bind(user_id)
+        .bind(now)
+        .execute(&mut **tx)
+        .await?;
+
+        if res.rows_affected() < 1 {
+            return Err(Error::builder().kind(ErrorKind::NotFound).message("User not found").build());
+        }
+
+        Ok(())
+    }
+
+    pub async fn update_role(&self, tx: &mut Transaction<'_, Postgres>, user_id: &str, role: &UserRole) -> Result<()> {
+        let now = self.clock.now();
+
+        let res = sqlx::query(
+            r#"
+UPDATE users
+    SET role = $2, updated_at = $3
+    WHERE id = $1;
+"#,
+        )
+        .bind(user_id)
+        .bind(role)
+        .bind(now)
+        .execute(&mut **tx)
+        .await?;
+
+        if res.rows_affected() < 1 {
+            return Err(Error::builder().kind(ErrorKind::NotFound).message("User not found").build());
+        }
+
+        Ok(())
+    }
+
+    pub async fn create_audit_log(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        operator_user_id: &str,
+        action: &AdminAuditAction,
+        target_id: Option<&str>,
+        detail: Option<&str>,
+    ) -> Result<()> {
+        let id = self.tsid_provider.lock().create().to_string();
+        let now = self.clock.now();
+
+        sqlx::query(
+            r#"
+INSERT INTO admin_audit_logs (id, operator_user_id, action, target_id, detail, created_at)
+    VALUES ($1, $2, $3, $4, $5, $6);
+"#,
+        )
+        .bind(id)
+        .bind(operator_user_id)
+        .bind(action)
+        .bind(target_id)
+        .bind(detail)
+        .bind(now)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_audit_logs(&self, target_id: Option<&str>, limit: i64) -> Result<Vec<AdminAuditLog>> {
+        let logs: Vec<AdminAuditLog> = sqlx::query_as(
+            r#"
+SELECT *
+    FROM admin_audit_logs
+    WHERE $1::VARCHAR IS NULL OR target_id = $1
+    ORDER BY created_at DESC, id DESC
+    LIMIT $2;
+"#,
+        )
+        .bind(target_id)
+        .bind(limit)
+        .fetch_all(self.db.as_ref())
+        .await?;
+
+        Ok(logs)
+    }
+}
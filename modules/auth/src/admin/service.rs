@@ -0,0 +1,545 @@
+use std::sync::Arc;
+
+use serde_json::json;
+use sqlx::{Postgres, Transaction};
+
+use omnius_opxs_email_send::{EmailSendJobBatchDetail, EmailSendJobCreator};
+use omnius_opxs_file_convert::{FileConvertJob, FileConvertJobCreator};
+
+use crate::{
+    api_key::ApiKeyRepo,
+    link::AccountLinkRepo,
+    model::{AdminAuditAction, AdminAuditLog, AdminUser, AdminUserDetail, User, UserRole},
+    permission::Permission,
+    prelude::*,
+};
+
+use super::AdminRepo;
+
+const LIMIT_MAX: i64 = 100;
+
+pub struct AdminService {
+    pub admin_repo: Arc<AdminRepo>,
+    pub link_repo: Arc<AccountLinkRepo>,
+    pub api_key_repo: Arc<ApiKeyRepo>,
+    pub file_convert_job_creator: Arc<FileConvertJobCreator>,
+    pub email_send_job_creator: Arc<EmailSendJobCreator>,
+}
+
+impl AdminService {
+    pub async fn search_users(&self, operator: &User, query: &str, limit: i64) -> Result<Vec<AdminUser>> {
+        operator.role.authorize(Permission::UsersRead)?;
+
+        let query = query.trim();
+        if query.is_empty() {
+            return Err(Error::builder().kind(ErrorKind::InvalidRequest).message("query is empty").build());
+        }
+
+        let users = self.admin_repo.search_users(query, limit.clamp(1, LIMIT_MAX)).await?;
+        self.record(operator, AdminAuditAction::SearchUsers, None, Some(&json!({ "query": query })))
+            .await?;
+
+        Ok(users)
+    }
+
+    pub async fn get_user_detail(&self, operator: &User, user_id: &str) -> Result<AdminUserDetail> {
+        operator.role.authorize(Permission::UsersRead)?;
+
+        let detail = AdminUserDetail {
+            user: self.admin_repo.get_user(user_id).await?,
+            auth_methods: self.link_repo.get_auth_methods(user_id).await?,
+            api_keys: self.api_key_repo.get_keys(user_id).await?,
+            sessions: self.admin_repo.get_sessions(user_id).await?,
+        };
+        self.record(operator, AdminAuditAction::ViewUser, Some(user_id), None).await?;
+
+        Ok(detail)
+    }
+
+    pub async fn force_logout(&self, operator: &User, user_id: &str) -> Result<()> {
+        operator.role.authorize(Permission::UsersWrite)?;
+
+        self.admin_repo.get_user(user_id).await?;
+
+        let mut tx = self.admin_repo.begin().await?;
+        let count = self.admin_repo.delete_sessions(&mut tx, user_id).await?;
+        self.record_in(
+            &mut tx,
+            operator,
+            AdminAuditAction::ForceLogout,
+            Some(user_id),
+            Some(&json!({ "revoked_sessions": count })),
+        )
+        .await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    pub async fn disable_user(&self, operator: &User, user_id: &str) -> Result<()> {
+        operator.role.authorize(Permission::UsersWrite)?;
+
+        if operator.id == user_id {
+            return Err(Error::builder().kind(ErrorKind::Forbidden).message("cannot disable own account").build());
+        }
+
+        let mut tx = self.admin_repo.begin().await?;
+        self.admin_repo.disable_user(&mut tx, user_id).await?;
+        self.record_in(&mut tx, operator, AdminAuditAction::DisableUser, Some(user_id), None)
+            .await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    pub async fn enable_user(&self, operator: &User, user_id: &str) -> Result<()> {
+        operator.role.authorize(Permission::UsersWrite)?;
+
+        let mut tx = self.admin_repo.begin().await?;
+        self.admin_repo.enable_user(&mut tx, user_id).await?;
+        self.record_in(&mut tx, operator, AdminAuditAction::EnableUser, Some(user_id), None)
+            .await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    pub async fn update_role(&self, operator: &User, user_id: &str, role: &UserRole) -> Result<()> {
+        operator.role.authorize(Permission::RolesWrite)?;
+
+        // Prevents admins from locking themselves out of the admin API.
+        if operator.id == user_id {
+            return Err(Error::builder().kind(ErrorKind::Forbidden).message("cannot change own role").build());
+        }
+
+        let mut tx = self.admin_repo.begin().await?;
+        self.admin_repo.update_role(&mut tx, user_id, role).await?;
+        self.record_in(
+            &mut tx,
+            operator,
+            AdminAuditAction::UpdateUserRole,
+            Some(user_id),
+            Some(&json!({ "role": role })),
+        )
+        .await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    pub async fn get_user_jobs(&self, operator: &User, user_id: &str) -> Result<Vec<FileConvertJob>> {
+        operator.role.authorize(Permission::JobsRead)?;
+
+        let jobs = self
+            .file_convert_job_creator
+            .file_convert_job_repository
+            .get_jobs_by_user_id(user_id, LIMIT_MAX)
+            .await?;
+        self.record(operator, AdminAuditAction::ViewUserJobs, Some(user_id), None).await?;
+
+        Ok(jobs)
+    }
+
+    pub async fn get_failed_file_convert_jobs(&self, operator: &User, limit: i64) -> Result<Vec<FileConvertJob>> {
+        operator.role.authorize(Permission::JobsRead)?;
+
+        let jobs = self
+            .file_convert_job_creator
+            .file_convert_job_repository
+            .get_failed_jobs(limit.clamp(1, LIMIT_MAX))
+            .await?;
+        self.record(operator, AdminAuditAction::ViewFailedJobs, None, Some(&json!({ "type": "file-convert" })))
+            .await?;
+
+        Ok(jobs)
+    }
+
+    pub async fn retry_file_convert_job(&self, operator: &User, job_id: &str) -> Result<()> {
+        operator.role.authorize(Permission::JobsWrite)?;
+
+        let mut tx = self.admin_repo.begin().await?;
+        self.record_in(&mut tx, operator, AdminAuditAction::RetryFileConvertJob, Some(job_id), None)
+            .await?;
+        self.file_convert_job_creator.retry_job(tx, job_id).await?;
+
+        Ok(())
+    }
+
+    pub async fn get_failed_email_send_jobs(&self, operator: &User, limit: i64) -> Result<Vec<EmailSendJobBatchDetail>> {
+        operator.role.authorize(Permission::JobsRead)?;
+
+        let details = self
+            .email_send_job_creator
+            .email_send_job_repository
+            .get_failed_job_batch_details(limit.clamp(1, LIMIT_MAX))
+            .await?;
+        self.record(operator, AdminAuditAction::ViewFailedJobs, None, Some(&json!({ "type": "email-send" })))
+            .await?;
+
+        Ok(details)
+    }
+
+    pub async fn retry_email_send_job(&self, operator: &User, job_id: &str) -> Result<()> {
+        operator.role.authorize(Permission::JobsWrite)?;
+
+        let mut tx = self.admin_repo.begin().await?;
+        self.record_in(&mut tx, operator, AdminAuditAction::RetryEmailSendJob, Some(job_id), None)
+            .await?;
+        self.email_send_job_creator.retry_job(tx, job_id).await?;
+
+        Ok(())
+    }
+
+    pub async fn get_audit_logs(&self, operator: &User, target_id: Option<&str>, limit: i64) -> Result<Vec<AdminAuditLog>> {
+        operator.role.authorize(Permission::UsersRead)?;
+
+        self.admin_repo.get_audit_logs(target_id, limit.clamp(1, LIMIT_MAX)).await
+    }
+
+    async fn record(&self, operator: &User, action: AdminAuditAction, target_id: Option<&str>, detail: Option<&serde_json::Value>) -> Result<()> {
+        let mut tx = self.admin_repo.begin().await?;
+        self.record_in(&mut tx, operator, action, target_id, detail).await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn record_in(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        operator: &User,
+        action: AdminAuditAction,
+        target_id: Option<&str>,
+        detail: Option<&serde_json::Value>,
+    ) -> Result<()> {
+        let detail = detail.map(|v| v.to_string());
+        self.admin_repo
+            .create_audit_log(tx, &operator.id, &action, target_id, detail.as_deref())
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+    use parking_lot::Mutex;
+    use sqlx::{PgPool, postgres::PgPoolOptions};
+    use testresult::TestResult;
+
+    use omnius_core_base::{
+        clock::{Clock, ClockUtc},
+        random_bytes::RandomBytesProviderImpl,
+        tsid::{TsidProvider, TsidProviderImpl},
+    };
+    use omnius_core_cloud::aws::s3::S3ClientMock;
+    use omnius_core_migration::postgres::PostgresMigrator;
+    use omnius_core_testkit::containers::postgres::PostgresContainer;
+
+    use omnius_opxs_base::{
+        aws::{S3ObjectMetadataClientMock, SqsMessageSenderMock},
+        shared::POSTGRES_VERSION,
+    };
+    use omnius_opxs_email_send::EmailSendJobRepository;
+    use omnius_opxs_file_convert::FileConvertJobRepository;
+
+    use crate::{
+        email::EmailAuthRepo,
+        model::{UserLocale, UserRole},
+        token::TokenRepo,
+        user::UserRepo,
+    };
+
+    use super::*;
+
+    fn new_admin_service(
+        db: Arc<PgPool>,
+        clock: Arc<dyn Clock<Utc> + Send + Sync>,
+        tsid_provider: Arc<Mutex<dyn TsidProvider + Send + Sync>>,
+        sqs_sender: Arc<SqsMessageSenderMock>,
+    ) -> AdminService {
+        AdminService {
+            admin_repo: Arc::new(AdminRepo {
+                db: db.clone(),
+                clock: clock.clone(),
+                tsid_provider: tsid_provider.clone(),
+            }),
+            link_repo: Arc::new(AccountLinkRepo { db: db.clone() }),
+            api_key_repo: Arc::new(ApiKeyRepo {
+                db: db.clone(),
+                clock: clock.clone(),
+            }),
+            file_convert_job_creator: Arc::new(FileConvertJobCreator {
+                file_convert_job_repository: Arc::new(FileConvertJobRepository {
+                    db: db.clone(),
+                    clock: clock.clone(),
+                    tsid_provider,
+                }),
+                clock: clock.clone(),
+                s3_client: Arc::new(S3ClientMock::new()),
+                s3_metadata_client: Arc::new(S3ObjectMetadataClientMock::new()),
+                sqs_sender: sqs_sender.clone(),
+            }),
+            email_send_job_creator: Arc::new(EmailSendJobCreator {
+                email_send_job_repository: Arc::new(EmailSendJobRepository { db, clock }),
+                sqs_sender,
+            }),
+        }
+    }
+
+    #[tokio::test]
+    async fn simple_test() -> TestResult {
+        let container = PostgresContainer::new(POSTGRES_VERSION).await?;
+
+        let db = Arc::new(
+            PgPoolOptions::new()
+                .max_connections(100)
+                .idle_timeout(Some(Duration::minutes(15).to_std()?))
+                .connect(&container.connection_string)
+                .await?,
+        );
+
+        let migrations_path = concat!(env!("CARGO_MANIFEST_DIR"), "/../../conf/migrations");
+        let migrator = PostgresMigrator::new(&container.connection_string, migrations_path, "opxs-api", "").await?;
+        migrator.migrate().await?;
+
+        let clock = Arc::new(ClockUtc {});
+        let tsid_provider = Arc::new(Mutex::new(TsidProviderImpl::new(ClockUtc, RandomBytesProviderImpl::new(), 16)));
+        let email_auth_repo = EmailAuthRepo {
+            db: db.clone(),
+            clock: clock.clone(),
+            tsid_provider: tsid_provider.clone(),
+        };
+        let user_repo = UserRepo {
+            db: db.clone(),
+            clock: clock.clone(),
+        };
+        let token_repo = TokenRepo {
+            db: db.clone(),
+            clock: clock.clone(),
+        };
+        let admin_service = new_admin_service(db, clock, tsid_provider, Arc::new(SqsMessageSenderMock::new()));
+
+        let admin_id = email_auth_repo
+            .create_user("admin_name", "admin@example.com", "hash", &UserLocale::Ja)
+            .await?;
+        user_repo.update_role(&admin_id, &UserRole::Admin).await?;
+        let admin = user_repo.get_user(&admin_id).await?;
+
+        let user_id = email_auth_repo
+            .create_user("user_name", "user_50%@example.com", "hash", &UserLocale::Ja)
+            .await?;
+        let user = user_repo.get_user(&user_id).await?;
+        let expires_at = Utc::now() + Duration::days(1);
+        token_repo.create_token(&user_id, "refresh_token_1", &expires_at).await?;
+
+        // non-admins are rejected
+        assert_eq!(
+            *admin_service.search_users(&user, "user", 10).await.unwrap_err().kind(),
+            ErrorKind::Forbidden
+        );
+
+        // search by name, email (wildcards are escaped) and id
+        assert_eq!(admin_service.search_users(&admin, "USER_NA", 10).await?.len(), 1);
+        assert_eq!(admin_service.search_users(&admin, "50%@", 10).await?.len(), 1);
+        assert_eq!(admin_service.search_users(&admin, "5_%", 10).await?.len(), 0);
+        let users = admin_service.search_users(&admin, &user_id, 10).await?;
+        assert_eq!(users.first().map(|n| n.email.clone()), Some(Some("user_50%@example.com".to_string())));
+
+        // detail
+        let detail = admin_service.get_user_detail(&admin, &user_id).await?;
+        assert_eq!(detail.auth_methods.len(), 1);
+        assert_eq!(detail.sessions.len(), 1);
+
+        // force logout
+        admin_service.force_logout(&admin, &user_id).await?;
+        assert!(admin_service.get_user_detail(&admin, &user_id).await?.sessions.is_empty());
+
+        // disable
+        token_repo.create_token(&user_id, "refresh_token_2", &expires_at).await?;
+        assert_eq!(
+            *admin_service.disable_user(&admin, &admin_id).await.unwrap_err().kind(),
+            ErrorKind::Forbidden
+        );
+        admin_service.disable_user(&admin, &user_id).await?;
+        assert!(admin_service.get_user_detail(&admin, &user_id).await?.user.disabled_at.is_some());
+        assert_eq!(*user_repo.get_user(&user_id).await.unwrap_err().kind(), ErrorKind::NotFound);
+        assert_eq!(*token_repo.get_user_id("refresh_token_2").await.unwrap_err().kind(), ErrorKind::NotFound);
+        assert_eq!(
+            *token_repo
+                .create_token(&user_id, "refresh_token_3", &expires_at)
+                .await
+                .unwrap_err()
+                .kind(),
+            ErrorKind::AccountDisabled
+        );
+
+        // enable
+        admin_service.enable_user(&admin, &user_id).await?;
+        user_repo.get_user(&user_id).await?;
+        token_repo.create_token(&user_id, "refresh_token_3", &expires_at).await?;
+
+        assert_eq!(
+            *admin_service.enable_user(&admin, "unknown").await.unwrap_err().kind(),
+            ErrorKind::NotFound
+        );
+
+        // audit
+        let logs = admin_service.get_audit_logs(&admin, Some(&user_id), 100).await?;
+        let actions: Vec<AdminAuditAction> = logs.iter().map(|n| n.action).collect();
+        assert_eq!(
+            actions,
+            vec![
+                AdminAuditAction::EnableUser,
+                AdminAuditAction::ViewUser,
+                AdminAuditAction::DisableUser,
+                AdminAuditAction::ViewUser,
+                AdminAuditAction::ForceLogout,
+                AdminAuditAction::ViewUser,
+            ]
+        );
+        assert!(logs.iter().all(|n| n.operator_user_id == admin_id));
+        assert_eq!(admin_service.get_audit_logs(&admin, None, 100).await?.len(), 10);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn update_role_test() -> TestResult {
+        let container = PostgresContainer::new(POSTGRES_VERSION).await?;
+
+        let db = Arc::new(
+            PgPoolOptions::new()
+                .max_connections(100)
+                .idle_timeout(Some(Duration::minutes(15).to_std()?))
+                .connect(&container.connection_string)
+                .await?,
+        );
+
+        let migrations_path = concat!(env!("CARGO_MANIFEST_DIR"), "/../../conf/migrations");
+        let migrator = PostgresMigrator::new(&container.connection_string, migrations_path, "opxs-api", "").await?;
+        migrator.migrate().await?;
+
+        let clock = Arc::new(ClockUtc {});
+        let tsid_provider = Arc::new(Mutex::new(TsidProviderImpl::new(ClockUtc, RandomBytesProviderImpl::new(), 16)));
+        let email_auth_repo = EmailAuthRepo {
+            db: db.clone(),
+            clock: clock.clone(),
+            tsid_provider: tsid_provider.clone(),
+        };
+        let user_repo = UserRepo {
+            db: db.clone(),
+            clock: clock.clone(),
+        };
+        let admin_service = new_admin_service(db, clock, tsid_provider, Arc::new(SqsMessageSenderMock::new()));
+
+        let admin_id = email_auth_repo.create_user("admin", "admin_email", "hash", &UserLocale::Ja).await?;
+        let user_id = email_auth_repo.create_user("user", "user_email", "hash", &UserLocale::Ja).await?;
+        user_repo.update_role(&admin_id, &UserRole::Admin).await?;
+        let admin = user_repo.get_user(&admin_id).await?;
+        let user = user_repo.get_user(&user_id).await?;
+
+        // forbidden without the permission
+        assert_eq!(
+            *admin_service.update_role(&user, &admin_id, &UserRole::User).await.unwrap_err().kind(),
+            ErrorKind::Forbidden
+        );
+        assert_eq!(user_repo.get_user(&admin_id).await?.role, UserRole::Admin);
+
+        // forbidden on the own account
+        assert_eq!(
+            *admin_service.update_role(&admin, &admin_id, &UserRole::User).await.unwrap_err().kind(),
+            ErrorKind::Forbidden
+        );
+
+        // promote and demote
+        admin_service.update_role(&admin, &user_id, &UserRole::Admin).await?;
+        assert_eq!(user_repo.get_user(&user_id).await?.role, UserRole::Admin);
+        admin_service.update_role(&admin, &user_id, &UserRole::User).await?;
+        assert_eq!(user_repo.get_user(&user_id).await?.role, UserRole::User);
+
+        // a failed change leaves no audit log
+        assert_eq!(
+            *admin_service.update_role(&admin, "unknown", &UserRole::Admin).await.unwrap_err().kind(),
+            ErrorKind::NotFound
+        );
+        assert!(admin_service.get_audit_logs(&admin, Some("unknown"), 100).await?.is_empty());
+        assert_eq!(admin_service.get_audit_logs(&admin, Some(&user_id), 100).await?.len(), 2);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn retry_job_test() -> TestResult {
+        let container = PostgresContainer::new(POSTGRES_VERSION).await?;
+
+        let db = Arc::new(
+            PgPoolOptions::new()
+                .max_connections(100)
+                .idle_timeout(Some(Duration::minutes(15).to_std()?))
+                .connect(&container.connection_string)
+                .await?,
+        );
+
+        let migrations_path = concat!(env!("CARGO_MANIFEST_DIR"), "/../../conf/migrations");
+        let migrator = PostgresMigrator::new(&container.connection_string, migrations_path, "opxs-api", "").await?;
+        migrator.migrate().await?;
+
+        let clock = Arc::new(ClockUtc {});
+        let tsid_provider = Arc::new(Mutex::new(TsidProviderImpl::new(ClockUtc, RandomBytesProviderImpl::new(), 16)));
+        let email_auth_repo = EmailAuthRepo {
+            db: db.clone(),
+            clock: clock.clone(),
+            tsid_provider: tsid_provider.clone(),
+        };
+        let user_repo = UserRepo {
+            db: db.clone(),
+            clock: clock.clone(),
+        };
+        let sqs_sender = Arc::new(SqsMessageSenderMock::new());
+        let admin_service = new_admin_service(db.clone(), clock, tsid_provider, sqs_sender.clone());
+
+        let admin_id = email_auth_repo.create_user("admin", "admin_email", "hash", &UserLocale::Ja).await?;
+        user_repo.update_role(&admin_id, &UserRole::Admin).await?;
+        let admin = user_repo.get_user(&admin_id).await?;
+
+        sqlx::query(
+            r#"
+INSERT INTO file_convert_jobs (id, user_id, type, status, in_file_name, out_file_name, failed_reason, created_at, updated_at)
+    VALUES
+        ('failed_job_id', $1, 'Image', 'Failed', 'in.jpg', 'out.png', 'test', now(), now()),
+        ('completed_job_id', $1, 'Image', 'Completed', 'in.jpg', 'out.png', NULL, now(), now());
+"#,
+        )
+        .bind(&admin_id)
+        .execute(db.as_ref())
+        .await?;
+
+        assert_eq!(admin_service.get_failed_file_convert_jobs(&admin, 10).await?.len(), 1);
+        assert_eq!(admin_service.get_user_jobs(&admin, &admin_id).await?.len(), 2);
+
+        // the job is queued again together with its audit log
+        admin_service.retry_file_convert_job(&admin, "failed_job_id").await?;
+        assert_eq!(sqs_sender.send_message_inputs.lock().len(), 1);
+        assert!(admin_service.get_failed_file_convert_jobs(&admin, 10).await?.is_empty());
+        let logs = admin_service.get_audit_logs(&admin, Some("failed_job_id"), 100).await?;
+        assert_eq!(
+            logs.iter().map(|n| n.action).collect::<Vec<_>>(),
+            vec![AdminAuditAction::RetryFileConvertJob]
+        );
+
+        // a job that is not failed is neither retried nor audited
+        assert_eq!(
+            *admin_service.retry_file_convert_job(&admin, "completed_job_id").await.unwrap_err().kind(),
+            ErrorKind::NotFound
+        );
+        assert_eq!(
+            *admin_service.retry_email_send_job(&admin, "unknown_job_id").await.unwrap_err().kind(),
+            ErrorKind::NotFound
+        );
+        assert!(admin_service.get_audit_logs(&admin, Some("completed_job_id"), 100).await?.is_empty());
+        assert!(admin_service.get_audit_logs(&admin, Some("unknown_job_id"), 100).await?.is_empty());
+        assert_eq!(sqs_sender.send_message_inputs.lock().len(), 1);
+
+        Ok(())
+    }
+}
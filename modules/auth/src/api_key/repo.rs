@@ -0,0 +1,120 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+
+use omnius_core_base::clock::Clock;
+
+use crate::{
+    model::{ApiKey, ApiKeyScope},
+    prelude::*,
+};
+
+pub struct ApiKeyRepo {
+    pub db: Arc<PgPool>,
+    pub clock: Arc<dyn Clock<Utc> + Send + Sync>,
+}
+
+impl ApiKeyRepo {
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create_key(
+        &self,
+        id: &str,
+        user_id: &str,
+        name: &str,
+        prefix: &str,
+        key_hash: &str,
+        scopes: &[ApiKeyScope],
+        expires_at: Option<&DateTime<Utc>>,
+    ) -> Result<ApiKey> {
+        let now = self.clock.now();
+        let api_key: ApiKey = sqlx::query_as(
+            r#"
+INSERT INTO api_keys (id, user_id, name, prefix, key_hash, scopes, expires_at, created_at)
+    VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+    RETURNING *;
+"#,
+        )
+        .bind(id)
+        .bind(user_id)
+        .bind(name)
+        .bind(prefix)
+        .bind(key_hash)
+        .bind(scopes)
+        .bind(expires_at)
+        .bind(now)
+        .fetch_one(self.db.as_ref())
+        .await?;
+
+        Ok(api_key)
+    }
+
+    pub async fn get_keys(&self, user_id: &str) -> Result<Vec<ApiKey>> {
+        let api_keys: Vec<ApiKey> = sqlx::query_as(
+            r#"
+SELECT *
+    FROM api_keys
+    WHERE user_id = $1
+    ORDER BY created_at;
+"#,
+        )
+        .bind(user_id)
+        .fetch_all(self.db.as_ref())
+        .await?;
+
+        Ok(api_keys)
+    }
+
+    pub async fn get_active_key(&self, key_hash: &str) -> Result<ApiKey> {
+        let now = self.clock.now();
+        let api_key: Option<ApiKey> = sqlx::query_as(
+            r#"
+SELECT *
+    FROM api_keys
+    WHERE key_hash = $1 AND (expires_at IS NULL OR expires_at > $2);
+"#,
+        )
+        .bind(key_hash)
+        .bind(now)
+        .fetch_optional(self.db.as_ref())
+        .await?;
+
+        api_key.ok_or_else(|| Error::builder().kind(ErrorKind::NotFound).message("api key not found").build())
+    }
+
+    pub async fn update_last_used(&self, id: &str) -> Result<()> {
+        let now = self.clock.now();
+        sqlx::query(
+            r#"
+UPDATE api_keys
+    SET last_used_at = $2
+    WHERE id = $1;
+"#,
+        )
+        .bind(id)
+        .bind(now)
+        .execute(self.db.as_ref())
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete_key(&self, user_id: &str, id: &str) -> Result<()> {
+        let result = sqlx::query(
+            r#"
+DELETE FROM api_keys
+    WHERE id = $1 AND user_id = $2;
+"#,
+        )
+        .bind(id)
+        .bind(user_id)
+        .execute(self.db.as_ref())
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(Error::builder().kind(ErrorKind::NotFound).message("api key not found").build());
+        }
+
+        Ok(())
+    }
+}
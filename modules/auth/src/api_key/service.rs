@@ -0,0 +1,204 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Duration, Utc};
+use parking_lot::Mutex;
+use ring::digest;
+
+use omnius_core_base::{clock::Clock, random_bytes::RandomBytesProvider, tsid::TsidProvider};
+
+use crate::{
+    model::{ApiKey, ApiKeyScope, IssuedApiKey},
+    prelude::*,
+};
+
+use super::ApiKeyRepo;
+
+pub const API_KEY_PREFIX: &str = "opxs_";
+const API_KEY_DISPLAY_PREFIX_LEN: usize = API_KEY_PREFIX.len() + 8;
+const LAST_USED_UPDATE_INTERVAL: Duration = Duration::minutes(1);
+
+pub struct ApiKeyService {
+    pub clock: Arc<dyn Clock<Utc> + Send + Sync>,
+    pub random_bytes_provider: Arc<Mutex<dyn RandomBytesProvider + Send + Sync>>,
+    pub tsid_provider: Arc<Mutex<dyn TsidProvider + Send + Sync>>,
+    pub api_key_repo: Arc<ApiKeyRepo>,
+}
+
+impl ApiKeyService {
+    pub fn is_api_key(value: &str) -> bool {
+        value.starts_with(API_KEY_PREFIX)
+    }
+
+    pub async fn create(&self, user_id: &str, name: &str, scopes: &[ApiKeyScope], expires_at: Option<&DateTime<Utc>>) -> Result<IssuedApiKey> {
+        if scopes.is_empty() {
+            return Err(Error::builder().kind(ErrorKind::InvalidRequest).message("scopes are empty").build());
+        }
+        if expires_at.is_some_and(|v| *v <= self.clock.now()) {
+            return Err(Error::builder()
+                .kind(ErrorKind::InvalidRequest)
+                .message("expires_at is in the past")
+                .build());
+        }
+
+        let mut scopes = scopes.to_vec();
+        scopes.sort();
+        scopes.dedup();
+
+        let id = self.tsid_provider.lock().create().to_string();
+        let secret = format!("{}{}", API_KEY_PREFIX, hex::encode(self.random_bytes_provider.lock().get_bytes(32)));
+        let prefix = &secret[..API_KEY_DISPLAY_PREFIX_LEN];
+        let key_hash = Self::hash(&secret);
+
+        let api_key = self
+            .api_key_repo
+            .create_key(&id, user_id, name, prefix, &key_hash, &scopes, expires_at)
+            .await?;
+
+        Ok(IssuedApiKey { api_key, secret })
+    }
+
+    pub async fn get_keys(&self, user_id: &str) -> Result<Vec<ApiKey>> {
+        self.api_key_repo.get_keys(user_id).await
+    }
+
+    pub async fn revoke(&self, user_id: &str, id: &str) -> Result<()> {
+        self.api_key_repo.delete_key(user_id, id).await
+    }
+
+    pub async fn authenticate(&self, secret: &str) -> Result<ApiKey> {
+        if !Self::is_api_key(secret) {
+            return Err(Error::builder().kind(ErrorKind::Unauthorized).message("not an api key").build());
+        }
+
+        let api_key = match self.api_key_repo.get_active_key(&Self::hash(secret)).await {
+            Ok(v) => v,
+            Err(e) if *e.kind() == ErrorKind::NotFound => {
+                return Err(Error::builder().kind(ErrorKind::Unauthorized).message("invalid api key").build());
+            }
+            Err(e) => return Err(e),
+        };
+
+        // Avoids a write on every request for keys used in tight loops.
+        let now = self.clock.now().naive_utc();
+        if api_key.last_used_at.is_none_or(|v| v + LAST_USED_UPDATE_INTERVAL <= now) {
+            self.api_key_repo.update_last_used(&api_key.id).await?;
+        }
+
+        Ok(api_key)
+    }
+
+    fn hash(secret: &str) -> String {
+        hex::encode(digest::digest(&digest::SHA256, secret.as_bytes()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::postgres::PgPoolOptions;
+    use testresult::TestResult;
+
+    use omnius_core_base::{clock::ClockUtc, random_bytes::RandomBytesProviderImpl, tsid::TsidProviderImpl};
+    use omnius_core_migration::postgres::PostgresMigrator;
+    use omnius_core_testkit::containers::postgres::PostgresContainer;
+
+    use omnius_opxs_base::shared::POSTGRES_VERSION;
+
+    use crate::model::{UserAuthenticationType, UserRole};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn simple_test() -> TestResult {
+        let container = PostgresContainer::new(POSTGRES_VERSION).await?;
+
+        let db = Arc::new(
+            PgPoolOptions::new()
+                .max_connections(100)
+                .idle_timeout(Some(Duration::minutes(15).to_std()?))
+                .connect(&container.connection_string)
+                .await?,
+        );
+
+        let migrations_path = concat!(env!("CARGO_MANIFEST_DIR"), "/../../conf/migrations");
+        let migrator = PostgresMigrator::new(&container.connection_string, migrations_path, "opxs-api", "").await?;
+        migrator.migrate().await?;
+
+        let clock = Arc::new(ClockUtc {});
+        let api_key_service = ApiKeyService {
+            clock: clock.clone(),
+            random_bytes_provider: Arc::new(Mutex::new(RandomBytesProviderImpl::new())),
+            tsid_provider: Arc::new(Mutex::new(TsidProviderImpl::new(ClockUtc, RandomBytesProviderImpl::new(), 16))),
+            api_key_repo: Arc::new(ApiKeyRepo {
+                db: db.clone(),
+                clock: clock.clone(),
+            }),
+        };
+
+        let now = DateTime::from_timestamp(0, 0).ok_or("invalid timestamp")?;
+        let user_id = "test_user_id";
+
+        // create user
+        sqlx::query(
+            r#"
+INSERT INTO users (id, name, authentication_type, role, created_at, updated_at)
+    VALUES ($1, $2, $3, $4, $5, $6)
+"#,
+        )
+        .bind(user_id)
+        .bind("test_user_name")
+        .bind(UserAuthenticationType::Email)
+        .bind(UserRole::User)
+        .bind(now)
+        .bind(now)
+        .execute(db.as_ref())
+        .await?;
+
+        assert_eq!(
+            *api_key_service.create(user_id, "empty", &[], None).await.unwrap_err().kind(),
+            ErrorKind::InvalidRequest
+        );
+        assert_eq!(
+            *api_key_service
+                .create(user_id, "expired", &[ApiKeyScope::FileConvert], Some(&now))
+                .await
+                .unwrap_err()
+                .kind(),
+            ErrorKind::InvalidRequest
+        );
+
+        let issued = api_key_service.create(user_id, "ci", &[ApiKeyScope::FileConvert], None).await?;
+        assert!(issued.secret.starts_with(&issued.api_key.prefix));
+        assert_ne!(issued.api_key.key_hash, issued.secret);
+
+        let api_key = api_key_service.authenticate(&issued.secret).await?;
+        assert_eq!(api_key.user_id, user_id);
+        assert_eq!(api_key.scopes, vec![ApiKeyScope::FileConvert]);
+
+        // duplicated scopes are stored once
+        let scopes = [ApiKeyScope::Account, ApiKeyScope::FileConvert, ApiKeyScope::Account];
+        let issued_multi = api_key_service.create(user_id, "multi", &scopes, None).await?;
+        assert_eq!(issued_multi.api_key.scopes, vec![ApiKeyScope::FileConvert, ApiKeyScope::Account]);
+        api_key_service.revoke(user_id, &issued_multi.api_key.id).await?;
+
+        let api_keys = api_key_service.get_keys(user_id).await?;
+        assert_eq!(api_keys.len(), 1);
+        assert!(api_keys[0].last_used_at.is_some());
+
+        assert_eq!(
+            *api_key_service.authenticate("opxs_unknown").await.unwrap_err().kind(),
+            ErrorKind::Unauthorized
+        );
+
+        api_key_service.revoke(user_id, &issued.api_key.id).await?;
+        assert_eq!(
+            *api_key_service.authenticate(&issued.secret).await.unwrap_err().kind(),
+            ErrorKind::Unauthorized
+        );
+        assert_eq!(
+            *api_key_service.revoke(user_id, &issued.api_key.id).await.unwrap_err().kind(),
+            ErrorKind::NotFound
+        );
+
+        Ok(())
+    }
+}
@@ -1,5 +1,10 @@
 use std::num::NonZeroU32;
 
+use argon2::{
+    Argon2,
+    password_hash::{PasswordHash, PasswordHasher as _, PasswordVerifier as _, SaltString},
+};
+use base64::{Engine as _, engine::general_purpose::STANDARD_NO_PAD as BASE64};
 use ring::{
     digest, pbkdf2,
     rand::{self, SecureRandom},
@@ -7,62 +12,129 @@ use ring::{
 
 use crate::prelude::*;
 
+const PBKDF2_HMAC_SHA256_IDENT: &str = "pbkdf2-sha256";
+const ARGON2ID_IDENT: &str = "argon2id";
+
+// Hashes are stored as PHC strings (`$<id>$<params>$<salt>$<hash>`), so the algorithm and parameters
+// that produced a hash can always be recovered from the hash itself.
 #[derive(Clone)]
 pub struct Kdf {
     pub algorithm: KdfAlgorithm,
-    pub iterations: u32,
 }
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq, Eq)]
 pub enum KdfAlgorithm {
-    Pbkdf2HmacSha256,
+    Pbkdf2HmacSha256 { iterations: u32 },
+    Argon2id { memory_cost: u32, time_cost: u32, parallelism: u32 },
 }
 
 impl Kdf {
-    pub fn gen_salt(&self) -> Result<Vec<u8>> {
-        let mut salt = vec![0; self.get_credential_len()];
-
-        let rng = rand::SystemRandom::new();
-        rng.fill(&mut salt)?;
+    pub fn hash(&self, secret: &str) -> Result<String> {
+        let salt = Self::gen_salt()?;
 
-        Ok(salt)
-    }
-
-    pub fn derive(&self, secret: &str, salt: &[u8]) -> Result<Vec<u8>> {
         match self.algorithm {
-            KdfAlgorithm::Pbkdf2HmacSha256 => {
-                let mut hash = vec![0; self.get_credential_len()];
+            KdfAlgorithm::Pbkdf2HmacSha256 { iterations } => {
+                let mut hash = vec![0; digest::SHA256_OUTPUT_LEN];
                 pbkdf2::derive(
                     pbkdf2::PBKDF2_HMAC_SHA256,
-                    NonZeroU32::new(self.iterations).ok_or_else(|| Error::builder().kind(ErrorKind::UnexpectedError).build())?,
-                    salt,
+                    Self::non_zero(iterations)?,
+                    &salt,
                     secret.as_bytes(),
                     &mut hash,
                 );
-                Ok(hash)
+                Ok(format!(
+                    "${}$i={}${}${}",
+                    PBKDF2_HMAC_SHA256_IDENT,
+                    iterations,
+                    BASE64.encode(salt),
+                    BASE64.encode(hash)
+                ))
+            }
+            KdfAlgorithm::Argon2id {
+                memory_cost,
+                time_cost,
+                parallelism,
+            } => {
+                let params = argon2::Params::new(memory_cost, time_cost, parallelism, None)?;
+                let argon2 = Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params);
+                let salt = SaltString::encode_b64(&salt)?;
+                let hash = argon2.hash_password(secret.as_bytes(), &salt)?;
+                Ok(hash.to_string())
             }
         }
     }
 
-    pub fn verify(&self, secret: &str, salt: &[u8], derived_key: &[u8]) -> Result<bool> {
-        match self.algorithm {
-            KdfAlgorithm::Pbkdf2HmacSha256 => {
+    // Verification follows the algorithm recorded in the hash, not the configured one.
+    pub fn verify(&self, secret: &str, password_hash: &str) -> Result<bool> {
+        let password_hash = PasswordHash::new(password_hash)?;
+
+        match password_hash.algorithm.as_str() {
+            PBKDF2_HMAC_SHA256_IDENT => {
+                let iterations = password_hash.params.get_decimal("i").ok_or_else(|| {
+                    Error::builder()
+                        .kind(ErrorKind::InvalidFormat)
+                        .message("pbkdf2 iterations not found")
+                        .build()
+                })?;
+                let salt = password_hash
+                    .salt
+                    .ok_or_else(|| Error::builder().kind(ErrorKind::InvalidFormat).message("salt not found").build())?;
+                let salt = BASE64.decode(salt.as_str())?;
+                let derived_key = password_hash
+                    .hash
+                    .ok_or_else(|| Error::builder().kind(ErrorKind::InvalidFormat).message("hash not found").build())?;
+
                 let result = pbkdf2::verify(
                     pbkdf2::PBKDF2_HMAC_SHA256,
-                    NonZeroU32::new(self.iterations).ok_or_else(|| Error::builder().kind(ErrorKind::UnexpectedError).build())?,
-                    salt,
+                    Self::non_zero(iterations)?,
+                    &salt,
                     secret.as_bytes(),
-                    derived_key,
+                    derived_key.as_bytes(),
                 );
                 Ok(result.is_ok())
             }
+            ARGON2ID_IDENT => match Argon2::default().verify_password(secret.as_bytes(), &password_hash) {
+                Ok(_) => Ok(true),
+                Err(argon2::password_hash::Error::Password) => Ok(false),
+                Err(e) => Err(e.into()),
+            },
+            v => Err(Error::builder()
+                .kind(ErrorKind::InvalidFormat)
+                .message(format!("unsupported kdf algorithm: {v}"))
+                .build()),
         }
     }
 
-    fn get_credential_len(&self) -> usize {
-        match self.algorithm {
-            KdfAlgorithm::Pbkdf2HmacSha256 => digest::SHA256_OUTPUT_LEN,
-        }
+    pub fn needs_rehash(&self, password_hash: &str) -> Result<bool> {
+        let password_hash = PasswordHash::new(password_hash)?;
+
+        let current = match password_hash.algorithm.as_str() {
+            PBKDF2_HMAC_SHA256_IDENT => password_hash
+                .params
+                .get_decimal("i")
+                .map(|iterations| KdfAlgorithm::Pbkdf2HmacSha256 { iterations }),
+            ARGON2ID_IDENT => argon2::Params::try_from(&password_hash).ok().map(|v| KdfAlgorithm::Argon2id {
+                memory_cost: v.m_cost(),
+                time_cost: v.t_cost(),
+                parallelism: v.p_cost(),
+            }),
+            _ => None,
+        };
+
+        Ok(current.as_ref() != Some(&self.algorithm))
+    }
+
+    fn gen_salt() -> Result<Vec<u8>> {
+        let mut salt = vec![0; 16];
+
+        let rng = rand::SystemRandom::new();
+        rng.fill(&mut salt)?;
+
+        Ok(salt)
+    }
+
+    fn non_zero(iterations: u32) -> Result<NonZeroU32> {
+        NonZeroU32::new(iterations).ok_or_else(|| Error::builder().kind(ErrorKind::UnexpectedError).build())
     }
 }
 
@@ -75,19 +147,37 @@ mod tests {
 
     #[test]
     fn simple_test() -> TestResult {
-        let kdf = Kdf {
-            algorithm: KdfAlgorithm::Pbkdf2HmacSha256,
-            iterations: 100,
+        let pbkdf2 = Kdf {
+            algorithm: KdfAlgorithm::Pbkdf2HmacSha256 { iterations: 100 },
+        };
+        let argon2id = Kdf {
+            algorithm: KdfAlgorithm::Argon2id {
+                memory_cost: 1024,
+                time_cost: 1,
+                parallelism: 1,
+            },
         };
 
-        let salt = kdf.gen_salt()?;
-        let hash = kdf.derive("test", &salt)?;
-
-        let result_ok = kdf.verify("test", &salt, &hash)?;
-        assert!(result_ok);
+        for kdf in [&pbkdf2, &argon2id] {
+            let hash = kdf.hash("test")?;
+            assert!(kdf.verify("test", &hash)?);
+            assert!(!kdf.verify("test_error", &hash)?);
+            assert!(!kdf.needs_rehash(&hash)?);
+        }
 
-        let result_failed = kdf.verify("test_error", &salt, &hash)?;
-        assert!(!result_failed);
+        let old_hash = pbkdf2.hash("test")?;
+        assert!(old_hash.starts_with("$pbkdf2-sha256$i=100$"));
+        assert!(argon2id.verify("test", &old_hash)?);
+        assert!(argon2id.needs_rehash(&old_hash)?);
+
+        let stronger = Kdf {
+            algorithm: KdfAlgorithm::Argon2id {
+                memory_cost: 2048,
+                time_cost: 1,
+                parallelism: 1,
+            },
+        };
+        assert!(stronger.needs_rehash(&argon2id.hash("test")?)?);
 
         Ok(())
     }
@@ -1,13 +1,13 @@
 use std::sync::Arc;
 
-use chrono::Utc;
+use chrono::{DateTime, Duration, Utc};
 use parking_lot::Mutex;
 use sqlx::PgPool;
 
 use omnius_core_base::{clock::Clock, tsid::TsidProvider};
 
 use crate::{
-    model::{EmailUser, UserAuthenticationType, UserRole},
+    model::{EmailChange, EmailChangeRequest, EmailUser, UserAuthenticationType, UserLocale, UserRole},
     prelude::*,
 };
 
@@ -18,7 +18,7 @@ pub struct EmailAuthRepo {
 }
 
 impl EmailAuthRepo {
-    pub async fn create_user(&self, name: &str, email: &str, password_hash: &str, salt: &str) -> Result<String> {
+    pub async fn create_user(&self, name: &str, email: &str, password_hash: &str, locale: &UserLocale) -> Result<String> {
         let user_id = self.tsid_provider.lock().create().to_string();
         let now = self.clock.now();
 
@@ -26,14 +26,15 @@ impl EmailAuthRepo {
 
         sqlx::query(
             r#"
-INSERT INTO users (id, name, authentication_type, role, created_at, updated_at)
-    VALUES ($1, $2, $3, $4, $5, $6)
+INSERT INTO users (id, name, authentication_type, role, locale, created_at, updated_at)
+    VALUES ($1, $2, $3, $4, $5, $6, $7)
 "#,
         )
         .bind(&user_id)
         .bind(name)
         .bind(UserAuthenticationType::Email)
         .bind(UserRole::User)
+        .bind(locale)
         .bind(now)
         .bind(now)
         .execute(&mut *tx)
@@ -41,21 +42,19 @@ INSERT INTO users (id, name, authentication_type, role, created_at, updated_at)
 
         sqlx::query(
             r#"
-INSERT INTO user_auth_emails (user_id, email, password_hash, salt, created_at, updated_at)
-    VALUES ($1, $2, $3, $4, $5, $6)
+INSERT INTO user_auth_emails (user_id, email, password_hash, confirmation_sent_at, created_at, updated_at)
+    VALUES ($1, $2, $3, $4, $4, $4)
     ON CONFLICT (email)
     DO UPDATE SET
         user_id = $1,
         password_hash = $3,
-        salt = $4,
-        updated_at = $6;
+        confirmation_sent_at = $4,
+        updated_at = $4;
 "#,
         )
         .bind(&user_id)
         .bind(email)
         .bind(password_hash)
-        .bind(salt)
-        .bind(now)
         .bind(now)
         .execute(&mut *tx)
         .await?;
@@ -65,20 +64,29 @@ INSERT INTO user_auth_emails (user_id, email, password_hash, salt, created_at, u
         Ok(user_id)
     }
 
-    pub async fn delete_user(&self, id: &str) -> Result<()> {
-        let mut tx = self.db.begin().await?;
-
-        let queries = vec![
-            sqlx::query("DELETE FROM users WHERE id = $1").bind(id),
-            sqlx::query("DELETE FROM user_auth_emails WHERE user_id = $1").bind(id),
-            sqlx::query("DELETE FROM refresh_tokens WHERE user_id = $1").bind(id),
-        ];
-
-        for query in queries {
-            query.execute(&mut *tx).await?;
-        }
+    // Unverified rows of the same email are taken over, as in `create_user`.
+    pub async fn add_email(&self, user_id: &str, email: &str, password_hash: &str) -> Result<()> {
+        let now = self.clock.now();
 
-        tx.commit().await?;
+        sqlx::query(
+            r#"
+INSERT INTO user_auth_emails (user_id, email, password_hash, confirmation_sent_at, created_at, updated_at)
+    VALUES ($1, $2, $3, $4, $4, $4)
+    ON CONFLICT (email)
+    DO UPDATE SET
+        user_id = $1,
+        password_hash = $3,
+        confirmation_sent_at = $4,
+        updated_at = $4
+    WHERE user_auth_emails.email_verified = false;
+"#,
+        )
+        .bind(user_id)
+        .bind(email)
+        .bind(password_hash)
+        .bind(now)
+        .execute(self.db.as_ref())
+        .await?;
 
         Ok(())
     }
@@ -105,7 +113,7 @@ SELECT EXISTS (
     pub async fn get_user(&self, email: &str) -> Result<EmailUser> {
         let user: Option<EmailUser> = sqlx::query_as(
             r#"
-SELECT u.id, u.name, u.role, e.email, e.password_hash, e.salt, u.created_at, u.updated_at
+SELECT u.id, u.name, u.role, u.locale, e.email, e.password_hash, u.created_at, u.updated_at
     FROM users u
     JOIN user_auth_emails e on u.id = e.user_id
     WHERE e.email = $1 AND e.email_verified = true
@@ -120,6 +128,80 @@ SELECT u.id, u.name, u.role, e.email, e.password_hash, e.salt, u.created_at, u.u
         Ok(user)
     }
 
+    pub async fn get_unverified_user(&self, email: &str) -> Result<Option<EmailUser>> {
+        let user: Option<EmailUser> = sqlx::query_as(
+            r#"
+SELECT u.id, u.name, u.role, u.locale, e.email, e.password_hash, u.created_at, u.updated_at
+    FROM users u
+    JOIN user_auth_emails e on u.id = e.user_id
+    WHERE e.email = $1 AND e.email_verified = false
+    LIMIT 1;
+"#,
+        )
+        .bind(email)
+        .fetch_optional(self.db.as_ref())
+        .await?;
+
+        Ok(user)
+    }
+
+    // Claims the right to send another confirmation email; false while the previous one is still within `interval`.
+    pub async fn try_mark_confirmation_sent(&self, email: &str, interval: Duration) -> Result<bool> {
+        let now = self.clock.now();
+
+        let result = sqlx::query(
+            r#"
+UPDATE user_auth_emails
+    SET confirmation_sent_at = $2, updated_at = $2
+    WHERE email = $1 AND email_verified = false AND (confirmation_sent_at IS NULL OR confirmation_sent_at <= $3);
+"#,
+        )
+        .bind(email)
+        .bind(now)
+        .bind(now - interval)
+        .execute(self.db.as_ref())
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn get_user_by_id(&self, user_id: &str) -> Result<EmailUser> {
+        let user: Option<EmailUser> = sqlx::query_as(
+            r#"
+SELECT u.id, u.name, u.role, u.locale, e.email, e.password_hash, u.created_at, u.updated_at
+    FROM users u
+    JOIN user_auth_emails e on u.id = e.user_id
+    WHERE u.id = $1 AND e.email_verified = true
+    LIMIT 1;
+"#,
+        )
+        .bind(user_id)
+        .fetch_optional(self.db.as_ref())
+        .await?;
+
+        let user = user.ok_or_else(|| Error::builder().kind(ErrorKind::NotFound).message("user not found").build())?;
+        Ok(user)
+    }
+
+    pub async fn update_password_hash(&self, user_id: &str, password_hash: &str) -> Result<()> {
+        let now = self.clock.now();
+
+        sqlx::query(
+            r#"
+UPDATE user_auth_emails
+    SET password_hash = $2, updated_at = $3
+    WHERE user_id = $1;
+"#,
+        )
+        .bind(user_id)
+        .bind(password_hash)
+        .bind(now)
+        .execute(self.db.as_ref())
+        .await?;
+
+        Ok(())
+    }
+
     pub async fn update_email_verified(&self, email: &str, email_verified: bool) -> Result<()> {
         let now = self.clock.now();
 
@@ -138,4 +220,114 @@ UPDATE user_auth_emails
 
         Ok(())
     }
+
+    // A user has at most one pending request; a new request replaces the previous token.
+    pub async fn create_email_change_request(&self, user_id: &str, new_email: &str, token: &str, expires_at: &DateTime<Utc>) -> Result<()> {
+        let now = self.clock.now();
+
+        sqlx::query(
+            r#"
+INSERT INTO email_change_requests (user_id, new_email, token, expires_at, created_at)
+    VALUES ($1, $2, $3, $4, $5)
+    ON CONFLICT (user_id)
+    DO UPDATE SET
+        new_email = $2,
+        token = $3,
+        expires_at = $4,
+        created_at = $5;
+"#,
+        )
+        .bind(user_id)
+        .bind(new_email)
+        .bind(token)
+        .bind(expires_at)
+        .bind(now)
+        .execute(self.db.as_ref())
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn apply_email_change(&self, token: &str) -> Result<EmailChange> {
+        let now = self.clock.now();
+
+        let mut tx = self.db.begin().await?;
+
+        let request: Option<EmailChangeRequest> = sqlx::query_as(
+            r#"
+DELETE FROM email_change_requests
+    WHERE token = $1 AND expires_at > $2
+    RETURNING *;
+"#,
+        )
+        .bind(token)
+        .bind(now)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let request = request.ok_or_else(|| {
+            Error::builder()
+                .kind(ErrorKind::NotFound)
+                .message("email change request not found")
+                .build()
+        })?;
+
+        let current: Option<(String, UserLocale, String)> = sqlx::query_as(
+            r#"
+SELECT u.name, u.locale, e.email
+    FROM users u
+    JOIN user_auth_emails e on u.id = e.user_id
+    WHERE u.id = $1 AND e.email_verified = true
+    FOR UPDATE;
+"#,
+        )
+        .bind(&request.user_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let (user_name, locale, old_email) = current.ok_or_else(|| Error::builder().kind(ErrorKind::NotFound).message("user not found").build())?;
+
+        let (taken,): (bool,) = sqlx::query_as(
+            r#"
+SELECT EXISTS (
+    SELECT email
+        FROM user_auth_emails
+        WHERE email = $1 AND email_verified = true
+);
+"#,
+        )
+        .bind(&request.new_email)
+        .fetch_one(&mut *tx)
+        .await?;
+        if taken {
+            return Err(Error::builder().kind(ErrorKind::Duplicated).message("duplicated email").build());
+        }
+
+        // An unverified registration of the new address would collide with the primary key.
+        sqlx::query("DELETE FROM user_auth_emails WHERE email = $1 AND email_verified = false")
+            .bind(&request.new_email)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query(
+            r#"
+UPDATE user_auth_emails
+    SET email = $2, updated_at = $3
+    WHERE user_id = $1;
+"#,
+        )
+        .bind(&request.user_id)
+        .bind(&request.new_email)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(EmailChange {
+            user_id: request.user_id,
+            user_name,
+            locale,
+            old_email,
+            new_email: request.new_email,
+        })
+    }
 }
@@ -5,69 +5,187 @@ use parking_lot::Mutex;
 
 use omnius_core_base::{clock::Clock, random_bytes::RandomBytesProvider};
 
-use omnius_opxs_base::JwtConfig;
+use omnius_opxs_base::{JwtConfig, UnregisterConfig};
 
 use crate::{
     crypto::{jwt, kdf::Kdf},
+    mailer::AuthMailer,
+    model::{EmailUser, UserLocale},
+    password::PasswordPolicy,
     prelude::*,
+    throttle::LoginThrottleService,
+    user::UserRepo,
 };
 
 use super::EmailAuthRepo;
 
+const EMAIL_CONFIRM_EXPIRES_IN: Duration = Duration::minutes(30);
+const EMAIL_CONFIRM_RESEND_INTERVAL: Duration = Duration::minutes(2);
+const EMAIL_CHANGE_EXPIRES_IN: Duration = Duration::minutes(30);
+
 #[derive(Clone)]
 pub struct EmailAuthService {
     pub auth_repo: Arc<EmailAuthRepo>,
+    pub user_repo: Arc<UserRepo>,
     pub clock: Arc<dyn Clock<Utc> + Send + Sync>,
     pub random_bytes_provider: Arc<Mutex<dyn RandomBytesProvider + Send + Sync>>,
     pub jwt_conf: JwtConfig,
+    pub unregister_conf: UnregisterConfig,
     pub kdf: Kdf,
+    pub password_policy: Arc<PasswordPolicy>,
+    pub login_throttle: Arc<LoginThrottleService>,
+    pub mailer: Arc<dyn AuthMailer + Send + Sync>,
 }
 
 impl EmailAuthService {
-    pub async fn register(&self, name: &str, email: &str, password: &str) -> Result<String> {
+    pub async fn register(&self, name: &str, email: &str, password: &str, locale: &UserLocale) -> Result<String> {
         if self.auth_repo.exist_user(email).await? {
             return Err(Error::builder().kind(ErrorKind::Duplicated).message("duplicated email").build());
         }
 
-        let salt = self.kdf.gen_salt()?;
-        let password_hash = self.kdf.derive(password, &salt)?;
+        self.password_policy.validate(password, email, name).await?;
 
-        self.auth_repo
-            .create_user(name, email, &hex::encode(password_hash), &hex::encode(salt))
-            .await?;
+        let password_hash = self.kdf.hash(password)?;
+        self.auth_repo.create_user(name, email, &password_hash, locale).await?;
 
-        let now = self.clock.now();
-        let sub = email.to_string();
-        let exp = now + Duration::minutes(30);
-        let token = jwt::sign(&self.jwt_conf.secret.current, &sub, exp, now)?;
+        self.gen_confirm_token(email)
+    }
+
+    pub async fn attach(&self, user_id: &str, name: &str, email: &str, password: &str) -> Result<String> {
+        if self.auth_repo.exist_user(email).await? {
+            return Err(Error::builder().kind(ErrorKind::Duplicated).message("duplicated email").build());
+        }
+
+        self.password_policy.validate(password, email, name).await?;
 
-        Ok(token)
+        let password_hash = self.kdf.hash(password)?;
+        self.auth_repo.add_email(user_id, email, &password_hash).await?;
+
+        self.gen_confirm_token(email)
     }
 
     pub async fn unregister(&self, id: &str) -> Result<()> {
-        self.auth_repo.delete_user(id).await?;
-        Ok(())
+        let scheduled_at = self.clock.now() + Duration::days(self.unregister_conf.grace_period_days);
+        self.user_repo.schedule_deletion(id, &scheduled_at).await
     }
 
-    pub async fn login(&self, email: &str, password: &str) -> Result<String> {
+    pub async fn login(&self, email: &str, password: &str, ip_address: &str) -> Result<String> {
+        self.login_throttle.check(email, ip_address).await?;
+
         if !self.auth_repo.exist_user(email).await? {
+            // Only reveal the pending verification to someone who knows the password.
+            if let Some(user) = self.auth_repo.get_unverified_user(email).await?
+                && self.kdf.verify(password, &user.password_hash)?
+            {
+                return Err(Error::builder().kind(ErrorKind::EmailNotVerified).message("email not verified").build());
+            }
+
+            self.login_throttle.record_failure(email, ip_address).await?;
             return Err(Error::builder().kind(ErrorKind::NotFound).message("user not found").build());
         }
 
         let user = self.auth_repo.get_user(email).await?;
-        let salt = hex::decode(user.salt)?;
-        let password_hash = hex::decode(user.password_hash)?;
 
-        if !self.kdf.verify(password, &salt, &password_hash)? {
+        if !self.kdf.verify(password, &user.password_hash)? {
+            if let Some(unlock_token) = self.login_throttle.record_failure(email, ip_address).await? {
+                self.mailer.send_account_unlock(&user.name, email, &unlock_token, &user.locale).await?;
+            }
             return Err(Error::builder().kind(ErrorKind::Unauthorized).message("invalid password").build());
         }
 
+        self.login_throttle.reset(email).await?;
+
+        // Logging in during the grace period restores an unregistered account.
+        self.user_repo.cancel_deletion(&user.id).await?;
+
+        // The plaintext is only available here, so this is the one place an outdated hash can be upgraded.
+        if self.kdf.needs_rehash(&user.password_hash)? {
+            let password_hash = self.kdf.hash(password)?;
+            if let Err(e) = self.auth_repo.update_password_hash(&user.id, &password_hash).await {
+                warn!(error = ?e, user_id = user.id, "failed to rehash password");
+            }
+        }
+
         Ok(user.id)
     }
 
+    pub async fn change_password(&self, user_id: &str, current_password: &str, new_password: &str) -> Result<()> {
+        let user = self.auth_repo.get_user_by_id(user_id).await?;
+
+        if !self.kdf.verify(current_password, &user.password_hash)? {
+            return Err(Error::builder().kind(ErrorKind::Unauthorized).message("invalid password").build());
+        }
+
+        self.password_policy.validate(new_password, &user.email, &user.name).await?;
+
+        let password_hash = self.kdf.hash(new_password)?;
+        self.auth_repo.update_password_hash(user_id, &password_hash).await
+    }
+
+    pub async fn request_email_change(&self, user_id: &str, password: &str, new_email: &str) -> Result<()> {
+        let user = self.auth_repo.get_user_by_id(user_id).await?;
+
+        if !self.kdf.verify(password, &user.password_hash)? {
+            return Err(Error::builder().kind(ErrorKind::Unauthorized).message("invalid password").build());
+        }
+
+        if user.email == new_email || self.auth_repo.exist_user(new_email).await? {
+            return Err(Error::builder().kind(ErrorKind::Duplicated).message("duplicated email").build());
+        }
+
+        let token = hex::encode(self.random_bytes_provider.lock().get_bytes(32));
+        let expires_at = self.clock.now() + EMAIL_CHANGE_EXPIRES_IN;
+        self.auth_repo
+            .create_email_change_request(user_id, new_email, &token, &expires_at)
+            .await?;
+
+        self.mailer.send_email_change_confirm(&user.name, new_email, &token, &user.locale).await
+    }
+
+    pub async fn confirm_email_change(&self, token: &str) -> Result<String> {
+        let change = self.auth_repo.apply_email_change(token).await?;
+
+        // The change is already committed, so failing the request here would only make a retry fail on the used token.
+        if let Err(e) = self
+            .mailer
+            .send_email_changed_notice(&change.user_name, &change.old_email, &change.new_email, &change.locale)
+            .await
+        {
+            warn!(error = ?e, user_id = change.user_id, "failed to send email changed notice");
+        }
+
+        Ok(change.user_id)
+    }
+
+    pub async fn unlock(&self, unlock_token: &str) -> Result<()> {
+        self.login_throttle.unlock(unlock_token).await
+    }
+
+    /// Returns the pending user and a new confirm token, or `None` when there is no pending registration for `email`
+    /// or a confirmation was sent to it recently. Both cases look the same to the caller so that pending addresses are not revealed.
+    pub async fn resend_confirm(&self, email: &str) -> Result<Option<(EmailUser, String)>> {
+        let Some(user) = self.auth_repo.get_unverified_user(email).await? else {
+            return Ok(None);
+        };
+
+        if !self.auth_repo.try_mark_confirmation_sent(email, EMAIL_CONFIRM_RESEND_INTERVAL).await? {
+            debug!(user_id = user.id, "confirmation email was sent recently");
+            return Ok(None);
+        }
+
+        let token = self.gen_confirm_token(email)?;
+        Ok(Some((user, token)))
+    }
+
     pub async fn confirm(&self, token: &str) -> Result<String> {
         let now = self.clock.now();
-        let claims = jwt::verify(&self.jwt_conf.secret.current, token, now)?;
+        let claims = match jwt::verify(&self.jwt_conf.secret.current, token, now) {
+            Ok(v) => v,
+            Err(e) if *e.kind() == ErrorKind::InvalidFormat => {
+                return Err(Error::builder().kind(ErrorKind::InvalidRequest).message("invalid confirm token").source(e).build());
+            }
+            Err(e) => return Err(e),
+        };
 
         let email = claims.sub;
         self.auth_repo.update_email_verified(&email, true).await?;
@@ -76,11 +194,18 @@ impl EmailAuthService {
 
         Ok(user.id)
     }
+
+    fn gen_confirm_token(&self, email: &str) -> Result<String> {
+        let now = self.clock.now();
+        let sub = email.to_string();
+        let exp = now + EMAIL_CONFIRM_EXPIRES_IN;
+        jwt::sign(&self.jwt_conf.secret.current, &sub, exp, now)
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use chrono::Duration;
+    use chrono::{Duration, NaiveDateTime};
     use parking_lot::Mutex;
     use sqlx::postgres::PgPoolOptions;
     use testresult::TestResult;
@@ -91,7 +216,11 @@ mod tests {
 
     use omnius_opxs_base::{JwtSecretConfig, shared::POSTGRES_VERSION};
 
-    use crate::crypto::kdf::KdfAlgorithm;
+    use crate::{
+        crypto::kdf::KdfAlgorithm,
+        mailer::{AuthMailerMock, SendEmailChangedNoticeInput},
+        throttle::{LoginThrottleRepo, LoginThrottleService},
+    };
 
     use super::*;
 
@@ -113,14 +242,15 @@ mod tests {
 
         let user_name = "user_name";
         let user_email = "user_email";
-        let password = "password";
+        let password = "vT9#qLm2!xRw8zPk";
         let invalid_password = "invalid_password";
+        let ip_address = "192.0.2.1";
 
         let clock = Arc::new(ClockUtc {});
         let random_bytes_provider = Arc::new(Mutex::new(RandomBytesProviderImpl::new()));
         let tsid_provider = Arc::new(Mutex::new(TsidProviderImpl::new(ClockUtc, RandomBytesProviderImpl::new(), 16)));
         let auth_repo = Arc::new(EmailAuthRepo {
-            db,
+            db: db.clone(),
             clock: clock.clone(),
             tsid_provider,
         });
@@ -131,40 +261,139 @@ mod tests {
             },
         };
         let kdf = Kdf {
-            algorithm: KdfAlgorithm::Pbkdf2HmacSha256,
-            iterations: 10,
+            algorithm: KdfAlgorithm::Argon2id {
+                memory_cost: 1024,
+                time_cost: 1,
+                parallelism: 1,
+            },
         };
 
+        let user_repo = Arc::new(UserRepo {
+            db: db.clone(),
+            clock: clock.clone(),
+        });
+        let login_throttle = Arc::new(LoginThrottleService {
+            clock: clock.clone(),
+            random_bytes_provider: random_bytes_provider.clone(),
+            throttle_repo: Arc::new(LoginThrottleRepo { db, clock: clock.clone() }),
+        });
+        let mailer = Arc::new(AuthMailerMock::new());
+
         let auth_service = EmailAuthService {
             auth_repo: auth_repo.clone(),
+            user_repo,
             clock,
             random_bytes_provider,
             jwt_conf,
+            unregister_conf: UnregisterConfig { grace_period_days: 30 },
             kdf,
+            password_policy: Arc::new(PasswordPolicy {
+                min_length: 10,
+                min_score: 3,
+                breached_list: None,
+            }),
+            login_throttle,
+            mailer: mailer.clone(),
         };
 
         // register
-        let token = auth_service.register(user_name, user_email, password).await?;
-        assert_eq!(*auth_service.login(user_email, password).await.unwrap_err().kind(), ErrorKind::NotFound);
+        assert_eq!(
+            *auth_service
+                .register(user_name, user_email, "password", &UserLocale::En)
+                .await
+                .unwrap_err()
+                .kind(),
+            ErrorKind::WeakPassword
+        );
+        let token = auth_service.register(user_name, user_email, password, &UserLocale::En).await?;
+        assert_eq!(
+            *auth_service.login(user_email, password, ip_address).await.unwrap_err().kind(),
+            ErrorKind::EmailNotVerified
+        );
+        assert_eq!(
+            *auth_service.login(user_email, invalid_password, ip_address).await.unwrap_err().kind(),
+            ErrorKind::NotFound
+        );
+        // a recent send looks the same as an unknown address
+        assert!(auth_service.resend_confirm(user_email).await?.is_none());
+        assert!(auth_service.resend_confirm("unknown_email").await?.is_none());
+        assert_eq!(*auth_service.confirm("invalid_token").await.unwrap_err().kind(), ErrorKind::InvalidRequest);
         auth_service.confirm(&token).await?;
+        assert!(auth_service.resend_confirm(user_email).await?.is_none());
 
         assert_eq!(
-            *auth_service.login(user_email, invalid_password).await.unwrap_err().kind(),
+            *auth_service.login(user_email, invalid_password, ip_address).await.unwrap_err().kind(),
             ErrorKind::Unauthorized
         );
+        assert!(mailer.send_account_unlock_inputs.lock().is_empty());
 
         // login
-        assert!(auth_service.login(user_email, password).await.is_ok());
+        assert!(auth_service.login(user_email, password, ip_address).await.is_ok());
 
         // get user
         let user = auth_repo.get_user(user_email).await?;
         assert_eq!(user.name, user_name.to_string());
+        assert_eq!(user.locale, UserLocale::En);
+        assert!(user.password_hash.starts_with("$argon2id$"));
+
+        // rehash a legacy hash on login
+        let legacy_kdf = Kdf {
+            algorithm: KdfAlgorithm::Pbkdf2HmacSha256 { iterations: 10 },
+        };
+        auth_repo.update_password_hash(&user.id, &legacy_kdf.hash(password)?).await?;
+        assert!(auth_service.login(user_email, password, ip_address).await.is_ok());
+        let user = auth_repo.get_user(user_email).await?;
+        assert!(user.password_hash.starts_with("$argon2id$"));
+
+        // change password
+        let new_password = "Hq4$wZr7!pLm2kYx";
+        assert_eq!(
+            *auth_service
+                .change_password(&user.id, invalid_password, new_password)
+                .await
+                .unwrap_err()
+                .kind(),
+            ErrorKind::Unauthorized
+        );
+        assert_eq!(
+            *auth_service.change_password(&user.id, password, "password").await.unwrap_err().kind(),
+            ErrorKind::WeakPassword
+        );
+        auth_service.change_password(&user.id, password, new_password).await?;
+        assert!(auth_service.login(user_email, new_password, ip_address).await.is_ok());
+
+        // change email
+        let new_email = "new_user_email";
+        auth_service.request_email_change(&user.id, new_password, new_email).await?;
+        let token = mailer.send_email_change_confirm_inputs.lock().last().unwrap().token.clone();
+        assert_eq!(auth_service.confirm_email_change(&token).await?, user.id);
+        assert_eq!(
+            *mailer.send_email_changed_notice_inputs.lock().last().unwrap(),
+            SendEmailChangedNoticeInput {
+                user_name: user_name.to_string(),
+                old_email: user_email.to_string(),
+                new_email: new_email.to_string(),
+                locale: UserLocale::En,
+            }
+        );
+        assert_eq!(*auth_service.confirm_email_change(&token).await.unwrap_err().kind(), ErrorKind::NotFound);
+        assert!(auth_service.login(new_email, new_password, ip_address).await.is_ok());
 
         // unregister
         assert!(auth_service.unregister(user.id.as_str()).await.is_ok());
+        let (deletion_scheduled_at,): (Option<NaiveDateTime>,) = sqlx::query_as("SELECT deletion_scheduled_at FROM users WHERE id = $1")
+            .bind(&user.id)
+            .fetch_one(auth_repo.db.as_ref())
+            .await?;
+        assert!(deletion_scheduled_at.is_some());
 
-        // login
-        assert_eq!(*auth_service.login(user_email, password).await.unwrap_err().kind(), ErrorKind::NotFound);
+        // login restores the account
+        assert!(auth_service.login(new_email, new_password, ip_address).await.is_ok());
+        let (deletion_scheduled_at,): (Option<NaiveDateTime>,) = sqlx::query_as("SELECT deletion_scheduled_at FROM users WHERE id = $1")
+            .bind(&user.id)
+            .fetch_one(auth_repo.db.as_ref())
+            .await?;
+        assert!(deletion_scheduled_at.is_none());
 
         Ok(())
     }
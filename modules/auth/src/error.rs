@@ -103,10 +103,20 @@ pub enum ErrorKind {
     GcpError,
 
     InvalidFormat,
+    InvalidRequest,
     NotFound,
     TokenExpired,
     Unauthorized,
+    Forbidden,
     Duplicated,
+    LoginThrottled,
+    AccountLocked,
+    AccountDisabled,
+    LinkRequired,
+    LastCredential,
+    WeakPassword,
+    EmailNotVerified,
+    RateLimited,
 }
 
 impl std::fmt::Display for ErrorKind {
@@ -125,10 +135,20 @@ impl std::fmt::Display for ErrorKind {
             ErrorKind::GcpError => write!(fmt, "gcp error"),
 
             ErrorKind::InvalidFormat => write!(fmt, "invalid format"),
+            ErrorKind::InvalidRequest => write!(fmt, "invalid request"),
             ErrorKind::NotFound => write!(fmt, "not found"),
             ErrorKind::TokenExpired => write!(fmt, "token expired"),
             ErrorKind::Unauthorized => write!(fmt, "unauthorized"),
+            ErrorKind::Forbidden => write!(fmt, "forbidden"),
             ErrorKind::Duplicated => write!(fmt, "duplicated"),
+            ErrorKind::LoginThrottled => write!(fmt, "login throttled"),
+            ErrorKind::AccountLocked => write!(fmt, "account locked"),
+            ErrorKind::AccountDisabled => write!(fmt, "account disabled"),
+            ErrorKind::LinkRequired => write!(fmt, "link required"),
+            ErrorKind::WeakPassword => write!(fmt, "weak password"),
+            ErrorKind::EmailNotVerified => write!(fmt, "email not verified"),
+            ErrorKind::RateLimited => write!(fmt, "rate limited"),
+            ErrorKind::LastCredential => write!(fmt, "last credential"),
         }
     }
 }
@@ -158,7 +178,7 @@ impl From<serde_json::Error> for Error {
 impl From<std::env::VarError> for Error {
     fn from(e: std::env::VarError) -> Self {
         match e {
-            std::env::VarError::NotPresent => Error::builder().kind(ErrorKind::NotFound).message("not found env var").build(),
+            std::env::VarError::NotPresent => Error::builder().kind(ErrorKind::UnexpectedError).message("not found env var").build(),
             std::env::VarError::NotUnicode(_) => Error::builder().kind(ErrorKind::InvalidFormat).message("invalid utf-8").build(),
         }
     }
@@ -213,6 +233,22 @@ impl From<ring::error::Unspecified> for Error {
     }
 }
 
+impl From<argon2::Error> for Error {
+    fn from(e: argon2::Error) -> Self {
+        Error::builder().kind(ErrorKind::CryptoError).message("argon2 error").source(e).build()
+    }
+}
+
+impl From<argon2::password_hash::Error> for Error {
+    fn from(e: argon2::password_hash::Error) -> Self {
+        Error::builder()
+            .kind(ErrorKind::InvalidFormat)
+            .message("password hash error")
+            .source(e)
+            .build()
+    }
+}
+
 impl From<hex::FromHexError> for Error {
     fn from(e: hex::FromHexError) -> Self {
         Error::builder()
@@ -252,7 +288,55 @@ impl From<omnius_core_cloud::Error> for Error {
             omnius_core_cloud::ErrorKind::AwsError => Error::builder().kind(ErrorKind::AwsError).source(e).build(),
             omnius_core_cloud::ErrorKind::GcpError => Error::builder().kind(ErrorKind::GcpError).source(e).build(),
             omnius_core_cloud::ErrorKind::InvalidFormat => Error::builder().kind(ErrorKind::InvalidFormat).source(e).build(),
-            omnius_core_cloud::ErrorKind::NotFound => Error::builder().kind(ErrorKind::NotFound).source(e).build(),
+            omnius_core_cloud::ErrorKind::NotFound => Error::builder().kind(ErrorKind::UnexpectedError).source(e).build(),
+        }
+    }
+}
+
+impl From<omnius_opxs_email_send::Error> for Error {
+    fn from(e: omnius_opxs_email_send::Error) -> Self {
+        match e.kind() {
+            omnius_opxs_email_send::ErrorKind::Unknown => Error::builder().kind(ErrorKind::Unknown).source(e).build(),
+            omnius_opxs_email_send::ErrorKind::IoError => Error::builder().kind(ErrorKind::IoError).source(e).build(),
+            omnius_opxs_email_send::ErrorKind::TimeError => Error::builder().kind(ErrorKind::TimeError).source(e).build(),
+            omnius_opxs_email_send::ErrorKind::SerdeError => Error::builder().kind(ErrorKind::SerdeError).source(e).build(),
+            omnius_opxs_email_send::ErrorKind::DatabaseError => Error::builder().kind(ErrorKind::DatabaseError).source(e).build(),
+            omnius_opxs_email_send::ErrorKind::HttpClientError => Error::builder().kind(ErrorKind::HttpClientError).source(e).build(),
+            omnius_opxs_email_send::ErrorKind::CryptoError => Error::builder().kind(ErrorKind::CryptoError).source(e).build(),
+            omnius_opxs_email_send::ErrorKind::UnexpectedError => Error::builder().kind(ErrorKind::UnexpectedError).source(e).build(),
+            omnius_opxs_email_send::ErrorKind::AwsError => Error::builder().kind(ErrorKind::AwsError).source(e).build(),
+            omnius_opxs_email_send::ErrorKind::GcpError => Error::builder().kind(ErrorKind::GcpError).source(e).build(),
+            omnius_opxs_email_send::ErrorKind::InvalidFormat => Error::builder().kind(ErrorKind::InvalidFormat).source(e).build(),
+            omnius_opxs_email_send::ErrorKind::TokenExpired => Error::builder().kind(ErrorKind::TokenExpired).source(e).build(),
+            omnius_opxs_email_send::ErrorKind::NotFound => Error::builder().kind(ErrorKind::NotFound).source(e).build(),
+            omnius_opxs_email_send::ErrorKind::Unauthorized => Error::builder().kind(ErrorKind::Unauthorized).source(e).build(),
+            omnius_opxs_email_send::ErrorKind::Duplicated => Error::builder().kind(ErrorKind::Duplicated).source(e).build(),
+            omnius_opxs_email_send::ErrorKind::UnsupportedType => Error::builder().kind(ErrorKind::UnexpectedError).source(e).build(),
+        }
+    }
+}
+
+impl From<omnius_opxs_file_convert::Error> for Error {
+    fn from(e: omnius_opxs_file_convert::Error) -> Self {
+        match e.kind() {
+            omnius_opxs_file_convert::ErrorKind::Unknown => Error::builder().kind(ErrorKind::Unknown).source(e).build(),
+            omnius_opxs_file_convert::ErrorKind::IoError => Error::builder().kind(ErrorKind::IoError).source(e).build(),
+            omnius_opxs_file_convert::ErrorKind::TimeError => Error::builder().kind(ErrorKind::TimeError).source(e).build(),
+            omnius_opxs_file_convert::ErrorKind::SerdeError => Error::builder().kind(ErrorKind::SerdeError).source(e).build(),
+            omnius_opxs_file_convert::ErrorKind::DatabaseError => Error::builder().kind(ErrorKind::DatabaseError).source(e).build(),
+            omnius_opxs_file_convert::ErrorKind::HttpClientError => Error::builder().kind(ErrorKind::HttpClientError).source(e).build(),
+            omnius_opxs_file_convert::ErrorKind::CryptoError => Error::builder().kind(ErrorKind::CryptoError).source(e).build(),
+            omnius_opxs_file_convert::ErrorKind::TaskError => Error::builder().kind(ErrorKind::UnexpectedError).source(e).build(),
+            omnius_opxs_file_convert::ErrorKind::UnexpectedError => Error::builder().kind(ErrorKind::UnexpectedError).source(e).build(),
+            omnius_opxs_file_convert::ErrorKind::AwsError => Error::builder().kind(ErrorKind::AwsError).source(e).build(),
+            omnius_opxs_file_convert::ErrorKind::GcpError => Error::builder().kind(ErrorKind::GcpError).source(e).build(),
+            omnius_opxs_file_convert::ErrorKind::ProcessFailed => Error::builder().kind(ErrorKind::UnexpectedError).source(e).build(),
+            omnius_opxs_file_convert::ErrorKind::InvalidFormat => Error::builder().kind(ErrorKind::InvalidFormat).source(e).build(),
+            omnius_opxs_file_convert::ErrorKind::NotFound => Error::builder().kind(ErrorKind::NotFound).source(e).build(),
+            omnius_opxs_file_convert::ErrorKind::TokenExpired => Error::builder().kind(ErrorKind::TokenExpired).source(e).build(),
+            omnius_opxs_file_convert::ErrorKind::Unauthorized => Error::builder().kind(ErrorKind::Unauthorized).source(e).build(),
+            omnius_opxs_file_convert::ErrorKind::Duplicated => Error::builder().kind(ErrorKind::Duplicated).source(e).build(),
+            omnius_opxs_file_convert::ErrorKind::UnsupportedType => Error::builder().kind(ErrorKind::UnexpectedError).source(e).build(),
         }
     }
 }
@@ -1,9 +1,16 @@
+pub mod admin;
+pub mod api_key;
 pub mod crypto;
 pub mod email;
 mod error;
+pub mod link;
+pub mod mailer;
 pub mod model;
+pub mod password;
+pub mod permission;
 mod prelude;
 pub mod provider;
+pub mod throttle;
 pub mod token;
 pub mod user;
 
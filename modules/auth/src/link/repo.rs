@@ -0,0 +1,147 @@
+use std::sync::Arc;
+
+use sqlx::{PgPool, Postgres, Transaction};
+
+use crate::{model::UserAuthMethod, prelude::*};
+
+pub struct AccountLinkRepo {
+    pub db: Arc<PgPool>,
+}
+
+impl AccountLinkRepo {
+    pub async fn get_auth_methods(&self, user_id: &str) -> Result<Vec<UserAuthMethod>> {
+        let methods: Vec<UserAuthMethod> = sqlx::query_as(
+            r#"
+SELECT 'email' AS method_type, email AS identifier, email_verified AS verified, created_at
+    FROM user_auth_emails
+    WHERE user_id = $1
+UNION ALL
+SELECT provider_type AS method_type, provider_user_id AS identifier, true AS verified, created_at
+    FROM user_auth_providers
+    WHERE user_id = $1
+ORDER BY created_at;
+"#,
+        )
+        .bind(user_id)
+        .fetch_all(self.db.as_ref())
+        .await?;
+
+        Ok(methods)
+    }
+
+    pub async fn delete_email(&self, user_id: &str) -> Result<()> {
+        let mut tx = self.db.begin().await?;
+
+        Self::lock_user(&mut tx, user_id).await?;
+
+        let email: Option<(bool,)> = sqlx::query_as(
+            r#"
+SELECT email_verified
+    FROM user_auth_emails
+    WHERE user_id = $1;
+"#,
+        )
+        .bind(user_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some((email_verified,)) = email else {
+            return Err(Error::builder().kind(ErrorKind::NotFound).message("email credential not found").build());
+        };
+
+        // An unverified email cannot be used for login, so it is always removable.
+        if email_verified {
+            let (remaining,): (i64,) = sqlx::query_as(
+                r#"
+SELECT COUNT(*)
+    FROM user_auth_providers
+    WHERE user_id = $1;
+"#,
+            )
+            .bind(user_id)
+            .fetch_one(&mut *tx)
+            .await?;
+
+            if remaining < 1 {
+                return Err(Error::builder()
+                    .kind(ErrorKind::LastCredential)
+                    .message("cannot detach the last credential")
+                    .build());
+            }
+        }
+
+        sqlx::query("DELETE FROM user_auth_emails WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    pub async fn delete_provider(&self, user_id: &str, provider_type: &str) -> Result<()> {
+        let mut tx = self.db.begin().await?;
+
+        Self::lock_user(&mut tx, user_id).await?;
+
+        let (existed,): (bool,) = sqlx::query_as(
+            r#"
+SELECT EXISTS (
+    SELECT 1
+        FROM user_auth_providers
+        WHERE user_id = $1 AND provider_type = $2
+);
+"#,
+        )
+        .bind(user_id)
+        .bind(provider_type)
+        .fetch_one(&mut *tx)
+        .await?;
+        if !existed {
+            return Err(Error::builder().kind(ErrorKind::NotFound).message("provider credential not found").build());
+        }
+
+        let (remaining,): (i64,) = sqlx::query_as(
+            r#"
+SELECT
+    (SELECT COUNT(*) FROM user_auth_emails WHERE user_id = $1 AND email_verified = true)
+    + (SELECT COUNT(*) FROM user_auth_providers WHERE user_id = $1 AND provider_type <> $2);
+"#,
+        )
+        .bind(user_id)
+        .bind(provider_type)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        if remaining < 1 {
+            return Err(Error::builder()
+                .kind(ErrorKind::LastCredential)
+                .message("cannot detach the last credential")
+                .build());
+        }
+
+        sqlx::query("DELETE FROM user_auth_providers WHERE user_id = $1 AND provider_type = $2")
+            .bind(user_id)
+            .bind(provider_type)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    // Serializes concurrent detach requests of the same user.
+    async fn lock_user(tx: &mut Transaction<'_, Postgres>, user_id: &str) -> Result<()> {
+        let user: Option<(String,)> = sqlx::query_as("SELECT id FROM users WHERE id = $1 FOR UPDATE")
+            .bind(user_id)
+            .fetch_optional(&mut **tx)
+            .await?;
+
+        if user.is_none() {
+            return Err(Error::builder().kind(ErrorKind::NotFound).message("user not found").build());
+        }
+
+        Ok(())
+    }
+}
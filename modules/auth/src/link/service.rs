@@ -0,0 +1,106 @@
+use std::sync::Arc;
+
+use crate::{model::UserAuthMethod, prelude::*};
+
+use super::AccountLinkRepo;
+
+pub struct AccountLinkService {
+    pub link_repo: Arc<AccountLinkRepo>,
+}
+
+impl AccountLinkService {
+    pub async fn get_auth_methods(&self, user_id: &str) -> Result<Vec<UserAuthMethod>> {
+        self.link_repo.get_auth_methods(user_id).await
+    }
+
+    pub async fn detach_email(&self, user_id: &str) -> Result<()> {
+        self.link_repo.delete_email(user_id).await
+    }
+
+    pub async fn detach_provider(&self, user_id: &str, provider_type: &str) -> Result<()> {
+        self.link_repo.delete_provider(user_id, provider_type).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+    use parking_lot::Mutex;
+    use sqlx::postgres::PgPoolOptions;
+    use testresult::TestResult;
+
+    use omnius_core_base::{clock::ClockUtc, random_bytes::RandomBytesProviderImpl, tsid::TsidProviderImpl};
+    use omnius_core_migration::postgres::PostgresMigrator;
+    use omnius_core_testkit::containers::postgres::PostgresContainer;
+
+    use omnius_opxs_base::shared::POSTGRES_VERSION;
+
+    use crate::{email::EmailAuthRepo, model::UserLocale, provider::ProviderAuthRepo};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn simple_test() -> TestResult {
+        let container = PostgresContainer::new(POSTGRES_VERSION).await?;
+
+        let db = Arc::new(
+            PgPoolOptions::new()
+                .max_connections(100)
+                .idle_timeout(Some(Duration::minutes(15).to_std()?))
+                .connect(&container.connection_string)
+                .await?,
+        );
+
+        let migrations_path = concat!(env!("CARGO_MANIFEST_DIR"), "/../../conf/migrations");
+        let migrator = PostgresMigrator::new(&container.connection_string, migrations_path, "opxs-api", "").await?;
+        migrator.migrate().await?;
+
+        let user_email = "user_email";
+
+        let clock = Arc::new(ClockUtc {});
+        let tsid_provider = Arc::new(Mutex::new(TsidProviderImpl::new(ClockUtc, RandomBytesProviderImpl::new(), 16)));
+        let email_auth_repo = EmailAuthRepo {
+            db: db.clone(),
+            clock: clock.clone(),
+            tsid_provider: tsid_provider.clone(),
+        };
+        let provider_auth_repo = ProviderAuthRepo {
+            db: db.clone(),
+            clock,
+            tsid_provider,
+        };
+        let link_service = AccountLinkService {
+            link_repo: Arc::new(AccountLinkRepo { db }),
+        };
+
+        let user_id = email_auth_repo.create_user("user_name", user_email, "hash", &UserLocale::Ja).await?;
+        email_auth_repo.update_email_verified(user_email, true).await?;
+
+        // last credential
+        assert_eq!(*link_service.detach_email(&user_id).await.unwrap_err().kind(), ErrorKind::LastCredential);
+
+        // attach providers
+        provider_auth_repo.add_provider(&user_id, "google", "google_user_id").await?;
+        provider_auth_repo.add_provider(&user_id, "github", "github_user_id").await?;
+        assert_eq!(
+            *provider_auth_repo
+                .add_provider(&user_id, "google", "other_user_id")
+                .await
+                .unwrap_err()
+                .kind(),
+            ErrorKind::Duplicated
+        );
+
+        let methods = link_service.get_auth_methods(&user_id).await?;
+        let method_types: Vec<&str> = methods.iter().map(|n| n.method_type.as_str()).collect();
+        assert_eq!(method_types, vec!["email", "google", "github"]);
+
+        // detach
+        link_service.detach_email(&user_id).await?;
+        link_service.detach_provider(&user_id, "google").await?;
+        assert_eq!(*link_service.detach_provider(&user_id, "google").await.unwrap_err().kind(), ErrorKind::NotFound);
+        assert_eq!(*link_service.detach_provider(&user_id, "github").await.unwrap_err().kind(), ErrorKind::LastCredential);
+
+        Ok(())
+    }
+}
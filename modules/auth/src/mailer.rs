@@ -0,0 +1,5 @@
+mod auth_mailer;
+mod auth_mailer_mock;
+
+pub use auth_mailer::*;
+pub use auth_mailer_mock::*;
@@ -0,0 +1,10 @@
+use async_trait::async_trait;
+
+use crate::{model::UserLocale, prelude::*};
+
+#[async_trait]
+pub trait AuthMailer {
+    async fn send_account_unlock(&self, user_name: &str, email: &str, unlock_token: &str, locale: &UserLocale) -> Result<()>;
+    async fn send_email_change_confirm(&self, user_name: &str, new_email: &str, token: &str, locale: &UserLocale) -> Result<()>;
+    async fn send_email_changed_notice(&self, user_name: &str, old_email: &str, new_email: &str, locale: &UserLocale) -> Result<()>;
+}
@@ -0,0 +1,90 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use parking_lot::Mutex;
+
+use crate::{model::UserLocale, prelude::*};
+
+use super::AuthMailer;
+
+pub struct AuthMailerMock {
+    pub send_account_unlock_inputs: Arc<Mutex<Vec<SendAccountUnlockInput>>>,
+    pub send_email_change_confirm_inputs: Arc<Mutex<Vec<SendEmailChangeConfirmInput>>>,
+    pub send_email_changed_notice_inputs: Arc<Mutex<Vec<SendEmailChangedNoticeInput>>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SendAccountUnlockInput {
+    pub user_name: String,
+    pub email: String,
+    pub unlock_token: String,
+    pub locale: UserLocale,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SendEmailChangeConfirmInput {
+    pub user_name: String,
+    pub new_email: String,
+    pub token: String,
+    pub locale: UserLocale,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SendEmailChangedNoticeInput {
+    pub user_name: String,
+    pub old_email: String,
+    pub new_email: String,
+    pub locale: UserLocale,
+}
+
+#[async_trait]
+impl AuthMailer for AuthMailerMock {
+    async fn send_account_unlock(&self, user_name: &str, email: &str, unlock_token: &str, locale: &UserLocale) -> Result<()> {
+        self.send_account_unlock_inputs.lock().push(SendAccountUnlockInput {
+            user_name: user_name.to_string(),
+            email: email.to_string(),
+            unlock_token: unlock_token.to_string(),
+            locale: *locale,
+        });
+
+        Ok(())
+    }
+
+    async fn send_email_change_confirm(&self, user_name: &str, new_email: &str, token: &str, locale: &UserLocale) -> Result<()> {
+        self.send_email_change_confirm_inputs.lock().push(SendEmailChangeConfirmInput {
+            user_name: user_name.to_string(),
+            new_email: new_email.to_string(),
+            token: token.to_string(),
+            locale: *locale,
+        });
+
+        Ok(())
+    }
+
+    async fn send_email_changed_notice(&self, user_name: &str, old_email: &str, new_email: &str, locale: &UserLocale) -> Result<()> {
+        self.send_email_changed_notice_inputs.lock().push(SendEmailChangedNoticeInput {
+            user_name: user_name.to_string(),
+            old_email: old_email.to_string(),
+            new_email: new_email.to_string(),
+            locale: *locale,
+        });
+
+        Ok(())
+    }
+}
+
+impl AuthMailerMock {
+    pub fn new() -> Self {
+        Self {
+            send_account_unlock_inputs: Arc::new(Mutex::new(vec![])),
+            send_email_change_confirm_inputs: Arc::new(Mutex::new(vec![])),
+            send_email_changed_notice_inputs: Arc::new(Mutex::new(vec![])),
+        }
+    }
+}
+
+impl Default for AuthMailerMock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -11,6 +11,8 @@ pub struct AuthToken {
     pub refresh_token_expires_at: NaiveDateTime,
 }
 
+/// How the account was created. It is not updated when credentials are linked or unlinked,
+/// so the current login methods must be read from `AccountLinkService::get_auth_methods`.
 #[derive(Debug, Serialize, Deserialize, sqlx::Type, ToSchema)]
 #[sqlx(type_name = "user_authentication_type")]
 pub enum UserAuthenticationType {
@@ -18,18 +20,28 @@ pub enum UserAuthenticationType {
     Provider,
 }
 
-#[derive(Debug, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
 #[sqlx(type_name = "user_role")]
 pub enum UserRole {
     Admin,
     User,
 }
 
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "user_locale")]
+#[serde(rename_all = "lowercase")]
+pub enum UserLocale {
+    #[default]
+    Ja,
+    En,
+}
+
 #[derive(Debug, Serialize, Deserialize, sqlx::FromRow, Validate, ToSchema)]
 pub struct User {
     pub id: String,
     pub name: String,
     pub role: UserRole,
+    pub locale: UserLocale,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
 }
@@ -39,11 +51,177 @@ pub struct EmailUser {
     pub id: String,
     pub name: String,
     pub role: UserRole,
+    pub locale: UserLocale,
     pub email: String,
     #[serde(skip_serializing)]
     pub password_hash: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
+pub struct UserProfile {
+    pub id: String,
+    pub name: String,
+    pub role: UserRole,
+    pub display_name: Option<String>,
+    pub locale: UserLocale,
+    pub time_zone: String,
+    pub avatar_job_id: Option<String>,
+    /// Uploaded avatar whose conversion has not finished yet. It replaces `avatar_job_id` once the job completes.
+    pub pending_avatar_job_id: Option<String>,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct UserProfileUpdate {
+    pub display_name: Option<String>,
+    pub locale: Option<UserLocale>,
+    pub time_zone: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "login_attempt_key_type")]
+pub enum LoginAttemptKeyType {
+    Email,
+    IpAddress,
+}
+
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+pub struct LoginAttempt {
+    pub key_type: LoginAttemptKeyType,
+    pub key: String,
+    pub failed_count: i32,
+    pub locked_until: Option<NaiveDateTime>,
     #[serde(skip_serializing)]
-    pub salt: String,
+    pub unlock_token_hash: Option<String>,
+    pub last_failed_at: NaiveDateTime,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
 }
+
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+pub struct OAuth2Session {
+    pub state: String,
+    pub provider_type: String,
+    #[serde(skip_serializing)]
+    pub nonce: String,
+    #[serde(skip_serializing)]
+    pub code_verifier: String,
+    pub expires_at: NaiveDateTime,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
+pub struct UserAuthMethod {
+    pub method_type: String,
+    pub identifier: String,
+    pub verified: bool,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "api_key_scope")]
+pub enum ApiKeyScope {
+    FileConvert,
+    Account,
+}
+
+impl sqlx::postgres::PgHasArrayType for ApiKeyScope {
+    fn array_type_info() -> sqlx::postgres::PgTypeInfo {
+        sqlx::postgres::PgTypeInfo::with_name("_api_key_scope")
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
+pub struct ApiKey {
+    pub id: String,
+    #[serde(skip_serializing)]
+    pub user_id: String,
+    pub name: String,
+    pub prefix: String,
+    #[serde(skip_serializing)]
+    pub key_hash: String,
+    pub scopes: Vec<ApiKeyScope>,
+    pub expires_at: Option<NaiveDateTime>,
+    pub last_used_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct IssuedApiKey {
+    pub api_key: ApiKey,
+    pub secret: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+pub struct EmailChangeRequest {
+    pub user_id: String,
+    pub new_email: String,
+    #[serde(skip_serializing)]
+    pub token: String,
+    pub expires_at: NaiveDateTime,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug)]
+pub struct EmailChange {
+    pub user_id: String,
+    pub user_name: String,
+    pub locale: UserLocale,
+    pub old_email: String,
+    pub new_email: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
+pub struct AdminUser {
+    pub id: String,
+    pub name: String,
+    pub role: UserRole,
+    pub email: Option<String>,
+    pub disabled_at: Option<NaiveDateTime>,
+    pub deletion_scheduled_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
+pub struct UserSession {
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub expires_at: NaiveDateTime,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AdminUserDetail {
+    pub user: AdminUser,
+    pub auth_methods: Vec<UserAuthMethod>,
+    pub api_keys: Vec<ApiKey>,
+    pub sessions: Vec<UserSession>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "admin_audit_action")]
+pub enum AdminAuditAction {
+    SearchUsers,
+    ViewUser,
+    ViewUserJobs,
+    ForceLogout,
+    DisableUser,
+    EnableUser,
+    UpdateUserRole,
+    ViewFailedJobs,
+    RetryFileConvertJob,
+    RetryEmailSendJob,
+}
+
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
+pub struct AdminAuditLog {
+    pub id: String,
+    pub operator_user_id: String,
+    pub action: AdminAuditAction,
+    pub target_id: Option<String>,
+    pub detail: Option<String>,
+    pub created_at: NaiveDateTime,
+}
@@ -0,0 +1,5 @@
+mod breached;
+mod policy;
+
+pub use breached::*;
+pub use policy::*;
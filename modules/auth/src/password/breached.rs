@@ -0,0 +1,61 @@
+use std::{
+    io::ErrorKind as IoErrorKind,
+    path::{Path, PathBuf},
+};
+
+use ring::digest;
+
+use crate::prelude::*;
+
+const PREFIX_LEN: usize = 5;
+
+/// Breached password hashes read from local files, so passwords never leave the process.
+/// The directory holds one Pwned Passwords range file per 5 character SHA-1 prefix (`<PREFIX>.txt`, one `<suffix>:<count>` per line),
+/// the layout written by PwnedPasswordsDownloader. Only the range of the checked password is read, so the full dump is never loaded.
+pub struct BreachedPasswordList {
+    dir: PathBuf,
+}
+
+impl BreachedPasswordList {
+    pub async fn open<P: AsRef<Path>>(dir: P) -> Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        if !tokio::fs::metadata(&dir).await?.is_dir() {
+            return Err(Error::builder()
+                .kind(ErrorKind::InvalidFormat)
+                .message(format!("breached password path is not a directory: {}", dir.display()))
+                .build());
+        }
+
+        Ok(Self { dir })
+    }
+
+    pub async fn contains(&self, password: &str) -> Result<bool> {
+        let hash = hex::encode_upper(digest::digest(&digest::SHA1_FOR_LEGACY_USE_ONLY, password.as_bytes()));
+        let (prefix, suffix) = hash.split_at(PREFIX_LEN);
+
+        let text = match tokio::fs::read_to_string(self.dir.join(format!("{prefix}.txt"))).await {
+            Ok(v) => v,
+            Err(e) if e.kind() == IoErrorKind::NotFound => return Ok(false),
+            Err(e) => return Err(e.into()),
+        };
+
+        Self::range_contains(&text, suffix)
+    }
+
+    fn range_contains(text: &str, suffix: &str) -> Result<bool> {
+        for line in text.lines().map(|v| v.trim()).filter(|v| !v.is_empty()) {
+            let v = line.split(':').next().unwrap_or_default();
+            if v.len() != digest::SHA1_OUTPUT_LEN * 2 - PREFIX_LEN || !v.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(Error::builder()
+                    .kind(ErrorKind::InvalidFormat)
+                    .message(format!("invalid breached password line: {line}"))
+                    .build());
+            }
+            if v.eq_ignore_ascii_case(suffix) {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+}
@@ -0,0 +1,166 @@
+use std::sync::Arc;
+
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::prelude::*;
+
+use super::BreachedPasswordList;
+
+// Parts of the email or name shorter than this are too common to reject on.
+const MIN_PERSONAL_INFO_LEN: usize = 3;
+
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    pub min_score: u8,
+    pub breached_list: Option<Arc<BreachedPasswordList>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub enum PasswordViolation {
+    TooShort,
+    TooWeak,
+    ContainsEmail,
+    ContainsName,
+    Breached,
+}
+
+/// Attached as the source of a `WeakPassword` error so callers can report each violation.
+#[derive(Debug)]
+pub struct PasswordPolicyError {
+    pub violations: Vec<PasswordViolation>,
+}
+
+impl std::fmt::Display for PasswordPolicyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "password policy violations: {:?}", self.violations)
+    }
+}
+
+impl std::error::Error for PasswordPolicyError {}
+
+impl PasswordPolicyError {
+    pub fn find(e: &Error) -> Option<&Self> {
+        std::error::Error::source(e).and_then(|v| v.downcast_ref::<Self>())
+    }
+}
+
+impl PasswordPolicy {
+    pub async fn check(&self, password: &str, email: &str, name: &str) -> Vec<PasswordViolation> {
+        let mut violations = Vec::new();
+
+        if password.chars().count() < self.min_length {
+            violations.push(PasswordViolation::TooShort);
+        }
+
+        let password_lower = password.to_lowercase();
+        let email_lower = email.to_lowercase();
+        let email_local = email_lower.split('@').next().unwrap_or_default();
+        if [email_lower.as_str(), email_local]
+            .iter()
+            .any(|v| Self::contains_info(&password_lower, v))
+        {
+            violations.push(PasswordViolation::ContainsEmail);
+        }
+        if name.to_lowercase().split_whitespace().any(|v| Self::contains_info(&password_lower, v)) {
+            violations.push(PasswordViolation::ContainsName);
+        }
+
+        let score = u8::from(zxcvbn::zxcvbn(password, &[email, name]).score());
+        if score < self.min_score {
+            violations.push(PasswordViolation::TooWeak);
+        }
+
+        if let Some(breached_list) = &self.breached_list {
+            match breached_list.contains(password).await {
+                Ok(true) => violations.push(PasswordViolation::Breached),
+                Ok(false) => {}
+                // The other checks still apply, so a broken range file does not block registration.
+                Err(e) => warn!(error = ?e, "breached password lookup failed"),
+            }
+        }
+
+        violations
+    }
+
+    pub async fn validate(&self, password: &str, email: &str, name: &str) -> Result<()> {
+        let violations = self.check(password, email, name).await;
+        if violations.is_empty() {
+            return Ok(());
+        }
+
+        Err(Error::builder()
+            .kind(ErrorKind::WeakPassword)
+            .message("password does not satisfy the policy")
+            .source(PasswordPolicyError { violations })
+            .build())
+    }
+
+    fn contains_info(password: &str, info: &str) -> bool {
+        info.chars().count() >= MIN_PERSONAL_INFO_LEN && password.contains(info)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use testresult::TestResult;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn simple_test() -> TestResult {
+        // SHA-1 of "correct horse battery staple" is ABF7AAD6438836DBE526AA231ABDE2D0EEF74D42
+        let dir = tempfile::tempdir()?;
+        std::fs::write(
+            dir.path().join("ABF7A.txt"),
+            "0018A45C4D1DEF81644B54AB7F969B88D65:3\nAD6438836DBE526AA231ABDE2D0EEF74D42:42\n",
+        )?;
+        let policy = PasswordPolicy {
+            min_length: 10,
+            min_score: 3,
+            breached_list: Some(Arc::new(BreachedPasswordList::open(dir.path()).await?)),
+        };
+        let email = "alice.smith@example.com";
+        let name = "Alice Smith";
+
+        assert!(policy.validate("vT9#qLm2!xRw8zPk", email, name).await.is_ok());
+
+        let violations = policy.check("abc", email, name).await;
+        assert!(violations.contains(&PasswordViolation::TooShort));
+        assert!(violations.contains(&PasswordViolation::TooWeak));
+
+        assert!(
+            policy
+                .check("alice.smith#Qz81!x", email, name)
+                .await
+                .contains(&PasswordViolation::ContainsEmail)
+        );
+        assert!(
+            policy
+                .check("Qz81!xSMITHvT9#qL", email, name)
+                .await
+                .contains(&PasswordViolation::ContainsName)
+        );
+        assert!(
+            policy
+                .check("correct horse battery staple", email, name)
+                .await
+                .contains(&PasswordViolation::Breached)
+        );
+
+        let e = policy.validate("abc", email, name).await.unwrap_err();
+        assert_eq!(*e.kind(), ErrorKind::WeakPassword);
+        assert!(PasswordPolicyError::find(&e).unwrap().violations.contains(&PasswordViolation::TooShort));
+
+        let breached_list = BreachedPasswordList::open(dir.path()).await?;
+        assert!(breached_list.contains("correct horse battery staple").await?);
+        // no range file for the prefix
+        assert!(!breached_list.contains("vT9#qLm2!xRw8zPk").await?);
+
+        std::fs::write(dir.path().join("ABF7A.txt"), "not a hash\n")?;
+        assert!(breached_list.contains("correct horse battery staple").await.is_err());
+        assert!(BreachedPasswordList::open(dir.path().join("ABF7A.txt")).await.is_err());
+
+        Ok(())
+    }
+}
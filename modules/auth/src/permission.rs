@@ -0,0 +1,76 @@
+use crate::{model::UserRole, prelude::*};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permission {
+    UsersRead,
+    UsersWrite,
+    RolesWrite,
+    JobsRead,
+    JobsWrite,
+}
+
+impl UserRole {
+    pub fn permissions(&self) -> &'static [Permission] {
+        match self {
+            UserRole::Admin => &[
+                Permission::UsersRead,
+                Permission::UsersWrite,
+                Permission::RolesWrite,
+                Permission::JobsRead,
+                Permission::JobsWrite,
+            ],
+            UserRole::User => &[],
+        }
+    }
+
+    pub fn has_permission(&self, permission: Permission) -> bool {
+        self.permissions().contains(&permission)
+    }
+
+    pub fn authorize(&self, permission: Permission) -> Result<()> {
+        if !self.has_permission(permission) {
+            return Err(Error::builder()
+                .kind(ErrorKind::Forbidden)
+                .message(format!("permission denied: {permission:?}"))
+                .build());
+        }
+
+        Ok(())
+    }
+}
+
+// Type-level markers for extractors such as `RequirePermission<UsersWrite>`.
+pub trait PermissionMarker {
+    const PERMISSION: Permission;
+}
+
+pub mod markers {
+    use super::{Permission, PermissionMarker};
+
+    macro_rules! permission_marker {
+        ($($name:ident),*) => {
+            $(
+                pub struct $name;
+
+                impl PermissionMarker for $name {
+                    const PERMISSION: Permission = Permission::$name;
+                }
+            )*
+        };
+    }
+
+    permission_marker!(UsersRead, UsersWrite, RolesWrite, JobsRead, JobsWrite);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn permissions_test() {
+        assert!(UserRole::Admin.has_permission(Permission::RolesWrite));
+        assert!(!UserRole::User.has_permission(Permission::RolesWrite));
+        assert_eq!(*UserRole::User.authorize(Permission::UsersRead).unwrap_err().kind(), ErrorKind::Forbidden);
+        assert!(UserRole::Admin.authorize(Permission::UsersRead).is_ok());
+    }
+}
@@ -1,5 +1,20 @@
+mod github;
 mod google;
+mod oauth2;
+mod oidc;
 mod repo;
+mod service;
+mod session_repo;
+mod session_service;
 
+#[cfg(test)]
+mod testkit;
+
+pub use github::*;
 pub use google::*;
+pub use oauth2::*;
+pub use oidc::*;
 pub use repo::*;
+pub use service::*;
+pub use session_repo::*;
+pub use session_service::*;
@@ -0,0 +1,3 @@
+mod oauth2;
+
+pub use oauth2::*;
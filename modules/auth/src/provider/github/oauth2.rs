@@ -0,0 +1,113 @@
+use async_trait::async_trait;
+use reqwest::{
+    StatusCode,
+    header::{ACCEPT, USER_AGENT},
+};
+use serde::Deserialize;
+
+use crate::{
+    prelude::*,
+    provider::{OAuth2Provider, ProviderUserInfo},
+};
+
+pub struct GitHubOAuth2ProviderImpl {
+    pub client_id: String,
+    pub client_secret: String,
+}
+
+impl GitHubOAuth2ProviderImpl {
+    async fn get_access_token(&self, code: &str, redirect_uri: &str, code_verifier: &str) -> Result<String> {
+        let client = reqwest::Client::new();
+        let res = client
+            .post("https://github.com/login/oauth/access_token")
+            .header(ACCEPT, "application/json")
+            .form(&[
+                ("client_id", self.client_id.as_str()),
+                ("client_secret", self.client_secret.as_str()),
+                ("redirect_uri", redirect_uri),
+                ("code", code),
+                ("code_verifier", code_verifier),
+            ])
+            .send()
+            .await?;
+
+        if res.status() != StatusCode::OK {
+            let message = res.text().await?;
+            return Err(Error::builder()
+                .kind(ErrorKind::HttpClientError)
+                .message(format!("get github access token error: {message}"))
+                .build());
+        }
+
+        // GitHub returns 200 even if the code exchange fails.
+        let token = res.json::<GitHubAccessToken>().await?;
+        match token {
+            GitHubAccessToken::Ok { access_token } => Ok(access_token),
+            GitHubAccessToken::Err { error, error_description } => Err(Error::builder()
+                .kind(ErrorKind::Unauthorized)
+                .message(format!("get github access token error: {error} {}", error_description.unwrap_or_default()))
+                .build()),
+        }
+    }
+
+    async fn get_user(&self, access_token: &str) -> Result<GitHubUser> {
+        let client = reqwest::Client::new();
+        let res = client
+            .get("https://api.github.com/user")
+            .header(ACCEPT, "application/vnd.github+json")
+            .header(USER_AGENT, "opxs")
+            .bearer_auth(access_token)
+            .send()
+            .await?;
+
+        if res.status() != StatusCode::OK {
+            let message = res.text().await?;
+            return Err(Error::builder()
+                .kind(ErrorKind::HttpClientError)
+                .message(format!("get github user error: {message}"))
+                .build());
+        }
+
+        let user = res.json::<GitHubUser>().await?;
+        Ok(user)
+    }
+}
+
+#[async_trait]
+impl OAuth2Provider for GitHubOAuth2ProviderImpl {
+    fn provider_type(&self) -> &str {
+        "github"
+    }
+
+    // GitHub does not issue ID tokens, so the nonce has nothing to be compared with.
+    async fn authenticate(&self, code: &str, redirect_uri: &str, _nonce: &str, code_verifier: &str) -> Result<ProviderUserInfo> {
+        let access_token = self.get_access_token(code, redirect_uri, code_verifier).await?;
+        let user = self.get_user(&access_token).await?;
+
+        Ok(ProviderUserInfo {
+            provider_user_id: user.id.to_string(),
+            name: user.name.unwrap_or(user.login),
+            email: user.email,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum GitHubAccessToken {
+    Ok {
+        access_token: String,
+    },
+    Err {
+        error: String,
+        error_description: Option<String>,
+    },
+}
+
+#[derive(Deserialize)]
+struct GitHubUser {
+    pub id: i64,
+    pub login: String,
+    pub name: Option<String>,
+    pub email: Option<String>,
+}
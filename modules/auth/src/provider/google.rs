@@ -1,5 +1,9 @@
+mod id_token;
+mod jwks;
 mod oauth2;
 mod service;
 
+pub use id_token::*;
+pub use jwks::*;
 pub use oauth2::*;
 pub use service::*;
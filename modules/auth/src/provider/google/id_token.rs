@@ -0,0 +1,72 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+
+use omnius_core_base::clock::Clock;
+
+use crate::provider::{JwksCache, OidcIdTokenVerifier};
+
+const GOOGLE_ISSUERS: [&str; 2] = ["https://accounts.google.com", "accounts.google.com"];
+
+impl OidcIdTokenVerifier {
+    /// Google signs ID tokens with its published JWKS and uses two spellings of its issuer.
+    pub fn google(clock: Arc<dyn Clock<Utc> + Send + Sync>, jwks_cache: Arc<JwksCache>, client_id: &str) -> Self {
+        Self {
+            clock,
+            jwks_cache,
+            issuers: GOOGLE_ISSUERS.iter().map(|v| v.to_string()).collect(),
+            audience: client_id.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+    use serde_json::json;
+    use testresult::TestResult;
+
+    use omnius_opxs_base::testkit::ClockMock;
+
+    use crate::{
+        prelude::*,
+        provider::testkit::{JwksFetcherMock, gen_key, sign},
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn google_test() -> TestResult {
+        let client_id = "client_id";
+
+        let (encoding_key, jwk) = gen_key("kid1")?;
+
+        let now = Utc::now();
+        let clock = Arc::new(ClockMock::new(now));
+        let jwks_cache = Arc::new(JwksCache::new(
+            clock.clone(),
+            Arc::new(JwksFetcherMock::new(vec![jwk])),
+            Duration::hours(1),
+        ));
+        let verifier = OidcIdTokenVerifier::google(clock, jwks_cache, client_id);
+
+        let exp = (now + Duration::minutes(10)).timestamp();
+
+        for issuer in GOOGLE_ISSUERS {
+            let token = sign(&encoding_key, "kid1", issuer, json!(client_id), "nonce", exp)?;
+            let claims = verifier.verify(&token).await?;
+            assert_eq!(claims.sub, "sub");
+            assert_eq!(claims.nonce.as_deref(), Some("nonce"));
+        }
+
+        // invalid issuer
+        let token = sign(&encoding_key, "kid1", "https://example.com", json!(client_id), "nonce", exp)?;
+        assert_eq!(*verifier.verify(&token).await.unwrap_err().kind(), ErrorKind::Unauthorized);
+
+        // invalid audience
+        let token = sign(&encoding_key, "kid1", GOOGLE_ISSUERS[0], json!("other_client_id"), "nonce", exp)?;
+        assert_eq!(*verifier.verify(&token).await.unwrap_err().kind(), ErrorKind::Unauthorized);
+
+        Ok(())
+    }
+}
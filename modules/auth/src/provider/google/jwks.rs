@@ -0,0 +1,26 @@
+use async_trait::async_trait;
+use jsonwebtoken::jwk::JwkSet;
+use reqwest::StatusCode;
+
+use crate::{prelude::*, provider::JwksFetcher};
+
+pub struct GoogleJwksFetcherImpl;
+
+#[async_trait]
+impl JwksFetcher for GoogleJwksFetcherImpl {
+    async fn fetch(&self) -> Result<JwkSet> {
+        let client = reqwest::Client::new();
+        let res = client.get("https://www.googleapis.com/oauth2/v3/certs").send().await?;
+
+        if res.status() != StatusCode::OK {
+            let message = res.text().await?;
+            return Err(Error::builder()
+                .kind(ErrorKind::GcpError)
+                .message(format!("get jwks error: {message}"))
+                .build());
+        }
+
+        let jwks = res.json::<JwkSet>().await?;
+        Ok(jwks)
+    }
+}
@@ -1,22 +1,39 @@
+use std::sync::Arc;
+
 use async_trait::async_trait;
-use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD as BASE64};
 use reqwest::StatusCode;
 use serde::Deserialize;
 use serde_json::json;
 
-use crate::prelude::*;
+use crate::{prelude::*, provider::OidcIdTokenVerifier};
 
 #[async_trait]
 pub trait GoogleOAuth2Provider {
-    async fn get_oauth2_token(&self, code: &str, redirect_uri: &str, client_id: &str, client_secret: &str) -> Result<OAuth2TokenResult>;
+    async fn get_oauth2_token(
+        &self,
+        code: &str,
+        redirect_uri: &str,
+        client_id: &str,
+        client_secret: &str,
+        code_verifier: &str,
+    ) -> Result<OAuth2TokenResult>;
     async fn get_user_info(&self, access_token: &str) -> Result<UserInfo>;
 }
 
-pub struct GoogleOAuth2ProviderImpl;
+pub struct GoogleOAuth2ProviderImpl {
+    pub id_token_verifier: Arc<OidcIdTokenVerifier>,
+}
 
 #[async_trait]
 impl GoogleOAuth2Provider for GoogleOAuth2ProviderImpl {
-    async fn get_oauth2_token(&self, code: &str, redirect_uri: &str, client_id: &str, client_secret: &str) -> Result<OAuth2TokenResult> {
+    async fn get_oauth2_token(
+        &self,
+        code: &str,
+        redirect_uri: &str,
+        client_id: &str,
+        client_secret: &str,
+        code_verifier: &str,
+    ) -> Result<OAuth2TokenResult> {
         let client = reqwest::Client::new();
         let res = client
             .post("https://accounts.google.com/o/oauth2/token")
@@ -25,7 +42,8 @@ impl GoogleOAuth2Provider for GoogleOAuth2ProviderImpl {
                 "client_secret": client_secret.to_string(),
                 "grant_type": "authorization_code".to_string(),
                 "redirect_uri": redirect_uri.to_string(),
-                "code": code.to_string()
+                "code": code.to_string(),
+                "code_verifier": code_verifier.to_string()
             }))
             .send()
             .await?;
@@ -39,9 +57,14 @@ impl GoogleOAuth2Provider for GoogleOAuth2ProviderImpl {
         }
 
         let oauth2_token = res.json::<OAuth2Token>().await?;
+        let claims = self.id_token_verifier.verify(&oauth2_token.id_token).await?;
+
         Ok(OAuth2TokenResult {
-            access_token: oauth2_token.access_token.clone(),
-            id_token_claims: oauth2_token.id_token_claims()?,
+            access_token: oauth2_token.access_token,
+            id_token_claims: IdTokenClaims {
+                sub: claims.sub,
+                nonce: claims.nonce.unwrap_or_default(),
+            },
         })
     }
 
@@ -72,24 +95,13 @@ struct OAuth2Token {
     pub id_token: String,
 }
 
-impl OAuth2Token {
-    pub fn id_token_claims(&self) -> Result<IdTokenClaims> {
-        let jwt: Vec<&str> = self.id_token.split('.').collect();
-        let payload = jwt[1];
-        let payload = BASE64.decode(payload)?;
-        let payload = String::from_utf8(payload)?;
-        let payload: IdTokenClaims = serde_json::from_str(&payload)?;
-        Ok(payload)
-    }
-}
-
 #[derive(Debug, Clone)]
 pub struct OAuth2TokenResult {
     pub access_token: String,
     pub id_token_claims: IdTokenClaims,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone)]
 pub struct IdTokenClaims {
     pub sub: String,
     pub nonce: String,
@@ -99,4 +111,6 @@ pub struct IdTokenClaims {
 pub struct UserInfo {
     pub name: String,
     pub email: String,
+    #[serde(default)]
+    pub verified_email: bool,
 }
@@ -1,53 +1,104 @@
 use std::sync::Arc;
 
+use chrono::{Duration, Utc};
+
+use omnius_core_base::clock::Clock;
 use omnius_opxs_base::AuthConfig;
 
-use crate::{prelude::*, provider::ProviderAuthRepo};
+use crate::{
+    email::EmailAuthRepo,
+    prelude::*,
+    provider::{OAuth2SessionParams, OAuth2SessionService, ProviderAuthRepo},
+    user::UserRepo,
+};
+
+use super::{GoogleOAuth2Provider, OAuth2TokenResult, UserInfo};
 
-use super::GoogleOAuth2Provider;
+const PROVIDER_TYPE: &str = "google";
 
 #[derive(Clone)]
 pub struct GoogleAuthService {
+    pub clock: Arc<dyn Clock<Utc> + Send + Sync>,
     pub oauth2_provider: Arc<dyn GoogleOAuth2Provider + Send + Sync>,
     pub auth_repo: Arc<ProviderAuthRepo>,
+    pub user_repo: Arc<UserRepo>,
+    pub session_service: Arc<OAuth2SessionService>,
+    pub email_auth_repo: Arc<EmailAuthRepo>,
     pub auth_conf: AuthConfig,
 }
 
 impl GoogleAuthService {
-    pub async fn register(&self, auth_code: &str, auth_redirect_uri: &str, auth_nonce: &str) -> Result<String> {
-        let oauth2_token_result = self
-            .oauth2_provider
-            .get_oauth2_token(
-                auth_code,
-                auth_redirect_uri,
-                &self.auth_conf.google.client_id,
-                &self.auth_conf.google.client_secret,
-            )
-            .await?;
+    pub async fn create_session(&self) -> Result<OAuth2SessionParams> {
+        self.session_service.create(PROVIDER_TYPE).await
+    }
+
+    pub async fn register(&self, auth_code: &str, auth_redirect_uri: &str, auth_state: &str, session_state: &str) -> Result<String> {
+        let oauth2_token_result = self.get_oauth2_token(auth_code, auth_redirect_uri, auth_state, session_state).await?;
         let access_token = oauth2_token_result.access_token;
         let id_token_claims = oauth2_token_result.id_token_claims;
 
-        if auth_nonce != id_token_claims.nonce {
-            return Err(Error::builder().kind(ErrorKind::Unauthorized).message("Nonce mismatch error").build());
-        }
-
-        if let Ok(user) = self.auth_repo.get_user("google", &id_token_claims.sub).await {
+        if let Ok(user) = self.auth_repo.get_user(PROVIDER_TYPE, &id_token_claims.sub).await {
+            self.user_repo.cancel_deletion(&user.id).await?;
             return Ok(user.id);
         }
 
         let user_info = self.oauth2_provider.get_user_info(&access_token).await?;
+        self.check_link_required(&user_info).await?;
 
-        let user_id = self.auth_repo.create_user(&user_info.name, "google", &id_token_claims.sub).await?;
+        let user_id = self.auth_repo.create_user(&user_info.name, PROVIDER_TYPE, &id_token_claims.sub).await?;
 
         Ok(user_id)
     }
 
     pub async fn unregister(&self, id: &str) -> Result<()> {
-        self.auth_repo.delete_user(id).await?;
+        let scheduled_at = self.clock.now() + Duration::days(self.auth_conf.unregister.grace_period_days);
+        self.user_repo.schedule_deletion(id, &scheduled_at).await
+    }
+
+    pub async fn login(&self, auth_code: &str, auth_redirect_uri: &str, auth_state: &str, session_state: &str) -> Result<String> {
+        let oauth2_token_result = self.get_oauth2_token(auth_code, auth_redirect_uri, auth_state, session_state).await?;
+        let access_token = oauth2_token_result.access_token;
+        let id_token_claims = oauth2_token_result.id_token_claims;
+
+        match self.auth_repo.get_user(PROVIDER_TYPE, &id_token_claims.sub).await {
+            Ok(user) => {
+                self.user_repo.cancel_deletion(&user.id).await?;
+                Ok(user.id)
+            }
+            Err(e) if *e.kind() == ErrorKind::NotFound => {
+                let user_info = self.oauth2_provider.get_user_info(&access_token).await?;
+                self.check_link_required(&user_info).await?;
+                Err(e)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    pub async fn link(&self, user_id: &str, auth_code: &str, auth_redirect_uri: &str, auth_state: &str, session_state: &str) -> Result<()> {
+        let oauth2_token_result = self.get_oauth2_token(auth_code, auth_redirect_uri, auth_state, session_state).await?;
+        let id_token_claims = oauth2_token_result.id_token_claims;
+
+        self.auth_repo.add_provider(user_id, PROVIDER_TYPE, &id_token_claims.sub).await?;
+
+        Ok(())
+    }
+
+    // A Google account whose verified email is already registered must be linked from the existing account,
+    // otherwise a second user would be created for the same person.
+    async fn check_link_required(&self, user_info: &UserInfo) -> Result<()> {
+        if user_info.verified_email && self.email_auth_repo.exist_user(&user_info.email).await? {
+            return Err(Error::builder()
+                .kind(ErrorKind::LinkRequired)
+                .message("email is already registered, link from the existing account")
+                .build());
+        }
+
         Ok(())
     }
 
-    pub async fn login(&self, auth_code: &str, auth_redirect_uri: &str, auth_nonce: &str) -> Result<String> {
+    async fn get_oauth2_token(&self, auth_code: &str, auth_redirect_uri: &str, auth_state: &str, session_state: &str) -> Result<OAuth2TokenResult> {
+        let session = self.session_service.take(PROVIDER_TYPE, auth_state, session_state).await?;
+
         let oauth2_token_result = self
             .oauth2_provider
             .get_oauth2_token(
@@ -55,25 +106,24 @@ impl GoogleAuthService {
                 auth_redirect_uri,
                 &self.auth_conf.google.client_id,
                 &self.auth_conf.google.client_secret,
+                &session.code_verifier,
             )
             .await?;
-        let id_token_claims = oauth2_token_result.id_token_claims;
 
-        if auth_nonce != id_token_claims.nonce {
+        if session.nonce != oauth2_token_result.id_token_claims.nonce {
             return Err(Error::builder().kind(ErrorKind::Unauthorized).message("Nonce mismatch error").build());
         }
 
-        let user = self.auth_repo.get_user("google", &id_token_claims.sub).await?;
-
-        Ok(user.id)
+        Ok(oauth2_token_result)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use async_trait::async_trait;
-    use chrono::Duration;
+    use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD as BASE64};
     use parking_lot::Mutex;
+    use ring::digest::{SHA256, digest};
     use sqlx::postgres::PgPoolOptions;
     use testresult::TestResult;
 
@@ -81,9 +131,12 @@ mod tests {
     use omnius_core_migration::postgres::PostgresMigrator;
     use omnius_core_testkit::containers::postgres::PostgresContainer;
 
-    use omnius_opxs_base::{GoogleAuthConfig, JwtConfig, JwtSecretConfig, shared::POSTGRES_VERSION};
+    use omnius_opxs_base::{GoogleAuthConfig, JwtConfig, JwtSecretConfig, PasswordPolicyConfig, UnregisterConfig, shared::POSTGRES_VERSION};
 
-    use crate::provider::{IdTokenClaims, OAuth2TokenResult, UserInfo};
+    use crate::{
+        model::UserLocale,
+        provider::{IdTokenClaims, OAuth2SessionRepo, OAuth2TokenResult, UserInfo},
+    };
 
     use super::*;
 
@@ -117,6 +170,7 @@ mod tests {
         let client_secret = "client_secret";
 
         let clock = Arc::new(ClockUtc {});
+        let random_bytes_provider = Arc::new(Mutex::new(RandomBytesProviderImpl::new()));
         let tsid_provider = Arc::new(Mutex::new(TsidProviderImpl::new(ClockUtc, RandomBytesProviderImpl::new(), 16)));
         let oauth2_provider = Arc::new(GoogleOAuth2ProviderMock::new(
             OAuth2TokenResult {
@@ -129,13 +183,28 @@ mod tests {
             UserInfo {
                 name: user_name.to_string(),
                 email: user_email.to_string(),
+                verified_email: true,
             },
         ));
         let auth_repo = Arc::new(ProviderAuthRepo {
-            db,
+            db: db.clone(),
+            clock: clock.clone(),
+            tsid_provider: tsid_provider.clone(),
+        });
+        let email_auth_repo = Arc::new(EmailAuthRepo {
+            db: db.clone(),
             clock: clock.clone(),
             tsid_provider,
         });
+        let user_repo = Arc::new(UserRepo {
+            db: db.clone(),
+            clock: clock.clone(),
+        });
+        let session_service = Arc::new(OAuth2SessionService {
+            clock: clock.clone(),
+            random_bytes_provider,
+            session_repo: Arc::new(OAuth2SessionRepo { db, clock: clock.clone() }),
+        });
         let auth_conf = AuthConfig {
             jwt: JwtConfig {
                 secret: JwtSecretConfig {
@@ -147,16 +216,30 @@ mod tests {
                 client_id: client_id.to_string(),
                 client_secret: client_secret.to_string(),
             },
+            github: None,
+            oidc: vec![],
+            password: PasswordPolicyConfig {
+                min_length: 8,
+                min_score: 2,
+                breached_password_dir: None,
+            },
+            unregister: UnregisterConfig { grace_period_days: 30 },
         };
 
         let auth_service = GoogleAuthService {
+            clock,
             oauth2_provider: oauth2_provider.clone(),
             auth_repo: auth_repo.clone(),
+            user_repo,
+            session_service,
+            email_auth_repo: email_auth_repo.clone(),
             auth_conf,
         };
 
         // register
-        let user_id = auth_service.register(code, redirect_uri, nonce).await?;
+        let session = auth_service.create_session().await?;
+        oauth2_provider.get_oauth2_token_result.lock().id_token_claims.nonce = session.nonce.clone();
+        let user_id = auth_service.register(code, redirect_uri, &session.state, &session.state).await?;
         println!("{user_id}");
         assert_eq!(*oauth2_provider.clone().get_oauth2_token_param.lock().code, code.to_string());
         assert_eq!(
@@ -168,9 +251,40 @@ mod tests {
             *oauth2_provider.clone().get_oauth2_token_param.lock().client_secret,
             client_secret.to_string()
         );
+        assert_eq!(
+            BASE64.encode(digest(&SHA256, oauth2_provider.clone().get_oauth2_token_param.lock().code_verifier.as_bytes())),
+            session.code_challenge
+        );
+
+        // session is single-use
+        assert_eq!(
+            *auth_service
+                .register(code, redirect_uri, &session.state, &session.state)
+                .await
+                .unwrap_err()
+                .kind(),
+            ErrorKind::Unauthorized
+        );
+
+        // state mismatch
+        let session = auth_service.create_session().await?;
+        oauth2_provider.get_oauth2_token_result.lock().id_token_claims.nonce = session.nonce.clone();
+        assert_eq!(
+            *auth_service.login(code, redirect_uri, &session.state, "other_state").await.unwrap_err().kind(),
+            ErrorKind::Unauthorized
+        );
+
+        // nonce mismatch
+        let session = auth_service.create_session().await?;
+        assert_eq!(
+            *auth_service.login(code, redirect_uri, &session.state, &session.state).await.unwrap_err().kind(),
+            ErrorKind::Unauthorized
+        );
 
         // login
-        assert_eq!(auth_service.login(code, redirect_uri, nonce).await?, user_id);
+        let session = auth_service.create_session().await?;
+        oauth2_provider.get_oauth2_token_result.lock().id_token_claims.nonce = session.nonce.clone();
+        assert_eq!(auth_service.login(code, redirect_uri, &session.state, &session.state).await?, user_id);
 
         // get user
         let user = auth_repo.get_user("google", provider_user_id).await?;
@@ -179,16 +293,52 @@ mod tests {
         // unregister
         assert!(auth_service.unregister(&user_id).await.is_ok());
 
-        // get user
+        // login restores the account
+        let session = auth_service.create_session().await?;
+        oauth2_provider.get_oauth2_token_result.lock().id_token_claims.nonce = session.nonce.clone();
+        assert_eq!(auth_service.login(code, redirect_uri, &session.state, &session.state).await?, user_id);
+
+        // purge
+        assert!(auth_service.unregister(&user_id).await.is_ok());
+        sqlx::query("DELETE FROM users WHERE id = $1")
+            .bind(&user_id)
+            .execute(auth_repo.db.as_ref())
+            .await?;
         assert!(auth_repo.get_user("google", provider_user_id).await.is_err());
 
+        // link required
+        let email_user_id = email_auth_repo.create_user(user_name, user_email, "hash", &UserLocale::Ja).await?;
+        email_auth_repo.update_email_verified(user_email, true).await?;
+
+        let session = auth_service.create_session().await?;
+        oauth2_provider.get_oauth2_token_result.lock().id_token_claims.nonce = session.nonce.clone();
+        assert_eq!(
+            *auth_service
+                .register(code, redirect_uri, &session.state, &session.state)
+                .await
+                .unwrap_err()
+                .kind(),
+            ErrorKind::LinkRequired
+        );
+
+        // link
+        let session = auth_service.create_session().await?;
+        oauth2_provider.get_oauth2_token_result.lock().id_token_claims.nonce = session.nonce.clone();
+        auth_service
+            .link(&email_user_id, code, redirect_uri, &session.state, &session.state)
+            .await?;
+
+        let session = auth_service.create_session().await?;
+        oauth2_provider.get_oauth2_token_result.lock().id_token_claims.nonce = session.nonce.clone();
+        assert_eq!(auth_service.login(code, redirect_uri, &session.state, &session.state).await?, email_user_id);
+
         Ok(())
     }
 
     #[derive(Debug, Clone)]
     struct GoogleOAuth2ProviderMock {
         get_oauth2_token_param: Arc<Mutex<GetOauth2TokenParam>>,
-        get_oauth2_token_result: OAuth2TokenResult,
+        get_oauth2_token_result: Arc<Mutex<OAuth2TokenResult>>,
         get_user_info_param: Arc<Mutex<String>>,
         get_user_info_result: UserInfo,
     }
@@ -199,6 +349,7 @@ mod tests {
         pub redirect_uri: String,
         pub client_id: String,
         pub client_secret: String,
+        pub code_verifier: String,
     }
 
     impl GoogleOAuth2ProviderMock {
@@ -209,8 +360,9 @@ mod tests {
                     redirect_uri: "".to_string(),
                     client_id: "".to_string(),
                     client_secret: "".to_string(),
+                    code_verifier: "".to_string(),
                 })),
-                get_oauth2_token_result,
+                get_oauth2_token_result: Arc::new(Mutex::new(get_oauth2_token_result)),
                 get_user_info_param: Arc::new(Mutex::new("".to_string())),
                 get_user_info_result,
             }
@@ -219,14 +371,22 @@ mod tests {
 
     #[async_trait]
     impl GoogleOAuth2Provider for GoogleOAuth2ProviderMock {
-        async fn get_oauth2_token(&self, code: &str, redirect_uri: &str, client_id: &str, client_secret: &str) -> Result<OAuth2TokenResult> {
+        async fn get_oauth2_token(
+            &self,
+            code: &str,
+            redirect_uri: &str,
+            client_id: &str,
+            client_secret: &str,
+            code_verifier: &str,
+        ) -> Result<OAuth2TokenResult> {
             *self.get_oauth2_token_param.lock() = GetOauth2TokenParam {
                 code: code.to_string(),
                 redirect_uri: redirect_uri.to_string(),
                 client_id: client_id.to_string(),
                 client_secret: client_secret.to_string(),
+                code_verifier: code_verifier.to_string(),
             };
-            return Ok(self.get_oauth2_token_result.clone());
+            return Ok(self.get_oauth2_token_result.lock().clone());
         }
 
         async fn get_user_info(&self, access_token: &str) -> Result<UserInfo> {
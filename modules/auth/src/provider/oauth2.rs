@@ -0,0 +1,16 @@
+use async_trait::async_trait;
+
+use crate::prelude::*;
+
+#[async_trait]
+pub trait OAuth2Provider {
+    fn provider_type(&self) -> &str;
+    async fn authenticate(&self, code: &str, redirect_uri: &str, nonce: &str, code_verifier: &str) -> Result<ProviderUserInfo>;
+}
+
+#[derive(Debug, Clone)]
+pub struct ProviderUserInfo {
+    pub provider_user_id: String,
+    pub name: String,
+    pub email: Option<String>,
+}
@@ -0,0 +1,9 @@
+mod discovery;
+mod id_token;
+mod jwks;
+mod oauth2;
+
+pub use discovery::*;
+pub use id_token::*;
+pub use jwks::*;
+pub use oauth2::*;
@@ -0,0 +1,33 @@
+use reqwest::StatusCode;
+use serde::Deserialize;
+
+use crate::prelude::*;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct OidcDiscoveryDocument {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub userinfo_endpoint: Option<String>,
+    pub jwks_uri: String,
+}
+
+impl OidcDiscoveryDocument {
+    pub async fn fetch(issuer_url: &str) -> Result<Self> {
+        let url = format!("{}/.well-known/openid-configuration", issuer_url.trim_end_matches('/'));
+
+        let client = reqwest::Client::new();
+        let res = client.get(url).send().await?;
+
+        if res.status() != StatusCode::OK {
+            let message = res.text().await?;
+            return Err(Error::builder()
+                .kind(ErrorKind::HttpClientError)
+                .message(format!("get oidc discovery document error: {message}"))
+                .build());
+        }
+
+        let document = res.json::<OidcDiscoveryDocument>().await?;
+        Ok(document)
+    }
+}
@@ -0,0 +1,164 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use serde::Deserialize;
+
+use omnius_core_base::clock::Clock;
+
+use crate::{prelude::*, provider::JwksCache};
+
+const ALLOWED_ALGORITHMS: [Algorithm; 6] = [
+    Algorithm::RS256,
+    Algorithm::RS384,
+    Algorithm::RS512,
+    Algorithm::PS256,
+    Algorithm::ES256,
+    Algorithm::ES384,
+];
+
+/// Verifies ID tokens issued by one of `issuers` for `audience`, signed by a key of the JWKS.
+pub struct OidcIdTokenVerifier {
+    pub clock: Arc<dyn Clock<Utc> + Send + Sync>,
+    pub jwks_cache: Arc<JwksCache>,
+    pub issuers: Vec<String>,
+    pub audience: String,
+}
+
+impl OidcIdTokenVerifier {
+    pub async fn verify(&self, id_token: &str) -> Result<OidcIdTokenClaims> {
+        let header = jsonwebtoken::decode_header(id_token)?;
+        if !ALLOWED_ALGORITHMS.contains(&header.alg) {
+            return Err(Error::builder()
+                .kind(ErrorKind::Unauthorized)
+                .message(format!("unsupported id token algorithm: {:?}", header.alg))
+                .build());
+        }
+        let kid = header
+            .kid
+            .ok_or_else(|| Error::builder().kind(ErrorKind::Unauthorized).message("id token kid not found").build())?;
+
+        let jwk = self.jwks_cache.find(&kid).await?;
+        let key = DecodingKey::from_jwk(&jwk)?;
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_audience(&[&self.audience]);
+        validation.set_issuer(&self.issuers);
+        validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+        validation.validate_exp = false;
+
+        let claims: OidcIdTokenClaims = jsonwebtoken::decode(id_token, &key, &validation).map(|token| token.claims).map_err(|e| {
+            Error::builder()
+                .kind(ErrorKind::Unauthorized)
+                .message("invalid id token")
+                .source(e)
+                .build()
+        })?;
+
+        let expired_at = DateTime::from_timestamp(claims.exp, 0).ok_or_else(|| Error::builder().kind(ErrorKind::TokenExpired).build())?;
+        if expired_at < self.clock.now() {
+            return Err(Error::builder().kind(ErrorKind::TokenExpired).message("id token expired").build());
+        }
+
+        Ok(claims)
+    }
+}
+
+#[derive(Deserialize)]
+pub struct OidcIdTokenClaims {
+    pub sub: String,
+    pub exp: i64,
+    pub nonce: Option<String>,
+    pub name: Option<String>,
+    pub preferred_username: Option<String>,
+    pub email: Option<String>,
+}
+
+impl OidcIdTokenClaims {
+    pub fn verify_nonce(&self, nonce: &str) -> Result<()> {
+        if self.nonce.as_deref() != Some(nonce) {
+            return Err(Error::builder().kind(ErrorKind::Unauthorized).message("Nonce mismatch error").build());
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD as BASE64};
+    use chrono::Duration;
+    use serde_json::json;
+    use testresult::TestResult;
+
+    use omnius_opxs_base::testkit::ClockMock;
+
+    use crate::provider::testkit::{JwksFetcherMock, gen_key, sign};
+
+    use super::*;
+
+    const ISSUER: &str = "https://login.example.com/v2.0";
+    const CLIENT_ID: &str = "client_id";
+
+    #[tokio::test]
+    async fn verify_test() -> TestResult {
+        let (encoding_key, jwk) = gen_key("kid1")?;
+        let (forged_encoding_key, _) = gen_key("kid1")?;
+
+        let now = Utc::now();
+        let clock = Arc::new(ClockMock::new(now));
+        let verifier = OidcIdTokenVerifier {
+            clock: clock.clone(),
+            jwks_cache: Arc::new(JwksCache::new(
+                clock.clone(),
+                Arc::new(JwksFetcherMock::new(vec![jwk])),
+                Duration::hours(1),
+            )),
+            issuers: vec![ISSUER.to_string()],
+            audience: CLIENT_ID.to_string(),
+        };
+
+        let exp = (now + Duration::minutes(10)).timestamp();
+
+        // valid, with the audience as an array
+        let token = sign(&encoding_key, "kid1", ISSUER, json!([CLIENT_ID, "other_client_id"]), "nonce", exp)?;
+        let claims = verifier.verify(&token).await?;
+        assert_eq!(claims.sub, "sub");
+        assert_eq!(claims.email.as_deref(), Some("user@example.com"));
+        assert!(claims.verify_nonce("nonce").is_ok());
+
+        // nonce of another login attempt
+        assert_eq!(*claims.verify_nonce("other_nonce").unwrap_err().kind(), ErrorKind::Unauthorized);
+
+        // forged signature
+        let token = sign(&forged_encoding_key, "kid1", ISSUER, json!(CLIENT_ID), "nonce", exp)?;
+        assert_eq!(*verifier.verify(&token).await.unwrap_err().kind(), ErrorKind::Unauthorized);
+
+        // unsigned
+        let token = format!(
+            "{}.{}.",
+            BASE64.encode(r#"{"alg":"none","kid":"kid1"}"#),
+            BASE64.encode(json!({ "iss": ISSUER, "aud": CLIENT_ID, "sub": "sub", "nonce": "nonce", "exp": exp }).to_string())
+        );
+        assert!(verifier.verify(&token).await.is_err());
+
+        // unknown kid
+        let token = sign(&encoding_key, "kid2", ISSUER, json!(CLIENT_ID), "nonce", exp)?;
+        assert_eq!(*verifier.verify(&token).await.unwrap_err().kind(), ErrorKind::Unauthorized);
+
+        // wrong audience
+        let token = sign(&encoding_key, "kid1", ISSUER, json!("other_client_id"), "nonce", exp)?;
+        assert_eq!(*verifier.verify(&token).await.unwrap_err().kind(), ErrorKind::Unauthorized);
+
+        // wrong issuer
+        let token = sign(&encoding_key, "kid1", "https://evil.example.com", json!(CLIENT_ID), "nonce", exp)?;
+        assert_eq!(*verifier.verify(&token).await.unwrap_err().kind(), ErrorKind::Unauthorized);
+
+        // expired
+        let token = sign(&encoding_key, "kid1", ISSUER, json!(CLIENT_ID), "nonce", exp)?;
+        clock.set(now + Duration::minutes(11));
+        assert_eq!(*verifier.verify(&token).await.unwrap_err().kind(), ErrorKind::TokenExpired);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,127 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use jsonwebtoken::jwk::{Jwk, JwkSet};
+use parking_lot::Mutex;
+use reqwest::StatusCode;
+
+use omnius_core_base::clock::Clock;
+
+use crate::prelude::*;
+
+const MIN_REFETCH_INTERVAL: Duration = Duration::seconds(60);
+
+#[async_trait]
+pub trait JwksFetcher {
+    async fn fetch(&self) -> Result<JwkSet>;
+}
+
+/// Fetches the signing keys published at the `jwks_uri` of an OIDC discovery document.
+pub struct OidcJwksFetcherImpl {
+    pub jwks_uri: String,
+}
+
+#[async_trait]
+impl JwksFetcher for OidcJwksFetcherImpl {
+    async fn fetch(&self) -> Result<JwkSet> {
+        let client = reqwest::Client::new();
+        let res = client.get(&self.jwks_uri).send().await?;
+
+        if res.status() != StatusCode::OK {
+            let message = res.text().await?;
+            return Err(Error::builder()
+                .kind(ErrorKind::HttpClientError)
+                .message(format!("get oidc jwks error: {message}"))
+                .build());
+        }
+
+        let jwks = res.json::<JwkSet>().await?;
+        Ok(jwks)
+    }
+}
+
+pub struct JwksCache {
+    clock: Arc<dyn Clock<Utc> + Send + Sync>,
+    fetcher: Arc<dyn JwksFetcher + Send + Sync>,
+    ttl: Duration,
+    cached: Mutex<Option<(JwkSet, DateTime<Utc>)>>,
+}
+
+impl JwksCache {
+    pub fn new(clock: Arc<dyn Clock<Utc> + Send + Sync>, fetcher: Arc<dyn JwksFetcher + Send + Sync>, ttl: Duration) -> Self {
+        Self {
+            clock,
+            fetcher,
+            ttl,
+            cached: Mutex::new(None),
+        }
+    }
+
+    pub async fn find(&self, kid: &str) -> Result<Jwk> {
+        let now = self.clock.now();
+
+        if let Some((jwks, fetched_at)) = self.cached.lock().as_ref()
+            && now < *fetched_at + self.ttl
+        {
+            if let Some(jwk) = jwks.find(kid) {
+                return Ok(jwk.clone());
+            }
+
+            // Tokens with made-up kids must not be able to make every request fetch the keys.
+            if now < *fetched_at + MIN_REFETCH_INTERVAL {
+                return Err(Error::builder().kind(ErrorKind::Unauthorized).message("jwk not found").build());
+            }
+        }
+
+        // The cache is expired or the key has been rotated.
+        let jwks = self.fetcher.fetch().await?;
+        let jwk = jwks.find(kid).cloned();
+        *self.cached.lock() = Some((jwks, now));
+
+        jwk.ok_or_else(|| Error::builder().kind(ErrorKind::Unauthorized).message("jwk not found").build())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use testresult::TestResult;
+
+    use omnius_opxs_base::testkit::ClockMock;
+
+    use crate::provider::testkit::{JwksFetcherMock, gen_key};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn find_test() -> TestResult {
+        let (_, jwk) = gen_key("kid1")?;
+
+        let clock = Arc::new(ClockMock::new(Utc::now()));
+        let fetcher = Arc::new(JwksFetcherMock::new(vec![jwk]));
+        let jwks_cache = JwksCache::new(clock.clone(), fetcher.clone(), Duration::hours(1));
+
+        jwks_cache.find("kid1").await?;
+        jwks_cache.find("kid1").await?;
+        assert_eq!(fetcher.fetch_count(), 1);
+
+        // unknown kid is rejected without refetch right after a fetch
+        assert_eq!(*jwks_cache.find("kid2").await.unwrap_err().kind(), ErrorKind::Unauthorized);
+        assert_eq!(*jwks_cache.find("kid3").await.unwrap_err().kind(), ErrorKind::Unauthorized);
+        assert_eq!(fetcher.fetch_count(), 1);
+
+        // unknown kid forces refetch once the interval has passed
+        clock.advance(MIN_REFETCH_INTERVAL);
+        assert!(jwks_cache.find("kid2").await.is_err());
+        assert_eq!(fetcher.fetch_count(), 2);
+        assert!(jwks_cache.find("kid2").await.is_err());
+        assert_eq!(fetcher.fetch_count(), 2);
+
+        // ttl expired
+        clock.advance(Duration::hours(2));
+        jwks_cache.find("kid1").await?;
+        assert_eq!(fetcher.fetch_count(), 3);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,124 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use reqwest::StatusCode;
+use serde::Deserialize;
+use tokio::sync::OnceCell;
+
+use omnius_core_base::clock::Clock;
+
+use crate::{
+    prelude::*,
+    provider::{JwksCache, OAuth2Provider, ProviderUserInfo},
+};
+
+use super::{OidcDiscoveryDocument, OidcIdTokenVerifier, OidcJwksFetcherImpl};
+
+pub struct OidcOAuth2ProviderImpl {
+    provider_type: String,
+    issuer_url: String,
+    client_id: String,
+    client_secret: String,
+    clock: Arc<dyn Clock<Utc> + Send + Sync>,
+    discovery: OnceCell<(OidcDiscoveryDocument, OidcIdTokenVerifier)>,
+}
+
+impl OidcOAuth2ProviderImpl {
+    pub fn new(provider_type: &str, issuer_url: &str, client_id: &str, client_secret: &str, clock: Arc<dyn Clock<Utc> + Send + Sync>) -> Self {
+        Self {
+            provider_type: provider_type.to_string(),
+            issuer_url: issuer_url.to_string(),
+            client_id: client_id.to_string(),
+            client_secret: client_secret.to_string(),
+            clock,
+            discovery: OnceCell::new(),
+        }
+    }
+
+    // The signing keys are only known once the discovery document is fetched, so the verifier is created with it.
+    async fn get_discovery(&self) -> Result<&(OidcDiscoveryDocument, OidcIdTokenVerifier)> {
+        self.discovery
+            .get_or_try_init(|| async {
+                let document = OidcDiscoveryDocument::fetch(&self.issuer_url).await?;
+
+                // A document served for another issuer would let that issuer's tokens pass verification.
+                if document.issuer != self.issuer_url {
+                    return Err(Error::builder()
+                        .kind(ErrorKind::HttpClientError)
+                        .message(format!("oidc issuer mismatch: {} != {}", document.issuer, self.issuer_url))
+                        .build());
+                }
+
+                let fetcher = Arc::new(OidcJwksFetcherImpl {
+                    jwks_uri: document.jwks_uri.clone(),
+                });
+                let verifier = OidcIdTokenVerifier {
+                    clock: self.clock.clone(),
+                    jwks_cache: Arc::new(JwksCache::new(self.clock.clone(), fetcher, Duration::hours(1))),
+                    issuers: vec![document.issuer.clone()],
+                    audience: self.client_id.clone(),
+                };
+                Ok((document, verifier))
+            })
+            .await
+    }
+
+    async fn get_id_token(&self, document: &OidcDiscoveryDocument, code: &str, redirect_uri: &str, code_verifier: &str) -> Result<String> {
+        let client = reqwest::Client::new();
+        let res = client
+            .post(&document.token_endpoint)
+            .form(&[
+                ("client_id", self.client_id.as_str()),
+                ("client_secret", self.client_secret.as_str()),
+                ("grant_type", "authorization_code"),
+                ("redirect_uri", redirect_uri),
+                ("code", code),
+                ("code_verifier", code_verifier),
+            ])
+            .send()
+            .await?;
+
+        if res.status() != StatusCode::OK {
+            let message = res.text().await?;
+            return Err(Error::builder()
+                .kind(ErrorKind::HttpClientError)
+                .message(format!("get oidc token error: {message}"))
+                .build());
+        }
+
+        let token = res.json::<OidcToken>().await?;
+        Ok(token.id_token)
+    }
+}
+
+#[async_trait]
+impl OAuth2Provider for OidcOAuth2ProviderImpl {
+    fn provider_type(&self) -> &str {
+        &self.provider_type
+    }
+
+    async fn authenticate(&self, code: &str, redirect_uri: &str, nonce: &str, code_verifier: &str) -> Result<ProviderUserInfo> {
+        let (document, verifier) = self.get_discovery().await?;
+        let id_token = self.get_id_token(document, code, redirect_uri, code_verifier).await?;
+        let claims = verifier.verify(&id_token).await?;
+        claims.verify_nonce(nonce)?;
+
+        let name = claims
+            .name
+            .or(claims.preferred_username)
+            .or_else(|| claims.email.clone())
+            .unwrap_or_else(|| claims.sub.clone());
+
+        Ok(ProviderUserInfo {
+            provider_user_id: claims.sub,
+            name,
+            email: claims.email,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct OidcToken {
+    pub id_token: String,
+}
@@ -57,20 +57,21 @@ INSERT INTO user_auth_providers (user_id, provider_type, provider_user_id, creat
         Ok(user_id)
     }
 
-    pub async fn delete_user(&self, id: &str) -> Result<()> {
-        let mut tx = self.db.begin().await?;
-
-        let queries = vec![
-            sqlx::query("DELETE FROM users WHERE id = $1").bind(id),
-            sqlx::query("DELETE FROM user_auth_providers WHERE user_id = $1").bind(id),
-            sqlx::query("DELETE FROM refresh_tokens WHERE user_id = $1").bind(id),
-        ];
-
-        for query in queries {
-            query.execute(&mut *tx).await?;
-        }
+    pub async fn add_provider(&self, user_id: &str, provider_type: &str, provider_user_id: &str) -> Result<()> {
+        let now = self.clock.now();
 
-        tx.commit().await?;
+        sqlx::query(
+            r#"
+INSERT INTO user_auth_providers (user_id, provider_type, provider_user_id, created_at)
+    VALUES ($1, $2, $3, $4)
+"#,
+        )
+        .bind(user_id)
+        .bind(provider_type)
+        .bind(provider_user_id)
+        .bind(now)
+        .execute(self.db.as_ref())
+        .await?;
 
         Ok(())
     }
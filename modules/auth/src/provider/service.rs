@@ -0,0 +1,241 @@
+use std::sync::Arc;
+
+use chrono::{Duration, Utc};
+
+use omnius_core_base::clock::Clock;
+use omnius_opxs_base::UnregisterConfig;
+
+use crate::{
+    prelude::*,
+    provider::{OAuth2SessionParams, OAuth2SessionService, ProviderAuthRepo},
+    user::UserRepo,
+};
+
+use super::{OAuth2Provider, ProviderUserInfo};
+
+#[derive(Clone)]
+pub struct ProviderAuthService {
+    pub oauth2_provider: Arc<dyn OAuth2Provider + Send + Sync>,
+    pub auth_repo: Arc<ProviderAuthRepo>,
+    pub user_repo: Arc<UserRepo>,
+    pub session_service: Arc<OAuth2SessionService>,
+    pub clock: Arc<dyn Clock<Utc> + Send + Sync>,
+    pub unregister_conf: UnregisterConfig,
+}
+
+impl ProviderAuthService {
+    pub fn provider_type(&self) -> &str {
+        self.oauth2_provider.provider_type()
+    }
+
+    pub async fn create_session(&self) -> Result<OAuth2SessionParams> {
+        self.session_service.create(self.provider_type()).await
+    }
+
+    pub async fn register(&self, auth_code: &str, auth_redirect_uri: &str, auth_state: &str, session_state: &str) -> Result<String> {
+        let user_info = self.authenticate(auth_code, auth_redirect_uri, auth_state, session_state).await?;
+
+        if let Ok(user) = self.auth_repo.get_user(self.provider_type(), &user_info.provider_user_id).await {
+            self.user_repo.cancel_deletion(&user.id).await?;
+            return Ok(user.id);
+        }
+
+        let user_id = self
+            .auth_repo
+            .create_user(&user_info.name, self.provider_type(), &user_info.provider_user_id)
+            .await?;
+
+        Ok(user_id)
+    }
+
+    pub async fn unregister(&self, id: &str) -> Result<()> {
+        let scheduled_at = self.clock.now() + Duration::days(self.unregister_conf.grace_period_days);
+        self.user_repo.schedule_deletion(id, &scheduled_at).await
+    }
+
+    pub async fn login(&self, auth_code: &str, auth_redirect_uri: &str, auth_state: &str, session_state: &str) -> Result<String> {
+        let user_info = self.authenticate(auth_code, auth_redirect_uri, auth_state, session_state).await?;
+
+        let user = self.auth_repo.get_user(self.provider_type(), &user_info.provider_user_id).await?;
+        self.user_repo.cancel_deletion(&user.id).await?;
+
+        Ok(user.id)
+    }
+
+    pub async fn link(&self, user_id: &str, auth_code: &str, auth_redirect_uri: &str, auth_state: &str, session_state: &str) -> Result<()> {
+        let user_info = self.authenticate(auth_code, auth_redirect_uri, auth_state, session_state).await?;
+
+        self.auth_repo
+            .add_provider(user_id, self.provider_type(), &user_info.provider_user_id)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn authenticate(&self, auth_code: &str, auth_redirect_uri: &str, auth_state: &str, session_state: &str) -> Result<ProviderUserInfo> {
+        let session = self.session_service.take(self.provider_type(), auth_state, session_state).await?;
+
+        self.oauth2_provider
+            .authenticate(auth_code, auth_redirect_uri, &session.nonce, &session.code_verifier)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+    use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD as BASE64};
+    use chrono::Duration;
+    use parking_lot::Mutex;
+    use ring::digest::{SHA256, digest};
+    use sqlx::postgres::PgPoolOptions;
+    use testresult::TestResult;
+
+    use omnius_core_base::{clock::ClockUtc, random_bytes::RandomBytesProviderImpl, tsid::TsidProviderImpl};
+    use omnius_core_migration::postgres::PostgresMigrator;
+    use omnius_core_testkit::containers::postgres::PostgresContainer;
+
+    use omnius_opxs_base::shared::POSTGRES_VERSION;
+
+    use crate::provider::OAuth2SessionRepo;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn simple_test() -> TestResult {
+        let container = PostgresContainer::new(POSTGRES_VERSION).await?;
+
+        let db = Arc::new(
+            PgPoolOptions::new()
+                .max_connections(100)
+                .idle_timeout(Some(Duration::minutes(15).to_std()?))
+                .connect(&container.connection_string)
+                .await?,
+        );
+
+        let migrations_path = concat!(env!("CARGO_MANIFEST_DIR"), "/../../conf/migrations");
+        let migrator = PostgresMigrator::new(&container.connection_string, migrations_path, "opxs-api", "").await?;
+        migrator.migrate().await?;
+
+        let provider_user_id = "provider_user_id";
+        let user_name = "user_name";
+
+        let code = "auth_code";
+        let redirect_uri = "auth_redirect_uri";
+
+        let clock = Arc::new(ClockUtc {});
+        let tsid_provider = Arc::new(Mutex::new(TsidProviderImpl::new(ClockUtc, RandomBytesProviderImpl::new(), 16)));
+        let oauth2_provider = Arc::new(OAuth2ProviderMock::new(
+            "github",
+            ProviderUserInfo {
+                provider_user_id: provider_user_id.to_string(),
+                name: user_name.to_string(),
+                email: None,
+            },
+        ));
+        let auth_repo = Arc::new(ProviderAuthRepo {
+            db: db.clone(),
+            clock: clock.clone(),
+            tsid_provider,
+        });
+        let user_repo = Arc::new(UserRepo {
+            db: db.clone(),
+            clock: clock.clone(),
+        });
+        let session_service = Arc::new(OAuth2SessionService {
+            clock: clock.clone(),
+            random_bytes_provider: Arc::new(Mutex::new(RandomBytesProviderImpl::new())),
+            session_repo: Arc::new(OAuth2SessionRepo { db, clock: clock.clone() }),
+        });
+
+        let auth_service = ProviderAuthService {
+            oauth2_provider: oauth2_provider.clone(),
+            auth_repo: auth_repo.clone(),
+            user_repo,
+            session_service,
+            clock,
+            unregister_conf: UnregisterConfig { grace_period_days: 30 },
+        };
+
+        // register
+        let session = auth_service.create_session().await?;
+        let user_id = auth_service.register(code, redirect_uri, &session.state, &session.state).await?;
+        let (param_code, param_redirect_uri, param_nonce, param_code_verifier) = oauth2_provider.authenticate_param.lock().clone();
+        assert_eq!(param_code, code);
+        assert_eq!(param_redirect_uri, redirect_uri);
+        assert_eq!(param_nonce, session.nonce);
+        assert_eq!(BASE64.encode(digest(&SHA256, param_code_verifier.as_bytes())), session.code_challenge);
+        assert_ne!(session.state, session.nonce);
+
+        // session is single-use
+        assert_eq!(
+            *auth_service
+                .register(code, redirect_uri, &session.state, &session.state)
+                .await
+                .unwrap_err()
+                .kind(),
+            ErrorKind::Unauthorized
+        );
+
+        // state mismatch
+        let session = auth_service.create_session().await?;
+        assert_eq!(
+            *auth_service
+                .login(code, redirect_uri, &session.state, "other_state")
+                .await
+                .unwrap_err()
+                .kind(),
+            ErrorKind::Unauthorized
+        );
+
+        // register twice
+        let session = auth_service.create_session().await?;
+        assert_eq!(auth_service.register(code, redirect_uri, &session.state, &session.state).await?, user_id);
+
+        // login
+        let session = auth_service.create_session().await?;
+        assert_eq!(auth_service.login(code, redirect_uri, &session.state, &session.state).await?, user_id);
+
+        // get user
+        let user = auth_repo.get_user("github", provider_user_id).await?;
+        assert_eq!(user.name, user_name.to_string());
+        assert!(auth_repo.get_user("microsoft", provider_user_id).await.is_err());
+
+        // unregister
+        assert!(auth_service.unregister(&user_id).await.is_ok());
+
+        // login restores the account
+        let session = auth_service.create_session().await?;
+        assert_eq!(auth_service.login(code, redirect_uri, &session.state, &session.state).await?, user_id);
+
+        Ok(())
+    }
+
+    struct OAuth2ProviderMock {
+        provider_type: String,
+        authenticate_param: Arc<Mutex<(String, String, String, String)>>,
+        authenticate_result: ProviderUserInfo,
+    }
+
+    impl OAuth2ProviderMock {
+        fn new(provider_type: &str, authenticate_result: ProviderUserInfo) -> Self {
+            Self {
+                provider_type: provider_type.to_string(),
+                authenticate_param: Arc::new(Mutex::new(Default::default())),
+                authenticate_result,
+            }
+        }
+    }
+
+    #[async_trait]
+    impl OAuth2Provider for OAuth2ProviderMock {
+        fn provider_type(&self) -> &str {
+            &self.provider_type
+        }
+
+        async fn authenticate(&self, code: &str, redirect_uri: &str, nonce: &str, code_verifier: &str) -> Result<ProviderUserInfo> {
+            *self.authenticate_param.lock() = (code.to_string(), redirect_uri.to_string(), nonce.to_string(), code_verifier.to_string());
+            Ok(self.authenticate_result.clone())
+        }
+    }
+}
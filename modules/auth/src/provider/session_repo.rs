@@ -0,0 +1,80 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+
+use omnius_core_base::clock::Clock;
+
+use crate::{model::OAuth2Session, prelude::*};
+
+pub struct OAuth2SessionRepo {
+    pub db: Arc<PgPool>,
+    pub clock: Arc<dyn Clock<Utc> + Send + Sync>,
+}
+
+impl OAuth2SessionRepo {
+    pub async fn create_session(
+        &self,
+        state: &str,
+        provider_type: &str,
+        nonce: &str,
+        code_verifier: &str,
+        expires_at: &DateTime<Utc>,
+    ) -> Result<()> {
+        let now = self.clock.now();
+
+        sqlx::query(
+            r#"
+INSERT INTO oauth2_sessions (state, provider_type, nonce, code_verifier, expires_at, created_at)
+    VALUES ($1, $2, $3, $4, $5, $6);
+"#,
+        )
+        .bind(state)
+        .bind(provider_type)
+        .bind(nonce)
+        .bind(code_verifier)
+        .bind(expires_at)
+        .bind(now)
+        .execute(self.db.as_ref())
+        .await?;
+
+        Ok(())
+    }
+
+    // Sessions are single-use, so they are deleted when taken.
+    pub async fn take_session(&self, state: &str, provider_type: &str) -> Result<OAuth2Session> {
+        let now = self.clock.now();
+
+        let session: Option<OAuth2Session> = sqlx::query_as(
+            r#"
+DELETE FROM oauth2_sessions
+    WHERE state = $1 AND provider_type = $2 AND expires_at > $3
+    RETURNING *;
+"#,
+        )
+        .bind(state)
+        .bind(provider_type)
+        .bind(now)
+        .fetch_optional(self.db.as_ref())
+        .await?;
+
+        let session = session.ok_or_else(|| Error::builder().kind(ErrorKind::NotFound).message("oauth2 session not found").build())?;
+        Ok(session)
+    }
+
+    pub async fn delete_expired_sessions(&self) -> Result<()> {
+        let now = self.clock.now();
+
+        sqlx::query(
+            r#"
+DELETE FROM oauth2_sessions
+    WHERE expires_at <= $1;
+"#,
+        )
+        .bind(now)
+        .execute(self.db.as_ref())
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,70 @@
+use std::sync::Arc;
+
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD as BASE64};
+use chrono::{Duration, Utc};
+use parking_lot::Mutex;
+use ring::digest::{SHA256, digest};
+
+use omnius_core_base::{clock::Clock, random_bytes::RandomBytesProvider};
+
+use crate::{model::OAuth2Session, prelude::*};
+
+use super::OAuth2SessionRepo;
+
+const SESSION_EXPIRES_IN: Duration = Duration::minutes(10);
+
+pub struct OAuth2SessionService {
+    pub clock: Arc<dyn Clock<Utc> + Send + Sync>,
+    pub random_bytes_provider: Arc<Mutex<dyn RandomBytesProvider + Send + Sync>>,
+    pub session_repo: Arc<OAuth2SessionRepo>,
+}
+
+#[derive(Debug, Clone)]
+pub struct OAuth2SessionParams {
+    pub state: String,
+    pub nonce: String,
+    pub code_challenge: String,
+    pub code_challenge_method: String,
+}
+
+impl OAuth2SessionService {
+    pub async fn create(&self, provider_type: &str) -> Result<OAuth2SessionParams> {
+        let state = hex::encode(self.random_bytes_provider.lock().get_bytes(32));
+        let nonce = hex::encode(self.random_bytes_provider.lock().get_bytes(32));
+        let code_verifier = BASE64.encode(self.random_bytes_provider.lock().get_bytes(32));
+        let expires_at = self.clock.now() + SESSION_EXPIRES_IN;
+
+        // Abandoned sign-ins are never taken, so they are swept here. A failure only delays the cleanup.
+        if let Err(e) = self.session_repo.delete_expired_sessions().await {
+            warn!(error = ?e, "delete expired oauth2 sessions failed");
+        }
+
+        self.session_repo
+            .create_session(&state, provider_type, &nonce, &code_verifier, &expires_at)
+            .await?;
+
+        Ok(OAuth2SessionParams {
+            state,
+            nonce,
+            code_challenge: BASE64.encode(digest(&SHA256, code_verifier.as_bytes())),
+            code_challenge_method: "S256".to_string(),
+        })
+    }
+
+    // `auth_state` is returned from the provider via the redirect, `session_state` is bound to the browser by a signed cookie.
+    pub async fn take(&self, provider_type: &str, auth_state: &str, session_state: &str) -> Result<OAuth2Session> {
+        if auth_state != session_state {
+            return Err(Error::builder().kind(ErrorKind::Unauthorized).message("State mismatch error").build());
+        }
+
+        match self.session_repo.take_session(auth_state, provider_type).await {
+            Ok(v) => Ok(v),
+            Err(e) if *e.kind() == ErrorKind::NotFound => Err(Error::builder()
+                .kind(ErrorKind::Unauthorized)
+                .message("OAuth2 session not found")
+                .source(e)
+                .build()),
+            Err(e) => Err(e),
+        }
+    }
+}
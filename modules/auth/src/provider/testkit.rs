@@ -0,0 +1,73 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use async_trait::async_trait;
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD as BASE64};
+use jsonwebtoken::{Algorithm, EncodingKey, Header, jwk::JwkSet};
+use ring::{
+    rand::SystemRandom,
+    signature::{ECDSA_P256_SHA256_FIXED_SIGNING, EcdsaKeyPair, KeyPair},
+};
+use serde_json::json;
+use testresult::TestResult;
+
+use crate::{prelude::*, provider::JwksFetcher};
+
+pub fn gen_key(kid: &str) -> TestResult<(EncodingKey, serde_json::Value)> {
+    let rng = SystemRandom::new();
+    let pkcs8 = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &rng)?;
+    let key_pair = EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, pkcs8.as_ref(), &rng)?;
+
+    // uncompressed point: 0x04 || x || y
+    let public_key = key_pair.public_key().as_ref();
+    let jwk = json!({
+        "kty": "EC",
+        "crv": "P-256",
+        "x": BASE64.encode(&public_key[1..33]),
+        "y": BASE64.encode(&public_key[33..65]),
+        "kid": kid,
+        "alg": "ES256",
+        "use": "sig",
+    });
+
+    Ok((EncodingKey::from_ec_der(pkcs8.as_ref()), jwk))
+}
+
+pub fn sign(key: &EncodingKey, kid: &str, iss: &str, aud: serde_json::Value, nonce: &str, exp: i64) -> TestResult<String> {
+    let mut header = Header::new(Algorithm::ES256);
+    header.kid = Some(kid.to_string());
+    let claims = json!({
+        "iss": iss,
+        "aud": aud,
+        "sub": "sub",
+        "nonce": nonce,
+        "email": "user@example.com",
+        "exp": exp,
+    });
+    Ok(jsonwebtoken::encode(&header, &claims, key)?)
+}
+
+pub struct JwksFetcherMock {
+    keys: Vec<serde_json::Value>,
+    fetch_count: AtomicUsize,
+}
+
+impl JwksFetcherMock {
+    pub fn new(keys: Vec<serde_json::Value>) -> Self {
+        Self {
+            keys,
+            fetch_count: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn fetch_count(&self) -> usize {
+        self.fetch_count.load(Ordering::SeqCst)
+    }
+}
+
+#[async_trait]
+impl JwksFetcher for JwksFetcherMock {
+    async fn fetch(&self) -> Result<JwkSet> {
+        self.fetch_count.fetch_add(1, Ordering::SeqCst);
+        Ok(serde_json::from_value(json!({ "keys": self.keys }))?)
+    }
+}
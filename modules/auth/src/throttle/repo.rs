@@ -0,0 +1,121 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+
+use omnius_core_base::clock::Clock;
+
+use crate::{
+    model::{LoginAttempt, LoginAttemptKeyType},
+    prelude::*,
+};
+
+pub struct LoginThrottleRepo {
+    pub db: Arc<PgPool>,
+    pub clock: Arc<dyn Clock<Utc> + Send + Sync>,
+}
+
+impl LoginThrottleRepo {
+    pub async fn get_attempt(&self, key_type: &LoginAttemptKeyType, key: &str) -> Result<Option<LoginAttempt>> {
+        let attempt: Option<LoginAttempt> = sqlx::query_as(
+            r#"
+SELECT *
+    FROM login_attempts
+    WHERE key_type = $1 AND key = $2;
+"#,
+        )
+        .bind(key_type)
+        .bind(key)
+        .fetch_optional(self.db.as_ref())
+        .await?;
+
+        Ok(attempt)
+    }
+
+    pub async fn increment_failure(&self, key_type: &LoginAttemptKeyType, key: &str, window_started_at: &DateTime<Utc>) -> Result<LoginAttempt> {
+        let now = self.clock.now();
+
+        let attempt: LoginAttempt = sqlx::query_as(
+            r#"
+INSERT INTO login_attempts (key_type, key, failed_count, last_failed_at, created_at, updated_at)
+    VALUES ($1, $2, 1, $3, $3, $3)
+    ON CONFLICT (key_type, key)
+    DO UPDATE SET
+        failed_count = CASE WHEN login_attempts.last_failed_at < $4 THEN 1 ELSE login_attempts.failed_count + 1 END,
+        locked_until = CASE WHEN login_attempts.last_failed_at < $4 THEN NULL ELSE login_attempts.locked_until END,
+        unlock_token_hash = CASE WHEN login_attempts.last_failed_at < $4 THEN NULL ELSE login_attempts.unlock_token_hash END,
+        last_failed_at = $3,
+        updated_at = $3
+    RETURNING *;
+"#,
+        )
+        .bind(key_type)
+        .bind(key)
+        .bind(now)
+        .bind(window_started_at)
+        .fetch_one(self.db.as_ref())
+        .await?;
+
+        Ok(attempt)
+    }
+
+    pub async fn update_locked_until(
+        &self,
+        key_type: &LoginAttemptKeyType,
+        key: &str,
+        locked_until: &DateTime<Utc>,
+        unlock_token_hash: Option<&str>,
+    ) -> Result<()> {
+        let now = self.clock.now();
+
+        sqlx::query(
+            r#"
+UPDATE login_attempts
+    SET locked_until = $3, unlock_token_hash = COALESCE($4, unlock_token_hash), updated_at = $5
+    WHERE key_type = $1 AND key = $2;
+"#,
+        )
+        .bind(key_type)
+        .bind(key)
+        .bind(locked_until)
+        .bind(unlock_token_hash)
+        .bind(now)
+        .execute(self.db.as_ref())
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete_attempt(&self, key_type: &LoginAttemptKeyType, key: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+DELETE FROM login_attempts
+    WHERE key_type = $1 AND key = $2;
+"#,
+        )
+        .bind(key_type)
+        .bind(key)
+        .execute(self.db.as_ref())
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete_attempt_by_unlock_token_hash(&self, unlock_token_hash: &str) -> Result<()> {
+        let res = sqlx::query(
+            r#"
+DELETE FROM login_attempts
+    WHERE unlock_token_hash = $1;
+"#,
+        )
+        .bind(unlock_token_hash)
+        .execute(self.db.as_ref())
+        .await?;
+
+        if res.rows_affected() < 1 {
+            return Err(Error::builder().kind(ErrorKind::NotFound).message("unlock token not found").build());
+        }
+
+        Ok(())
+    }
+}
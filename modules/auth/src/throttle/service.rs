@@ -0,0 +1,213 @@
+use std::sync::Arc;
+
+use chrono::{Duration, Utc};
+use parking_lot::Mutex;
+use ring::digest;
+
+use omnius_core_base::{clock::Clock, random_bytes::RandomBytesProvider};
+
+use crate::{
+    model::{LoginAttempt, LoginAttemptKeyType},
+    prelude::*,
+};
+
+use super::LoginThrottleRepo;
+
+const FAILURE_WINDOW: Duration = Duration::hours(24);
+const DELAY_BASE: Duration = Duration::seconds(1);
+const DELAY_MAX: Duration = Duration::minutes(15);
+const ACCOUNT_DELAY_THRESHOLD: i32 = 3;
+const ACCOUNT_LOCKOUT_THRESHOLD: i32 = 10;
+const ACCOUNT_LOCKOUT_DURATION: Duration = Duration::hours(24);
+const IP_ADDRESS_DELAY_THRESHOLD: i32 = 10;
+
+pub struct LoginThrottleService {
+    pub clock: Arc<dyn Clock<Utc> + Send + Sync>,
+    pub random_bytes_provider: Arc<Mutex<dyn RandomBytesProvider + Send + Sync>>,
+    pub throttle_repo: Arc<LoginThrottleRepo>,
+}
+
+impl LoginThrottleService {
+    pub async fn check(&self, email: &str, ip_address: &str) -> Result<()> {
+        if let Some(attempt) = self.throttle_repo.get_attempt(&LoginAttemptKeyType::Email, email).await? {
+            if self.is_locked(&attempt) {
+                if attempt.failed_count >= ACCOUNT_LOCKOUT_THRESHOLD {
+                    return Err(Error::builder().kind(ErrorKind::AccountLocked).message("account locked").build());
+                }
+                return Err(Error::builder().kind(ErrorKind::LoginThrottled).message("too many login failures").build());
+            }
+        }
+
+        if let Some(attempt) = self.throttle_repo.get_attempt(&LoginAttemptKeyType::IpAddress, ip_address).await? {
+            if self.is_locked(&attempt) {
+                return Err(Error::builder().kind(ErrorKind::LoginThrottled).message("too many login failures").build());
+            }
+        }
+
+        Ok(())
+    }
+
+    // Returns the unlock token only when this failure has just locked the account.
+    pub async fn record_failure(&self, email: &str, ip_address: &str) -> Result<Option<String>> {
+        let now = self.clock.now();
+        let window_started_at = now - FAILURE_WINDOW;
+
+        let mut unlock_token: Option<String> = None;
+
+        let attempt = self
+            .throttle_repo
+            .increment_failure(&LoginAttemptKeyType::Email, email, &window_started_at)
+            .await?;
+        if attempt.failed_count >= ACCOUNT_LOCKOUT_THRESHOLD {
+            if attempt.unlock_token_hash.is_none() {
+                let token = hex::encode(self.random_bytes_provider.lock().get_bytes(32));
+                self.throttle_repo
+                    .update_locked_until(
+                        &LoginAttemptKeyType::Email,
+                        email,
+                        &(now + ACCOUNT_LOCKOUT_DURATION),
+                        Some(&Self::hash_unlock_token(&token)),
+                    )
+                    .await?;
+                unlock_token = Some(token);
+            }
+        } else if let Some(delay) = Self::compute_delay(attempt.failed_count, ACCOUNT_DELAY_THRESHOLD) {
+            self.throttle_repo
+                .update_locked_until(&LoginAttemptKeyType::Email, email, &(now + delay), None)
+                .await?;
+        }
+
+        let attempt = self
+            .throttle_repo
+            .increment_failure(&LoginAttemptKeyType::IpAddress, ip_address, &window_started_at)
+            .await?;
+        if let Some(delay) = Self::compute_delay(attempt.failed_count, IP_ADDRESS_DELAY_THRESHOLD) {
+            self.throttle_repo
+                .update_locked_until(&LoginAttemptKeyType::IpAddress, ip_address, &(now + delay), None)
+                .await?;
+        }
+
+        Ok(unlock_token)
+    }
+
+    // The IP address counter is deliberately kept: one valid login must not clear failures spread over other accounts.
+    pub async fn reset(&self, email: &str) -> Result<()> {
+        self.throttle_repo.delete_attempt(&LoginAttemptKeyType::Email, email).await
+    }
+
+    pub async fn unlock(&self, unlock_token: &str) -> Result<()> {
+        self.throttle_repo
+            .delete_attempt_by_unlock_token_hash(&Self::hash_unlock_token(unlock_token))
+            .await
+    }
+
+    fn hash_unlock_token(unlock_token: &str) -> String {
+        hex::encode(digest::digest(&digest::SHA256, unlock_token.as_bytes()))
+    }
+
+    fn is_locked(&self, attempt: &LoginAttempt) -> bool {
+        let now = self.clock.now();
+        attempt.locked_until.is_some_and(|locked_until| locked_until.and_utc() > now)
+    }
+
+    fn compute_delay(failed_count: i32, threshold: i32) -> Option<Duration> {
+        if failed_count < threshold {
+            return None;
+        }
+
+        let exponent = (failed_count - threshold).min(16) as u32;
+        Some((DELAY_BASE * 2_i32.pow(exponent)).min(DELAY_MAX))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::postgres::PgPoolOptions;
+    use testresult::TestResult;
+
+    use omnius_core_base::{clock::ClockUtc, random_bytes::RandomBytesProviderImpl};
+    use omnius_core_migration::postgres::PostgresMigrator;
+    use omnius_core_testkit::containers::postgres::PostgresContainer;
+
+    use omnius_opxs_base::shared::POSTGRES_VERSION;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn simple_test() -> TestResult {
+        let container = PostgresContainer::new(POSTGRES_VERSION).await?;
+
+        let db = Arc::new(
+            PgPoolOptions::new()
+                .max_connections(100)
+                .idle_timeout(Some(Duration::minutes(15).to_std()?))
+                .connect(&container.connection_string)
+                .await?,
+        );
+
+        let migrations_path = concat!(env!("CARGO_MANIFEST_DIR"), "/../../conf/migrations");
+        let migrator = PostgresMigrator::new(&container.connection_string, migrations_path, "opxs-api", "").await?;
+        migrator.migrate().await?;
+
+        let email = "user_email";
+        let ip_address = "192.0.2.1";
+        let other_ip_address = "192.0.2.2";
+
+        let clock = Arc::new(ClockUtc {});
+        let throttle_service = LoginThrottleService {
+            clock: clock.clone(),
+            random_bytes_provider: Arc::new(Mutex::new(RandomBytesProviderImpl::new())),
+            throttle_repo: Arc::new(LoginThrottleRepo { db, clock }),
+        };
+
+        // delay
+        for _ in 0..ACCOUNT_DELAY_THRESHOLD - 1 {
+            assert!(throttle_service.record_failure(email, ip_address).await?.is_none());
+        }
+        assert!(throttle_service.check(email, ip_address).await.is_ok());
+
+        assert!(throttle_service.record_failure(email, ip_address).await?.is_none());
+        assert_eq!(
+            *throttle_service.check(email, ip_address).await.unwrap_err().kind(),
+            ErrorKind::LoginThrottled
+        );
+
+        // lockout
+        for _ in ACCOUNT_DELAY_THRESHOLD..ACCOUNT_LOCKOUT_THRESHOLD - 1 {
+            assert!(throttle_service.record_failure(email, ip_address).await?.is_none());
+        }
+        let unlock_token = throttle_service.record_failure(email, ip_address).await?;
+        assert!(unlock_token.is_some());
+        assert!(throttle_service.record_failure(email, ip_address).await?.is_none());
+        let attempt = throttle_service
+            .throttle_repo
+            .get_attempt(&LoginAttemptKeyType::Email, email)
+            .await?
+            .unwrap();
+        assert_ne!(attempt.unlock_token_hash.as_deref(), unlock_token.as_deref());
+        assert_eq!(
+            *throttle_service.check(email, other_ip_address).await.unwrap_err().kind(),
+            ErrorKind::AccountLocked
+        );
+
+        // ip address
+        assert_eq!(
+            *throttle_service.check("other_email", ip_address).await.unwrap_err().kind(),
+            ErrorKind::LoginThrottled
+        );
+
+        // unlock
+        throttle_service.unlock(&unlock_token.unwrap()).await?;
+        assert!(throttle_service.check(email, other_ip_address).await.is_ok());
+
+        // reset keeps the ip address counter
+        throttle_service.reset(email).await?;
+        assert_eq!(
+            *throttle_service.check(email, ip_address).await.unwrap_err().kind(),
+            ErrorKind::LoginThrottled
+        );
+        assert_eq!(*throttle_service.unlock("invalid_token").await.unwrap_err().kind(), ErrorKind::NotFound);
+
+        Ok(())
+    }
+}
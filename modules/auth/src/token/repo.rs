@@ -15,10 +15,12 @@ pub struct TokenRepo {
 impl TokenRepo {
     pub async fn create_token(&self, user_id: &str, refresh_token: &str, refresh_token_expires_at: &DateTime<Utc>) -> Result<()> {
         let now = self.clock.now();
-        sqlx::query(
+        let res = sqlx::query(
             r#"
 INSERT INTO refresh_tokens (refresh_token, user_id, expires_at, created_at)
-    VALUES ($1, $2, $3, $4);
+    SELECT $1, id, $3, $4
+        FROM users
+        WHERE id = $2 AND disabled_at IS NULL;
 "#,
         )
         .bind(refresh_token)
@@ -28,6 +30,10 @@ INSERT INTO refresh_tokens (refresh_token, user_id, expires_at, created_at)
         .execute(self.db.as_ref())
         .await?;
 
+        if res.rows_affected() < 1 {
+            return Err(Error::builder().kind(ErrorKind::AccountDisabled).message("user is disabled").build());
+        }
+
         Ok(())
     }
 
@@ -91,7 +97,7 @@ DELETE FROM refresh_tokens
 SELECT u.*
     FROM users u
     JOIN refresh_tokens t on t.user_id = u.id
-    WHERE t.refresh_token = $1 AND expires_at > $2;
+    WHERE t.refresh_token = $1 AND expires_at > $2 AND u.disabled_at IS NULL;
 "#,
         )
         .bind(refresh_token)
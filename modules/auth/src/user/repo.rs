@@ -1,11 +1,18 @@
 use std::sync::Arc;
 
+use chrono::{DateTime, Utc};
 use sqlx::PgPool;
 
-use crate::{model::User, prelude::*};
+use omnius_core_base::clock::Clock;
+
+use crate::{
+    model::{User, UserLocale, UserProfile, UserRole},
+    prelude::*,
+};
 
 pub struct UserRepo {
     pub db: Arc<PgPool>,
+    pub clock: Arc<dyn Clock<Utc> + Send + Sync>,
 }
 
 impl UserRepo {
@@ -14,7 +21,7 @@ impl UserRepo {
             r#"
 SELECT *
     FROM users
-    WHERE id = $1;
+    WHERE id = $1 AND deletion_scheduled_at IS NULL AND disabled_at IS NULL;
 "#,
         )
         .bind(user_id)
@@ -27,4 +34,163 @@ SELECT *
 
         Ok(user.unwrap())
     }
+
+    // Sessions are revoked right away; the account itself is kept until the purge job removes it after `scheduled_at`.
+    pub async fn schedule_deletion(&self, id: &str, scheduled_at: &DateTime<Utc>) -> Result<()> {
+        let now = self.clock.now();
+
+        let mut tx = self.db.begin().await?;
+
+        let res = sqlx::query(
+            r#"
+UPDATE users
+    SET deletion_scheduled_at = $2, updated_at = $3
+    WHERE id = $1;
+"#,
+        )
+        .bind(id)
+        .bind(scheduled_at)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+
+        if res.rows_affected() < 1 {
+            return Err(Error::builder().kind(ErrorKind::NotFound).message("user not found").build());
+        }
+
+        sqlx::query("DELETE FROM refresh_tokens WHERE user_id = $1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    // A single conditional UPDATE, so a cancellation can not race with the purge of an account whose grace period has ended.
+    pub async fn cancel_deletion(&self, id: &str) -> Result<()> {
+        let now = self.clock.now();
+
+        let res = sqlx::query(
+            r#"
+UPDATE users
+    SET deletion_scheduled_at = NULL, updated_at = $2
+    WHERE id = $1 AND deletion_scheduled_at IS NOT NULL AND deletion_scheduled_at > $2;
+"#,
+        )
+        .bind(id)
+        .bind(now)
+        .execute(self.db.as_ref())
+        .await?;
+
+        if res.rows_affected() < 1 {
+            let (due,): (bool,) = sqlx::query_as("SELECT EXISTS (SELECT id FROM users WHERE id = $1 AND deletion_scheduled_at <= $2);")
+                .bind(id)
+                .bind(now)
+                .fetch_one(self.db.as_ref())
+                .await?;
+            if due {
+                return Err(Error::builder().kind(ErrorKind::NotFound).message("user is being deleted").build());
+            }
+        }
+
+        Ok(())
+    }
+
+    pub async fn update_role(&self, user_id: &str, role: &UserRole) -> Result<()> {
+        let now = self.clock.now();
+
+        let res = sqlx::query(
+            r#"
+UPDATE users
+    SET role = $2, updated_at = $3
+    WHERE id = $1;
+"#,
+        )
+        .bind(user_id)
+        .bind(role)
+        .bind(now)
+        .execute(self.db.as_ref())
+        .await?;
+
+        if res.rows_affected() < 1 {
+            return Err(Error::builder().kind(ErrorKind::NotFound).message("User not found").build());
+        }
+
+        Ok(())
+    }
+
+    pub async fn get_profile(&self, user_id: &str) -> Result<UserProfile> {
+        let profile: Option<UserProfile> = sqlx::query_as(
+            r#"
+SELECT id, name, role, display_name, locale, time_zone, avatar_job_id, pending_avatar_job_id, updated_at
+    FROM users
+    WHERE id = $1;
+"#,
+        )
+        .bind(user_id)
+        .fetch_optional(self.db.as_ref())
+        .await?;
+
+        profile.ok_or_else(|| Error::builder().kind(ErrorKind::NotFound).message("User not found").build())
+    }
+
+    // `None` leaves the column unchanged; an empty display name clears it.
+    pub async fn update_profile(
+        &self,
+        user_id: &str,
+        display_name: Option<&str>,
+        locale: Option<&UserLocale>,
+        time_zone: Option<&str>,
+    ) -> Result<()> {
+        let now = self.clock.now();
+
+        let res = sqlx::query(
+            r#"
+UPDATE users
+    SET display_name = CASE WHEN $2::VARCHAR IS NULL THEN display_name ELSE NULLIF($2, '') END,
+        locale = COALESCE($3, locale),
+        time_zone = COALESCE($4, time_zone),
+        updated_at = $5
+    WHERE id = $1;
+"#,
+        )
+        .bind(user_id)
+        .bind(display_name)
+        .bind(locale)
+        .bind(time_zone)
+        .bind(now)
+        .execute(self.db.as_ref())
+        .await?;
+
+        if res.rows_affected() < 1 {
+            return Err(Error::builder().kind(ErrorKind::NotFound).message("User not found").build());
+        }
+
+        Ok(())
+    }
+
+    pub async fn update_pending_avatar_job_id(&self, user_id: &str, job_id: &str) -> Result<()> {
+        let now = self.clock.now();
+
+        let res = sqlx::query(
+            r#"
+UPDATE users
+    SET pending_avatar_job_id = $2, updated_at = $3
+    WHERE id = $1;
+"#,
+        )
+        .bind(user_id)
+        .bind(job_id)
+        .bind(now)
+        .execute(self.db.as_ref())
+        .await?;
+
+        if res.rows_affected() < 1 {
+            return Err(Error::builder().kind(ErrorKind::NotFound).message("User not found").build());
+        }
+
+        Ok(())
+    }
 }
@@ -1,6 +1,9 @@
 use std::sync::Arc;
 
-use crate::{model::User, prelude::*};
+use crate::{
+    model::{User, UserProfile, UserProfileUpdate},
+    prelude::*,
+};
 
 use super::UserRepo;
 
@@ -13,4 +16,129 @@ impl UserService {
         let user = self.user_repo.get_user(user_id).await?;
         Ok(user)
     }
+
+    pub async fn get_profile(&self, user_id: &str) -> Result<UserProfile> {
+        self.user_repo.get_profile(user_id).await
+    }
+
+    pub async fn update_profile(&self, user_id: &str, update: &UserProfileUpdate) -> Result<UserProfile> {
+        if let Some(time_zone) = update.time_zone.as_deref()
+            && time_zone.parse::<chrono_tz::Tz>().is_err()
+        {
+            return Err(Error::builder()
+                .kind(ErrorKind::InvalidRequest)
+                .message(format!("unknown time zone: {time_zone}"))
+                .build());
+        }
+
+        self.user_repo
+            .update_profile(
+                user_id,
+                update.display_name.as_deref().map(str::trim),
+                update.locale.as_ref(),
+                update.time_zone.as_deref(),
+            )
+            .await?;
+
+        self.user_repo.get_profile(user_id).await
+    }
+
+    /// Keeps the current avatar until the file-convert executor finishes the thumbnail job of the uploaded one.
+    pub async fn set_pending_avatar(&self, user_id: &str, job_id: &str) -> Result<()> {
+        self.user_repo.update_pending_avatar_job_id(user_id, job_id).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+    use parking_lot::Mutex;
+    use sqlx::postgres::PgPoolOptions;
+    use testresult::TestResult;
+
+    use omnius_core_base::{clock::ClockUtc, random_bytes::RandomBytesProviderImpl, tsid::TsidProviderImpl};
+    use omnius_core_migration::postgres::PostgresMigrator;
+    use omnius_core_testkit::containers::postgres::PostgresContainer;
+
+    use omnius_opxs_base::shared::POSTGRES_VERSION;
+
+    use crate::{email::EmailAuthRepo, model::UserLocale};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn simple_test() -> TestResult {
+        let container = PostgresContainer::new(POSTGRES_VERSION).await?;
+
+        let db = Arc::new(
+            PgPoolOptions::new()
+                .max_connections(100)
+                .idle_timeout(Some(Duration::minutes(15).to_std()?))
+                .connect(&container.connection_string)
+                .await?,
+        );
+
+        let migrations_path = concat!(env!("CARGO_MANIFEST_DIR"), "/../../conf/migrations");
+        let migrator = PostgresMigrator::new(&container.connection_string, migrations_path, "opxs-api", "").await?;
+        migrator.migrate().await?;
+
+        let clock = Arc::new(ClockUtc {});
+        let tsid_provider = Arc::new(Mutex::new(TsidProviderImpl::new(ClockUtc, RandomBytesProviderImpl::new(), 16)));
+        let email_auth_repo = EmailAuthRepo {
+            db: db.clone(),
+            clock: clock.clone(),
+            tsid_provider,
+        };
+        let user_service = UserService {
+            user_repo: Arc::new(UserRepo { db, clock }),
+        };
+
+        let user_id = email_auth_repo.create_user("user_name", "user_email", "hash", &UserLocale::Ja).await?;
+
+        // default profile
+        let profile = user_service.get_profile(&user_id).await?;
+        assert_eq!(profile.display_name, None);
+        assert_eq!(profile.locale, UserLocale::Ja);
+        assert_eq!(profile.time_zone, "UTC");
+
+        // update profile
+        let update = UserProfileUpdate {
+            display_name: Some(" Display Name ".to_string()),
+            locale: Some(UserLocale::En),
+            time_zone: Some("Asia/Tokyo".to_string()),
+        };
+        let profile = user_service.update_profile(&user_id, &update).await?;
+        assert_eq!(profile.display_name, Some("Display Name".to_string()));
+        assert_eq!(profile.locale, UserLocale::En);
+        assert_eq!(profile.time_zone, "Asia/Tokyo");
+
+        // partial update keeps the other fields
+        let update = UserProfileUpdate {
+            display_name: Some("".to_string()),
+            ..Default::default()
+        };
+        let profile = user_service.update_profile(&user_id, &update).await?;
+        assert_eq!(profile.display_name, None);
+        assert_eq!(profile.locale, UserLocale::En);
+
+        // invalid time zone
+        let update = UserProfileUpdate {
+            time_zone: Some("Mars/Olympus_Mons".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            *user_service.update_profile(&user_id, &update).await.unwrap_err().kind(),
+            ErrorKind::InvalidRequest
+        );
+
+        // the uploaded avatar stays pending until its conversion finishes
+        user_service.set_pending_avatar(&user_id, "avatar_job_id").await?;
+        let profile = user_service.get_profile(&user_id).await?;
+        assert_eq!(profile.avatar_job_id, None);
+        assert_eq!(profile.pending_avatar_job_id, Some("avatar_job_id".to_string()));
+
+        assert_eq!(*user_service.get_profile("unknown").await.unwrap_err().kind(), ErrorKind::NotFound);
+
+        Ok(())
+    }
 }
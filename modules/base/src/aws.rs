@@ -0,0 +1,9 @@
+mod s3;
+mod s3_mock;
+mod sqs;
+mod sqs_mock;
+
+pub use s3::*;
+pub use s3_mock::*;
+pub use sqs::*;
+pub use sqs_mock::*;
@@ -0,0 +1,87 @@
+use std::{collections::HashMap, time::SystemTime};
+
+use async_trait::async_trait;
+use aws_sdk_s3::presigning::PresigningConfig;
+use chrono::{DateTime, Duration, Utc};
+
+use omnius_core_base::error::OmniErrorBuilder as _;
+use omnius_core_cloud::{Error, ErrorKind, Result};
+
+/// Object metadata set by a presigned upload and read back by the consumer of the object.
+#[async_trait]
+pub trait S3ObjectMetadataClient {
+    async fn gen_put_presigned_uri_with_metadata(
+        &self,
+        key: &str,
+        start_time: DateTime<Utc>,
+        expires_in: Duration,
+        metadata: &HashMap<String, String>,
+    ) -> Result<String>;
+    async fn get_object_metadata(&self, key: &str) -> Result<HashMap<String, String>>;
+}
+
+pub struct S3ObjectMetadataClientImpl {
+    pub client: aws_sdk_s3::Client,
+    pub bucket: String,
+}
+
+#[async_trait]
+impl S3ObjectMetadataClient for S3ObjectMetadataClientImpl {
+    async fn gen_put_presigned_uri_with_metadata(
+        &self,
+        key: &str,
+        start_time: DateTime<Utc>,
+        expires_in: Duration,
+        metadata: &HashMap<String, String>,
+    ) -> Result<String> {
+        let config = PresigningConfig::builder()
+            .start_time(SystemTime::from(start_time))
+            .expires_in(
+                expires_in
+                    .to_std()
+                    .map_err(|e| Error::builder().kind(ErrorKind::TimeError).source(e).build())?,
+            )
+            .build()
+            .map_err(|e| Error::builder().kind(ErrorKind::AwsError).source(e).build())?;
+
+        // S3 accepts `x-amz-meta-*` as signed query parameters, so the uploader only needs the URL and can not change the metadata.
+        let query: Vec<String> = metadata
+            .iter()
+            .map(|(k, v)| format!("x-amz-meta-{}={}", urlencoding::encode(k), urlencoding::encode(v)))
+            .collect();
+
+        let req = self
+            .client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .customize()
+            .mutate_request(move |req| {
+                if query.is_empty() {
+                    return;
+                }
+                let separator = if req.uri().contains('?') { "&" } else { "?" };
+                let uri = format!("{}{}{}", req.uri(), separator, query.join("&"));
+                if let Err(e) = req.set_uri(uri) {
+                    tracing::warn!(error = ?e, "failed to add object metadata to presigned uri");
+                }
+            })
+            .presigned(config)
+            .await
+            .map_err(|e| Error::builder().kind(ErrorKind::AwsError).message("s3 presign error").source(e).build())?;
+
+        Ok(req.uri().to_string())
+    }
+
+    async fn get_object_metadata(&self, key: &str) -> Result<HashMap<String, String>> {
+        let res = self.client.head_object().bucket(&self.bucket).key(key).send().await.map_err(|e| {
+            Error::builder()
+                .kind(ErrorKind::AwsError)
+                .message("s3 head object error")
+                .source(e)
+                .build()
+        })?;
+
+        Ok(res.metadata().cloned().unwrap_or_default())
+    }
+}
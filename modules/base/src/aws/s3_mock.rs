@@ -0,0 +1,74 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use parking_lot::Mutex;
+
+use omnius_core_base::error::OmniErrorBuilder as _;
+use omnius_core_cloud::{Error, ErrorKind, Result};
+
+use super::S3ObjectMetadataClient;
+
+pub struct S3ObjectMetadataClientMock {
+    pub gen_put_presigned_uri_with_metadata_inputs: Arc<Mutex<Vec<GenPutPresignedUriWithMetadataInput>>>,
+    pub gen_put_presigned_uri_with_metadata_outputs: Arc<Mutex<VecDeque<String>>>,
+    pub objects_metadata: Arc<Mutex<HashMap<String, HashMap<String, String>>>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GenPutPresignedUriWithMetadataInput {
+    pub key: String,
+    pub metadata: HashMap<String, String>,
+}
+
+#[async_trait]
+impl S3ObjectMetadataClient for S3ObjectMetadataClientMock {
+    async fn gen_put_presigned_uri_with_metadata(
+        &self,
+        key: &str,
+        _start_time: DateTime<Utc>,
+        _expires_in: Duration,
+        metadata: &HashMap<String, String>,
+    ) -> Result<String> {
+        self.gen_put_presigned_uri_with_metadata_inputs
+            .lock()
+            .push(GenPutPresignedUriWithMetadataInput {
+                key: key.to_string(),
+                metadata: metadata.clone(),
+            });
+        // The upload is not simulated, so the metadata is attached as if the object had been put.
+        self.objects_metadata.lock().insert(key.to_string(), metadata.clone());
+
+        self.gen_put_presigned_uri_with_metadata_outputs
+            .lock()
+            .pop_front()
+            .ok_or_else(|| Error::builder().kind(ErrorKind::NotFound).message("output is not set").build())
+    }
+
+    async fn get_object_metadata(&self, key: &str) -> Result<HashMap<String, String>> {
+        self.objects_metadata
+            .lock()
+            .get(key)
+            .cloned()
+            .ok_or_else(|| Error::builder().kind(ErrorKind::NotFound).message("object is not found").build())
+    }
+}
+
+impl S3ObjectMetadataClientMock {
+    pub fn new() -> Self {
+        Self {
+            gen_put_presigned_uri_with_metadata_inputs: Arc::new(Mutex::new(vec![])),
+            gen_put_presigned_uri_with_metadata_outputs: Arc::new(Mutex::new(VecDeque::new())),
+            objects_metadata: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+}
+
+impl Default for S3ObjectMetadataClientMock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,50 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use aws_sdk_sqs::types::MessageAttributeValue;
+
+use omnius_core_base::error::OmniErrorBuilder as _;
+use omnius_core_cloud::{Error, ErrorKind, Result};
+
+/// Sends a message with string message attributes, which carry metadata such as the trace context without changing the body.
+#[async_trait]
+pub trait SqsMessageSender {
+    async fn send_message(&self, body: &str, attributes: &HashMap<String, String>) -> Result<()>;
+}
+
+pub struct SqsMessageSenderImpl {
+    pub client: aws_sdk_sqs::Client,
+    pub queue_url: String,
+    pub delay_seconds: Option<i32>,
+}
+
+#[async_trait]
+impl SqsMessageSender for SqsMessageSenderImpl {
+    async fn send_message(&self, body: &str, attributes: &HashMap<String, String>) -> Result<()> {
+        let mut req = self
+            .client
+            .send_message()
+            .queue_url(&self.queue_url)
+            .message_body(body)
+            .set_delay_seconds(self.delay_seconds);
+
+        for (name, value) in attributes.iter() {
+            let value = MessageAttributeValue::builder()
+                .data_type("String")
+                .string_value(value)
+                .build()
+                .map_err(|e| Error::builder().kind(ErrorKind::AwsError).source(e).build())?;
+            req = req.message_attributes(name, value);
+        }
+
+        req.send().await.map_err(|e| {
+            Error::builder()
+                .kind(ErrorKind::AwsError)
+                .message("sqs send message error")
+                .source(e)
+                .build()
+        })?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,43 @@
+use std::{collections::HashMap, sync::Arc};
+
+use async_trait::async_trait;
+use parking_lot::Mutex;
+
+use omnius_core_cloud::Result;
+
+use super::SqsMessageSender;
+
+pub struct SqsMessageSenderMock {
+    pub send_message_inputs: Arc<Mutex<Vec<SendMessageInput>>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SendMessageInput {
+    pub body: String,
+    pub attributes: HashMap<String, String>,
+}
+
+#[async_trait]
+impl SqsMessageSender for SqsMessageSenderMock {
+    async fn send_message(&self, body: &str, attributes: &HashMap<String, String>) -> Result<()> {
+        self.send_message_inputs.lock().push(SendMessageInput {
+            body: body.to_string(),
+            attributes: attributes.clone(),
+        });
+        Ok(())
+    }
+}
+
+impl SqsMessageSenderMock {
+    pub fn new() -> Self {
+        Self {
+            send_message_inputs: Arc::new(Mutex::new(vec![])),
+        }
+    }
+}
+
+impl Default for SqsMessageSenderMock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
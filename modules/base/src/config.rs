@@ -1,4 +1,9 @@
-use std::env;
+use std::{
+    env,
+    net::{IpAddr, SocketAddr},
+    path::PathBuf,
+    str::FromStr,
+};
 
 use aws_config::BehaviorVersion;
 
@@ -11,10 +16,13 @@ use super::info::RunMode;
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct AppConfig {
     pub postgres: PostgresConfig,
+    pub server: ServerConfig,
     pub web: WebConfig,
     pub auth: AuthConfig,
     pub email: EmailConfig,
     pub image: ImageConfig,
+    pub data_export: DataExportConfig,
+    pub rate_limit: RateLimitConfig,
     pub notify: Option<NotifyConfig>,
 }
 
@@ -23,9 +31,59 @@ pub struct PostgresConfig {
     pub url: String,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerConfig {
+    pub mode: ServeMode,
+    pub bind_address: SocketAddr,
+    pub tls: Option<TlsConfig>,
+    /// Reverse proxies whose `X-Forwarded-For` hops are believed when resolving the client address.
+    /// The header is client-controlled, so it is ignored unless the connecting peer is listed here.
+    pub trusted_proxies: Vec<IpAddr>,
+    /// Separate plain HTTP listener serving `/metrics`. Disabled when unset, so metrics are never exposed on the public listener.
+    pub metrics_bind_address: Option<SocketAddr>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServeMode {
+    Http,
+    Lambda,
+}
+
+impl FromStr for ServeMode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "http" => Ok(ServeMode::Http),
+            "lambda" => Ok(ServeMode::Lambda),
+            _ => Err(Error::builder()
+                .kind(ErrorKind::InvalidFormat)
+                .message(format!("invalid serve mode: {s}"))
+                .build()),
+        }
+    }
+}
+
+/// PEM encoded certificate chain and private key for serving HTTPS directly.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TlsConfig {
+    pub cert_path: PathBuf,
+    pub key_path: PathBuf,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct WebConfig {
     pub origin: String,
+    pub cors: CorsConfig,
+}
+
+/// Cross-origin policy for the web frontend. `origin` is always allowed in addition to `extra_origins`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorsConfig {
+    /// Allows any origin, method and header without credentials. Intended for local development only.
+    pub permissive: bool,
+    pub extra_origins: Vec<String>,
+    pub max_age_secs: u64,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -38,6 +96,10 @@ pub struct JwtSecretConfig {
 pub struct AuthConfig {
     pub jwt: JwtConfig,
     pub google: GoogleAuthConfig,
+    pub github: Option<GitHubAuthConfig>,
+    pub oidc: Vec<OidcAuthConfig>,
+    pub password: PasswordPolicyConfig,
+    pub unregister: UnregisterConfig,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -51,6 +113,37 @@ pub struct GoogleAuthConfig {
     pub client_secret: String,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitHubAuthConfig {
+    pub client_id: String,
+    pub client_secret: String,
+}
+
+/// Generic OpenID Connect provider resolved from `{issuer_url}/.well-known/openid-configuration`.
+/// `name` is used as the provider type and as the route segment (`/api/v1/auth/{name}/`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OidcAuthConfig {
+    pub name: String,
+    pub issuer_url: String,
+    pub client_id: String,
+    pub client_secret: String,
+}
+
+/// `min_score` is on the zxcvbn 0-4 scale.
+/// `breached_password_dir` points to local Pwned Passwords range files (`<SHA-1 prefix>.txt`), see `BreachedPasswordList`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PasswordPolicyConfig {
+    pub min_length: usize,
+    pub min_score: u8,
+    pub breached_password_dir: Option<String>,
+}
+
+/// Unregistered accounts stay restorable by logging in for `grace_period_days`, then they are purged.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnregisterConfig {
+    pub grace_period_days: i64,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct EmailConfig {
     pub from_email_address: String,
@@ -76,6 +169,36 @@ pub struct ImageConfig {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ImageConvertConfig {
     pub s3: Option<S3Config>,
+    pub sqs: Option<SqsConfig>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataExportConfig {
+    pub s3: Option<S3Config>,
+    pub sqs: Option<SqsConfig>,
+}
+
+/// Token buckets applied per route group; a missing bucket disables that dimension.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RateLimitConfig {
+    pub auth: RateLimitPolicyConfig,
+    pub email_resend: RateLimitPolicyConfig,
+    pub file_convert_upload: RateLimitPolicyConfig,
+}
+
+/// `per_email` keys on the target address of unauthenticated requests such as a confirmation resend.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RateLimitPolicyConfig {
+    pub per_ip: Option<TokenBucketConfig>,
+    pub per_user: Option<TokenBucketConfig>,
+    pub per_email: Option<TokenBucketConfig>,
+}
+
+/// Holds up to `capacity` requests and regains `refill_per_minute` of them every minute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenBucketConfig {
+    pub capacity: u32,
+    pub refill_per_minute: u32,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -108,8 +231,20 @@ impl AppConfig {
 
             return Ok(Self {
                 postgres: PostgresConfig { url: postgres_url },
+                server: ServerConfig {
+                    mode: ServeMode::Http,
+                    bind_address: SocketAddr::from(([0, 0, 0, 0], 8080)),
+                    tls: None,
+                    trusted_proxies: vec![],
+                    metrics_bind_address: None,
+                },
                 web: WebConfig {
                     origin: "https://localhost.omnius-labs.com/".to_string(),
+                    cors: CorsConfig {
+                        permissive: true,
+                        extra_origins: vec![],
+                        max_age_secs: 600,
+                    },
                 },
                 auth: AuthConfig {
                     jwt: JwtConfig {
@@ -122,6 +257,29 @@ impl AppConfig {
                         client_id: env::var("GOOGLE_AUTH_CLIENT_ID").unwrap_or_else(|_| "".to_string()),
                         client_secret: env::var("GOOGLE_AUTH_CLIENT_SECRET").unwrap_or_else(|_| "".to_string()),
                     },
+                    github: match (env::var("GITHUB_AUTH_CLIENT_ID"), env::var("GITHUB_AUTH_CLIENT_SECRET")) {
+                        (Ok(client_id), Ok(client_secret)) => Some(GitHubAuthConfig { client_id, client_secret }),
+                        _ => None,
+                    },
+                    oidc: match (
+                        env::var("MICROSOFT_AUTH_TENANT_ID"),
+                        env::var("MICROSOFT_AUTH_CLIENT_ID"),
+                        env::var("MICROSOFT_AUTH_CLIENT_SECRET"),
+                    ) {
+                        (Ok(tenant_id), Ok(client_id), Ok(client_secret)) => vec![OidcAuthConfig {
+                            name: "microsoft".to_string(),
+                            issuer_url: format!("https://login.microsoftonline.com/{tenant_id}/v2.0"),
+                            client_id,
+                            client_secret,
+                        }],
+                        _ => vec![],
+                    },
+                    password: PasswordPolicyConfig {
+                        min_length: 8,
+                        min_score: 2,
+                        breached_password_dir: env::var("BREACHED_PASSWORD_DIR").ok(),
+                    },
+                    unregister: UnregisterConfig { grace_period_days: 1 },
                 },
                 email: EmailConfig {
                     from_email_address: "Opxs <no-reply@opxs-dev.omnius-labs.com>".to_string(),
@@ -129,7 +287,40 @@ impl AppConfig {
                     ses: None,
                 },
                 image: ImageConfig {
-                    convert: ImageConvertConfig { s3: None },
+                    convert: ImageConvertConfig { s3: None, sqs: None },
+                },
+                data_export: DataExportConfig { s3: None, sqs: None },
+                rate_limit: RateLimitConfig {
+                    auth: RateLimitPolicyConfig {
+                        per_ip: Some(TokenBucketConfig {
+                            capacity: 100,
+                            refill_per_minute: 100,
+                        }),
+                        per_user: None,
+                        per_email: None,
+                    },
+                    email_resend: RateLimitPolicyConfig {
+                        per_ip: Some(TokenBucketConfig {
+                            capacity: 100,
+                            refill_per_minute: 100,
+                        }),
+                        per_user: None,
+                        per_email: Some(TokenBucketConfig {
+                            capacity: 100,
+                            refill_per_minute: 100,
+                        }),
+                    },
+                    file_convert_upload: RateLimitPolicyConfig {
+                        per_ip: Some(TokenBucketConfig {
+                            capacity: 100,
+                            refill_per_minute: 100,
+                        }),
+                        per_user: Some(TokenBucketConfig {
+                            capacity: 100,
+                            refill_per_minute: 100,
+                        }),
+                        per_email: None,
+                    },
                 },
                 notify: None,
             });
@@ -146,6 +337,11 @@ impl AppConfig {
         let jwt_secret_retired = secret_value.get_str("jwt_secret_retired")?;
         let auth_google_client_id = secret_value.get_str("auth_google_client_id")?;
         let auth_google_client_secret = secret_value.get_str("auth_google_client_secret")?;
+        let auth_github_client_id = secret_value.get_str("auth_github_client_id").ok();
+        let auth_github_client_secret = secret_value.get_str("auth_github_client_secret").ok();
+        let auth_microsoft_tenant_id = secret_value.get_str("auth_microsoft_tenant_id").ok();
+        let auth_microsoft_client_id = secret_value.get_str("auth_microsoft_client_id").ok();
+        let auth_microsoft_client_secret = secret_value.get_str("auth_microsoft_client_secret").ok();
         let discord_release_webhook_url = secret_value.get_str("discord_release_webhook_url")?;
 
         match info.mode {
@@ -163,8 +359,20 @@ impl AppConfig {
 
                 Ok(Self {
                     postgres: PostgresConfig { url: postgres_url },
+                    server: ServerConfig {
+                        mode: ServeMode::Lambda,
+                        bind_address: SocketAddr::from(([0, 0, 0, 0], 8080)),
+                        tls: None,
+                        trusted_proxies: vec![],
+                        metrics_bind_address: None,
+                    },
                     web: WebConfig {
                         origin: "https://opxs-dev.omnius-labs.com/".to_string(),
+                        cors: CorsConfig {
+                            permissive: false,
+                            extra_origins: vec!["https://localhost.omnius-labs.com".to_string(), "http://localhost:3000".to_string()],
+                            max_age_secs: 600,
+                        },
                     },
                     auth: AuthConfig {
                         jwt: JwtConfig {
@@ -177,6 +385,25 @@ impl AppConfig {
                             client_id: auth_google_client_id,
                             client_secret: auth_google_client_secret,
                         },
+                        github: match (auth_github_client_id, auth_github_client_secret) {
+                            (Some(client_id), Some(client_secret)) => Some(GitHubAuthConfig { client_id, client_secret }),
+                            _ => None,
+                        },
+                        oidc: match (auth_microsoft_tenant_id, auth_microsoft_client_id, auth_microsoft_client_secret) {
+                            (Some(tenant_id), Some(client_id), Some(client_secret)) => vec![OidcAuthConfig {
+                                name: "microsoft".to_string(),
+                                issuer_url: format!("https://login.microsoftonline.com/{tenant_id}/v2.0"),
+                                client_id,
+                                client_secret,
+                            }],
+                            _ => vec![],
+                        },
+                        password: PasswordPolicyConfig {
+                            min_length: 10,
+                            min_score: 3,
+                            breached_password_dir: env::var("BREACHED_PASSWORD_DIR").ok(),
+                        },
+                        unregister: UnregisterConfig { grace_period_days: 30 },
                     },
                     email: EmailConfig {
                         from_email_address: "Opxs <no-reply@opxs-dev.omnius-labs.com>".to_string(),
@@ -192,6 +419,49 @@ impl AppConfig {
                             s3: Some(S3Config {
                                 bucket: "opxs.v1.dev.file-convert".to_string(),
                             }),
+                            sqs: Some(SqsConfig {
+                                queue_url: "opxs-batch-file-convert-sqs".to_string(),
+                            }),
+                        },
+                    },
+                    data_export: DataExportConfig {
+                        s3: Some(S3Config {
+                            bucket: "opxs.v1.dev.data-export".to_string(),
+                        }),
+                        sqs: Some(SqsConfig {
+                            queue_url: "opxs-batch-data-export-sqs".to_string(),
+                        }),
+                    },
+                    rate_limit: RateLimitConfig {
+                        auth: RateLimitPolicyConfig {
+                            per_ip: Some(TokenBucketConfig {
+                                capacity: 20,
+                                refill_per_minute: 10,
+                            }),
+                            per_user: None,
+                            per_email: None,
+                        },
+                        email_resend: RateLimitPolicyConfig {
+                            per_ip: Some(TokenBucketConfig {
+                                capacity: 10,
+                                refill_per_minute: 2,
+                            }),
+                            per_user: None,
+                            per_email: Some(TokenBucketConfig {
+                                capacity: 3,
+                                refill_per_minute: 1,
+                            }),
+                        },
+                        file_convert_upload: RateLimitPolicyConfig {
+                            per_ip: Some(TokenBucketConfig {
+                                capacity: 60,
+                                refill_per_minute: 30,
+                            }),
+                            per_user: Some(TokenBucketConfig {
+                                capacity: 30,
+                                refill_per_minute: 10,
+                            }),
+                            per_email: None,
                         },
                     },
                     notify: Some(NotifyConfig {
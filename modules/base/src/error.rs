@@ -197,6 +197,36 @@ impl From<reqwest::Error> for Error {
     }
 }
 
+impl From<prometheus::Error> for Error {
+    fn from(e: prometheus::Error) -> Self {
+        Error::builder()
+            .kind(ErrorKind::UnexpectedError)
+            .message("metrics error")
+            .source(e)
+            .build()
+    }
+}
+
+impl From<tracing_subscriber::util::TryInitError> for Error {
+    fn from(e: tracing_subscriber::util::TryInitError) -> Self {
+        Error::builder()
+            .kind(ErrorKind::UnexpectedError)
+            .message("tracing init error")
+            .source(e)
+            .build()
+    }
+}
+
+impl From<opentelemetry_otlp::ExporterBuildError> for Error {
+    fn from(e: opentelemetry_otlp::ExporterBuildError) -> Self {
+        Error::builder()
+            .kind(ErrorKind::UnexpectedError)
+            .message("otlp exporter error")
+            .source(e)
+            .build()
+    }
+}
+
 impl From<omnius_core_cloud::Error> for Error {
     fn from(e: omnius_core_cloud::Error) -> Self {
         match e.kind() {
@@ -1,8 +1,12 @@
+pub mod aws;
 mod config;
 mod error;
 mod info;
+pub mod metrics;
 mod prelude;
 pub mod shared;
+pub mod telemetry;
+pub mod testkit;
 pub mod util;
 mod world;
 
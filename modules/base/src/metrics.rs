@@ -0,0 +1,132 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use prometheus::{Encoder as _, TextEncoder, proto::MetricType};
+use serde_json::{Map, Value, json};
+
+use crate::prelude::*;
+
+pub fn render() -> Result<String> {
+    let mut buf = Vec::new();
+    TextEncoder::new().encode(&prometheus::gather(), &mut buf)?;
+    String::from_utf8(buf).map_err(|e| Error::builder().kind(ErrorKind::InvalidFormat).message("invalid utf-8").source(e).build())
+}
+
+/// Writes the default registry as CloudWatch Embedded Metric Format (EMF) log lines.
+/// Batches have no scrape endpoint, so counters and histograms are reported as deltas since the previous emission.
+pub struct EmfEmitter {
+    namespace: String,
+    last_values: Mutex<HashMap<String, f64>>,
+}
+
+impl EmfEmitter {
+    pub fn new(namespace: &str) -> Self {
+        Self {
+            namespace: namespace.to_string(),
+            last_values: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn emit(&self, now: &DateTime<Utc>) {
+        for doc in self.collect(now) {
+            println!("{doc}");
+        }
+    }
+
+    pub fn collect(&self, now: &DateTime<Utc>) -> Vec<Value> {
+        let mut last_values = self.last_values.lock();
+        let mut docs = Vec::new();
+
+        for family in prometheus::gather() {
+            let name = family.get_name();
+            for metric in family.get_metric() {
+                let labels: Vec<(String, String)> = metric
+                    .get_label()
+                    .iter()
+                    .map(|n| (n.get_name().to_string(), n.get_value().to_string()))
+                    .collect();
+
+                let values = match family.get_field_type() {
+                    MetricType::COUNTER => vec![(name.to_string(), "Count", metric.get_counter().get_value(), true)],
+                    MetricType::GAUGE => vec![(name.to_string(), "None", metric.get_gauge().get_value(), false)],
+                    MetricType::HISTOGRAM => vec![
+                        (format!("{name}_sum"), "Seconds", metric.get_histogram().get_sample_sum(), true),
+                        (format!("{name}_count"), "Count", metric.get_histogram().get_sample_count() as f64, true),
+                    ],
+                    _ => continue,
+                };
+
+                for (metric_name, unit, mut value, cumulative) in values {
+                    if cumulative {
+                        let prev = last_values.insert(Self::series_key(&metric_name, &labels), value).unwrap_or(0.0);
+                        value -= prev;
+                        if value == 0.0 {
+                            continue;
+                        }
+                    }
+                    docs.push(self.to_doc(now, &metric_name, unit, &labels, value));
+                }
+            }
+        }
+
+        docs
+    }
+
+    fn series_key(name: &str, labels: &[(String, String)]) -> String {
+        let labels: Vec<String> = labels.iter().map(|(k, v)| format!("{k}={v}")).collect();
+        format!("{name}{{{}}}", labels.join(","))
+    }
+
+    fn to_doc(&self, now: &DateTime<Utc>, name: &str, unit: &str, labels: &[(String, String)], value: f64) -> Value {
+        let dimensions: Vec<&str> = labels.iter().map(|(k, _)| k.as_str()).collect();
+        let mut doc = Map::new();
+        doc.insert(
+            "_aws".to_string(),
+            json!({
+                "Timestamp": now.timestamp_millis(),
+                "CloudWatchMetrics": [{
+                    "Namespace": self.namespace,
+                    "Dimensions": [dimensions],
+                    "Metrics": [{ "Name": name, "Unit": unit }],
+                }],
+            }),
+        );
+        for (k, v) in labels {
+            doc.insert(k.clone(), Value::String(v.clone()));
+        }
+        doc.insert(name.to_string(), json!(value));
+        Value::Object(doc)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use prometheus::{IntCounterVec, register_int_counter_vec};
+
+    use super::*;
+
+    #[test]
+    fn emf_test() {
+        let counter: IntCounterVec = register_int_counter_vec!("emf_test_total", "test counter", &["status"]).unwrap();
+        let emitter = EmfEmitter::new("opxs/test");
+        let now = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+
+        counter.with_label_values(&["ok"]).inc_by(3);
+        let docs: Vec<Value> = emitter.collect(&now).into_iter().filter(|n| n.get("emf_test_total").is_some()).collect();
+        assert_eq!(docs.len(), 1);
+        assert_eq!(docs[0]["emf_test_total"], json!(3.0));
+        assert_eq!(docs[0]["status"], json!("ok"));
+        assert_eq!(docs[0]["_aws"]["Timestamp"], json!(1_700_000_000_000i64));
+        assert_eq!(docs[0]["_aws"]["CloudWatchMetrics"][0]["Dimensions"], json!([["status"]]));
+
+        // Only the increase since the previous emission is reported, and unchanged series are skipped.
+        counter.with_label_values(&["ok"]).inc();
+        let docs: Vec<Value> = emitter.collect(&now).into_iter().filter(|n| n.get("emf_test_total").is_some()).collect();
+        assert_eq!(docs[0]["emf_test_total"], json!(1.0));
+        let docs: Vec<Value> = emitter.collect(&now).into_iter().filter(|n| n.get("emf_test_total").is_some()).collect();
+        assert!(docs.is_empty());
+
+        assert!(render().unwrap().contains("emf_test_total{status=\"ok\"} 4"));
+    }
+}
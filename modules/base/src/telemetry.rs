@@ -0,0 +1,175 @@
+use std::{collections::HashMap, env};
+
+use axum::http::HeaderMap;
+use opentelemetry::{global, trace::TracerProvider as _};
+use opentelemetry_otlp::{SpanExporter, WithExportConfig as _};
+use opentelemetry_sdk::{Resource, propagation::TraceContextPropagator, trace::SdkTracerProvider};
+use tracing::Span;
+use tracing_opentelemetry::OpenTelemetrySpanExt as _;
+use tracing_subscriber::{EnvFilter, fmt, layer::SubscriberExt as _, util::SubscriberInitExt as _};
+
+use crate::prelude::*;
+
+const PROPAGATION_HEADERS: [&str; 2] = ["traceparent", "tracestate"];
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct TelemetryConfig {
+    pub otlp_endpoint: Option<String>,
+}
+
+impl TelemetryConfig {
+    pub fn from_env() -> Self {
+        let otlp_endpoint = env::var("OTEL_EXPORTER_OTLP_ENDPOINT").ok().filter(|n| !n.is_empty());
+        Self { otlp_endpoint }
+    }
+}
+
+pub struct Telemetry {
+    provider: Option<SdkTracerProvider>,
+}
+
+impl Telemetry {
+    /// Installs the global tracing subscriber. Spans are exported over OTLP/HTTP only when an endpoint is configured.
+    pub fn init(service_name: &str, conf: &TelemetryConfig) -> Result<Self> {
+        global::set_text_map_propagator(TraceContextPropagator::new());
+
+        let provider = match &conf.otlp_endpoint {
+            Some(endpoint) => Some(build_provider(service_name, endpoint)?),
+            None => None,
+        };
+        let otel_layer = provider
+            .as_ref()
+            .map(|n| tracing_opentelemetry::layer().with_tracer(n.tracer(service_name.to_string())));
+
+        let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info,sqlx=off"));
+        let registry = tracing_subscriber::registry().with(filter).with(otel_layer);
+        if cfg!(debug_assertions) {
+            registry.with(fmt::layer().with_target(false)).try_init()?;
+        } else {
+            registry.with(fmt::layer().with_target(false).json()).try_init()?;
+        }
+
+        Ok(Self { provider })
+    }
+
+    /// Lambda may freeze the process between invocations, so batches flush before returning.
+    pub fn flush(&self) {
+        if let Some(provider) = &self.provider
+            && let Err(e) = provider.force_flush()
+        {
+            warn!(error = ?e, "trace flush failed");
+        }
+    }
+
+    pub fn shutdown(&self) {
+        if let Some(provider) = &self.provider
+            && let Err(e) = provider.shutdown()
+        {
+            warn!(error = ?e, "trace shutdown failed");
+        }
+    }
+}
+
+fn build_provider(service_name: &str, endpoint: &str) -> Result<SdkTracerProvider> {
+    let exporter = SpanExporter::builder()
+        .with_http()
+        .with_endpoint(format!("{}/v1/traces", endpoint.trim_end_matches('/')))
+        .build()?;
+
+    Ok(SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(Resource::builder().with_service_name(service_name.to_string()).build())
+        .build())
+}
+
+/// Returns the W3C trace context of the current span, to be carried in SQS message attributes or S3 object metadata.
+pub fn current_trace_context() -> HashMap<String, String> {
+    let cx = Span::current().context();
+    let mut carrier = HashMap::new();
+    global::get_text_map_propagator(|n| n.inject_context(&cx, &mut carrier));
+    carrier
+}
+
+pub fn set_parent(span: &Span, carrier: &HashMap<String, String>) {
+    if carrier.is_empty() {
+        return;
+    }
+    let cx = global::get_text_map_propagator(|n| n.extract(carrier));
+    span.set_parent(cx);
+}
+
+pub fn set_parent_from_headers(span: &Span, headers: &HeaderMap) {
+    let carrier = trace_context_from(headers.iter().filter_map(|(k, v)| v.to_str().ok().map(|v| (k.as_str(), v))));
+    set_parent(span, &carrier);
+}
+
+/// Picks the trace context out of carriers that also hold unrelated entries, such as message attributes or object metadata.
+pub fn trace_context_from<'a>(entries: impl IntoIterator<Item = (&'a str, &'a str)>) -> HashMap<String, String> {
+    entries
+        .into_iter()
+        .filter(|(k, _)| PROPAGATION_HEADERS.contains(k))
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    };
+
+    use axum::{Router, extract::State, routing::post};
+    use opentelemetry::trace::TraceContextExt as _;
+    use testresult::TestResult;
+    use tracing::info_span;
+
+    use super::*;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn simple_test() -> TestResult {
+        // Stands in for an OpenTelemetry collector by counting OTLP/HTTP trace exports.
+        let received = Arc::new(AtomicUsize::new(0));
+        let app = Router::new()
+            .route(
+                "/v1/traces",
+                post(|State(received): State<Arc<AtomicUsize>>| async move {
+                    received.fetch_add(1, Ordering::SeqCst);
+                }),
+            )
+            .with_state(received.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let endpoint = format!("http://{}", listener.local_addr()?);
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        global::set_text_map_propagator(TraceContextPropagator::new());
+        let provider = build_provider("test", &endpoint)?;
+        let subscriber = tracing_subscriber::registry().with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+
+        let (carrier, parent_trace_id, child_trace_id) = tracing::subscriber::with_default(subscriber, || {
+            let parent = info_span!("producer");
+            let carrier = parent.in_scope(current_trace_context);
+
+            let child = info_span!("consumer");
+            set_parent(&child, &carrier);
+
+            (
+                carrier,
+                parent.context().span().span_context().trace_id(),
+                child.context().span().span_context().trace_id(),
+            )
+        });
+
+        assert!(carrier.contains_key("traceparent"));
+        assert_eq!(parent_trace_id, child_trace_id);
+
+        let mut attributes = carrier.clone();
+        attributes.insert("content-type".to_string(), "image/png".to_string());
+        assert_eq!(trace_context_from(attributes.iter().map(|(k, v)| (k.as_str(), v.as_str()))), carrier);
+
+        tokio::task::spawn_blocking(move || provider.force_flush()).await??;
+        assert!(received.load(Ordering::SeqCst) > 0);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,3 @@
+mod clock;
+
+pub use clock::*;
@@ -0,0 +1,29 @@
+use chrono::{DateTime, Duration, Utc};
+use parking_lot::Mutex;
+
+use omnius_core_base::clock::Clock;
+
+/// A clock that stays at the time set by the test.
+pub struct ClockMock {
+    now: Mutex<DateTime<Utc>>,
+}
+
+impl ClockMock {
+    pub fn new(now: DateTime<Utc>) -> Self {
+        Self { now: Mutex::new(now) }
+    }
+
+    pub fn set(&self, now: DateTime<Utc>) {
+        *self.now.lock() = now;
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        *self.now.lock() += duration;
+    }
+}
+
+impl Clock<Utc> for ClockMock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.now.lock()
+    }
+}
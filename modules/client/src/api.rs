@@ -0,0 +1,6 @@
+pub mod admin;
+pub mod api_key;
+pub mod auth;
+pub mod file_convert;
+pub mod health;
+pub mod user;
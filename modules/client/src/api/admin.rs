@@ -0,0 +1,119 @@
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    OpxsClient,
+    model::{AdminAuditLog, AdminUser, AdminUserDetail, EmailSendJobBatchDetail, FileConvertJob, UserRole},
+    prelude::*,
+};
+
+/// Endpoints under `/api/v2/admin`. Each one requires a permission granted to the admin role.
+pub struct AdminApi<'a> {
+    pub(crate) client: &'a OpxsClient,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct UserJobsOutput {
+    pub file_convert_jobs: Vec<FileConvertJob>,
+}
+
+#[derive(Serialize)]
+struct UpdateRoleInput {
+    role: UserRole,
+}
+
+impl AdminApi<'_> {
+    /// Matches `query` against the user id exactly, and against the name and email partially.
+    pub async fn search_users(&self, query: &str, limit: Option<i64>) -> Result<Vec<AdminUser>> {
+        let res = self
+            .client
+            .send(Method::GET, "/api/v2/admin/users", |n| {
+                n.query(&[("query", query)]).query(&[("limit", limit)])
+            })
+            .await?;
+        OpxsClient::json(res).await
+    }
+
+    pub async fn get_user(&self, user_id: &str) -> Result<AdminUserDetail> {
+        let res = self.client.send(Method::GET, &format!("/api/v2/admin/users/{user_id}"), |n| n).await?;
+        OpxsClient::json(res).await
+    }
+
+    pub async fn get_user_jobs(&self, user_id: &str) -> Result<UserJobsOutput> {
+        let res = self
+            .client
+            .send(Method::GET, &format!("/api/v2/admin/users/{user_id}/jobs"), |n| n)
+            .await?;
+        OpxsClient::json(res).await
+    }
+
+    pub async fn force_logout(&self, user_id: &str) -> Result<()> {
+        self.client
+            .send(Method::POST, &format!("/api/v2/admin/users/{user_id}/logout"), |n| n)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn disable_user(&self, user_id: &str) -> Result<()> {
+        self.client
+            .send(Method::POST, &format!("/api/v2/admin/users/{user_id}/disable"), |n| n)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn enable_user(&self, user_id: &str) -> Result<()> {
+        self.client
+            .send(Method::POST, &format!("/api/v2/admin/users/{user_id}/enable"), |n| n)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn update_role(&self, user_id: &str, role: UserRole) -> Result<()> {
+        self.client
+            .send(Method::PUT, &format!("/api/v2/admin/users/{user_id}/role"), |n| {
+                n.json(&UpdateRoleInput { role })
+            })
+            .await?;
+        Ok(())
+    }
+
+    pub async fn failed_file_convert_jobs(&self, limit: Option<i64>) -> Result<Vec<FileConvertJob>> {
+        let res = self
+            .client
+            .send(Method::GET, "/api/v2/admin/jobs/file-convert/failed", |n| n.query(&[("limit", limit)]))
+            .await?;
+        OpxsClient::json(res).await
+    }
+
+    pub async fn retry_file_convert_job(&self, job_id: &str) -> Result<()> {
+        self.client
+            .send(Method::POST, &format!("/api/v2/admin/jobs/file-convert/{job_id}/retry"), |n| n)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn failed_email_send_jobs(&self, limit: Option<i64>) -> Result<Vec<EmailSendJobBatchDetail>> {
+        let res = self
+            .client
+            .send(Method::GET, "/api/v2/admin/jobs/email-send/failed", |n| n.query(&[("limit", limit)]))
+            .await?;
+        OpxsClient::json(res).await
+    }
+
+    pub async fn retry_email_send_job(&self, job_id: &str) -> Result<()> {
+        self.client
+            .send(Method::POST, &format!("/api/v2/admin/jobs/email-send/{job_id}/retry"), |n| n)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn audit_logs(&self, target_id: Option<&str>, limit: Option<i64>) -> Result<Vec<AdminAuditLog>> {
+        let res = self
+            .client
+            .send(Method::GET, "/api/v2/admin/audit-logs", |n| {
+                n.query(&[("target_id", target_id)]).query(&[("limit", limit)])
+            })
+            .await?;
+        OpxsClient::json(res).await
+    }
+}
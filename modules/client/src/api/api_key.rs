@@ -0,0 +1,38 @@
+use chrono::NaiveDateTime;
+use reqwest::Method;
+use serde::Serialize;
+
+use crate::{
+    OpxsClient,
+    model::{ApiKey, ApiKeyScope, IssuedApiKey},
+    prelude::*,
+};
+
+pub struct ApiKeyApi<'a> {
+    pub(crate) client: &'a OpxsClient,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CreateInput {
+    pub name: String,
+    pub scopes: Vec<ApiKeyScope>,
+    pub expires_at: Option<NaiveDateTime>,
+}
+
+impl ApiKeyApi<'_> {
+    pub async fn list(&self) -> Result<Vec<ApiKey>> {
+        let res = self.client.send(Method::GET, "/api/v2/api-keys", |n| n).await?;
+        OpxsClient::json(res).await
+    }
+
+    /// The secret of the issued key is returned only once.
+    pub async fn create(&self, input: &CreateInput) -> Result<IssuedApiKey> {
+        let res = self.client.send(Method::POST, "/api/v2/api-keys", |n| n.json(input)).await?;
+        OpxsClient::json(res).await
+    }
+
+    pub async fn revoke(&self, api_key_id: &str) -> Result<()> {
+        self.client.send(Method::DELETE, &format!("/api/v2/api-keys/{api_key_id}"), |n| n).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,240 @@
+use reqwest::{Method, header};
+use serde::Serialize;
+
+use crate::{
+    OpxsClient,
+    model::{AuthToken, ProviderNonce, User, UserAuthMethod, UserLocale},
+    prelude::*,
+};
+
+const OAUTH2_STATE_COOKIE: &str = "oauth2_state";
+
+/// Session, email and OAuth provider endpoints under `/api/v2/auth`.
+/// `provider` is `google` or one of the configured providers such as `github`.
+/// The authorization code of a provider is obtained by the caller, e.g. through a browser redirect.
+pub struct AuthApi<'a> {
+    pub(crate) client: &'a OpxsClient,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RegisterInput {
+    pub name: String,
+    pub email: String,
+    pub password: String,
+    pub locale: UserLocale,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LoginInput {
+    pub email: String,
+    pub password: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LinkInput {
+    pub email: String,
+    pub password: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChangePasswordInput {
+    pub current_password: String,
+    pub new_password: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChangeInput {
+    pub new_email: String,
+    pub password: String,
+}
+
+/// Completes a provider sign-in with the `code` and `state` the provider redirected back with.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderInput {
+    pub redirect_uri: String,
+    pub code: String,
+    pub state: String,
+}
+
+#[derive(Serialize)]
+struct TokenInput<'a> {
+    token: &'a str,
+}
+
+#[derive(Serialize)]
+struct EmailInput<'a> {
+    email: &'a str,
+}
+
+impl AuthApi<'_> {
+    pub async fn me(&self) -> Result<User> {
+        let res = self.client.send(Method::GET, "/api/v2/auth/me", |n| n).await?;
+        OpxsClient::json(res).await
+    }
+
+    pub async fn methods(&self) -> Result<Vec<UserAuthMethod>> {
+        let res = self.client.send(Method::GET, "/api/v2/auth/methods", |n| n).await?;
+        OpxsClient::json(res).await
+    }
+
+    pub async fn refresh(&self) -> Result<AuthToken> {
+        self.client.refresh_token().await
+    }
+
+    /// Revokes every session of the user and clears the local one.
+    pub async fn logout(&self) -> Result<()> {
+        self.client.send(Method::DELETE, "/api/v2/auth/token", |n| n).await?;
+        self.client.set_token(None).await;
+        Ok(())
+    }
+
+    pub async fn email_register(&self, input: &RegisterInput) -> Result<()> {
+        self.client
+            .send_public(Method::POST, "/api/v2/auth/email/register", |n| n.json(input))
+            .await?;
+        Ok(())
+    }
+
+    /// Confirms the registration and starts a session.
+    pub async fn email_confirm(&self, token: &str) -> Result<AuthToken> {
+        let res = self
+            .client
+            .send_public(Method::POST, "/api/v2/auth/email/confirm", |n| n.json(&TokenInput { token }))
+            .await?;
+        self.start_session(res).await
+    }
+
+    pub async fn email_resend(&self, email: &str) -> Result<()> {
+        self.client
+            .send_public(Method::POST, "/api/v2/auth/email/resend", |n| n.json(&EmailInput { email }))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn email_unregister(&self) -> Result<()> {
+        self.client.send(Method::POST, "/api/v2/auth/email/unregister", |n| n).await?;
+        Ok(())
+    }
+
+    /// Signs in and starts a session.
+    pub async fn email_login(&self, input: &LoginInput) -> Result<AuthToken> {
+        let res = self
+            .client
+            .send_public(Method::POST, "/api/v2/auth/email/login", |n| n.json(input))
+            .await?;
+        self.start_session(res).await
+    }
+
+    pub async fn email_unlock(&self, token: &str) -> Result<()> {
+        self.client
+            .send_public(Method::POST, "/api/v2/auth/email/unlock", |n| n.json(&TokenInput { token }))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn email_link(&self, input: &LinkInput) -> Result<()> {
+        self.client.send(Method::POST, "/api/v2/auth/email/link", |n| n.json(input)).await?;
+        Ok(())
+    }
+
+    pub async fn email_unlink(&self) -> Result<()> {
+        self.client.send(Method::DELETE, "/api/v2/auth/email/link", |n| n).await?;
+        Ok(())
+    }
+
+    pub async fn email_change_password(&self, input: &ChangePasswordInput) -> Result<()> {
+        self.client.send(Method::PUT, "/api/v2/auth/email/password", |n| n.json(input)).await?;
+        Ok(())
+    }
+
+    pub async fn email_change(&self, input: &ChangeInput) -> Result<()> {
+        self.client.send(Method::POST, "/api/v2/auth/email/change", |n| n.json(input)).await?;
+        Ok(())
+    }
+
+    pub async fn email_change_confirm(&self, token: &str) -> Result<()> {
+        self.client
+            .send_public(Method::POST, "/api/v2/auth/email/change/confirm", |n| n.json(&TokenInput { token }))
+            .await?;
+        Ok(())
+    }
+
+    /// Starts a provider sign-in. The client keeps the state cookie issued with it for the next register, login or link.
+    pub async fn provider_nonce(&self, provider: &str) -> Result<ProviderNonce> {
+        let res = self
+            .client
+            .send_public(Method::GET, &format!("/api/v2/auth/{provider}/nonce"), |n| n)
+            .await?;
+        let cookie = res
+            .headers()
+            .get_all(header::SET_COOKIE)
+            .iter()
+            .filter_map(|n| n.to_str().ok()?.split(';').next())
+            .find(|n| n.starts_with(&format!("{OAUTH2_STATE_COOKIE}=")))
+            .map(|n| n.to_string());
+        *self.client.oauth2_state.lock().await = cookie;
+        OpxsClient::json(res).await
+    }
+
+    /// Creates an account for the provider identity and starts a session.
+    pub async fn provider_register(&self, provider: &str, input: &ProviderInput) -> Result<AuthToken> {
+        let cookie = self.take_oauth2_state().await?;
+        let res = self
+            .client
+            .send_public(Method::POST, &format!("/api/v2/auth/{provider}/register"), |n| {
+                n.header(header::COOKIE, &cookie).json(input)
+            })
+            .await?;
+        self.start_session(res).await
+    }
+
+    /// Signs in with the provider identity and starts a session.
+    pub async fn provider_login(&self, provider: &str, input: &ProviderInput) -> Result<AuthToken> {
+        let cookie = self.take_oauth2_state().await?;
+        let res = self
+            .client
+            .send_public(Method::POST, &format!("/api/v2/auth/{provider}/login"), |n| {
+                n.header(header::COOKIE, &cookie).json(input)
+            })
+            .await?;
+        self.start_session(res).await
+    }
+
+    pub async fn provider_link(&self, provider: &str, input: &ProviderInput) -> Result<()> {
+        let cookie = self.take_oauth2_state().await?;
+        self.client
+            .send(Method::POST, &format!("/api/v2/auth/{provider}/link"), |n| {
+                n.header(header::COOKIE, &cookie).json(input)
+            })
+            .await?;
+        Ok(())
+    }
+
+    pub async fn provider_unregister(&self, provider: &str) -> Result<()> {
+        self.client
+            .send(Method::POST, &format!("/api/v2/auth/{provider}/unregister"), |n| n)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn provider_unlink(&self, provider: &str) -> Result<()> {
+        self.client.send(Method::DELETE, &format!("/api/v2/auth/{provider}/link"), |n| n).await?;
+        Ok(())
+    }
+
+    // The server accepts the state cookie only once, so it is taken whether or not the request succeeds.
+    async fn take_oauth2_state(&self) -> Result<String> {
+        self.client.oauth2_state.lock().await.take().ok_or_else(|| {
+            Error::builder()
+                .kind(ErrorKind::Unauthorized)
+                .message("no provider sign-in in progress")
+                .build()
+        })
+    }
+
+    async fn start_session(&self, res: reqwest::Response) -> Result<AuthToken> {
+        let token: AuthToken = OpxsClient::json(res).await?;
+        self.client.set_token(Some(token.clone())).await;
+        Ok(token)
+    }
+}
@@ -0,0 +1,64 @@
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    OpxsClient,
+    model::{FileConvertImageInputFileType, FileConvertImageOutputFileType, FileConvertJobStatus},
+    prelude::*,
+};
+
+pub struct FileConvertApi<'a> {
+    pub(crate) client: &'a OpxsClient,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UploadInput {
+    pub in_file_name: String,
+    pub in_type: FileConvertImageInputFileType,
+    pub out_file_name: String,
+    pub out_type: FileConvertImageOutputFileType,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct UploadOutput {
+    pub job_id: String,
+    pub upload_url: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct StatusOutput {
+    pub status: FileConvertJobStatus,
+    pub download_url: Option<String>,
+}
+
+impl FileConvertApi<'_> {
+    /// Creates a conversion job. The job starts once the source file is put to `upload_url`.
+    pub async fn image_upload(&self, input: &UploadInput) -> Result<UploadOutput> {
+        let res = self
+            .client
+            .send(Method::POST, "/api/v2/file-convert/image/upload", |n| n.json(input))
+            .await?;
+        OpxsClient::json(res).await
+    }
+
+    pub async fn image_status(&self, job_id: &str) -> Result<StatusOutput> {
+        let res = self
+            .client
+            .send(Method::GET, "/api/v2/file-convert/image/status", |n| n.query(&[("job_id", job_id)]))
+            .await?;
+        OpxsClient::json(res).await
+    }
+
+    /// Puts the source file to a presigned upload URL. The URL carries its own authorization.
+    pub async fn upload_content(&self, upload_url: &str, content: Vec<u8>) -> Result<()> {
+        let res = self.client.http.put(upload_url).body(content).send().await?;
+        OpxsClient::check(res).await?;
+        Ok(())
+    }
+
+    pub async fn download_content(&self, download_url: &str) -> Result<Vec<u8>> {
+        let res = self.client.http.get(download_url).send().await?;
+        let res = OpxsClient::check(res).await?;
+        Ok(res.bytes().await?.to_vec())
+    }
+}
@@ -0,0 +1,40 @@
+use reqwest::{Method, StatusCode};
+use serde::Deserialize;
+
+use crate::{OpxsClient, model::HealthStatus, prelude::*};
+
+pub struct HealthApi<'a> {
+    pub(crate) client: &'a OpxsClient,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ReadinessReport {
+    pub status: HealthStatus,
+    pub components: Vec<ComponentHealth>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ComponentHealth {
+    pub name: String,
+    pub status: HealthStatus,
+    pub critical: bool,
+    pub latency_ms: u64,
+    pub message: Option<String>,
+}
+
+impl HealthApi<'_> {
+    pub async fn live(&self) -> Result<()> {
+        self.client.send_public(Method::GET, "/api/v2/health/live", |n| n).await?;
+        Ok(())
+    }
+
+    /// The report is returned also when the instance is not ready (503).
+    pub async fn ready(&self) -> Result<ReadinessReport> {
+        let res = self.client.request(false, Method::GET, "/api/v2/health/ready", |n| n).await?;
+        if res.status() == StatusCode::SERVICE_UNAVAILABLE {
+            return OpxsClient::json(res).await;
+        }
+        let res = OpxsClient::check(res).await?;
+        OpxsClient::json(res).await
+    }
+}
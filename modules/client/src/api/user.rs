@@ -0,0 +1,90 @@
+use chrono::NaiveDateTime;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    OpxsClient,
+    model::{DataExportJobStatus, FileConvertImageInputFileType, UserLocale, UserRole},
+    prelude::*,
+};
+
+pub struct UserApi<'a> {
+    pub(crate) client: &'a OpxsClient,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ProfileOutput {
+    pub id: String,
+    pub name: String,
+    pub role: UserRole,
+    pub display_name: Option<String>,
+    pub locale: UserLocale,
+    pub time_zone: String,
+    pub avatar_url: Option<String>,
+    pub updated_at: NaiveDateTime,
+}
+
+/// Fields left as `None` are not changed.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct UpdateProfileInput {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locale: Option<UserLocale>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_zone: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AvatarUploadInput {
+    pub in_file_name: String,
+    pub in_type: FileConvertImageInputFileType,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct AvatarUploadOutput {
+    pub job_id: String,
+    pub upload_url: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ExportOutput {
+    pub job_id: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ExportStatusOutput {
+    pub status: DataExportJobStatus,
+    pub download_url: Option<String>,
+}
+
+impl UserApi<'_> {
+    pub async fn me(&self) -> Result<ProfileOutput> {
+        let res = self.client.send(Method::GET, "/api/v2/users/me", |n| n).await?;
+        OpxsClient::json(res).await
+    }
+
+    pub async fn update_me(&self, input: &UpdateProfileInput) -> Result<ProfileOutput> {
+        let res = self.client.send(Method::PATCH, "/api/v2/users/me", |n| n.json(input)).await?;
+        OpxsClient::json(res).await
+    }
+
+    /// The image is uploaded to the returned URL, see `FileConvertApi::upload_content`.
+    pub async fn upload_avatar(&self, input: &AvatarUploadInput) -> Result<AvatarUploadOutput> {
+        let res = self.client.send(Method::POST, "/api/v2/users/me/avatar", |n| n.json(input)).await?;
+        OpxsClient::json(res).await
+    }
+
+    pub async fn create_export(&self) -> Result<ExportOutput> {
+        let res = self.client.send(Method::POST, "/api/v2/users/me/exports", |n| n).await?;
+        OpxsClient::json(res).await
+    }
+
+    pub async fn export_status(&self, job_id: &str) -> Result<ExportStatusOutput> {
+        let res = self
+            .client
+            .send(Method::GET, &format!("/api/v2/users/me/exports/{job_id}"), |n| n)
+            .await?;
+        OpxsClient::json(res).await
+    }
+}
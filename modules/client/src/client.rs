@@ -0,0 +1,358 @@
+use chrono::{Duration, Utc};
+use reqwest::{Method, RequestBuilder, Response, StatusCode, header::HeaderName};
+use serde::{Serialize, de::DeserializeOwned};
+use tokio::sync::Mutex;
+use url::Url;
+
+use crate::{
+    api::{admin::AdminApi, api_key::ApiKeyApi, auth::AuthApi, file_convert::FileConvertApi, health::HealthApi, user::UserApi},
+    model::{ApiErrorMessage, AuthToken},
+    prelude::*,
+};
+
+const API_KEY_HEADER: HeaderName = HeaderName::from_static("x-api-key");
+const TOKEN_REFRESH_PATH: &str = "/api/v2/auth/token";
+
+// Refresh slightly before the server-side expiry so that a token does not lapse while a request is in flight.
+const REFRESH_MARGIN: Duration = Duration::seconds(30);
+
+/// Typed client for the Opxs API.
+/// Requests are authenticated with the API key when one is set, otherwise with the access token of the current session.
+/// The access token is refreshed through `/api/v2/auth/token` when it is about to expire or is rejected with 401.
+pub struct OpxsClient {
+    pub(crate) http: reqwest::Client,
+    base_url: Url,
+    api_key: Option<String>,
+    token: Mutex<Option<AuthToken>>,
+    // Signed state cookie issued with a provider nonce, sent back when the sign-in completes.
+    pub(crate) oauth2_state: Mutex<Option<String>>,
+}
+
+#[derive(Serialize)]
+struct RefreshInput<'a> {
+    refresh_token: &'a str,
+}
+
+impl OpxsClient {
+    pub fn new(base_url: &str) -> Result<Self> {
+        Ok(Self {
+            http: reqwest::Client::new(),
+            base_url: Url::parse(base_url)?,
+            api_key: None,
+            token: Mutex::new(None),
+            oauth2_state: Mutex::new(None),
+        })
+    }
+
+    pub fn with_api_key(mut self, api_key: &str) -> Self {
+        self.api_key = Some(api_key.to_string());
+        self
+    }
+
+    pub async fn token(&self) -> Option<AuthToken> {
+        self.token.lock().await.clone()
+    }
+
+    /// Restores a session, e.g. one persisted by an internal tool between runs.
+    pub async fn set_token(&self, token: Option<AuthToken>) {
+        *self.token.lock().await = token;
+    }
+
+    pub fn auth(&self) -> AuthApi<'_> {
+        AuthApi { client: self }
+    }
+
+    pub fn file_convert(&self) -> FileConvertApi<'_> {
+        FileConvertApi { client: self }
+    }
+
+    pub fn user(&self) -> UserApi<'_> {
+        UserApi { client: self }
+    }
+
+    pub fn admin(&self) -> AdminApi<'_> {
+        AdminApi { client: self }
+    }
+
+    pub fn api_key(&self) -> ApiKeyApi<'_> {
+        ApiKeyApi { client: self }
+    }
+
+    pub fn health(&self) -> HealthApi<'_> {
+        HealthApi { client: self }
+    }
+
+    /// Exchanges the refresh token of the current session for a new token pair.
+    /// The session is cleared when the refresh token is rejected.
+    pub async fn refresh_token(&self) -> Result<AuthToken> {
+        let mut token = self.token.lock().await;
+        self.refresh_locked(&mut token).await
+    }
+
+    pub(crate) async fn send(&self, method: Method, path: &str, build: impl Fn(RequestBuilder) -> RequestBuilder) -> Result<Response> {
+        let res = self.request(true, method, path, build).await?;
+        Self::check(res).await
+    }
+
+    pub(crate) async fn send_public(&self, method: Method, path: &str, build: impl Fn(RequestBuilder) -> RequestBuilder) -> Result<Response> {
+        let res = self.request(false, method, path, build).await?;
+        Self::check(res).await
+    }
+
+    /// Sends the request without converting error statuses, for endpoints that return a body with them.
+    pub(crate) async fn request(
+        &self,
+        authorized: bool,
+        method: Method,
+        path: &str,
+        build: impl Fn(RequestBuilder) -> RequestBuilder,
+    ) -> Result<Response> {
+        let url = self.base_url.join(path)?;
+        let access_token = if authorized && self.api_key.is_none() {
+            self.access_token().await?
+        } else {
+            None
+        };
+
+        let res = self
+            .execute(build(self.http.request(method.clone(), url.clone())), authorized, access_token.as_deref())
+            .await?;
+        let Some(rejected) = access_token.filter(|_| res.status() == StatusCode::UNAUTHORIZED) else {
+            return Ok(res);
+        };
+
+        // The token can be revoked before its expiry, e.g. by a logout on another device. Retry once with a new one.
+        let access_token = self.refresh_rejected(&rejected).await?;
+        self.execute(build(self.http.request(method, url)), authorized, Some(&access_token)).await
+    }
+
+    pub(crate) async fn json<T: DeserializeOwned>(res: Response) -> Result<T> {
+        let bytes = res.bytes().await?;
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+
+    pub(crate) async fn check(res: Response) -> Result<Response> {
+        let status = res.status();
+        if status.is_success() {
+            return Ok(res);
+        }
+
+        let kind = match status {
+            StatusCode::UNAUTHORIZED => ErrorKind::Unauthorized,
+            _ => ErrorKind::ApiError,
+        };
+        let builder = Error::builder()
+            .kind(kind)
+            .message(format!("{} failed with {}", res.url().path(), status));
+        let bytes = res.bytes().await?;
+        match serde_json::from_slice::<ApiErrorMessage>(&bytes) {
+            Ok(v) => Err(builder.source(v).build()),
+            Err(_) => Err(builder.build()),
+        }
+    }
+
+    async fn execute(&self, req: RequestBuilder, authorized: bool, access_token: Option<&str>) -> Result<Response> {
+        let req = match (authorized, &self.api_key, access_token) {
+            (true, Some(api_key), _) => req.header(API_KEY_HEADER, api_key),
+            (true, None, Some(access_token)) => req.bearer_auth(access_token),
+            _ => req,
+        };
+        Ok(req.send().await?)
+    }
+
+    async fn access_token(&self) -> Result<Option<String>> {
+        let mut token = self.token.lock().await;
+        let Some(current) = token.as_ref() else {
+            return Ok(None);
+        };
+        if current.access_token_expires_at - REFRESH_MARGIN > Utc::now().naive_utc() {
+            return Ok(Some(current.access_token.clone()));
+        }
+
+        self.refresh_locked(&mut token).await.map(|n| Some(n.access_token))
+    }
+
+    async fn refresh_rejected(&self, rejected: &str) -> Result<String> {
+        let mut token = self.token.lock().await;
+        // Another request has already refreshed the token while this one was in flight.
+        if let Some(current) = token.as_ref()
+            && current.access_token != rejected
+        {
+            return Ok(current.access_token.clone());
+        }
+
+        self.refresh_locked(&mut token).await.map(|n| n.access_token)
+    }
+
+    async fn refresh_locked(&self, token: &mut Option<AuthToken>) -> Result<AuthToken> {
+        let Some(refresh_token) = token.as_ref().map(|n| n.refresh_token.clone()) else {
+            return Err(Error::builder().kind(ErrorKind::Unauthorized).message("no session to refresh").build());
+        };
+
+        let res = self
+            .http
+            .post(self.base_url.join(TOKEN_REFRESH_PATH)?)
+            .json(&RefreshInput {
+                refresh_token: &refresh_token,
+            })
+            .send()
+            .await?;
+        let refreshed: AuthToken = match Self::check(res).await {
+            Ok(res) => Self::json(res).await?,
+            Err(e) => {
+                if *e.kind() == ErrorKind::Unauthorized {
+                    *token = None;
+                }
+                return Err(e);
+            }
+        };
+
+        *token = Some(refreshed.clone());
+        Ok(refreshed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    };
+
+    use axum::{
+        Json, Router,
+        extract::State,
+        http::{HeaderMap, StatusCode as AxumStatusCode},
+        response::{IntoResponse, Response as AxumResponse},
+        routing::{get, post},
+    };
+    use serde_json::{Value, json};
+    use testresult::TestResult;
+
+    use crate::{
+        api::{auth::LoginInput, file_convert::UploadInput},
+        model::{FileConvertImageInputFileType, FileConvertImageOutputFileType, FileConvertJobStatus, UserLocale},
+    };
+
+    use super::*;
+
+    fn gen_token(access_token: &str, refresh_token: &str, expires_in: Duration) -> AuthToken {
+        let now = Utc::now().naive_utc();
+        AuthToken {
+            access_token: access_token.to_string(),
+            access_token_expires_at: now + expires_in,
+            refresh_token: refresh_token.to_string(),
+            refresh_token_expires_at: now + Duration::days(14),
+        }
+    }
+
+    fn unauthorized() -> AxumResponse {
+        let body = json!({ "type": "about:blank", "title": "Unauthorized", "status": 401, "error_code": "Unauthorized" });
+        (AxumStatusCode::UNAUTHORIZED, Json(body)).into_response()
+    }
+
+    #[tokio::test]
+    async fn refresh_test() -> TestResult {
+        // Stands in for the API: issues "fresh" on refresh and accepts only that access token.
+        let refreshed = Arc::new(AtomicUsize::new(0));
+        let app = Router::new()
+            .route(
+                "/api/v2/auth/token",
+                post(|State(refreshed): State<Arc<AtomicUsize>>, Json(input): Json<Value>| async move {
+                    if input["refresh_token"] == "revoked" {
+                        return unauthorized();
+                    }
+                    refreshed.fetch_add(1, Ordering::SeqCst);
+                    Json(gen_token("fresh", "rotated", Duration::minutes(5))).into_response()
+                }),
+            )
+            .route(
+                "/api/v2/auth/me",
+                get(|headers: HeaderMap| async move {
+                    if headers.get("authorization").is_none_or(|n| n != "Bearer fresh") {
+                        return unauthorized();
+                    }
+                    let now = Utc::now().naive_utc();
+                    Json(json!({ "id": "1", "name": "test", "role": "User", "locale": "ja", "created_at": now, "updated_at": now })).into_response()
+                }),
+            )
+            .with_state(refreshed.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let client = OpxsClient::new(&format!("http://{}", listener.local_addr()?))?;
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        // A revoked access token is rejected with 401, refreshed and the request is retried.
+        client.set_token(Some(gen_token("revoked", "valid", Duration::minutes(5)))).await;
+        assert_eq!(client.auth().me().await?.name, "test");
+        assert_eq!(refreshed.load(Ordering::SeqCst), 1);
+        assert_eq!(client.token().await.unwrap().refresh_token, "rotated");
+
+        // An access token close to its expiry is refreshed before the request is sent.
+        client.set_token(Some(gen_token("stale", "valid", Duration::seconds(10)))).await;
+        client.auth().me().await?;
+        assert_eq!(refreshed.load(Ordering::SeqCst), 2);
+
+        // A rejected refresh token ends the session.
+        client.set_token(Some(gen_token("stale", "revoked", Duration::seconds(10)))).await;
+        let e = client.auth().me().await.unwrap_err();
+        assert_eq!(*e.kind(), ErrorKind::Unauthorized);
+        assert_eq!(ApiErrorMessage::find(&e).unwrap().error_code, "Unauthorized");
+        assert!(client.token().await.is_none());
+
+        Ok(())
+    }
+
+    // Run with `cargo make e2e-test`, which starts the API in local mode, whose JWT secret is "current".
+    #[ignore]
+    #[tokio::test]
+    async fn local_e2e_test() -> TestResult {
+        let base_url = std::env::var("OPXS_API_URL").unwrap_or_else(|_| "http://localhost:8080".to_string());
+        let client = OpxsClient::new(&base_url)?;
+
+        let email = format!("e2e-{}@example.com", uuid::Uuid::new_v4().simple());
+        let password = "correct-horse-battery-staple".to_string();
+        client
+            .auth()
+            .email_register(&crate::api::auth::RegisterInput {
+                name: "e2e".to_string(),
+                email: email.clone(),
+                password: password.clone(),
+                locale: UserLocale::En,
+            })
+            .await?;
+
+        // The confirmation link is delivered by email, so the test signs the same token the API would have sent.
+        let now = Utc::now();
+        let confirm_token = omnius_opxs_auth::crypto::jwt::sign("current", &email, now + Duration::hours(1), now)?;
+        client.auth().email_confirm(&confirm_token).await?;
+        assert_eq!(client.auth().me().await?.name, "e2e");
+
+        client.set_token(None).await;
+        client.auth().email_login(&LoginInput { email, password }).await?;
+        assert!(client.auth().methods().await?.iter().any(|n| n.method_type == "email"));
+
+        let before = client.token().await.unwrap();
+        let refreshed = client.auth().refresh().await?;
+        assert_ne!(before.refresh_token, refreshed.refresh_token);
+
+        let upload = client
+            .file_convert()
+            .image_upload(&UploadInput {
+                in_file_name: "logo.png".to_string(),
+                in_type: FileConvertImageInputFileType::Png,
+                out_file_name: "logo.webp".to_string(),
+                out_type: FileConvertImageOutputFileType::WebP,
+            })
+            .await?;
+        let content = tokio::fs::read(concat!(env!("CARGO_MANIFEST_DIR"), "/../../docs/logo.png")).await?;
+        client.file_convert().upload_content(&upload.upload_url, content).await?;
+        let status = client.file_convert().image_status(&upload.job_id).await?;
+        assert_ne!(status.status, FileConvertJobStatus::Unknown);
+
+        client.auth().logout().await?;
+        let e = client.auth().me().await.unwrap_err();
+        assert_eq!(*e.kind(), ErrorKind::Unauthorized);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,140 @@
+use std::backtrace::Backtrace;
+
+use omnius_core_base::error::{OmniError, OmniErrorBuilder};
+
+pub struct Error {
+    kind: ErrorKind,
+    message: Option<String>,
+    source: Option<Box<dyn std::error::Error + Send + Sync>>,
+    backtrace: Option<Backtrace>,
+}
+
+pub struct ErrorBuilder {
+    inner: Error,
+}
+
+impl Error {
+    pub fn builder() -> ErrorBuilder {
+        ErrorBuilder {
+            inner: Self {
+                kind: ErrorKind::Unknown,
+                message: None,
+                source: None,
+                backtrace: None,
+            },
+        }
+    }
+}
+
+impl OmniError for Error {
+    type ErrorKind = ErrorKind;
+
+    fn kind(&self) -> &Self::ErrorKind {
+        &self.kind
+    }
+
+    fn message(&self) -> Option<&str> {
+        self.message.as_deref()
+    }
+
+    fn backtrace(&self) -> Option<&Backtrace> {
+        self.backtrace.as_ref()
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.source.as_ref().map(|s| &**s as &(dyn std::error::Error + 'static))
+    }
+}
+
+impl std::fmt::Debug for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        OmniError::fmt(self, f)
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        OmniError::fmt(self, f)
+    }
+}
+
+impl OmniErrorBuilder<Error> for ErrorBuilder {
+    type ErrorKind = ErrorKind;
+
+    fn kind(mut self, kind: Self::ErrorKind) -> Self {
+        self.inner.kind = kind;
+        self
+    }
+
+    fn message<S: Into<String>>(mut self, message: S) -> Self {
+        self.inner.message = Some(message.into());
+        self
+    }
+
+    fn source<E: Into<Box<dyn std::error::Error + Send + Sync>>>(mut self, source: E) -> Self {
+        self.inner.source = Some(source.into());
+        self
+    }
+
+    fn backtrace(mut self) -> Self {
+        self.inner.backtrace = Some(Backtrace::capture());
+        self
+    }
+
+    fn build(self) -> Error {
+        self.inner
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorKind {
+    Unknown,
+    SerdeError,
+    HttpClientError,
+
+    InvalidFormat,
+    Unauthorized,
+    ApiError,
+}
+
+impl std::fmt::Display for ErrorKind {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ErrorKind::Unknown => write!(fmt, "unknown"),
+            ErrorKind::SerdeError => write!(fmt, "serde error"),
+            ErrorKind::HttpClientError => write!(fmt, "http client error"),
+
+            ErrorKind::InvalidFormat => write!(fmt, "invalid format"),
+            ErrorKind::Unauthorized => write!(fmt, "unauthorized"),
+            ErrorKind::ApiError => write!(fmt, "api error"),
+        }
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::builder().kind(ErrorKind::SerdeError).message("serde json error").source(e).build()
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        Error::builder()
+            .kind(ErrorKind::HttpClientError)
+            .message("http client error")
+            .source(e)
+            .build()
+    }
+}
+
+impl From<url::ParseError> for Error {
+    fn from(e: url::ParseError) -> Self {
+        Error::builder()
+            .kind(ErrorKind::InvalidFormat)
+            .message("URL parse error")
+            .source(e)
+            .build()
+    }
+}
@@ -0,0 +1,14 @@
+pub mod api;
+mod client;
+mod error;
+pub mod model;
+mod prelude;
+
+mod result {
+    #[allow(unused)]
+    pub type Result<T> = std::result::Result<T, crate::error::Error>;
+}
+
+pub use client::*;
+pub use error::*;
+pub use result::*;
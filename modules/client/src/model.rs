@@ -0,0 +1,263 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+use crate::prelude::*;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuthToken {
+    pub access_token: String,
+    pub access_token_expires_at: NaiveDateTime,
+    pub refresh_token: String,
+    pub refresh_token_expires_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UserRole {
+    Admin,
+    User,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UserLocale {
+    #[default]
+    Ja,
+    En,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct User {
+    pub id: String,
+    pub name: String,
+    pub role: UserRole,
+    pub locale: UserLocale,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UserAuthMethod {
+    pub method_type: String,
+    pub identifier: String,
+    pub verified: bool,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ApiKeyScope {
+    FileConvert,
+    Account,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiKey {
+    pub id: String,
+    pub name: String,
+    pub prefix: String,
+    pub scopes: Vec<ApiKeyScope>,
+    pub expires_at: Option<NaiveDateTime>,
+    pub last_used_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IssuedApiKey {
+    pub api_key: ApiKey,
+    pub secret: String,
+}
+
+/// Issued when a sign-in with an OAuth provider starts. `state` and `value` (the OIDC nonce) go into the authorization request.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProviderNonce {
+    pub value: String,
+    pub state: String,
+    pub code_challenge: String,
+    pub code_challenge_method: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AdminUser {
+    pub id: String,
+    pub name: String,
+    pub role: UserRole,
+    pub email: Option<String>,
+    pub disabled_at: Option<NaiveDateTime>,
+    pub deletion_scheduled_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UserSession {
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub expires_at: NaiveDateTime,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AdminUserDetail {
+    pub user: AdminUser,
+    pub auth_methods: Vec<UserAuthMethod>,
+    pub api_keys: Vec<ApiKey>,
+    pub sessions: Vec<UserSession>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AdminAuditAction {
+    SearchUsers,
+    ViewUser,
+    ViewUserJobs,
+    ForceLogout,
+    DisableUser,
+    EnableUser,
+    UpdateUserRole,
+    ViewFailedJobs,
+    RetryFileConvertJob,
+    RetryEmailSendJob,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AdminAuditLog {
+    pub id: String,
+    pub operator_user_id: String,
+    pub action: AdminAuditAction,
+    pub target_id: Option<String>,
+    pub detail: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileConvertImageInputFileType {
+    Unknown,
+    Gif,
+    Jpg,
+    Png,
+    #[serde(rename = "webp")]
+    WebP,
+    Bmp,
+    Heif,
+    Heic,
+    Avif,
+    Svg,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileConvertImageOutputFileType {
+    Gif,
+    Jpg,
+    Png,
+    #[serde(rename = "webp")]
+    WebP,
+    Bmp,
+    Avif,
+    Svg,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FileConvertJobStatus {
+    Unknown,
+    Preparing,
+    Waiting,
+    Processing,
+    Completed,
+    Rejected,
+    Failed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FileConvertJobType {
+    Unknown,
+    Image,
+    Thumbnail,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileConvertJob {
+    pub id: String,
+    pub user_id: String,
+    pub typ: FileConvertJobType,
+    pub status: FileConvertJobStatus,
+    pub param: Option<String>,
+    pub in_file_name: String,
+    pub out_file_name: String,
+    pub failed_reason: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EmailSendJobBatchDetailStatus {
+    Unknown,
+    Preparing,
+    Waiting,
+    Processing,
+    Requested,
+    Completed,
+    Rejected,
+    Failed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EmailSendJobBatchDetail {
+    pub job_id: String,
+    pub batch_id: i32,
+    pub email_address: String,
+    pub retry_count: i32,
+    pub message_id: Option<String>,
+    pub status: EmailSendJobBatchDetailStatus,
+    pub failed_reason: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DataExportJobStatus {
+    Waiting,
+    Processing,
+    Completed,
+    Failed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthStatus {
+    Up,
+    Degraded,
+    Down,
+}
+
+/// Error body returned by the API (RFC 7807 `application/problem+json`). Carried as the source of `ErrorKind::ApiError`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiErrorMessage {
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    pub title: String,
+    pub status: u16,
+    pub error_code: String,
+    #[serde(default)]
+    pub details: Vec<ApiErrorDetail>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiErrorDetail {
+    pub field: String,
+    pub code: String,
+    pub message: Option<String>,
+}
+
+impl std::fmt::Display for ApiErrorMessage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} ({})", self.status, self.error_code, self.title)
+    }
+}
+
+impl std::error::Error for ApiErrorMessage {}
+
+impl ApiErrorMessage {
+    pub fn find(e: &Error) -> Option<&Self> {
+        std::error::Error::source(e).and_then(|v| v.downcast_ref::<Self>())
+    }
+}
@@ -0,0 +1,256 @@
+use std::{io::Write as _, path::Path, sync::Arc};
+
+use chrono::{Duration, Utc};
+use parking_lot::Mutex;
+use serde_json::json;
+use tempfile::tempdir;
+use tracing::info;
+use zip::{ZipWriter, write::SimpleFileOptions};
+
+use omnius_core_base::{clock::Clock, tsid::TsidProvider};
+use omnius_core_cloud::aws::s3::S3Client;
+
+use omnius_opxs_email_send::{EmailLocale, EmailSendJobCreator};
+
+use crate::{DataExportJobRepository, gen_archive_file_name, gen_archive_key, prelude::*};
+
+const DOWNLOAD_URL_EXPIRES_IN_DAYS: i64 = 7;
+
+pub struct DataExportExecutor {
+    pub data_export_job_repository: Arc<DataExportJobRepository>,
+    pub clock: Arc<dyn Clock<Utc> + Send + Sync>,
+    pub tsid_provider: Arc<Mutex<dyn TsidProvider + Send + Sync>>,
+    pub s3_client: Arc<dyn S3Client + Send + Sync>,
+    pub file_convert_s3_client: Arc<dyn S3Client + Send + Sync>,
+    pub email_send_job_creator: Arc<EmailSendJobCreator>,
+    pub from_email_address: String,
+}
+
+impl DataExportExecutor {
+    pub async fn execute(&self, job_ids: &[String]) -> Result<()> {
+        for job_id in job_ids.iter() {
+            info!("Start processing job: {}", job_id);
+
+            self.data_export_job_repository.update_status_to_processing(job_id).await?;
+
+            let res = self.execute_one(job_id).await;
+
+            if let Err(e) = res {
+                self.data_export_job_repository
+                    .update_status_to_failed(job_id, e.to_string().as_str())
+                    .await?;
+                continue;
+            }
+
+            self.data_export_job_repository.update_status_to_completed(job_id).await?;
+
+            self.notify(job_id).await?;
+        }
+        Ok(())
+    }
+
+    async fn execute_one(&self, job_id: &str) -> Result<()> {
+        let job = self.data_export_job_repository.get_job(job_id).await?;
+        let now = self.clock.now();
+        let expires_in = Duration::days(DOWNLOAD_URL_EXPIRES_IN_DAYS);
+
+        let mut converted_files = Vec::new();
+        for file in self.data_export_job_repository.get_converted_files(&job.user_id).await? {
+            let download_url = self
+                .file_convert_s3_client
+                .gen_get_presigned_uri(format!("out/{}", file.job_id).as_str(), now, expires_in, &file.out_file_name)
+                .await?;
+            converted_files.push(json!({
+                "job_id": file.job_id,
+                "file_name": file.out_file_name,
+                "download_url": download_url,
+            }));
+        }
+
+        let entries = [
+            ("user.json", self.data_export_job_repository.get_user(&job.user_id).await?),
+            ("auth_methods.json", self.data_export_job_repository.get_auth_methods(&job.user_id).await?),
+            ("sessions.json", self.data_export_job_repository.get_sessions(&job.user_id).await?),
+            (
+                "file_convert_jobs.json",
+                self.data_export_job_repository.get_file_convert_jobs(&job.user_id).await?,
+            ),
+            ("converted_files.json", serde_json::Value::Array(converted_files)),
+            (
+                "email_send_logs.json",
+                self.data_export_job_repository.get_email_send_logs(&job.user_id).await?,
+            ),
+        ];
+
+        let working_dir = tempdir()?;
+        let out_path = working_dir.path().join(gen_archive_file_name(job_id));
+
+        info!("Start writing archive: {}", job_id);
+
+        Self::write_archive(&out_path, &entries)?;
+
+        info!("Finish writing archive: {}", job_id);
+
+        self.s3_client.put_object(&gen_archive_key(job_id), &out_path).await?;
+
+        Ok(())
+    }
+
+    fn write_archive(path: &Path, entries: &[(&str, serde_json::Value)]) -> Result<()> {
+        let file = std::fs::File::create(path)?;
+        let mut writer = ZipWriter::new(file);
+        let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+
+        for (name, value) in entries {
+            writer.start_file(*name, options)?;
+            writer.write_all(&serde_json::to_vec_pretty(value)?)?;
+        }
+
+        writer.finish()?;
+
+        Ok(())
+    }
+
+    async fn notify(&self, job_id: &str) -> Result<()> {
+        let job = self.data_export_job_repository.get_job(job_id).await?;
+        let Some(recipient) = self.data_export_job_repository.get_recipient(&job.user_id).await? else {
+            return Ok(());
+        };
+
+        let now = self.clock.now();
+        let expires_in = Duration::days(DOWNLOAD_URL_EXPIRES_IN_DAYS);
+        let download_url = self
+            .s3_client
+            .gen_get_presigned_uri(&gen_archive_key(job_id), now, expires_in, &gen_archive_file_name(job_id))
+            .await?;
+
+        let locale = match recipient.locale.as_str() {
+            "En" => EmailLocale::En,
+            _ => EmailLocale::Ja,
+        };
+        let email_job_id = self.tsid_provider.lock().create().to_string();
+        self.email_send_job_creator
+            .create_data_export_ready_job(
+                &email_job_id,
+                &recipient.name,
+                &recipient.email,
+                &self.from_email_address,
+                &download_url,
+                DOWNLOAD_URL_EXPIRES_IN_DAYS,
+                &locale,
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::postgres::PgPoolOptions;
+    use testresult::TestResult;
+
+    use omnius_core_base::{clock::ClockUtc, random_bytes::RandomBytesProviderImpl, tsid::TsidProviderImpl};
+    use omnius_core_cloud::aws::{s3::S3ClientMock, sqs::SqsSenderMock};
+    use omnius_core_migration::postgres::PostgresMigrator;
+    use omnius_core_testkit::containers::postgres::PostgresContainer;
+
+    use omnius_opxs_base::{aws::SqsMessageSenderMock, shared::POSTGRES_VERSION};
+    use omnius_opxs_email_send::EmailSendJobRepository;
+
+    use crate::{DataExportJobCreator, DataExportJobStatus};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn simple_test() -> TestResult {
+        let container = PostgresContainer::new(POSTGRES_VERSION).await?;
+
+        let db = Arc::new(
+            PgPoolOptions::new()
+                .max_connections(100)
+                .idle_timeout(Some(Duration::minutes(15).to_std().unwrap()))
+                .connect(&container.connection_string)
+                .await
+                .unwrap(),
+        );
+        let clock = Arc::new(ClockUtc {});
+        let tsid_provider = Arc::new(Mutex::new(TsidProviderImpl::new(ClockUtc, RandomBytesProviderImpl::new(), 16)));
+        let s3_client = Arc::new(S3ClientMock::new());
+        let file_convert_s3_client = Arc::new(S3ClientMock::new());
+        let sqs_sender = Arc::new(SqsSenderMock::new());
+        let email_sqs_sender = Arc::new(SqsMessageSenderMock::new());
+
+        let migrations_path = concat!(env!("CARGO_MANIFEST_DIR"), "/../../conf/migrations");
+        let migrator = PostgresMigrator::new(&container.connection_string, migrations_path, "opxs-api", "")
+            .await
+            .unwrap();
+        migrator.migrate().await.unwrap();
+
+        sqlx::query(
+            r#"
+INSERT INTO users (id, name, authentication_type, role, locale, created_at, updated_at)
+    VALUES ('test_user_id', 'test_user', 'Email', 'User', 'En', now(), now());
+INSERT INTO user_auth_emails (user_id, email, password_hash, email_verified, created_at, updated_at)
+    VALUES ('test_user_id', 'test@example.com', 'secret_hash', true, now(), now());
+"#,
+        )
+        .execute(db.as_ref())
+        .await?;
+
+        let data_export_job_repository = Arc::new(DataExportJobRepository {
+            db: db.clone(),
+            clock: clock.clone(),
+        });
+
+        let job_creator = DataExportJobCreator {
+            data_export_job_repository: data_export_job_repository.clone(),
+            clock: clock.clone(),
+            s3_client: s3_client.clone(),
+            sqs_sender: sqs_sender.clone(),
+        };
+        let job_id = tsid_provider.lock().create().to_string();
+        assert_eq!(job_creator.create_job(&job_id, "test_user_id").await?, job_id);
+        let other_job_id = tsid_provider.lock().create().to_string();
+        assert_eq!(job_creator.create_job(&other_job_id, "test_user_id").await?, job_id);
+        assert_eq!(sqs_sender.send_message_inputs.lock().len(), 1);
+
+        let email_send_job_creator = Arc::new(EmailSendJobCreator {
+            email_send_job_repository: Arc::new(EmailSendJobRepository {
+                db: db.clone(),
+                clock: clock.clone(),
+            }),
+            sqs_sender: email_sqs_sender.clone(),
+        });
+        let executor = DataExportExecutor {
+            data_export_job_repository: data_export_job_repository.clone(),
+            clock: clock.clone(),
+            tsid_provider: tsid_provider.clone(),
+            s3_client: s3_client.clone(),
+            file_convert_s3_client: file_convert_s3_client.clone(),
+            email_send_job_creator,
+            from_email_address: "no-reply@example.com".to_string(),
+        };
+        s3_client
+            .gen_get_presigned_uri_outputs
+            .lock()
+            .push_back("https://get.s3.example.com".to_string());
+        executor.execute(&[job_id.clone()]).await?;
+
+        assert_eq!(
+            s3_client.put_object_inputs.lock().first().unwrap().key,
+            format!("out/{job_id}.zip").as_str()
+        );
+        assert_eq!(email_sqs_sender.send_message_inputs.lock().len(), 1);
+
+        s3_client
+            .gen_get_presigned_uri_outputs
+            .lock()
+            .push_back("https://get.s3.example.com".to_string());
+        let (status, download_url) = job_creator.get_download_url(&job_id, "test_user_id").await?;
+        assert_eq!(status, DataExportJobStatus::Completed);
+        assert_eq!(download_url, Some("https://get.s3.example.com".to_string()));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,58 @@
+use std::sync::Arc;
+
+use chrono::{Duration, Utc};
+
+use omnius_core_base::clock::Clock;
+use omnius_core_cloud::aws::{s3::S3Client, sqs::SqsSender};
+
+use crate::{DataExportJobSqsMessage, DataExportJobStatus, prelude::*};
+
+use super::DataExportJobRepository;
+
+pub struct DataExportJobCreator {
+    pub data_export_job_repository: Arc<DataExportJobRepository>,
+    pub clock: Arc<dyn Clock<Utc> + Send + Sync>,
+    pub s3_client: Arc<dyn S3Client + Send + Sync>,
+    pub sqs_sender: Arc<dyn SqsSender + Send + Sync>,
+}
+
+impl DataExportJobCreator {
+    /// Returns the id of the job that will produce the export. An export that is still in progress is reused.
+    pub async fn create_job(&self, job_id: &str, user_id: &str) -> Result<String> {
+        if let Some(job) = self.data_export_job_repository.get_pending_job(user_id).await? {
+            return Ok(job.id);
+        }
+
+        self.data_export_job_repository.create_job(job_id, user_id).await?;
+
+        let message = DataExportJobSqsMessage { job_id: job_id.to_string() };
+        self.sqs_sender.send_message(&serde_json::to_string(&message)?).await?;
+
+        Ok(job_id.to_string())
+    }
+
+    pub async fn get_download_url(&self, job_id: &str, user_id: &str) -> Result<(DataExportJobStatus, Option<String>)> {
+        let job = self.data_export_job_repository.get_job_by_user_id(job_id, user_id).await?;
+
+        if job.status != DataExportJobStatus::Completed {
+            return Ok((job.status, None));
+        }
+
+        let now = self.clock.now();
+        let expires_in = Duration::minutes(10);
+        let download_uri = self
+            .s3_client
+            .gen_get_presigned_uri(&gen_archive_key(job_id), now, expires_in, &gen_archive_file_name(job_id))
+            .await?;
+
+        Ok((job.status, Some(download_uri)))
+    }
+}
+
+pub(crate) fn gen_archive_key(job_id: &str) -> String {
+    format!("out/{job_id}.zip")
+}
+
+pub(crate) fn gen_archive_file_name(job_id: &str) -> String {
+    format!("opxs-export-{job_id}.zip")
+}
@@ -0,0 +1,5 @@
+mod job;
+mod sqs;
+
+pub use job::*;
+pub use sqs::*;
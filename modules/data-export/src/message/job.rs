@@ -0,0 +1,35 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "data_export_job_status")]
+pub enum DataExportJobStatus {
+    Waiting,
+    Processing,
+    Completed,
+    Failed,
+}
+
+#[derive(Serialize, Deserialize, Debug, sqlx::FromRow)]
+pub struct DataExportJob {
+    pub id: String,
+    pub user_id: String,
+    pub status: DataExportJobStatus,
+    pub failed_reason: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow)]
+pub struct DataExportRecipient {
+    pub name: String,
+    pub locale: String,
+    pub email: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct DataExportConvertedFile {
+    pub job_id: String,
+    pub out_file_name: String,
+}
@@ -0,0 +1,11 @@
+#[allow(unused)]
+pub use crate::error::{Error, ErrorKind};
+
+#[allow(unused)]
+pub use omnius_core_base::error::{OmniError as _, OmniErrorBuilder as _};
+
+#[allow(unused)]
+pub use crate::result::Result;
+
+#[allow(unused)]
+pub use tracing::{debug, error, info, trace, warn};
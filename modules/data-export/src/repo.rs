@@ -0,0 +1,298 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+use sqlx::PgPool;
+
+use omnius_core_base::clock::Clock;
+
+use crate::{DataExportConvertedFile, DataExportJob, DataExportJobStatus, DataExportRecipient, prelude::*};
+
+pub struct DataExportJobRepository {
+    pub db: Arc<PgPool>,
+    pub clock: Arc<dyn Clock<Utc> + Send + Sync>,
+}
+
+impl DataExportJobRepository {
+    pub async fn create_job(&self, job_id: &str, user_id: &str) -> Result<()> {
+        let now = self.clock.now();
+
+        sqlx::query(
+            r#"
+INSERT INTO data_export_jobs (id, user_id, status, created_at, updated_at)
+    VALUES ($1, $2, $3, $4, $4);
+"#,
+        )
+        .bind(job_id)
+        .bind(user_id)
+        .bind(DataExportJobStatus::Waiting)
+        .bind(now)
+        .execute(self.db.as_ref())
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_job(&self, id: &str) -> Result<DataExportJob> {
+        let res: Option<DataExportJob> = sqlx::query_as(
+            r#"
+SELECT *
+    FROM data_export_jobs
+    WHERE id = $1;
+"#,
+        )
+        .bind(id)
+        .fetch_optional(self.db.as_ref())
+        .await?;
+
+        res.ok_or_else(|| Error::builder().kind(ErrorKind::NotFound).message("job not found").build())
+    }
+
+    pub async fn get_job_by_user_id(&self, id: &str, user_id: &str) -> Result<DataExportJob> {
+        let res: Option<DataExportJob> = sqlx::query_as(
+            r#"
+SELECT *
+    FROM data_export_jobs
+    WHERE id = $1 AND user_id = $2;
+"#,
+        )
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(self.db.as_ref())
+        .await?;
+
+        res.ok_or_else(|| Error::builder().kind(ErrorKind::NotFound).message("job not found").build())
+    }
+
+    pub async fn get_pending_job(&self, user_id: &str) -> Result<Option<DataExportJob>> {
+        let res: Option<DataExportJob> = sqlx::query_as(
+            r#"
+SELECT *
+    FROM data_export_jobs
+    WHERE user_id = $1 AND status IN ('Waiting', 'Processing')
+    ORDER BY created_at DESC
+    LIMIT 1;
+"#,
+        )
+        .bind(user_id)
+        .fetch_optional(self.db.as_ref())
+        .await?;
+
+        Ok(res)
+    }
+
+    pub async fn update_status_to_processing(&self, job_id: &str) -> Result<()> {
+        self.update_status(job_id, DataExportJobStatus::Waiting, DataExportJobStatus::Processing)
+            .await
+    }
+
+    pub async fn update_status_to_completed(&self, job_id: &str) -> Result<()> {
+        self.update_status(job_id, DataExportJobStatus::Processing, DataExportJobStatus::Completed)
+            .await
+    }
+
+    async fn update_status(&self, job_id: &str, old_status: DataExportJobStatus, new_status: DataExportJobStatus) -> Result<()> {
+        let now = self.clock.now();
+
+        let res = sqlx::query(
+            r#"
+UPDATE data_export_jobs
+    SET status = $3, updated_at = $4
+    WHERE id = $1 AND status = $2;
+"#,
+        )
+        .bind(job_id)
+        .bind(old_status)
+        .bind(new_status)
+        .bind(now)
+        .execute(self.db.as_ref())
+        .await?;
+
+        if res.rows_affected() < 1 {
+            return Err(Error::builder().kind(ErrorKind::DatabaseError).message("no rows affected").build());
+        }
+
+        Ok(())
+    }
+
+    pub async fn update_status_to_failed(&self, job_id: &str, failed_reason: &str) -> Result<()> {
+        let now = self.clock.now();
+
+        let res = sqlx::query(
+            r#"
+UPDATE data_export_jobs
+    SET status = 'Failed', failed_reason = $2, updated_at = $3
+    WHERE id = $1 AND status = 'Processing';
+"#,
+        )
+        .bind(job_id)
+        .bind(failed_reason)
+        .bind(now)
+        .execute(self.db.as_ref())
+        .await?;
+
+        if res.rows_affected() < 1 {
+            return Err(Error::builder().kind(ErrorKind::DatabaseError).message("no rows affected").build());
+        }
+
+        Ok(())
+    }
+
+    // The export queries below select columns explicitly so that secrets (password hashes, token values, key hashes)
+    // never end up in the archive.
+
+    pub async fn get_user(&self, user_id: &str) -> Result<serde_json::Value> {
+        let res: Option<(serde_json::Value,)> = sqlx::query_as(
+            r#"
+SELECT row_to_json(t)
+    FROM (
+        SELECT id, name, role, display_name, locale, time_zone, created_at, updated_at
+            FROM users
+            WHERE id = $1
+    ) t;
+"#,
+        )
+        .bind(user_id)
+        .fetch_optional(self.db.as_ref())
+        .await?;
+
+        res.map(|(v,)| v)
+            .ok_or_else(|| Error::builder().kind(ErrorKind::NotFound).message("user not found").build())
+    }
+
+    pub async fn get_auth_methods(&self, user_id: &str) -> Result<serde_json::Value> {
+        let (res,): (serde_json::Value,) = sqlx::query_as(
+            r#"
+SELECT json_build_object(
+    'emails', (
+        SELECT COALESCE(json_agg(t ORDER BY t.created_at), '[]'::json)
+            FROM (
+                SELECT email, email_verified, created_at, updated_at
+                    FROM user_auth_emails
+                    WHERE user_id = $1
+            ) t
+    ),
+    'providers', (
+        SELECT COALESCE(json_agg(t ORDER BY t.created_at), '[]'::json)
+            FROM (
+                SELECT provider_type, provider_user_id, created_at
+                    FROM user_auth_providers
+                    WHERE user_id = $1
+            ) t
+    ),
+    'api_keys', (
+        SELECT COALESCE(json_agg(t ORDER BY t.created_at), '[]'::json)
+            FROM (
+                SELECT id, name, prefix, scopes, expires_at, last_used_at, created_at
+                    FROM api_keys
+                    WHERE user_id = $1
+            ) t
+    )
+);
+"#,
+        )
+        .bind(user_id)
+        .fetch_one(self.db.as_ref())
+        .await?;
+
+        Ok(res)
+    }
+
+    pub async fn get_sessions(&self, user_id: &str) -> Result<serde_json::Value> {
+        let (res,): (serde_json::Value,) = sqlx::query_as(
+            r#"
+SELECT COALESCE(json_agg(t ORDER BY t.created_at), '[]'::json)
+    FROM (
+        SELECT ip_address, user_agent, expires_at, created_at
+            FROM refresh_tokens
+            WHERE user_id = $1
+    ) t;
+"#,
+        )
+        .bind(user_id)
+        .fetch_one(self.db.as_ref())
+        .await?;
+
+        Ok(res)
+    }
+
+    pub async fn get_file_convert_jobs(&self, user_id: &str) -> Result<serde_json::Value> {
+        let (res,): (serde_json::Value,) = sqlx::query_as(
+            r#"
+SELECT COALESCE(json_agg(t ORDER BY t.created_at), '[]'::json)
+    FROM (
+        SELECT id, type, status, in_file_name, out_file_name, failed_reason, created_at, updated_at
+            FROM file_convert_jobs
+            WHERE user_id = $1
+    ) t;
+"#,
+        )
+        .bind(user_id)
+        .fetch_one(self.db.as_ref())
+        .await?;
+
+        Ok(res)
+    }
+
+    pub async fn get_converted_files(&self, user_id: &str) -> Result<Vec<DataExportConvertedFile>> {
+        let res: Vec<DataExportConvertedFile> = sqlx::query_as(
+            r#"
+SELECT id AS job_id, out_file_name
+    FROM file_convert_jobs
+    WHERE user_id = $1 AND status = 'Completed'
+    ORDER BY created_at;
+"#,
+        )
+        .bind(user_id)
+        .fetch_all(self.db.as_ref())
+        .await?;
+
+        Ok(res)
+    }
+
+    pub async fn get_email_send_logs(&self, user_id: &str) -> Result<serde_json::Value> {
+        let (res,): (serde_json::Value,) = sqlx::query_as(
+            r#"
+SELECT json_build_object(
+    'jobs', (
+        SELECT COALESCE(json_agg(t ORDER BY t.created_at), '[]'::json)
+            FROM (
+                SELECT j.type, d.email_address, d.status, d.failed_reason, d.created_at, d.updated_at
+                    FROM email_send_job_batch_details d
+                    JOIN email_send_jobs j ON j.id = d.job_id
+                    WHERE d.email_address IN (SELECT email FROM user_auth_emails WHERE user_id = $1)
+            ) t
+    ),
+    'events', (
+        SELECT COALESCE(json_agg(t ORDER BY t.created_at), '[]'::json)
+            FROM (
+                SELECT email_address, event_type, created_at
+                    FROM email_send_logs
+                    WHERE email_address IN (SELECT email FROM user_auth_emails WHERE user_id = $1)
+            ) t
+    )
+);
+"#,
+        )
+        .bind(user_id)
+        .fetch_one(self.db.as_ref())
+        .await?;
+
+        Ok(res)
+    }
+
+    pub async fn get_recipient(&self, user_id: &str) -> Result<Option<DataExportRecipient>> {
+        let res: Option<DataExportRecipient> = sqlx::query_as(
+            r#"
+SELECT u.name, u.locale::TEXT AS locale, e.email
+    FROM users u
+    JOIN user_auth_emails e ON u.id = e.user_id
+    WHERE u.id = $1 AND e.email_verified = true;
+"#,
+        )
+        .bind(user_id)
+        .fetch_optional(self.db.as_ref())
+        .await?;
+
+        Ok(res)
+    }
+}
@@ -160,7 +160,7 @@ impl From<serde_json::Error> for Error {
 impl From<std::env::VarError> for Error {
     fn from(e: std::env::VarError) -> Self {
         match e {
-            std::env::VarError::NotPresent => Error::builder().kind(ErrorKind::NotFound).message("not found env var").build(),
+            std::env::VarError::NotPresent => Error::builder().kind(ErrorKind::UnexpectedError).message("not found env var").build(),
             std::env::VarError::NotUnicode(_) => Error::builder().kind(ErrorKind::InvalidFormat).message("invalid utf-8").build(),
         }
     }
@@ -254,7 +254,7 @@ impl From<omnius_core_cloud::Error> for Error {
             omnius_core_cloud::ErrorKind::AwsError => Error::builder().kind(ErrorKind::AwsError).source(e).build(),
             omnius_core_cloud::ErrorKind::GcpError => Error::builder().kind(ErrorKind::GcpError).source(e).build(),
             omnius_core_cloud::ErrorKind::InvalidFormat => Error::builder().kind(ErrorKind::InvalidFormat).source(e).build(),
-            omnius_core_cloud::ErrorKind::NotFound => Error::builder().kind(ErrorKind::NotFound).source(e).build(),
+            omnius_core_cloud::ErrorKind::NotFound => Error::builder().kind(ErrorKind::UnexpectedError).source(e).build(),
         }
     }
 }
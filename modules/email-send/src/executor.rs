@@ -1,10 +1,16 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Instant};
 
 use omnius_core_cloud::aws::ses::SesSender;
+use tracing::{Instrument as _, info_span};
 
-use crate::prelude::*;
+use omnius_opxs_base::telemetry;
 
-use super::{EmailConfirmRequestParam, EmailSendJobBatchSqsMessage, EmailSendJobRepository, EmailSendJobType};
+use crate::{metrics, prelude::*};
+
+use super::{
+    AccountUnlockRequestParam, DataExportReadyRequestParam, EmailChangeConfirmRequestParam, EmailChangedNoticeRequestParam, EmailConfirmRequestParam,
+    EmailLocale, EmailSendJobBatchSqsMessage, EmailSendJobRepository, EmailSendJobType,
+};
 
 pub struct EmailSendExecutor {
     pub email_send_job_repository: Arc<EmailSendJobRepository>,
@@ -14,7 +20,17 @@ pub struct EmailSendExecutor {
 impl EmailSendExecutor {
     pub async fn execute(&self, ms: &[EmailSendJobBatchSqsMessage]) -> Result<()> {
         for m in ms.iter() {
-            self.execute_one(m).await?;
+            let span = info_span!("email_send_job", job_id = %m.job_id, batch_id = m.batch_id);
+            telemetry::set_parent(&span, &m.trace_context);
+
+            let start = Instant::now();
+            let res = self.execute_one(m).instrument(span).await;
+            let status = if res.is_ok() { "completed" } else { "failed" };
+            metrics::JOBS_TOTAL.with_label_values(&[status]).inc();
+            metrics::JOB_DURATION_SECONDS
+                .with_label_values(&[status])
+                .observe(start.elapsed().as_secs_f64());
+            res?;
         }
         Ok(())
     }
@@ -30,6 +46,34 @@ impl EmailSendExecutor {
                 let param = serde_json::from_str::<EmailConfirmRequestParam>(&param)?;
                 self.execute_email_confirm(&m.job_id, m.batch_id, &param).await
             }
+            EmailSendJobType::AccountUnlock => {
+                let param = job
+                    .param
+                    .ok_or_else(|| Error::builder().kind(ErrorKind::NotFound).message("param is not found").build())?;
+                let param = serde_json::from_str::<AccountUnlockRequestParam>(&param)?;
+                self.execute_account_unlock(&m.job_id, m.batch_id, &param).await
+            }
+            EmailSendJobType::EmailChangeConfirm => {
+                let param = job
+                    .param
+                    .ok_or_else(|| Error::builder().kind(ErrorKind::NotFound).message("param is not found").build())?;
+                let param = serde_json::from_str::<EmailChangeConfirmRequestParam>(&param)?;
+                self.execute_email_change_confirm(&m.job_id, m.batch_id, &param).await
+            }
+            EmailSendJobType::EmailChangedNotice => {
+                let param = job
+                    .param
+                    .ok_or_else(|| Error::builder().kind(ErrorKind::NotFound).message("param is not found").build())?;
+                let param = serde_json::from_str::<EmailChangedNoticeRequestParam>(&param)?;
+                self.execute_email_changed_notice(&m.job_id, m.batch_id, &param).await
+            }
+            EmailSendJobType::DataExportReady => {
+                let param = job
+                    .param
+                    .ok_or_else(|| Error::builder().kind(ErrorKind::NotFound).message("param is not found").build())?;
+                let param = serde_json::from_str::<DataExportReadyRequestParam>(&param)?;
+                self.execute_data_export_ready(&m.job_id, m.batch_id, &param).await
+            }
             _ => Err(Error::builder()
                 .kind(ErrorKind::UnsupportedType)
                 .message(format!("unsupported type: {:?}", job.typ))
@@ -42,9 +86,11 @@ impl EmailSendExecutor {
             .update_status_to_processing(job_id, batch_id, &param.to_email_address)
             .await?;
 
-        let subject = "Opxs: メールアドレスの確認をお願いします";
-        let body = &format!(
-            "\
+        let (subject, body) = match param.locale {
+            EmailLocale::Ja => (
+                "Opxs: メールアドレスの確認をお願いします",
+                format!(
+                    "\
 こんにちは、{user_name}様。
 
 Opxs へのご登録ありがとうございます。
@@ -60,17 +106,288 @@ Opxs へのご登録ありがとうございます。
 ありがとうございます。
 
 Opxs サポートチーム",
-            user_name = param.user_name,
-            email_confirm_url = param.email_confirm_url,
-        );
+                    user_name = param.user_name,
+                    email_confirm_url = param.email_confirm_url,
+                ),
+            ),
+            EmailLocale::En => (
+                "Opxs: Please confirm your email address",
+                format!(
+                    "\
+Hello {user_name},
 
-        let message_id = self
-            .ses_sender
-            .send_mail_simple_text(&param.to_email_address, &param.from_email_address, subject, body)
+Thank you for signing up for Opxs.
+
+Please click the link below to confirm your email address.
+
+{email_confirm_url}
+
+If you did not sign up for Opxs, please ignore this email.
+
+If you have any questions, please feel free to contact our support team.
+
+Thank you,
+
+The Opxs Support Team",
+                    user_name = param.user_name,
+                    email_confirm_url = param.email_confirm_url,
+                ),
+            ),
+        };
+
+        self.send_mail_simple_text(job_id, batch_id, &param.to_email_address, &param.from_email_address, subject, &body)
+            .await
+    }
+
+    async fn execute_account_unlock(&self, job_id: &str, batch_id: i32, param: &AccountUnlockRequestParam) -> Result<()> {
+        self.email_send_job_repository
+            .update_status_to_processing(job_id, batch_id, &param.to_email_address)
+            .await?;
+
+        let (subject, body) = match param.locale {
+            EmailLocale::Ja => (
+                "Opxs: アカウントがロックされました",
+                format!(
+                    "\
+こんにちは、{user_name}様。
+
+ログインの失敗が続いたため、お客様の Opxs アカウントを一時的にロックしました。
+
+ご自身による操作の場合は、以下のリンクをクリックしてロックを解除してください。
+
+{unlock_url}
+
+お心当たりがない場合は、第三者による不正なログインの可能性があります。パスワードの変更をご検討ください。
+
+ご不明点やお困りの点がございましたら、お気軽にサポートまでお問い合わせください。
+
+ありがとうございます。
+
+Opxs サポートチーム",
+                    user_name = param.user_name,
+                    unlock_url = param.unlock_url,
+                ),
+            ),
+            EmailLocale::En => (
+                "Opxs: Your account has been locked",
+                format!(
+                    "\
+Hello {user_name},
+
+Your Opxs account has been temporarily locked due to repeated failed sign-in attempts.
+
+If these attempts were made by you, please click the link below to unlock your account.
+
+{unlock_url}
+
+If you do not recognize this activity, someone else may be trying to sign in to your account. Please consider changing your password.
+
+If you have any questions, please feel free to contact our support team.
+
+Thank you,
+
+The Opxs Support Team",
+                    user_name = param.user_name,
+                    unlock_url = param.unlock_url,
+                ),
+            ),
+        };
+
+        self.send_mail_simple_text(job_id, batch_id, &param.to_email_address, &param.from_email_address, subject, &body)
+            .await
+    }
+
+    async fn execute_email_change_confirm(&self, job_id: &str, batch_id: i32, param: &EmailChangeConfirmRequestParam) -> Result<()> {
+        self.email_send_job_repository
+            .update_status_to_processing(job_id, batch_id, &param.to_email_address)
+            .await?;
+
+        let (subject, body) = match param.locale {
+            EmailLocale::Ja => (
+                "Opxs: 新しいメールアドレスの確認をお願いします",
+                format!(
+                    "\
+こんにちは、{user_name}様。
+
+Opxs アカウントのメールアドレスを、このアドレスに変更するリクエストを受け付けました。
+
+以下のリンクをクリックして、メールアドレスの変更を完了してください。
+
+{email_change_confirm_url}
+
+このメールに心当たりがない場合は、このメールを無視してください。メールアドレスは変更されません。
+
+ご不明点やお困りの点がございましたら、お気軽にサポートまでお問い合わせください。
+
+ありがとうございます。
+
+Opxs サポートチーム",
+                    user_name = param.user_name,
+                    email_change_confirm_url = param.email_change_confirm_url,
+                ),
+            ),
+            EmailLocale::En => (
+                "Opxs: Please confirm your new email address",
+                format!(
+                    "\
+Hello {user_name},
+
+We received a request to change the email address of your Opxs account to this address.
+
+Please click the link below to complete the change.
+
+{email_change_confirm_url}
+
+If you did not request this change, please ignore this email. Your email address will not be changed.
+
+If you have any questions, please feel free to contact our support team.
+
+Thank you,
+
+The Opxs Support Team",
+                    user_name = param.user_name,
+                    email_change_confirm_url = param.email_change_confirm_url,
+                ),
+            ),
+        };
+
+        self.send_mail_simple_text(job_id, batch_id, &param.to_email_address, &param.from_email_address, subject, &body)
+            .await
+    }
+
+    async fn execute_email_changed_notice(&self, job_id: &str, batch_id: i32, param: &EmailChangedNoticeRequestParam) -> Result<()> {
+        self.email_send_job_repository
+            .update_status_to_processing(job_id, batch_id, &param.to_email_address)
             .await?;
 
+        let (subject, body) = match param.locale {
+            EmailLocale::Ja => (
+                "Opxs: メールアドレスが変更されました",
+                format!(
+                    "\
+こんにちは、{user_name}様。
+
+お客様の Opxs アカウントのメールアドレスが {new_email_address} に変更されました。
+今後、このアドレスにはお知らせが届きません。
+
+お心当たりがない場合は、第三者による不正な操作の可能性があります。至急サポートまでお問い合わせください。
+
+ありがとうございます。
+
+Opxs サポートチーム",
+                    user_name = param.user_name,
+                    new_email_address = param.new_email_address,
+                ),
+            ),
+            EmailLocale::En => (
+                "Opxs: Your email address has been changed",
+                format!(
+                    "\
+Hello {user_name},
+
+The email address of your Opxs account has been changed to {new_email_address}.
+You will no longer receive notifications at this address.
+
+If you did not make this change, someone else may have access to your account. Please contact our support team immediately.
+
+Thank you,
+
+The Opxs Support Team",
+                    user_name = param.user_name,
+                    new_email_address = param.new_email_address,
+                ),
+            ),
+        };
+
+        self.send_mail_simple_text(job_id, batch_id, &param.to_email_address, &param.from_email_address, subject, &body)
+            .await
+    }
+
+    async fn execute_data_export_ready(&self, job_id: &str, batch_id: i32, param: &DataExportReadyRequestParam) -> Result<()> {
         self.email_send_job_repository
-            .set_message_id(job_id, batch_id, &param.to_email_address, message_id.as_str())
+            .update_status_to_processing(job_id, batch_id, &param.to_email_address)
+            .await?;
+
+        let (subject, body) = match param.locale {
+            EmailLocale::Ja => (
+                "Opxs: データのエクスポートが完了しました",
+                format!(
+                    "\
+こんにちは、{user_name}様。
+
+ご依頼いただいた Opxs アカウントデータのエクスポートが完了しました。
+
+以下のリンクから zip ファイルをダウンロードしてください。リンクの有効期限は {expires_in_days} 日間です。
+
+{download_url}
+
+このメールに心当たりがない場合は、第三者による不正な操作の可能性があります。パスワードの変更をご検討ください。
+
+ありがとうございます。
+
+Opxs サポートチーム",
+                    user_name = param.user_name,
+                    download_url = param.download_url,
+                    expires_in_days = param.expires_in_days,
+                ),
+            ),
+            EmailLocale::En => (
+                "Opxs: Your data export is ready",
+                format!(
+                    "\
+Hello {user_name},
+
+The export of your Opxs account data that you requested is ready.
+
+Please download the zip file from the link below. The link expires in {expires_in_days} days.
+
+{download_url}
+
+If you did not request this export, someone else may have access to your account. Please consider changing your password.
+
+Thank you,
+
+The Opxs Support Team",
+                    user_name = param.user_name,
+                    download_url = param.download_url,
+                    expires_in_days = param.expires_in_days,
+                ),
+            ),
+        };
+
+        self.send_mail_simple_text(job_id, batch_id, &param.to_email_address, &param.from_email_address, subject, &body)
+            .await
+    }
+
+    async fn send_mail_simple_text(
+        &self,
+        job_id: &str,
+        batch_id: i32,
+        to_email_address: &str,
+        from_email_address: &str,
+        subject: &str,
+        body: &str,
+    ) -> Result<()> {
+        let message_id = match self
+            .ses_sender
+            .send_mail_simple_text(to_email_address, from_email_address, subject, body)
+            .await
+        {
+            Ok(v) => {
+                metrics::SES_REQUESTS_TOTAL.with_label_values(&["accepted"]).inc();
+                v
+            }
+            Err(e) => {
+                metrics::SES_REQUESTS_TOTAL.with_label_values(&["rejected"]).inc();
+                self.email_send_job_repository
+                    .update_status_to_failed(job_id, batch_id, to_email_address, &e.to_string())
+                    .await?;
+                return Err(e.into());
+            }
+        };
+
+        self.email_send_job_repository
+            .set_message_id(job_id, batch_id, to_email_address, message_id.as_str())
             .await?;
 
         self.email_send_job_repository.update_status_to_requested(message_id.as_str()).await?;
@@ -91,13 +408,13 @@ mod tests {
         random_bytes::RandomBytesProviderImpl,
         tsid::{TsidProvider, TsidProviderImpl},
     };
-    use omnius_core_cloud::aws::{ses::SesSenderMock, sqs::SqsSenderMock};
+    use omnius_core_cloud::aws::ses::SesSenderMock;
     use omnius_core_migration::postgres::PostgresMigrator;
     use omnius_core_testkit::containers::postgres::PostgresContainer;
 
-    use omnius_opxs_base::shared::POSTGRES_VERSION;
+    use omnius_opxs_base::{aws::SqsMessageSenderMock, shared::POSTGRES_VERSION};
 
-    use crate::EmailSendJobCreator;
+    use crate::{EmailSendJobBatchDetailStatus, EmailSendJobCreator};
 
     use super::*;
 
@@ -122,9 +439,9 @@ mod tests {
             .unwrap();
         migrator.migrate().await.unwrap();
 
-        let email_send_job_repository = Arc::new(EmailSendJobRepository { db, clock });
+        let email_send_job_repository = Arc::new(EmailSendJobRepository { db: db.clone(), clock });
 
-        let send_email_sqs_sender = Arc::new(SqsSenderMock::new());
+        let send_email_sqs_sender = Arc::new(SqsMessageSenderMock::new());
 
         let job_id = tsid_provider.lock().create().to_string();
         let job_creator = EmailSendJobCreator {
@@ -132,22 +449,23 @@ mod tests {
             sqs_sender: send_email_sqs_sender.clone(),
         };
         job_creator
-            .create_job(
+            .create_email_confirm_job(
                 &job_id,
                 "test_name",
                 "lyrise1984@gmail.com",
                 "no-reply@opxs-dev.omnius-labs.com",
                 "https://example.com",
+                &EmailLocale::En,
             )
             .await
             .unwrap();
 
         let ses_sender = Arc::new(SesSenderMock::new());
         let sqs_send_message_input = send_email_sqs_sender.send_message_inputs.lock().first().cloned().unwrap();
-        let sqs_message = serde_json::from_str::<EmailSendJobBatchSqsMessage>(sqs_send_message_input.as_str()).unwrap();
+        let sqs_message = serde_json::from_str::<EmailSendJobBatchSqsMessage>(&sqs_send_message_input.body).unwrap();
 
         let executor = EmailSendExecutor {
-            email_send_job_repository,
+            email_send_job_repository: email_send_job_repository.clone(),
             ses_sender: ses_sender.clone(),
         };
         executor.execute(&[sqs_message]).await.unwrap();
@@ -159,9 +477,33 @@ mod tests {
             ses_send_mail_simple_text_input.from_address,
             "no-reply@opxs-dev.omnius-labs.com".to_string()
         );
+        assert_eq!(ses_send_mail_simple_text_input.subject, "Opxs: Please confirm your email address".to_string());
         println!("{}", ses_send_mail_simple_text_input.subject);
         println!("{}", ses_send_mail_simple_text_input.text_body);
 
+        // retry
+        assert_eq!(
+            *job_creator.retry_job(db.begin().await?, &job_id).await.unwrap_err().kind(),
+            ErrorKind::NotFound
+        );
+        sqlx::query("UPDATE email_send_job_batch_details SET status = 'Failed', failed_reason = 'test' WHERE job_id = $1")
+            .bind(&job_id)
+            .execute(db.as_ref())
+            .await?;
+        let failed_details = email_send_job_repository.get_failed_job_batch_details(10).await?;
+        assert_eq!(failed_details.len(), 1);
+        assert_eq!(failed_details[0].failed_reason, Some("test".to_string()));
+
+        job_creator.retry_job(db.begin().await?, &job_id).await?;
+        let sqs_send_message_input = send_email_sqs_sender.send_message_inputs.lock().last().cloned().unwrap();
+        let sqs_message = serde_json::from_str::<EmailSendJobBatchSqsMessage>(&sqs_send_message_input.body)?;
+        executor.execute(&[sqs_message]).await?;
+
+        assert_eq!(ses_sender.send_mail_simple_text_inputs.lock().len(), 2);
+        let details = email_send_job_repository.get_job_batch_details(&job_id, 0).await?;
+        assert_eq!(details[0].status, EmailSendJobBatchDetailStatus::Requested);
+        assert_eq!(details[0].retry_count, 1);
+
         Ok(())
     }
 }
@@ -1,32 +1,143 @@
 use std::sync::Arc;
 
-use omnius_core_cloud::aws::sqs::SqsSender;
+use serde::Serialize;
+use sqlx::{Postgres, Transaction};
+
+use omnius_opxs_base::{aws::SqsMessageSender, telemetry};
 
 use crate::prelude::*;
 
-use super::{EmailConfirmRequestParam, EmailSendJobBatchSqsMessage, EmailSendJobRepository};
+use super::{
+    AccountUnlockRequestParam, DataExportReadyRequestParam, EmailChangeConfirmRequestParam, EmailChangedNoticeRequestParam, EmailConfirmRequestParam,
+    EmailLocale, EmailSendJobBatchSqsMessage, EmailSendJobRepository, EmailSendJobType,
+};
 
 pub struct EmailSendJobCreator {
     pub email_send_job_repository: Arc<EmailSendJobRepository>,
-    pub sqs_sender: Arc<dyn SqsSender + Send + Sync>,
+    pub sqs_sender: Arc<dyn SqsMessageSender + Send + Sync>,
 }
 
 impl EmailSendJobCreator {
-    pub async fn create_job(
+    pub async fn create_email_confirm_job(
         &self,
         job_id: &str,
         user_name: &str,
         to_email_address: &str,
         from_email_address: &str,
         email_confirm_url: &str,
+        locale: &EmailLocale,
     ) -> Result<()> {
         let param = EmailConfirmRequestParam {
             user_name: user_name.to_string(),
             to_email_address: to_email_address.to_string(),
             from_email_address: from_email_address.to_string(),
+            locale: *locale,
             email_confirm_url: email_confirm_url.to_string(),
         };
-        self.email_send_job_repository.create_job(job_id, &param).await?;
+        self.create_job(job_id, &EmailSendJobType::EmailConfirm, to_email_address, &param).await
+    }
+
+    pub async fn create_account_unlock_job(
+        &self,
+        job_id: &str,
+        user_name: &str,
+        to_email_address: &str,
+        from_email_address: &str,
+        unlock_url: &str,
+        locale: &EmailLocale,
+    ) -> Result<()> {
+        let param = AccountUnlockRequestParam {
+            user_name: user_name.to_string(),
+            to_email_address: to_email_address.to_string(),
+            from_email_address: from_email_address.to_string(),
+            locale: *locale,
+            unlock_url: unlock_url.to_string(),
+        };
+        self.create_job(job_id, &EmailSendJobType::AccountUnlock, to_email_address, &param).await
+    }
+
+    pub async fn create_email_change_confirm_job(
+        &self,
+        job_id: &str,
+        user_name: &str,
+        to_email_address: &str,
+        from_email_address: &str,
+        email_change_confirm_url: &str,
+        locale: &EmailLocale,
+    ) -> Result<()> {
+        let param = EmailChangeConfirmRequestParam {
+            user_name: user_name.to_string(),
+            to_email_address: to_email_address.to_string(),
+            from_email_address: from_email_address.to_string(),
+            locale: *locale,
+            email_change_confirm_url: email_change_confirm_url.to_string(),
+        };
+        self.create_job(job_id, &EmailSendJobType::EmailChangeConfirm, to_email_address, &param).await
+    }
+
+    pub async fn create_email_changed_notice_job(
+        &self,
+        job_id: &str,
+        user_name: &str,
+        to_email_address: &str,
+        from_email_address: &str,
+        new_email_address: &str,
+        locale: &EmailLocale,
+    ) -> Result<()> {
+        let param = EmailChangedNoticeRequestParam {
+            user_name: user_name.to_string(),
+            to_email_address: to_email_address.to_string(),
+            from_email_address: from_email_address.to_string(),
+            locale: *locale,
+            new_email_address: new_email_address.to_string(),
+        };
+        self.create_job(job_id, &EmailSendJobType::EmailChangedNotice, to_email_address, &param).await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create_data_export_ready_job(
+        &self,
+        job_id: &str,
+        user_name: &str,
+        to_email_address: &str,
+        from_email_address: &str,
+        download_url: &str,
+        expires_in_days: i64,
+        locale: &EmailLocale,
+    ) -> Result<()> {
+        let param = DataExportReadyRequestParam {
+            user_name: user_name.to_string(),
+            to_email_address: to_email_address.to_string(),
+            from_email_address: from_email_address.to_string(),
+            locale: *locale,
+            download_url: download_url.to_string(),
+            expires_in_days,
+        };
+        self.create_job(job_id, &EmailSendJobType::DataExportReady, to_email_address, &param).await
+    }
+
+    // Commits `tx` before the batches are queued again.
+    pub async fn retry_job(&self, mut tx: Transaction<'_, Postgres>, job_id: &str) -> Result<()> {
+        let batch_ids = self.email_send_job_repository.update_status_to_retry(&mut tx, job_id).await?;
+        tx.commit().await?;
+
+        for batch_id in batch_ids {
+            let m = EmailSendJobBatchSqsMessage {
+                job_id: job_id.to_string(),
+                batch_id,
+                trace_context: telemetry::current_trace_context(),
+            };
+            self.sqs_sender.send_message(&serde_json::to_string(&m)?, &m.trace_context).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn create_job<TParam>(&self, job_id: &str, typ: &EmailSendJobType, to_email_address: &str, param: &TParam) -> Result<()>
+    where
+        TParam: ?Sized + Serialize,
+    {
+        self.email_send_job_repository.create_job(job_id, typ, to_email_address, param).await?;
         let batches = self.email_send_job_repository.get_job_batches(job_id).await?;
 
         let messages: Vec<EmailSendJobBatchSqsMessage> = batches
@@ -34,13 +145,14 @@ impl EmailSendJobCreator {
             .map(|n| EmailSendJobBatchSqsMessage {
                 job_id: n.job_id.clone(),
                 batch_id: n.batch_id,
+                trace_context: telemetry::current_trace_context(),
             })
             .collect();
 
         self.email_send_job_repository.update_status_to_waiting(job_id).await?;
 
         for m in messages.iter() {
-            self.sqs_sender.send_message(&serde_json::to_string(m).unwrap()).await?;
+            self.sqs_sender.send_message(&serde_json::to_string(m).unwrap(), &m.trace_context).await?;
         }
 
         Ok(())
@@ -3,6 +3,7 @@ mod executor;
 mod feedback;
 mod job_creator;
 mod message;
+mod metrics;
 mod prelude;
 mod repo;
 
@@ -1,10 +1,15 @@
 use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
 #[derive(Serialize, Deserialize, Debug)]
 pub enum EmailSendJobType {
     Unknown,
     EmailConfirm,
+    AccountUnlock,
+    EmailChangeConfirm,
+    EmailChangedNotice,
+    DataExportReady,
 }
 
 impl sqlx::Type<sqlx::Postgres> for EmailSendJobType {
@@ -17,6 +22,10 @@ impl sqlx::Encode<'_, sqlx::Postgres> for EmailSendJobType {
     fn encode_by_ref(&self, buf: &mut sqlx::postgres::PgArgumentBuffer) -> Result<sqlx::encode::IsNull, sqlx::error::BoxDynError> {
         match self {
             EmailSendJobType::EmailConfirm => buf.extend_from_slice(b"EmailConfirm"),
+            EmailSendJobType::AccountUnlock => buf.extend_from_slice(b"AccountUnlock"),
+            EmailSendJobType::EmailChangeConfirm => buf.extend_from_slice(b"EmailChangeConfirm"),
+            EmailSendJobType::EmailChangedNotice => buf.extend_from_slice(b"EmailChangedNotice"),
+            EmailSendJobType::DataExportReady => buf.extend_from_slice(b"DataExportReady"),
             _ => buf.extend_from_slice(b"Unknown"),
         }
         Ok(sqlx::encode::IsNull::No)
@@ -27,6 +36,10 @@ impl sqlx::Decode<'_, sqlx::Postgres> for EmailSendJobType {
     fn decode(value: sqlx::postgres::PgValueRef<'_>) -> std::result::Result<Self, Box<dyn std::error::Error + Send + Sync + 'static>> {
         match value.as_str() {
             Ok("EmailConfirm") => Ok(EmailSendJobType::EmailConfirm),
+            Ok("AccountUnlock") => Ok(EmailSendJobType::AccountUnlock),
+            Ok("EmailChangeConfirm") => Ok(EmailSendJobType::EmailChangeConfirm),
+            Ok("EmailChangedNotice") => Ok(EmailSendJobType::EmailChangedNotice),
+            Ok("DataExportReady") => Ok(EmailSendJobType::DataExportReady),
             _ => Ok(EmailSendJobType::Unknown),
         }
     }
@@ -101,7 +114,7 @@ pub struct EmailSendJobBatch {
     pub updated_at: NaiveDateTime,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, ToSchema)]
 pub enum EmailSendJobBatchDetailStatus {
     Unknown,
     Preparing,
@@ -150,15 +163,15 @@ impl sqlx::Decode<'_, sqlx::Postgres> for EmailSendJobBatchDetailStatus {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, sqlx::FromRow)]
+#[derive(Serialize, Deserialize, Debug, sqlx::FromRow, ToSchema)]
 pub struct EmailSendJobBatchDetail {
     pub job_id: String,
     pub batch_id: i32,
     pub email_address: String,
     pub retry_count: i32,
-    pub message_id: String,
+    pub message_id: Option<String>,
     pub status: EmailSendJobBatchDetailStatus,
-    pub failed_reason: String,
+    pub failed_reason: Option<String>,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
 }
@@ -1,9 +1,60 @@
 use serde::{Deserialize, Serialize};
 
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum EmailLocale {
+    #[default]
+    Ja,
+    En,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
 pub struct EmailConfirmRequestParam {
     pub user_name: String,
     pub to_email_address: String,
     pub from_email_address: String,
+    #[serde(default)]
+    pub locale: EmailLocale,
     pub email_confirm_url: String,
 }
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct AccountUnlockRequestParam {
+    pub user_name: String,
+    pub to_email_address: String,
+    pub from_email_address: String,
+    #[serde(default)]
+    pub locale: EmailLocale,
+    pub unlock_url: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct EmailChangeConfirmRequestParam {
+    pub user_name: String,
+    pub to_email_address: String,
+    pub from_email_address: String,
+    #[serde(default)]
+    pub locale: EmailLocale,
+    pub email_change_confirm_url: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct EmailChangedNoticeRequestParam {
+    pub user_name: String,
+    pub to_email_address: String,
+    pub from_email_address: String,
+    #[serde(default)]
+    pub locale: EmailLocale,
+    pub new_email_address: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct DataExportReadyRequestParam {
+    pub user_name: String,
+    pub to_email_address: String,
+    pub from_email_address: String,
+    #[serde(default)]
+    pub locale: EmailLocale,
+    pub download_url: String,
+    pub expires_in_days: i64,
+}
@@ -1,7 +1,11 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct EmailSendJobBatchSqsMessage {
     pub job_id: String,
     pub batch_id: i32,
+    #[serde(skip)]
+    pub trace_context: HashMap<String, String>,
 }
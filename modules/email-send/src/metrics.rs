@@ -0,0 +1,19 @@
+use std::sync::LazyLock;
+
+use prometheus::{HistogramVec, IntCounterVec, register_histogram_vec, register_int_counter_vec};
+
+pub(crate) static JOBS_TOTAL: LazyLock<IntCounterVec> =
+    LazyLock::new(|| register_int_counter_vec!("email_send_jobs_total", "Number of processed email send jobs", &["status"]).unwrap());
+
+pub(crate) static JOB_DURATION_SECONDS: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register_histogram_vec!(
+        "email_send_job_duration_seconds",
+        "Time spent processing an email send job",
+        &["status"],
+        vec![0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0]
+    )
+    .unwrap()
+});
+
+pub(crate) static SES_REQUESTS_TOTAL: LazyLock<IntCounterVec> =
+    LazyLock::new(|| register_int_counter_vec!("email_send_ses_requests_total", "Number of SES send requests by outcome", &["outcome"]).unwrap());
@@ -2,11 +2,12 @@ use std::sync::Arc;
 
 use chrono::Utc;
 use omnius_core_base::clock::Clock;
-use sqlx::PgPool;
+use serde::Serialize;
+use sqlx::{PgPool, Postgres, Transaction};
 
 use crate::{EmailSendJobBatchDetail, prelude::*};
 
-use super::{EmailConfirmRequestParam, EmailSendJob, EmailSendJobBatch, EmailSendJobBatchDetailStatus, EmailSendJobBatchStatus, EmailSendJobType};
+use super::{EmailSendJob, EmailSendJobBatch, EmailSendJobBatchDetailStatus, EmailSendJobBatchStatus, EmailSendJobType};
 
 pub struct EmailSendJobRepository {
     pub db: Arc<PgPool>,
@@ -14,7 +15,10 @@ pub struct EmailSendJobRepository {
 }
 
 impl EmailSendJobRepository {
-    pub async fn create_job(&self, job_id: &str, param: &EmailConfirmRequestParam) -> Result<()> {
+    pub async fn create_job<TParam>(&self, job_id: &str, typ: &EmailSendJobType, to_email_address: &str, param: &TParam) -> Result<()>
+    where
+        TParam: ?Sized + Serialize,
+    {
         let now = self.clock.now();
 
         let mut tx = self.db.begin().await?;
@@ -28,7 +32,7 @@ INSERT INTO email_send_jobs (id, batch_count, email_address_count, type, param,
         .bind(job_id)
         .bind(1)
         .bind(1)
-        .bind(EmailSendJobType::EmailConfirm)
+        .bind(typ)
         .bind(&serde_json::to_string(param)?)
         .bind(now)
         .execute(&mut *tx)
@@ -56,7 +60,7 @@ INSERT INTO email_send_job_batch_details (job_id, batch_id, email_address, retry
         )
         .bind(job_id)
         .bind(0)
-        .bind(param.to_email_address.as_str())
+        .bind(to_email_address)
         .bind(0)
         .bind(EmailSendJobBatchDetailStatus::Preparing)
         .bind(now)
@@ -115,6 +119,23 @@ SELECT *
         Ok(res)
     }
 
+    pub async fn get_failed_job_batch_details(&self, limit: i64) -> Result<Vec<EmailSendJobBatchDetail>> {
+        let res: Vec<EmailSendJobBatchDetail> = sqlx::query_as(
+            r#"
+SELECT *
+    FROM email_send_job_batch_details
+    WHERE status = 'Failed'
+    ORDER BY updated_at DESC
+    LIMIT $1
+"#,
+        )
+        .bind(limit)
+        .fetch_all(self.db.as_ref())
+        .await?;
+
+        Ok(res)
+    }
+
     pub async fn set_message_id(&self, job_id: &str, batch_id: i32, email_address: &str, message_id: &str) -> Result<()> {
         let now = self.clock.now();
 
@@ -161,6 +182,88 @@ UPDATE email_send_job_batch_details
         .await
     }
 
+    pub async fn update_status_to_failed(&self, job_id: &str, batch_id: i32, email_address: &str, failed_reason: &str) -> Result<()> {
+        let mut tx = self.db.begin().await?;
+        let now = self.clock.now();
+
+        let res = sqlx::query(
+            r#"
+UPDATE email_send_job_batch_details
+    SET status = 'Failed', failed_reason = $5, updated_at = $4
+    WHERE job_id = $1 AND batch_id = $2 AND email_address = $3 AND status = 'Processing'
+"#,
+        )
+        .bind(job_id)
+        .bind(batch_id)
+        .bind(email_address)
+        .bind(now)
+        .bind(failed_reason)
+        .execute(&mut *tx)
+        .await?;
+
+        if res.rows_affected() < 1 {
+            return Err(Error::builder().kind(ErrorKind::DatabaseError).message("no rows affected").build());
+        }
+
+        sqlx::query(
+            r#"
+UPDATE email_send_job_batches
+    SET status = 'Failed', updated_at = $3
+    WHERE job_id = $1 AND batch_id = $2
+"#,
+        )
+        .bind(job_id)
+        .bind(batch_id)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    // Returns the batches to be re-queued.
+    pub async fn update_status_to_retry(&self, tx: &mut Transaction<'_, Postgres>, job_id: &str) -> Result<Vec<i32>> {
+        let now = self.clock.now();
+
+        let batch_ids: Vec<(i32,)> = sqlx::query_as(
+            r#"
+UPDATE email_send_job_batch_details
+    SET status = 'Waiting', retry_count = retry_count + 1, failed_reason = NULL, updated_at = $2
+    WHERE job_id = $1 AND status = 'Failed'
+    RETURNING batch_id
+"#,
+        )
+        .bind(job_id)
+        .bind(now)
+        .fetch_all(&mut **tx)
+        .await?;
+
+        if batch_ids.is_empty() {
+            return Err(Error::builder().kind(ErrorKind::NotFound).message("failed job not found").build());
+        }
+
+        let mut batch_ids: Vec<i32> = batch_ids.into_iter().map(|(n,)| n).collect();
+        batch_ids.sort_unstable();
+        batch_ids.dedup();
+
+        sqlx::query(
+            r#"
+UPDATE email_send_job_batches
+    SET status = 'Waiting', updated_at = $3
+    WHERE job_id = $1 AND batch_id = ANY($2)
+"#,
+        )
+        .bind(job_id)
+        .bind(&batch_ids)
+        .bind(now)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(batch_ids)
+    }
+
     async fn update_status_by_job_id(&self, job_id: &str, old: &EmailSendJobBatchDetailStatus, new: &EmailSendJobBatchDetailStatus) -> Result<()> {
         let mut tx = self.db.begin().await?;
         let now = self.clock.now();
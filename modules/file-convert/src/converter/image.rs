@@ -2,7 +2,9 @@ use std::{path::Path, process::Stdio};
 
 use async_trait::async_trait;
 use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD as BASE64};
+use image::ImageFormat;
 use serde::{Deserialize, Serialize};
+use tempfile::tempdir;
 use tokio::{
     io::{AsyncReadExt, AsyncWriteExt},
     process::Command,
@@ -19,6 +21,14 @@ pub trait ImageConverter {
         out_path: &Path,
         out_type: &FileConvertImageOutputFileType,
     ) -> Result<()>;
+    async fn thumbnail(
+        &self,
+        in_path: &Path,
+        in_type: &FileConvertImageInputFileType,
+        out_path: &Path,
+        out_type: &FileConvertImageOutputFileType,
+        size: u32,
+    ) -> Result<()>;
 }
 
 #[derive(Debug)]
@@ -33,16 +43,53 @@ impl ImageConverter for ImageConverterImpl {
         out_path: &Path,
         out_type: &FileConvertImageOutputFileType,
     ) -> Result<()> {
-        let image_converter_dir = std::env::var("IMAGE_CONVERTER_DIR")?;
-        let image_converter = Path::new(&image_converter_dir).join("Omnius.ImageConverter");
-
-        let image_converter_option = ImageConverterOption {
+        self.run(&ImageConverterOption {
             in_path: in_path.to_string_lossy().to_string(),
             in_type: in_type.clone(),
             out_path: out_path.to_string_lossy().to_string(),
             out_type: out_type.clone(),
-        };
-        let image_converter_option = BASE64.encode(serde_json::to_string(&image_converter_option)?);
+        })
+        .await
+    }
+
+    async fn thumbnail(
+        &self,
+        in_path: &Path,
+        in_type: &FileConvertImageInputFileType,
+        out_path: &Path,
+        out_type: &FileConvertImageOutputFileType,
+        size: u32,
+    ) -> Result<()> {
+        // The external converter only changes formats, so the image is decoded to PNG and resized here.
+        let working_dir = tempdir()?;
+        let decoded_path = working_dir.path().join("decoded.png");
+        let resized_path = working_dir.path().join("resized.png");
+
+        self.convert(in_path, in_type, &decoded_path, &FileConvertImageOutputFileType::Png)
+            .await?;
+        resize_png(&decoded_path, &resized_path, size).await?;
+        self.convert(&resized_path, &FileConvertImageInputFileType::Png, out_path, out_type).await
+    }
+}
+
+async fn resize_png(in_path: &Path, out_path: &Path, size: u32) -> Result<()> {
+    let in_path = in_path.to_path_buf();
+    let out_path = out_path.to_path_buf();
+
+    tokio::task::spawn_blocking(move || -> Result<()> {
+        let image = image::open(&in_path)?;
+        image.thumbnail(size, size).save_with_format(&out_path, ImageFormat::Png)?;
+        Ok(())
+    })
+    .await?
+}
+
+impl ImageConverterImpl {
+    async fn run(&self, option: &ImageConverterOption) -> Result<()> {
+        let image_converter_dir = std::env::var("IMAGE_CONVERTER_DIR")?;
+        let image_converter = Path::new(&image_converter_dir).join("Omnius.ImageConverter");
+
+        let image_converter_option = BASE64.encode(serde_json::to_string(option)?);
 
         let mut cmd = Command::new(image_converter)
             .stdin(Stdio::piped())
@@ -104,8 +151,29 @@ struct ImageConverterOption {
 mod tests {
     use std::{env, path::Path};
 
+    use image::{GenericImageView as _, RgbImage};
+    use tempfile::tempdir;
+    use testresult::TestResult;
+
     use crate::{FileConvertImageInputFileType, FileConvertImageOutputFileType, ImageConverter as _, ImageConverterImpl};
 
+    use super::resize_png;
+
+    #[tokio::test]
+    async fn resize_png_test() -> TestResult {
+        let working_dir = tempdir()?;
+        let in_path = working_dir.path().join("in.png");
+        let out_path = working_dir.path().join("out.png");
+
+        RgbImage::new(1024, 512).save(&in_path)?;
+        resize_png(&in_path, &out_path, 256).await?;
+
+        // the aspect ratio is kept within the bounding box
+        assert_eq!(image::open(&out_path)?.dimensions(), (256, 128));
+
+        Ok(())
+    }
+
     #[ignore]
     #[tokio::test]
     async fn simple_test() {
@@ -12,6 +12,7 @@ use super::ImageConverter;
 
 pub struct ImageConverterMock {
     pub convert_inputs: Arc<Mutex<Vec<ConvertInput>>>,
+    pub thumbnail_inputs: Arc<Mutex<Vec<ThumbnailInput>>>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -22,6 +23,15 @@ pub struct ConvertInput {
     out_type: FileConvertImageOutputFileType,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThumbnailInput {
+    in_path: PathBuf,
+    in_type: FileConvertImageInputFileType,
+    out_path: PathBuf,
+    out_type: FileConvertImageOutputFileType,
+    size: u32,
+}
+
 #[async_trait]
 impl ImageConverter for ImageConverterMock {
     async fn convert(
@@ -40,12 +50,32 @@ impl ImageConverter for ImageConverterMock {
 
         Ok(())
     }
+
+    async fn thumbnail(
+        &self,
+        in_path: &Path,
+        in_type: &FileConvertImageInputFileType,
+        out_path: &Path,
+        out_type: &FileConvertImageOutputFileType,
+        size: u32,
+    ) -> Result<()> {
+        self.thumbnail_inputs.lock().push(ThumbnailInput {
+            in_path: in_path.to_path_buf(),
+            in_type: in_type.clone(),
+            out_path: out_path.to_path_buf(),
+            out_type: out_type.clone(),
+            size,
+        });
+
+        Ok(())
+    }
 }
 
 impl ImageConverterMock {
     pub fn new() -> Self {
         Self {
             convert_inputs: Arc::new(Mutex::new(vec![])),
+            thumbnail_inputs: Arc::new(Mutex::new(vec![])),
         }
     }
 }
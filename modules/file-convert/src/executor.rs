@@ -1,10 +1,15 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Instant};
 
 use omnius_core_cloud::aws::s3::S3Client;
 use tempfile::tempdir;
-use tracing::info;
+use tracing::{Instrument as _, info, info_span};
 
-use crate::{FileConvertImageRequestParam, FileConvertJobRepository, FileConvertJobType, ImageConverter, prelude::*};
+use omnius_opxs_base::telemetry;
+
+use crate::{
+    FileConvertImageRequestParam, FileConvertJob, FileConvertJobRepository, FileConvertJobSqsMessage, FileConvertJobType,
+    FileConvertThumbnailRequestParam, ImageConverter, metrics, prelude::*,
+};
 
 pub struct FileConvertExecutor {
     pub file_convert_job_repository: Arc<FileConvertJobRepository>,
@@ -14,35 +19,58 @@ pub struct FileConvertExecutor {
 }
 
 impl FileConvertExecutor {
-    pub async fn execute(&self, job_ids: &[String]) -> Result<()> {
-        for job_id in job_ids.iter() {
-            info!("Start processing job: {}", job_id);
+    pub async fn execute(&self, ms: &[FileConvertJobSqsMessage]) -> Result<()> {
+        for m in ms.iter() {
+            let span = info_span!("file_convert_job", job_id = %m.job_id);
+            telemetry::set_parent(&span, &m.trace_context);
 
-            self.file_convert_job_repository.update_status_to_processing(job_id).await?;
+            self.execute_job(&m.job_id).instrument(span).await?;
+        }
+        Ok(())
+    }
 
-            let res = self.execute_one(job_id).await;
+    async fn execute_job(&self, job_id: &str) -> Result<()> {
+        info!("Start processing job: {}", job_id);
 
-            if let Err(e) = res {
-                self.file_convert_job_repository
-                    .update_status_to_failed(job_id, e.to_string().as_str())
-                    .await?;
-                continue;
-            }
+        self.file_convert_job_repository.update_status_to_processing(job_id).await?;
+        let job = self.file_convert_job_repository.get_job(job_id).await?;
 
+        let start = Instant::now();
+        let res = self.execute_one(&job).await;
+        let completed = res.is_ok();
+        let status = if completed { "completed" } else { "failed" };
+        metrics::JOBS_TOTAL.with_label_values(&[status]).inc();
+        metrics::JOB_DURATION_SECONDS
+            .with_label_values(&[status])
+            .observe(start.elapsed().as_secs_f64());
+
+        if let Err(e) = res {
+            metrics::FAILURES_TOTAL.with_label_values(&[&format!("{:?}", e.kind())]).inc();
+            self.file_convert_job_repository
+                .update_status_to_failed(job_id, e.to_string().as_str())
+                .await?;
+        } else {
             self.file_convert_job_repository.update_status_to_completed(job_id).await?;
         }
+
+        // Thumbnail jobs are only created for avatar uploads, which stay pending until here.
+        if job.typ == FileConvertJobType::Thumbnail {
+            self.file_convert_job_repository.resolve_pending_avatar(job_id, completed).await?;
+        }
+
         Ok(())
     }
 
-    async fn execute_one(&self, job_id: &str) -> Result<()> {
-        let job = self.file_convert_job_repository.get_job(job_id).await?;
+    async fn execute_one(&self, job: &FileConvertJob) -> Result<()> {
+        let job_id = job.id.as_str();
 
         match &job.typ {
             FileConvertJobType::Image => {
                 let param = job
                     .param
+                    .as_deref()
                     .ok_or_else(|| Error::builder().kind(ErrorKind::NotFound).message("param is not found").build())?;
-                let param = serde_json::from_str::<FileConvertImageRequestParam>(&param)?;
+                let param = serde_json::from_str::<FileConvertImageRequestParam>(param)?;
 
                 let working_dir = tempdir()?;
 
@@ -63,6 +91,32 @@ impl FileConvertExecutor {
 
                 self.s3_client.put_object(format!("out/{job_id}").as_str(), &out_path).await?;
             }
+            FileConvertJobType::Thumbnail => {
+                let param = job
+                    .param
+                    .as_deref()
+                    .ok_or_else(|| Error::builder().kind(ErrorKind::NotFound).message("param is not found").build())?;
+                let param = serde_json::from_str::<FileConvertThumbnailRequestParam>(param)?;
+
+                let working_dir = tempdir()?;
+
+                let in_type = param.in_type.clone();
+                let in_path = working_dir.path().join(format!("in_{job_id}")).with_extension(in_type.to_extension());
+                let out_type = param.out_type.clone();
+                let out_path = working_dir.path().join(format!("out_{job_id}")).with_extension(out_type.to_extension());
+
+                self.s3_client.get_object(format!("in/{job_id}").as_str(), &in_path).await?;
+
+                info!("Start generating thumbnail: {:?}", param);
+
+                self.image_converter
+                    .thumbnail(in_path.as_path(), &in_type, out_path.as_path(), &out_type, param.size)
+                    .await?;
+
+                info!("Finish generating thumbnail: {:?}", param);
+
+                self.s3_client.put_object(format!("out/{job_id}").as_str(), &out_path).await?;
+            }
             _ => todo!(),
         }
 
@@ -72,6 +126,8 @@ impl FileConvertExecutor {
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashMap;
+
     use chrono::Duration;
     use parking_lot::Mutex;
     use sqlx::postgres::PgPoolOptions;
@@ -86,9 +142,15 @@ mod tests {
     use omnius_core_migration::postgres::PostgresMigrator;
     use omnius_core_testkit::containers::postgres::PostgresContainer;
 
-    use omnius_opxs_base::shared::POSTGRES_VERSION;
+    use omnius_opxs_base::{
+        aws::{S3ObjectMetadataClient as _, S3ObjectMetadataClientMock, SqsMessageSenderMock},
+        shared::POSTGRES_VERSION,
+    };
 
-    use crate::{FileConvertImageInputFileType, FileConvertImageOutputFileType, FileConvertJobCreator, ImageConverterMock};
+    use crate::{
+        FileConvertImageInputFileType, FileConvertImageOutputFileType, FileConvertJobCreator, FileConvertJobSqsMessage, FileConvertJobStatus,
+        ImageConverterMock,
+    };
 
     use super::*;
 
@@ -107,8 +169,10 @@ mod tests {
         let clock = Arc::new(ClockUtc {});
         let tsid_provider = Arc::new(Mutex::new(TsidProviderImpl::new(ClockUtc, RandomBytesProviderImpl::new(), 16)));
         let s3_client = Arc::new(S3ClientMock::new());
-        s3_client
-            .gen_put_presigned_uri_outputs
+        let s3_metadata_client = Arc::new(S3ObjectMetadataClientMock::new());
+        let sqs_sender = Arc::new(SqsMessageSenderMock::new());
+        s3_metadata_client
+            .gen_put_presigned_uri_with_metadata_outputs
             .lock()
             .push_back("https://put.s3.example.com".to_string());
         s3_client
@@ -123,7 +187,7 @@ mod tests {
         migrator.migrate().await.unwrap();
 
         let file_convert_job_repository = Arc::new(FileConvertJobRepository {
-            db,
+            db: db.clone(),
             clock: clock.clone(),
             tsid_provider: tsid_provider.clone(),
         });
@@ -132,6 +196,8 @@ mod tests {
             file_convert_job_repository: file_convert_job_repository.clone(),
             clock: clock.clone(),
             s3_client: s3_client.clone(),
+            s3_metadata_client: s3_metadata_client.clone(),
+            sqs_sender: sqs_sender.clone(),
         };
         let job_id = tsid_provider.lock().create().to_string();
         let param = FileConvertImageRequestParam {
@@ -142,7 +208,9 @@ mod tests {
             .create_job(&job_id, "test_user_id", &FileConvertJobType::Image, &param, "test.jpg", "test.png")
             .await
             .unwrap();
-        println!("upload_url: {upload_url}");
+        assert_eq!(upload_url, "https://put.s3.example.com");
+        // no span is active, so the upload carries an empty trace context
+        assert_eq!(s3_metadata_client.get_object_metadata(&format!("in/{job_id}")).await?, HashMap::new());
 
         let image_converter = Arc::new(ImageConverterMock::new());
         let executor = FileConvertExecutor {
@@ -150,12 +218,88 @@ mod tests {
             s3_client: s3_client.clone(),
             image_converter: image_converter.clone(),
         };
-        executor.execute(&[job_id.clone()]).await.unwrap();
+        executor
+            .execute(&[FileConvertJobSqsMessage {
+                job_id: job_id.clone(),
+                ..Default::default()
+            }])
+            .await
+            .unwrap();
 
         println!("{:?}", image_converter.convert_inputs.lock().first().unwrap());
         assert_eq!(s3_client.get_object_inputs.lock().first().unwrap().key, format!("in/{job_id}").as_str());
         assert_eq!(s3_client.put_object_inputs.lock().first().unwrap().key, format!("out/{job_id}").as_str());
 
+        s3_metadata_client
+            .gen_put_presigned_uri_with_metadata_outputs
+            .lock()
+            .push_back("https://put.s3.example.com".to_string());
+        let job_id = tsid_provider.lock().create().to_string();
+        let param = FileConvertThumbnailRequestParam {
+            in_type: FileConvertImageInputFileType::Jpg,
+            out_type: FileConvertImageOutputFileType::WebP,
+            size: 256,
+        };
+        job_creator
+            .create_job(&job_id, "test_user_id", &FileConvertJobType::Thumbnail, &param, "test.jpg", "test.webp")
+            .await
+            .unwrap();
+        sqlx::query(
+            r#"
+INSERT INTO users (id, name, authentication_type, role, avatar_job_id, pending_avatar_job_id, created_at, updated_at)
+    VALUES ('test_user_id', 'test_user', 'Email', 'User', 'old_job_id', $1, now(), now());
+"#,
+        )
+        .bind(&job_id)
+        .execute(db.as_ref())
+        .await?;
+        executor
+            .execute(&[FileConvertJobSqsMessage {
+                job_id: job_id.clone(),
+                ..Default::default()
+            }])
+            .await
+            .unwrap();
+
+        assert_eq!(image_converter.thumbnail_inputs.lock().len(), 1);
+        assert_eq!(s3_client.put_object_inputs.lock().last().unwrap().key, format!("out/{job_id}").as_str());
+        let (status, download_url) = job_creator.get_download_url(&job_id, "test_user_id").await.unwrap();
+        assert_eq!(status, FileConvertJobStatus::Completed);
+        assert_eq!(download_url, Some("https://get.s3.example.com".to_string()));
+
+        // the pending avatar is switched once its thumbnail job completes
+        let avatar: (Option<String>, Option<String>) =
+            sqlx::query_as("SELECT avatar_job_id, pending_avatar_job_id FROM users WHERE id = 'test_user_id'")
+                .fetch_one(db.as_ref())
+                .await?;
+        assert_eq!(avatar, (Some(job_id.clone()), None));
+
+        // retry
+        assert_eq!(
+            *job_creator.retry_job(db.begin().await?, &job_id).await.unwrap_err().kind(),
+            ErrorKind::NotFound
+        );
+        sqlx::query("UPDATE file_convert_jobs SET status = 'Failed', failed_reason = 'test' WHERE id = $1")
+            .bind(&job_id)
+            .execute(db.as_ref())
+            .await?;
+        let failed_jobs = file_convert_job_repository.get_failed_jobs(10).await?;
+        assert_eq!(failed_jobs.len(), 1);
+        assert_eq!(failed_jobs[0].failed_reason, Some("test".to_string()));
+
+        job_creator.retry_job(db.begin().await?, &job_id).await?;
+        let input = sqs_sender.send_message_inputs.lock().last().cloned().unwrap();
+        let mut m = serde_json::from_str::<FileConvertJobSqsMessage>(&input.body)?;
+        assert_eq!(m.job_id, job_id);
+        assert_eq!(input.body, format!(r#"{{"job_id":"{job_id}"}}"#));
+        m.trace_context = input.attributes;
+        executor.execute(&[m]).await?;
+        assert_eq!(
+            file_convert_job_repository.get_job(&job_id).await?.status,
+            FileConvertJobStatus::Completed
+        );
+        assert_eq!(file_convert_job_repository.get_jobs_by_user_id("test_user_id", 10).await?.len(), 2);
+
         Ok(())
     }
 }
@@ -2,11 +2,17 @@ use std::sync::Arc;
 
 use chrono::{Duration, Utc};
 use serde::Serialize;
+use sqlx::{Postgres, Transaction};
 
 use omnius_core_base::clock::Clock;
 use omnius_core_cloud::aws::s3::S3Client;
 
-use crate::{FileConvertJobStatus, FileConvertJobType, prelude::*};
+use omnius_opxs_base::{
+    aws::{S3ObjectMetadataClient, SqsMessageSender},
+    telemetry,
+};
+
+use crate::{FileConvertJobSqsMessage, FileConvertJobStatus, FileConvertJobType, prelude::*};
 
 use super::FileConvertJobRepository;
 
@@ -14,6 +20,8 @@ pub struct FileConvertJobCreator {
     pub file_convert_job_repository: Arc<FileConvertJobRepository>,
     pub clock: Arc<dyn Clock<Utc> + Send + Sync>,
     pub s3_client: Arc<dyn S3Client + Send + Sync>,
+    pub s3_metadata_client: Arc<dyn S3ObjectMetadataClient + Send + Sync>,
+    pub sqs_sender: Arc<dyn SqsMessageSender + Send + Sync>,
 }
 
 impl FileConvertJobCreator {
@@ -35,9 +43,10 @@ impl FileConvertJobCreator {
 
         let now = self.clock.now();
         let expires_in = Duration::minutes(5);
+        // The S3 event that starts the job has no trace context, so the upload carries it in the object metadata.
         let upload_uri = self
-            .s3_client
-            .gen_put_presigned_uri(format!("in/{job_id}").as_str(), now, expires_in)
+            .s3_metadata_client
+            .gen_put_presigned_uri_with_metadata(format!("in/{job_id}").as_str(), now, expires_in, &telemetry::current_trace_context())
             .await?;
 
         self.file_convert_job_repository.update_status_to_waiting(job_id).await?;
@@ -61,4 +70,18 @@ impl FileConvertJobCreator {
 
         Ok((job.status, Some(download_uri)))
     }
+
+    // The message is sent only after `tx` commits, so the executor never picks up a job that is still failed.
+    pub async fn retry_job(&self, mut tx: Transaction<'_, Postgres>, job_id: &str) -> Result<()> {
+        self.file_convert_job_repository.update_status_to_retry(&mut tx, job_id).await?;
+        tx.commit().await?;
+
+        let m = FileConvertJobSqsMessage {
+            job_id: job_id.to_string(),
+            trace_context: telemetry::current_trace_context(),
+        };
+        self.sqs_sender.send_message(&serde_json::to_string(&m)?, &m.trace_context).await?;
+
+        Ok(())
+    }
 }
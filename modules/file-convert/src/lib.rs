@@ -3,6 +3,7 @@ mod error;
 mod executor;
 mod job_creator;
 mod message;
+mod metrics;
 mod prelude;
 mod repo;
 
@@ -2,10 +2,11 @@ use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
-#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 pub enum FileConvertJobType {
     Unknown,
     Image,
+    Thumbnail,
 }
 
 impl sqlx::Type<sqlx::Postgres> for FileConvertJobType {
@@ -18,6 +19,7 @@ impl sqlx::Encode<'_, sqlx::Postgres> for FileConvertJobType {
     fn encode_by_ref(&self, buf: &mut sqlx::postgres::PgArgumentBuffer) -> Result<sqlx::encode::IsNull, sqlx::error::BoxDynError> {
         match self {
             FileConvertJobType::Image => buf.extend_from_slice(b"Image"),
+            FileConvertJobType::Thumbnail => buf.extend_from_slice(b"Thumbnail"),
             _ => buf.extend_from_slice(b"Unknown"),
         }
         Ok(sqlx::encode::IsNull::No)
@@ -28,6 +30,7 @@ impl sqlx::Decode<'_, sqlx::Postgres> for FileConvertJobType {
     fn decode(value: sqlx::postgres::PgValueRef<'_>) -> std::result::Result<Self, Box<dyn std::error::Error + Send + Sync + 'static>> {
         match value.as_str() {
             Ok("Image") => Ok(FileConvertJobType::Image),
+            Ok("Thumbnail") => Ok(FileConvertJobType::Thumbnail),
             _ => Ok(FileConvertJobType::Unknown),
         }
     }
@@ -79,7 +82,7 @@ impl sqlx::Decode<'_, sqlx::Postgres> for FileConvertJobStatus {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, sqlx::FromRow)]
+#[derive(Serialize, Deserialize, Debug, sqlx::FromRow, ToSchema)]
 pub struct FileConvertJob {
     pub id: String,
     pub user_id: String,
@@ -7,6 +7,14 @@ pub struct FileConvertImageRequestParam {
     pub out_type: FileConvertImageOutputFileType,
 }
 
+// Scales the image down to fit in a `size` x `size` square, keeping the aspect ratio.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileConvertThumbnailRequestParam {
+    pub in_type: FileConvertImageInputFileType,
+    pub out_type: FileConvertImageOutputFileType,
+    pub size: u32,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum FileConvertImageInputFileType {
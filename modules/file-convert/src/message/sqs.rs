@@ -1,5 +1,17 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 
+// Sent directly to the queue when a job is re-run, since no new S3 event is emitted for the existing input.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct FileConvertJobSqsMessage {
+    pub job_id: String,
+    // Carried in the `traceparent`/`tracestate` message attributes, not in the body.
+    #[serde(skip)]
+    pub trace_context: HashMap<String, String>,
+}
+
+// S3 event notification. The trace of the upload request is restored from the metadata of the uploaded object.
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ImageConvertJobSqsMessage {
     #[serde(rename = "Records")]
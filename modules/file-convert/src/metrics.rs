@@ -0,0 +1,19 @@
+use std::sync::LazyLock;
+
+use prometheus::{HistogramVec, IntCounterVec, register_histogram_vec, register_int_counter_vec};
+
+pub(crate) static JOBS_TOTAL: LazyLock<IntCounterVec> =
+    LazyLock::new(|| register_int_counter_vec!("file_convert_jobs_total", "Number of processed file convert jobs", &["status"]).unwrap());
+
+pub(crate) static JOB_DURATION_SECONDS: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register_histogram_vec!(
+        "file_convert_job_duration_seconds",
+        "Time spent processing a file convert job",
+        &["status"],
+        vec![0.1, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0]
+    )
+    .unwrap()
+});
+
+pub(crate) static FAILURES_TOTAL: LazyLock<IntCounterVec> =
+    LazyLock::new(|| register_int_counter_vec!("file_convert_failures_total", "Number of failed file convert jobs", &["reason"]).unwrap());
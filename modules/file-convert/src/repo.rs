@@ -4,7 +4,7 @@ use chrono::Utc;
 use omnius_core_base::{clock::Clock, tsid::TsidProvider};
 use parking_lot::Mutex;
 use serde::Serialize;
-use sqlx::PgPool;
+use sqlx::{PgPool, Postgres, Transaction};
 
 use crate::{FileConvertJob, FileConvertJobStatus, FileConvertJobType, prelude::*};
 
@@ -69,7 +69,7 @@ SELECT *
             r#"
 SELECT *
     FROM file_convert_jobs
-    WHERE id = $1, user_id = $2
+    WHERE id = $1 AND user_id = $2
 "#,
         )
         .bind(id)
@@ -80,6 +80,41 @@ SELECT *
         Ok(res)
     }
 
+    pub async fn get_jobs_by_user_id(&self, user_id: &str, limit: i64) -> Result<Vec<FileConvertJob>> {
+        let res: Vec<FileConvertJob> = sqlx::query_as(
+            r#"
+SELECT *
+    FROM file_convert_jobs
+    WHERE user_id = $1
+    ORDER BY created_at DESC
+    LIMIT $2
+"#,
+        )
+        .bind(user_id)
+        .bind(limit)
+        .fetch_all(self.db.as_ref())
+        .await?;
+
+        Ok(res)
+    }
+
+    pub async fn get_failed_jobs(&self, limit: i64) -> Result<Vec<FileConvertJob>> {
+        let res: Vec<FileConvertJob> = sqlx::query_as(
+            r#"
+SELECT *
+    FROM file_convert_jobs
+    WHERE status = 'Failed'
+    ORDER BY updated_at DESC
+    LIMIT $1
+"#,
+        )
+        .bind(limit)
+        .fetch_all(self.db.as_ref())
+        .await?;
+
+        Ok(res)
+    }
+
     pub async fn update_status_to_waiting(&self, job_id: &str) -> Result<()> {
         self.update_status(job_id, FileConvertJobStatus::Preparing, FileConvertJobStatus::Waiting)
             .await
@@ -141,4 +176,48 @@ UPDATE file_convert_jobs
 
         Ok(())
     }
+
+    // Only the user whose pending avatar is still this job is updated, so a newer upload is not overwritten by an older job.
+    pub async fn resolve_pending_avatar(&self, job_id: &str, completed: bool) -> Result<()> {
+        let now = self.clock.now();
+
+        sqlx::query(
+            r#"
+UPDATE users
+    SET avatar_job_id = CASE WHEN $2 THEN pending_avatar_job_id ELSE avatar_job_id END,
+        pending_avatar_job_id = NULL,
+        updated_at = $3
+    WHERE pending_avatar_job_id = $1;
+"#,
+        )
+        .bind(job_id)
+        .bind(completed)
+        .bind(now)
+        .execute(self.db.as_ref())
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn update_status_to_retry(&self, tx: &mut Transaction<'_, Postgres>, job_id: &str) -> Result<()> {
+        let now = self.clock.now();
+
+        let res = sqlx::query(
+            r#"
+UPDATE file_convert_jobs
+    SET status = 'Waiting', failed_reason = NULL, updated_at = $2
+    WHERE id = $1 AND status = 'Failed'
+"#,
+        )
+        .bind(job_id)
+        .bind(now)
+        .execute(&mut **tx)
+        .await?;
+
+        if res.rows_affected() < 1 {
+            return Err(Error::builder().kind(ErrorKind::NotFound).message("failed job not found").build());
+        }
+
+        Ok(())
+    }
 }